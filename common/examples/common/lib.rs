@@ -63,8 +63,8 @@ pub fn run_unpadded<F, S, PCS>(
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let raw_input: QArray<f32> = QArray::read(input_path);
-    let expected_output: QArray<u8> = QArray::read(expected_output_path);
+    let raw_input: QArray<f32> = QArray::read(input_path).unwrap();
+    let expected_output: QArray<u8> = QArray::read(expected_output_path).unwrap();
 
     let output_u8 = unpadded_inference::<F, S, PCS>(raw_input, model, qinfo);
 
@@ -85,8 +85,8 @@ pub fn run_unpadded<F, S, PCS>(
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let raw_input: QArray<f32> = QArray::read(input_path);
-    let expected_output: QArray<u8> = QArray::read(expected_output_path);
+    let raw_input: QArray<f32> = QArray::read(input_path).unwrap();
+    let expected_output: QArray<u8> = QArray::read(expected_output_path).unwrap();
 
     let output_u8 = padded_inference::<F, S, PCS>(raw_input, model, qinfo);
 
@@ -105,8 +105,8 @@ pub fn multi_run_unpadded<F, S, PCS>(
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path);
-    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path);
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
 
     for (raw_input, expected_output) in raw_inputs.into_iter().zip(expected_outputs.into_iter()) {
         assert_eq!(
@@ -131,8 +131,8 @@ pub fn multi_run_padded<F, S, PCS>(
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path);
-    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path);
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
 
     for (raw_input, expected_output) in raw_inputs.into_iter().zip(expected_outputs.into_iter()) {
         assert_eq!(
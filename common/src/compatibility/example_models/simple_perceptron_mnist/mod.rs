@@ -1,4 +1,4 @@
-use crate::{BMMNode, Model, Node, Poly, QArray, RequantiseBMMNode, ReshapeNode};
+use crate::{Model, ModelBuilder, Poly, QArray, QInfo};
 
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
@@ -28,24 +28,12 @@ where
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let flat_dim = INPUT_DIMS.iter().product();
-
-    let reshape: ReshapeNode = ReshapeNode::new(INPUT_DIMS.to_vec(), vec![flat_dim]);
-
-    let w_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights.json"));
-    let b_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias.json"));
-
-    let bmm: BMMNode<i8, i32> = BMMNode::new(w_array, b_array, Z_I);
-
-    let req_bmm: RequantiseBMMNode<i8> =
-        RequantiseBMMNode::new(OUTPUT_DIM, S_I, Z_I, S_W, Z_W, S_O, Z_O);
-
-    Model::new(
-        INPUT_DIMS.to_vec(),
-        vec![
-            Node::Reshape(reshape),
-            Node::BMM(bmm),
-            Node::RequantiseBMM(req_bmm),
-        ],
-    )
+    let w_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights.json")).unwrap();
+    let b_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias.json")).unwrap();
+
+    ModelBuilder::new(INPUT_DIMS.to_vec(), QInfo::new(S_I, Z_I))
+        .flatten()
+        .bmm(w_array, b_array, QInfo::new(S_W, Z_W), QInfo::new(S_O, Z_O))
+        .build()
+        .unwrap()
 }
@@ -5,7 +5,7 @@ use ark_poly_commit::PolynomialCommitment;
 pub mod parameters;
 use parameters::*;
 
-use crate::{BMMNode, Model, Node, Poly, QArray, ReLUNode, RequantiseBMMNode, ReshapeNode};
+use crate::{Model, ModelBuilder, Poly, QArray, QInfo};
 
 pub const INPUT_DIMS: &[usize] = &[28, 28];
 pub const INTER_DIM: usize = 28;
@@ -28,36 +28,26 @@ where
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let flat_dim = INPUT_DIMS.iter().product();
-
-    let reshape: ReshapeNode = ReshapeNode::new(INPUT_DIMS.to_vec(), vec![flat_dim]);
-
-    let w1_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights_1.json"));
-    let b1_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias_1.json"));
-    let w2_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights_2.json"));
-    let b2_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias_2.json"));
-
-    let bmm_1: BMMNode<i8, i32> = BMMNode::new(w1_array, b1_array, Z_1_I);
-
-    let req_bmm_1: RequantiseBMMNode<i8> =
-        RequantiseBMMNode::new(INTER_DIM, S_1_I, Z_1_I, S_1_W, Z_1_W, S_1_O, Z_1_O);
-
-    let relu: ReLUNode<i8> = ReLUNode::new(28, Z_1_O);
-
-    let bmm_2: BMMNode<i8, i32> = BMMNode::new(w2_array, b2_array, Z_2_I);
-
-    let req_bmm_2: RequantiseBMMNode<i8> =
-        RequantiseBMMNode::new(OUTPUT_DIM, S_2_I, Z_2_I, S_2_W, Z_2_W, S_2_O, Z_2_O);
-
-    Model::new(
-        INPUT_DIMS.to_vec(),
-        vec![
-            Node::Reshape(reshape),
-            Node::BMM(bmm_1),
-            Node::RequantiseBMM(req_bmm_1),
-            Node::ReLU(relu),
-            Node::BMM(bmm_2),
-            Node::RequantiseBMM(req_bmm_2),
-        ],
-    )
+    let w1_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights_1.json")).unwrap();
+    let b1_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias_1.json")).unwrap();
+    let w2_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights_2.json")).unwrap();
+    let b2_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias_2.json")).unwrap();
+
+    ModelBuilder::new(INPUT_DIMS.to_vec(), QInfo::new(S_1_I, Z_1_I))
+        .flatten()
+        .bmm(
+            w1_array,
+            b1_array,
+            QInfo::new(S_1_W, Z_1_W),
+            QInfo::new(S_1_O, Z_1_O),
+        )
+        .relu()
+        .bmm(
+            w2_array,
+            b2_array,
+            QInfo::new(S_2_W, Z_2_W),
+            QInfo::new(S_2_O, Z_2_O),
+        )
+        .build()
+        .unwrap()
 }
@@ -8,8 +8,7 @@ pub(crate) const Z_1_W: i8 = 0;
 pub(crate) const S_1_O: f32 = 0.059290364384651184;
 pub(crate) const Z_1_O: i8 = -128;
 
-pub(crate) const S_2_I: f32 = 0.059290364384651184;
-pub(crate) const Z_2_I: i8 = -128;
+// The input quantisation of the second layer is the output one of the first
 pub(crate) const S_2_W: f32 = 0.011379142291843891;
 pub(crate) const Z_2_W: i8 = 0;
 pub(crate) const S_2_O: f32 = 0.20425860583782196;
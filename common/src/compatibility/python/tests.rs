@@ -49,7 +49,8 @@ fn unpadded_inference(
 
 #[test]
 fn test_get_model_input() {
-    let expected_input = QArray::read("examples/simple_perceptron_mnist/data/input_test_150.json");
+    let expected_input =
+        QArray::read("examples/simple_perceptron_mnist/data/input_test_150.json").unwrap();
     assert_eq!(
         Python::with_gil(|py| get_model_input::<Vec<Vec<f32>>>(
             py,
@@ -63,7 +64,7 @@ fn test_get_model_input() {
 #[test]
 fn test_simple_perceptron_mnist_single_output() {
     let expected_output =
-        QArray::read("examples/simple_perceptron_mnist/data/output_test_150.json");
+        QArray::read("examples/simple_perceptron_mnist/data/output_test_150.json").unwrap();
     assert_eq!(
         Python::with_gil(|py| get_model_output(py, &get_model(py, "QSimplePerceptron", None), 150)),
         expected_output
@@ -73,7 +74,7 @@ fn test_simple_perceptron_mnist_single_output() {
 #[test]
 fn test_two_layer_perceptron_mnist_single_output() {
     let expected_output =
-        QArray::read("examples/two_layer_perceptron_mnist/data/output_test_150.json");
+        QArray::read("examples/two_layer_perceptron_mnist/data/output_test_150.json").unwrap();
     assert_eq!(
         Python::with_gil(|py| get_model_output(
             py,
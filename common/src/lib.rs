@@ -10,28 +10,59 @@ trait CommitmentState {}
 
 trait Proof {}
 
+pub use model::builder::ModelBuilder;
+pub use model::config::{
+    ProofConfig, ProofConfigError, SecurePCS, DEFAULT_LIGERO_RHO_INV, DEFAULT_SECURITY_BITS,
+};
+pub use model::encoding::{
+    max_encodable, FieldEncoding, NodeBounds, OverflowError, QuantisedInteger, ValueRange,
+};
+pub use model::evaluation::{AccumulatorOverflow, Evaluation, OverflowMode};
+pub use model::import::{
+    onnx::{parse_onnx_model, read_onnx_model},
+    tflite::{parse_tflite_model, read_tflite_model},
+    ImportError,
+};
 pub use model::nodes::{
-    bmm::{BMMNode, BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeProof},
+    batch_norm::BatchNormNode,
+    bmm::{
+        BMMNode, BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeMLEs, BMMNodeProof,
+        BMMNodeReductionProof,
+    },
+    custom::{CustomNode, CustomNodeData, CustomNodeOps, ValueType},
     relu::ReLUNode,
     requantise_bmm::{
         RequantiseBMMNode, RequantiseBMMNodeCommitment, RequantiseBMMNodeCommitmentState,
         RequantiseBMMNodeProof,
     },
-    reshape::ReshapeNode,
-    Node, NodeCommitment, NodeCommitmentState, NodeOpsPadded, NodeProof,
+    reshape::{ReshapeNode, ReshapeNodeReductionProof},
+    sparse_bmm::{
+        SparseBMMNode, SparseBMMNodeCommitment, SparseBMMNodeCommitmentState, SparseBMMNodeMLEs,
+        SparseBMMNodeProof, SparseBMMNodeReductionProof,
+    },
+    Node, NodeCommitment, NodeCommitmentState, NodeMLEs, NodeOpsPadded, NodeProof,
+    NodeReductionProof,
 };
-pub use model::qarray::{InnerType, QArray, QTypeArray};
-pub use model::{InferenceProof, Model};
+pub use model::optimisation::{FoldBatchNorm, FuseRequantiseReLU, MergeReshapes, OptimisationPass};
+pub use model::qarray::{broadcast_shape, InnerType, QArray, QArrayError, QTypeArray};
+pub use model::serialisation::{ModelFormat, SerialisationError, MODEL_FORMAT_VERSION};
+pub use model::setup::{SetupError, SetupKeys, UniversalSetup, SETUP_FORMAT_VERSION};
+pub use model::validation::ModelError;
+pub use model::{InferenceProof, LayeredInferenceProof, Model, NodeValueCommitment, OpeningClaim};
 pub use model::{LabeledPoly, Poly};
 pub use quantization::{quantise_f32_u8_nne, requantise_fc, BMMQInfo, QInfo, RoundingScheme};
+pub use utils::mle::{eq_evaluation, eq_evaluations, index_evaluation};
+pub use utils::pcs_types::{Brakedown, Hyrax, Ligero, MultilinearKZG, LINEAR_CODE_SECURITY_LEVEL};
+pub use utils::sponge::{poseidon_config, poseidon_sponge, POSEIDON_SECURITY_LEVEL};
 
 #[cfg(feature = "test-types")]
-pub use utils::{pcs_types::Ligero, test_sponge::test_sponge};
+pub use utils::test_sponge::test_sponge;
 
-#[cfg(feature = "test-types")]
+// The example models are also used by the unit tests of the crate
+#[cfg(any(test, feature = "test-types"))]
 pub mod compatibility;
 
-#[cfg(feature = "test-types")]
+#[cfg(any(test, feature = "test-types"))]
 pub use compatibility::example_models::{simple_perceptron_mnist, two_layer_perceptron_mnist};
 
 #[cfg(feature = "python")]
@@ -0,0 +1,144 @@
+// Fluent construction of models. The builder keeps track of the shape and
+// quantisation (scale and zero point) of the output of the last node added,
+// starting from those of the model input, and derives from them the input
+// dimensions and zero points of the following nodes, so that these only
+// need to be specified once.
+
+use crate::model::encoding::QuantisedInteger;
+use crate::model::nodes::{
+    bmm::BMMNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode, reshape::ReshapeNode,
+    sparse_bmm::SparseBMMNode, Node,
+};
+use crate::model::qarray::QArray;
+use crate::model::validation::ModelError;
+use crate::model::Model;
+use crate::quantization::{QInfo, QScaleType};
+
+#[cfg(test)]
+mod tests;
+
+/// Builder of a `Model` node by node, starting from the model input.
+/// Each BMM node is followed by the requantisation node bringing its output
+/// back to the small type, and receives the zero point of the values it is
+/// applied to, as do ReLU nodes. Inconsistencies which cannot be inferred
+/// away (e.g. a BMM node whose number of rows does not match the size of its
+/// input) are reported by `build` (cf. `Model::validate`).
+pub struct ModelBuilder<ST, LT> {
+    input_shape: Vec<usize>,
+    nodes: Vec<Node<ST, LT>>,
+
+    // Shape and quantisation of the output of the last node
+    shape: Vec<usize>,
+    scale: QScaleType,
+    zero_point: ST,
+}
+
+impl<ST, LT> ModelBuilder<ST, LT>
+where
+    ST: QuantisedInteger + TryFrom<LT>,
+    LT: QuantisedInteger + From<ST>,
+{
+    /// Starts a model whose input has the given shape and quantisation
+    pub fn new(input_shape: Vec<usize>, input_info: QInfo<ST>) -> Self {
+        Self {
+            shape: input_shape.clone(),
+            input_shape,
+            nodes: Vec::new(),
+            scale: input_info.scale,
+            zero_point: input_info.zero_point,
+        }
+    }
+
+    /// Reshapes the current output into the given shape
+    pub fn reshape(mut self, shape: Vec<usize>) -> Self {
+        self.nodes.push(Node::Reshape(ReshapeNode::new(
+            self.shape.clone(),
+            shape.clone(),
+        )));
+        self.shape = shape;
+        self
+    }
+
+    /// Reshapes the current output into a one-dimensional array, as expected
+    /// by BMM nodes
+    pub fn flatten(self) -> Self {
+        let len = self.shape.iter().product();
+        self.reshape(vec![len])
+    }
+
+    /// Multiplies the current output by `weights` (of shape (rows, columns))
+    /// and adds `bias`, requantising the result into the small type as per
+    /// the given quantisation of the weights and the output
+    pub fn bmm(
+        self,
+        weights: QArray<ST>,
+        bias: QArray<LT>,
+        weight_info: QInfo<ST>,
+        output_info: QInfo<ST>,
+    ) -> Self {
+        let node = Node::BMM(BMMNode::new(weights, bias, self.zero_point));
+        self.push_bmm(node, weight_info, output_info)
+    }
+
+    /// As `bmm`, but with a `SparseBMMNode` keeping only the weights of
+    /// absolute value above `threshold` (cf. `SparseBMMNode::from_dense`)
+    pub fn sparse_bmm(
+        self,
+        weights: &QArray<ST>,
+        bias: QArray<LT>,
+        threshold: ST,
+        weight_info: QInfo<ST>,
+        output_info: QInfo<ST>,
+    ) -> Self {
+        let node = Node::SparseBMM(SparseBMMNode::from_dense(
+            weights,
+            bias,
+            self.zero_point,
+            threshold,
+        ));
+        self.push_bmm(node, weight_info, output_info)
+    }
+
+    /// Applies ReLU to the current output
+    pub fn relu(mut self) -> Self {
+        let num_units = self.shape.iter().product();
+
+        self.nodes
+            .push(Node::ReLU(ReLUNode::new(num_units, self.zero_point)));
+        self
+    }
+
+    /// Builds the model, checking its consistency (cf. `Model::try_new`)
+    pub fn build(self) -> Result<Model<ST, LT>, ModelError> {
+        Model::try_new(self.input_shape, self.nodes)
+    }
+
+    // Adds a (sparse or dense) BMM node together with its requantisation
+    fn push_bmm(
+        mut self,
+        node: Node<ST, LT>,
+        weight_info: QInfo<ST>,
+        output_info: QInfo<ST>,
+    ) -> Self {
+        let shape = node.shape();
+
+        let requantise = RequantiseBMMNode::new(
+            shape.iter().product(),
+            self.scale,
+            self.zero_point,
+            weight_info.scale,
+            weight_info.zero_point,
+            output_info.scale,
+            output_info.zero_point,
+        );
+
+        self.nodes.push(node);
+        self.nodes.push(Node::RequantiseBMM(requantise));
+
+        self.shape = shape;
+        self.scale = output_info.scale;
+        self.zero_point = output_info.zero_point;
+
+        self
+    }
+}
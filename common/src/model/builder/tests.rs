@@ -0,0 +1,130 @@
+use super::*;
+
+const S_I: f32 = 0.004;
+const Z_I: i8 = -128;
+const S_1_W: f32 = 0.007;
+const S_1_O: f32 = 0.06;
+const Z_1_O: i8 = -100;
+const S_2_W: f32 = 0.01;
+const S_2_O: f32 = 0.2;
+const Z_2_O: i8 = 31;
+
+fn w1() -> QArray<i8> {
+    QArray::new((0..24).map(|x| 3 * x - 36).collect(), vec![6, 4])
+}
+
+fn b1() -> QArray<i32> {
+    QArray::new(vec![100, -20, 0, 7], vec![4])
+}
+
+fn w2() -> QArray<i8> {
+    QArray::new(vec![0, 5, -1, 0, 0, 0, 9, -7], vec![4, 2])
+}
+
+fn b2() -> QArray<i32> {
+    QArray::new(vec![-3, 12], vec![2])
+}
+
+fn built_model() -> Result<Model<i8, i32>, ModelError> {
+    ModelBuilder::new(vec![2, 3], QInfo::new(S_I, Z_I))
+        .flatten()
+        .bmm(w1(), b1(), QInfo::new(S_1_W, 0), QInfo::new(S_1_O, Z_1_O))
+        .relu()
+        .sparse_bmm(
+            &w2(),
+            b2(),
+            0,
+            QInfo::new(S_2_W, 0),
+            QInfo::new(S_2_O, Z_2_O),
+        )
+        .build()
+}
+
+#[test]
+fn test_builder_matches_manual_model() {
+    let manual = Model::new(
+        vec![2, 3],
+        vec![
+            Node::Reshape(ReshapeNode::new(vec![2, 3], vec![6])),
+            Node::BMM(BMMNode::new(w1(), b1(), Z_I)),
+            Node::RequantiseBMM(RequantiseBMMNode::new(4, S_I, Z_I, S_1_W, 0, S_1_O, Z_1_O)),
+            Node::ReLU(ReLUNode::new(4, Z_1_O)),
+            Node::SparseBMM(SparseBMMNode::from_dense(&w2(), b2(), Z_1_O, 0)),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                2, S_1_O, Z_1_O, S_2_W, 0, S_2_O, Z_2_O,
+            )),
+        ],
+    );
+
+    let built = built_model().unwrap();
+
+    assert_eq!(built.input_shape, vec![2, 3]);
+    assert_eq!(built.output_shape, vec![2]);
+    assert_eq!(built.content_hash(), manual.content_hash());
+
+    let input = QArray::new(vec![-128, 0, 127, 5, -7, 64], vec![2, 3]);
+    assert_eq!(built.evaluate(input.clone()), manual.evaluate(input));
+}
+
+#[test]
+fn test_quantisation_chaining() {
+    let model = built_model().unwrap();
+
+    match &model.nodes[2] {
+        Node::RequantiseBMM(r) => {
+            assert_eq!(r.q_info.input_info.scale, S_I);
+            assert_eq!(r.q_info.input_info.zero_point, Z_I);
+        }
+        _ => panic!("Expected a requantisation node"),
+    }
+
+    match &model.nodes[3] {
+        Node::ReLU(r) => assert_eq!(r.zero_point, Z_1_O),
+        _ => panic!("Expected a ReLU node"),
+    }
+
+    match &model.nodes[4] {
+        Node::SparseBMM(fc) => assert_eq!(fc.input_zero_point, Z_1_O),
+        _ => panic!("Expected a sparse BMM node"),
+    }
+}
+
+#[test]
+fn test_builder_errors() {
+    // BMM nodes receive one-dimensional inputs
+    let unflattened = ModelBuilder::new(vec![2, 3], QInfo::new(S_I, Z_I))
+        .bmm(w1(), b1(), QInfo::new(S_1_W, 0), QInfo::new(S_1_O, Z_1_O))
+        .build();
+
+    assert_eq!(
+        unflattened.err(),
+        Some(ModelError::ShapeMismatch {
+            node: 1,
+            expected: vec![6],
+            found: vec![2, 3],
+        })
+    );
+
+    // The second weight matrix does not have as many rows as the first one
+    // has columns
+    let mismatched = ModelBuilder::new(vec![6], QInfo::new(S_I, Z_I))
+        .bmm(w1(), b1(), QInfo::new(S_1_W, 0), QInfo::new(S_1_O, Z_1_O))
+        .bmm(w1(), b1(), QInfo::new(S_1_W, 0), QInfo::new(S_1_O, Z_1_O))
+        .build();
+
+    assert_eq!(
+        mismatched.err(),
+        Some(ModelError::ShapeMismatch {
+            node: 3,
+            expected: vec![6],
+            found: vec![4],
+        })
+    );
+
+    assert_eq!(
+        ModelBuilder::<i8, i32>::new(vec![6], QInfo::new(S_I, Z_I))
+            .build()
+            .err(),
+        Some(ModelError::NoNodes)
+    );
+}
@@ -0,0 +1,359 @@
+// Security configuration of inference proofs. A ProofConfig fixes the target
+// security level, from which the parameters of the polynomial commitment
+// schemes and the sponge are derived and against which the field is checked.
+// It is absorbed into the sponge at the start of every proof, so that a proof
+// produced under a configuration is only accepted under the same one.
+
+use ark_crypto_primitives::sponge::{
+    poseidon::{PoseidonConfig, PoseidonSponge},
+    Absorb, CryptographicSponge,
+};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+use ark_std::{
+    any::{type_name, Any},
+    fmt, log2,
+    rand::RngCore,
+};
+
+use crate::model::{OpeningClaim, Poly};
+use crate::utils::sponge::{try_poseidon_config, POSEIDON_SECURITY_LEVEL};
+
+#[cfg(test)]
+mod tests;
+
+/// Security level, in bits, of `ProofConfig::default`
+pub const DEFAULT_SECURITY_BITS: usize = 128;
+
+/// Inverse of the rate of the code used by Ligero unless configured
+/// otherwise, which is the one chosen by its own setup
+pub const DEFAULT_LIGERO_RHO_INV: usize = 4;
+
+// Largest degree of the sumcheck polynomials in any node proof (e.g. eq * h *
+// index in the lookups of sparse nodes)
+const MAX_SUMCHECK_DEGREE: usize = 3;
+
+// Bound on the number of variables of the sumchecks, which is only used to
+// bound their soundness error
+const MAX_SUMCHECK_NUM_VARS: usize = 64;
+
+/// Configuration of inference proofs, determined by a target security level
+/// (in bits). Keys are set up for it (cf. `Model::setup_keys`), the prover
+/// and verifier keys carry it and it is bound to the transcript of every
+/// proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofConfig {
+    security_bits: usize,
+    ligero_rho_inv: usize,
+}
+
+/// Reasons for which a choice of field, sponge or PCS does not meet a
+/// `ProofConfig`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofConfigError {
+    /// The field is too small for the sumchecks to reach the security level
+    FieldTooSmall {
+        field_bits: usize,
+        required_bits: usize,
+    },
+    /// There are no supported sponge parameters for the field
+    UnsupportedSpongeField { field_bits: usize },
+    /// The sponge does not reach the security level
+    SpongeTooWeak {
+        sponge_bits: usize,
+        required_bits: usize,
+    },
+    /// The sponge is not the Poseidon sponge of the configuration
+    UnsupportedSponge { sponge: String },
+    /// The PCS does not reach the security level
+    PCSTooWeak {
+        pcs: String,
+        pcs_bits: usize,
+        required_bits: usize,
+    },
+    /// The PCS verifier key was not set up for the configuration
+    PCSKeyMismatch { pcs: String },
+    /// The PCS setup failed
+    PCSSetup(String),
+}
+
+impl fmt::Display for ProofConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofConfigError::FieldTooSmall {
+                field_bits,
+                required_bits,
+            } => write!(
+                f,
+                "Field of {} bits is too small for the required {} bits",
+                field_bits, required_bits
+            ),
+            ProofConfigError::UnsupportedSpongeField { field_bits } => write!(
+                f,
+                "No sponge parameters are supported for the field of {} bits",
+                field_bits
+            ),
+            ProofConfigError::SpongeTooWeak {
+                sponge_bits,
+                required_bits,
+            } => write!(
+                f,
+                "Sponge provides {} bits of security, but {} are required",
+                sponge_bits, required_bits
+            ),
+            ProofConfigError::PCSTooWeak {
+                pcs,
+                pcs_bits,
+                required_bits,
+            } => write!(
+                f,
+                "PCS {} provides {} bits of security, but {} are required",
+                pcs, pcs_bits, required_bits
+            ),
+            ProofConfigError::UnsupportedSponge { sponge } => write!(
+                f,
+                "Sponge {} does not have the parameters of the configuration",
+                sponge
+            ),
+            ProofConfigError::PCSKeyMismatch { pcs } => write!(
+                f,
+                "Verifier key of PCS {} was not set up for the configuration",
+                pcs
+            ),
+            ProofConfigError::PCSSetup(e) => write!(f, "Unable to set up PCS: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProofConfigError {}
+
+impl Default for ProofConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_SECURITY_BITS)
+    }
+}
+
+impl ProofConfig {
+    pub fn new(security_bits: usize) -> Self {
+        Self {
+            security_bits,
+            ligero_rho_inv: DEFAULT_LIGERO_RHO_INV,
+        }
+    }
+
+    /// Sets the inverse of the rate of the code used by Ligero. Larger values
+    /// mean that fewer columns are opened (for the same security level) at
+    /// the cost of a longer encoding.
+    pub fn with_ligero_rho_inv(mut self, rho_inv: usize) -> Self {
+        assert!(rho_inv >= 2, "The Ligero code rate must be at most 1/2");
+
+        self.ligero_rho_inv = rho_inv;
+        self
+    }
+
+    pub fn security_bits(&self) -> usize {
+        self.security_bits
+    }
+
+    pub fn ligero_rho_inv(&self) -> usize {
+        self.ligero_rho_inv
+    }
+
+    /// Minimum number of bits of the field. Each sumcheck round errs with
+    /// probability at most MAX_SUMCHECK_DEGREE / |F|, which must remain below
+    /// 2^(-security_bits) over all rounds.
+    pub fn required_field_bits(&self) -> usize {
+        self.security_bits + log2(MAX_SUMCHECK_DEGREE * MAX_SUMCHECK_NUM_VARS) as usize
+    }
+
+    /// Checks that the field is large enough for the sumchecks (and the
+    /// random linear combinations of claims) to reach the security level
+    pub fn check_field<F: PrimeField>(&self) -> Result<(), ProofConfigError> {
+        // |F| > 2^(MODULUS_BIT_SIZE - 1)
+        let field_bits = F::MODULUS_BIT_SIZE as usize - 1;
+
+        if field_bits < self.required_field_bits() {
+            return Err(ProofConfigError::FieldTooSmall {
+                field_bits,
+                required_bits: self.required_field_bits(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the PCS reaches the security level
+    pub fn check_pcs<F, S, PCS>(&self) -> Result<(), ProofConfigError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
+    {
+        if PCS::security_bits() < self.security_bits {
+            return Err(ProofConfigError::PCSTooWeak {
+                pcs: type_name::<PCS>().to_string(),
+                pcs_bits: PCS::security_bits(),
+                required_bits: self.security_bits,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks the field and the PCSs of node values and node parameters
+    /// against the configuration
+    pub fn check<F, S, PCS, WPCS>(&self) -> Result<(), ProofConfigError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
+        WPCS: SecurePCS<F, S>,
+    {
+        self.check_field::<F>()?;
+        self.check_pcs::<F, S, PCS>()?;
+        self.check_pcs::<F, S, WPCS>()
+    }
+
+    /// Checks that `vk` was set up for the configuration (cf.
+    /// `SecurePCS::check_verifier_key`)
+    pub fn check_verifier_key<F, S, PCS>(
+        &self,
+        vk: &PCS::VerifierKey,
+    ) -> Result<(), ProofConfigError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
+    {
+        if !PCS::check_verifier_key(self, vk) {
+            return Err(ProofConfigError::PCSKeyMismatch {
+                pcs: type_name::<PCS>().to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Poseidon parameters for F meeting the security level (cf.
+    /// `poseidon_config`)
+    pub fn poseidon_config<F: PrimeField>(&self) -> Result<PoseidonConfig<F>, ProofConfigError> {
+        if POSEIDON_SECURITY_LEVEL < self.security_bits {
+            return Err(ProofConfigError::SpongeTooWeak {
+                sponge_bits: POSEIDON_SECURITY_LEVEL,
+                required_bits: self.security_bits,
+            });
+        }
+
+        try_poseidon_config().ok_or(ProofConfigError::UnsupportedSpongeField {
+            field_bits: F::MODULUS_BIT_SIZE as usize,
+        })
+    }
+
+    /// Poseidon sponge over F meeting the security level
+    pub fn poseidon_sponge<F: PrimeField>(&self) -> Result<PoseidonSponge<F>, ProofConfigError> {
+        Ok(PoseidonSponge::new(&self.poseidon_config()?))
+    }
+
+    /// Checks that `sponge` is a Poseidon sponge over F with the parameters
+    /// of `poseidon_config`. Its state is not checked: having absorbed some
+    /// elements already (e.g. for domain separation) only changes the
+    /// transcript, which the prover must then share. Any other sponge is
+    /// rejected, as its security level is unknown.
+    pub fn check_sponge<F, S>(&self, sponge: &S) -> Result<(), ProofConfigError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge + 'static,
+    {
+        let unsupported = || ProofConfigError::UnsupportedSponge {
+            sponge: type_name::<S>().to_string(),
+        };
+
+        let params = &(sponge as &dyn Any)
+            .downcast_ref::<PoseidonSponge<F>>()
+            .ok_or_else(unsupported)?
+            .parameters;
+        let expected = self.poseidon_config::<F>()?;
+
+        if params.full_rounds != expected.full_rounds
+            || params.partial_rounds != expected.partial_rounds
+            || params.alpha != expected.alpha
+            || params.rate != expected.rate
+            || params.capacity != expected.capacity
+            || params.ark != expected.ark
+            || params.mds != expected.mds
+        {
+            return Err(unsupported());
+        }
+
+        Ok(())
+    }
+}
+
+impl Absorb for ProofConfig {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.security_bits.to_sponge_bytes(dest);
+        self.ligero_rho_inv.to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.security_bits.to_sponge_field_elements(dest);
+        self.ligero_rho_inv.to_sponge_field_elements(dest);
+    }
+}
+
+/// Polynomial commitment schemes whose security level is known, so that they
+/// can be checked against a `ProofConfig`, and whose public parameters can be
+/// derived from one
+pub trait SecurePCS<F: PrimeField, S: CryptographicSponge>:
+    PolynomialCommitment<F, Poly<F>, S>
+{
+    /// Largest security level, in bits, the scheme can be set up for
+    fn security_bits() -> usize;
+
+    /// Public parameters supporting MLEs in up to `num_vars` variables at the
+    /// security level of `config`. By default, these are those of `setup`,
+    /// which is only correct for schemes whose parameters do not depend on
+    /// the security level beyond the choice of group or field.
+    fn setup_for_config<R: RngCore>(
+        _config: &ProofConfig,
+        num_vars: usize,
+        rng: &mut R,
+    ) -> Result<Self::UniversalParams, Self::Error> {
+        Self::setup(1, Some(num_vars), rng)
+    }
+
+    /// Committer and verifier keys for MLEs in up to `num_vars` variables,
+    /// which must not exceed the number supported by `pp`. By default, these
+    /// are those of `trim` and support as many variables as `pp` does, which
+    /// is correct (if wasteful) for any scheme accepting polynomials smaller
+    /// than its keys.
+    fn trim_to_num_vars(
+        pp: &Self::UniversalParams,
+        _num_vars: usize,
+    ) -> Result<(Self::CommitterKey, Self::VerifierKey), Self::Error> {
+        // The supported degree and the enforced degree bounds are meaningless
+        // for the multilinear PCSs and ignored by all of them. A hiding bound
+        // of 0 means that commitments are not hiding, which does not affect
+        // soundness (cf. the TODOs on hiding).
+        Self::trim(pp, 0, 0, None)
+    }
+
+    /// Whether the verifier key `vk` was set up for `config`. By default,
+    /// any key is, which is only correct for schemes whose keys do not
+    /// depend on the security level (cf. `setup_for_config`).
+    fn check_verifier_key(_config: &ProofConfig, _vk: &Self::VerifierKey) -> bool {
+        true
+    }
+
+    /// Checks a list of opening claims (cf. `OpeningClaim::check`), returning
+    /// whether all of them hold. By default, each claim is checked on its
+    /// own. Schemes whose commitments and opening proofs are homomorphic
+    /// combine the claims with random coefficients instead, which is cheaper
+    /// but does not tell which claim failed.
+    fn batch_check(vk: &Self::VerifierKey, claims: &[OpeningClaim<F, S, Self>]) -> bool
+    where
+        F: Absorb,
+    {
+        claims.iter().all(|claim| claim.check(vk))
+    }
+}
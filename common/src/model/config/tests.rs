@@ -0,0 +1,172 @@
+use ark_bn254::{Bn254, Fq, Fr, G1Affine};
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+use ark_std::test_rng;
+
+use crate::{poseidon_config, poseidon_sponge, Brakedown, Hyrax, Ligero, MultilinearKZG};
+
+use super::*;
+
+type S = PoseidonSponge<Fr>;
+
+#[test]
+fn test_default_config() {
+    let config = ProofConfig::default();
+
+    assert_eq!(config.security_bits(), DEFAULT_SECURITY_BITS);
+    assert_eq!(config.ligero_rho_inv(), DEFAULT_LIGERO_RHO_INV);
+}
+
+#[test]
+fn test_check_field() {
+    assert_eq!(ProofConfig::new(128).check_field::<Fr>(), Ok(()));
+
+    assert_eq!(
+        ProofConfig::new(250).check_field::<Fr>(),
+        Err(ProofConfigError::FieldTooSmall {
+            field_bits: 253,
+            required_bits: 258
+        })
+    );
+}
+
+#[test]
+fn test_check_linear_code_pcs() {
+    assert_eq!(
+        ProofConfig::new(128).check::<Fr, S, Ligero<Fr>, Brakedown<Fr>>(),
+        Ok(())
+    );
+
+    assert!(matches!(
+        ProofConfig::new(192).check_pcs::<Fr, S, Ligero<Fr>>(),
+        Err(ProofConfigError::PCSTooWeak { pcs_bits: 128, .. })
+    ));
+}
+
+#[test]
+fn test_check_curve_pcs() {
+    // Pollard's rho in the 254-bit group of BN254
+    assert_eq!(<Hyrax<G1Affine> as SecurePCS<Fr, S>>::security_bits(), 127);
+
+    // The tower number field sieve in the 3048-bit target field of BN254
+    assert_eq!(
+        <MultilinearKZG<Bn254> as SecurePCS<Fr, S>>::security_bits(),
+        100
+    );
+
+    assert!(matches!(
+        ProofConfig::new(128).check_pcs::<Fr, S, MultilinearKZG<Bn254>>(),
+        Err(ProofConfigError::PCSTooWeak {
+            pcs_bits: 100,
+            required_bits: 128,
+            ..
+        })
+    ));
+
+    assert_eq!(
+        ProofConfig::new(100).check::<Fr, S, Ligero<Fr>, MultilinearKZG<Bn254>>(),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_poseidon_config() {
+    let config = ProofConfig::new(128).poseidon_config::<Fr>().unwrap();
+
+    assert_eq!(config.ark, poseidon_config::<Fr>().ark);
+
+    assert_eq!(
+        ProofConfig::new(256).poseidon_config::<Fr>().unwrap_err(),
+        ProofConfigError::SpongeTooWeak {
+            sponge_bits: 128,
+            required_bits: 256
+        }
+    );
+}
+
+#[test]
+fn test_check_sponge() {
+    let config = ProofConfig::new(128);
+
+    assert_eq!(
+        config.check_sponge::<Fr, S>(&poseidon_sponge::<Fr>()),
+        Ok(())
+    );
+
+    // The state is not checked
+    let mut sponge = poseidon_sponge::<Fr>();
+    sponge.absorb(&Fr::from(1u64));
+    assert_eq!(config.check_sponge::<Fr, S>(&sponge), Ok(()));
+
+    // Unlike the parameters
+    let mut params = poseidon_config::<Fr>();
+    params.partial_rounds -= 1;
+    assert!(matches!(
+        config.check_sponge::<Fr, S>(&PoseidonSponge::new(&params)),
+        Err(ProofConfigError::UnsupportedSponge { .. })
+    ));
+
+    // And the sponge must be over the field of the proofs
+    assert!(matches!(
+        config.check_sponge::<Fr, PoseidonSponge<Fq>>(&poseidon_sponge::<Fq>()),
+        Err(ProofConfigError::UnsupportedSponge { .. })
+    ));
+}
+
+#[test]
+fn test_check_verifier_key() {
+    fn ligero_vk(
+        config: &ProofConfig,
+    ) -> <Ligero<Fr> as PolynomialCommitment<Fr, Poly<Fr>, S>>::VerifierKey {
+        let pp =
+            <Ligero<Fr> as SecurePCS<Fr, S>>::setup_for_config(config, 4, &mut test_rng()).unwrap();
+        <Ligero<Fr> as SecurePCS<Fr, S>>::trim_to_num_vars(&pp, 4)
+            .unwrap()
+            .1
+    }
+
+    let config = ProofConfig::new(128);
+
+    assert_eq!(
+        config.check_verifier_key::<Fr, S, Ligero<Fr>>(&ligero_vk(&config)),
+        Ok(())
+    );
+
+    // Keys for a lower level than the configuration's are rejected, unlike
+    // those for a higher one
+    assert!(matches!(
+        config.check_verifier_key::<Fr, S, Ligero<Fr>>(&ligero_vk(&ProofConfig::new(100))),
+        Err(ProofConfigError::PCSKeyMismatch { .. })
+    ));
+    assert_eq!(
+        ProofConfig::new(100).check_verifier_key::<Fr, S, Ligero<Fr>>(&ligero_vk(&config)),
+        Ok(())
+    );
+
+    // As are keys for another code rate
+    assert!(matches!(
+        config.check_verifier_key::<Fr, S, Ligero<Fr>>(&ligero_vk(&config.with_ligero_rho_inv(8))),
+        Err(ProofConfigError::PCSKeyMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_config_binds_transcript() {
+    let squeeze = |config: ProofConfig| {
+        let mut sponge = poseidon_sponge::<Fr>();
+        sponge.absorb(&config);
+        sponge.squeeze_field_elements::<Fr>(1)[0]
+    };
+
+    assert_eq!(
+        squeeze(ProofConfig::new(128)),
+        squeeze(ProofConfig::new(128))
+    );
+    assert_ne!(
+        squeeze(ProofConfig::new(128)),
+        squeeze(ProofConfig::new(100))
+    );
+    assert_ne!(
+        squeeze(ProofConfig::new(128)),
+        squeeze(ProofConfig::new(128).with_ligero_rho_inv(8))
+    );
+}
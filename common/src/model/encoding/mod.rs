@@ -0,0 +1,276 @@
+// Encoding of quantised values into the field of the proof system, and static
+// analysis of the values a model can compute. A quantised integer x is encoded
+// as its residue modulo the characteristic p of the field, i.e. negative
+// values as p - |x|. This is the only encoding under which the additions and
+// multiplications performed natively by the nodes are those of the field, but
+// it only reflects them faithfully as long as no value wraps around: the
+// proofs are sound for the integer computation if every value lies in the
+// range [-(p - 1) / 2, (p - 1) / 2] on which the encoding can be inverted.
+// Model::check_bounds establishes this (and that the large type does not
+// overflow either) for all valid inputs.
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::{any::type_name, fmt};
+
+use crate::model::qarray::InnerType;
+use crate::model::Model;
+
+#[cfg(test)]
+mod tests;
+
+/// Integer types of quantised values, which are encoded into prime fields
+/// through their integer value (cf. `FieldEncoding`)
+pub trait QuantisedInteger: InnerType {
+    fn to_i128(self) -> i128;
+
+    /// Value of the type equal to `x`, if any
+    fn try_from_i128(x: i128) -> Option<Self>;
+}
+
+impl<T> QuantisedInteger for T
+where
+    T: InnerType + Into<i128> + TryFrom<i128>,
+{
+    fn to_i128(self) -> i128 {
+        self.into()
+    }
+
+    fn try_from_i128(x: i128) -> Option<Self> {
+        Self::try_from(x).ok()
+    }
+}
+
+/// Encoding of the quantised integer type T into the field, used by the
+/// prover and the verifier for every value, parameter and zero point. Integers
+/// are mapped to their residue modulo the characteristic p, and field elements
+/// are decoded to the integer in [-(p - 1) / 2, (p - 1) / 2] they represent.
+pub trait FieldEncoding<T>: PrimeField {
+    fn encode(x: T) -> Self;
+
+    /// Integer of type T represented by the field element, if any
+    fn decode(&self) -> Option<T>;
+}
+
+impl<F, T> FieldEncoding<T> for F
+where
+    F: PrimeField,
+    T: QuantisedInteger,
+{
+    fn encode(x: T) -> Self {
+        let x = x.to_i128();
+        let magnitude = F::from(x.unsigned_abs());
+
+        if x < 0 {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    fn decode(&self) -> Option<T> {
+        let x = if self.into_bigint() <= F::MODULUS_MINUS_ONE_DIV_TWO {
+            i128::try_from(to_u128(self.into_bigint())?).ok()?
+        } else {
+            -i128::try_from(to_u128((-*self).into_bigint())?).ok()?
+        };
+
+        T::try_from_i128(x)
+    }
+}
+
+// Value of a big integer, if it fits in a u128
+fn to_u128<B: BigInteger>(x: B) -> Option<u128> {
+    let limbs = x.as_ref();
+
+    if limbs.iter().skip(2).any(|limb| *limb != 0) {
+        return None;
+    }
+
+    Some(limbs[0] as u128 + ((*limbs.get(1).unwrap_or(&0) as u128) << 64))
+}
+
+/// Largest absolute value of the integers the encoding into F can represent
+/// unambiguously, i.e. (p - 1) / 2 (capped at the largest i128)
+pub fn max_encodable<F: PrimeField>() -> i128 {
+    to_u128(F::MODULUS_MINUS_ONE_DIV_TWO)
+        .and_then(|half| i128::try_from(half).ok())
+        .unwrap_or(i128::MAX)
+}
+
+/// Closed interval of integers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueRange {
+    pub min: i128,
+    pub max: i128,
+}
+
+impl ValueRange {
+    pub fn new(min: i128, max: i128) -> Self {
+        assert!(min <= max, "Empty range [{}, {}]", min, max);
+
+        Self { min, max }
+    }
+
+    /// Range of all values of the type T
+    pub fn of<T: QuantisedInteger>() -> Self {
+        Self::new(T::MIN.to_i128(), T::MAX.to_i128())
+    }
+
+    pub fn contains(&self, other: &ValueRange) -> bool {
+        self.min <= other.min && other.max <= self.max
+    }
+
+    /// Smallest range containing both ranges
+    pub fn hull(&self, other: &ValueRange) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Range of x * c for x in the range
+    pub(crate) fn scale(&self, c: i128) -> Self {
+        Self::new(
+            (self.min * c).min(self.max * c),
+            (self.min * c).max(self.max * c),
+        )
+    }
+
+    pub(crate) fn shift(&self, c: i128) -> Self {
+        Self::new(self.min + c, self.max + c)
+    }
+}
+
+impl fmt::Display for ValueRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+/// Bounds on the values computed by a node over all inputs in a given range
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeBounds {
+    /// Range of the output values
+    pub output: ValueRange,
+
+    /// Range of every value computed by the node, both natively and by the
+    /// prover: the inputs and outputs, but also e.g. the partial sums of the
+    /// accumulators of BMM nodes or the lookup counts of sparse ones
+    pub intermediate: ValueRange,
+}
+
+impl NodeBounds {
+    /// Bounds of a node which computes no values other than its output
+    pub(crate) fn output_only(output: ValueRange) -> Self {
+        Self {
+            output,
+            intermediate: output,
+        }
+    }
+}
+
+// Range of the sum of values in the given ranges, together with one
+// containing all of its partial sums (in any order)
+pub(crate) fn accumulate(terms: impl IntoIterator<Item = ValueRange>) -> (ValueRange, ValueRange) {
+    let (mut sum, mut partial) = (ValueRange::new(0, 0), ValueRange::new(0, 0));
+
+    for term in terms {
+        sum = ValueRange::new(sum.min + term.min, sum.max + term.max);
+        partial = ValueRange::new(partial.min + term.min.min(0), partial.max + term.max.max(0));
+    }
+
+    (sum, partial)
+}
+
+/// Reasons for which a model may compute a value that is not represented
+/// faithfully. Nodes are indexed as in `Model::value_bounds`, i.e. the model
+/// input being the 0-th.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OverflowError {
+    /// Some value of the node may not fit in the large type
+    LargeType {
+        node: usize,
+        range: ValueRange,
+        type_name: String,
+    },
+    /// Some value of the node may exceed `max_encodable` in absolute value,
+    /// so that its encoding into the field is ambiguous
+    Field {
+        node: usize,
+        range: ValueRange,
+        field_bits: u32,
+    },
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverflowError::LargeType {
+                node,
+                range,
+                type_name,
+            } => write!(
+                f,
+                "Values of node {} range over {}, which does not fit in {}",
+                node, range, type_name
+            ),
+            OverflowError::Field {
+                node,
+                range,
+                field_bits,
+            } => write!(
+                f,
+                "Values of node {} range over {}, which cannot be encoded into a field of {} bits",
+                node, range, field_bits
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+impl<ST, LT> Model<ST, LT>
+where
+    ST: QuantisedInteger + TryFrom<LT>,
+    LT: QuantisedInteger + From<ST>,
+{
+    /// Bounds on the values of each node (the model input, whose values are
+    /// those of ST, being the 0-th) over all possible inputs
+    pub fn value_bounds(&self) -> Vec<NodeBounds> {
+        let mut bounds = vec![NodeBounds::output_only(ValueRange::of::<ST>())];
+
+        for node in &self.nodes {
+            bounds.push(node.bounds(bounds.last().unwrap().output));
+        }
+
+        bounds
+    }
+
+    /// Checks that, for any input, every value computed by the model (and
+    /// its proof) fits in LT and is encoded unambiguously into F, i.e. that
+    /// the field relations checked by the verifier imply the integer ones.
+    /// Returns the bounds of `value_bounds` on success.
+    pub fn check_bounds<F: PrimeField>(&self) -> Result<Vec<NodeBounds>, OverflowError> {
+        let bounds = self.value_bounds();
+
+        let lt_range = ValueRange::of::<LT>();
+        let field_range = ValueRange::new(-max_encodable::<F>(), max_encodable::<F>());
+
+        for (node, b) in bounds.iter().enumerate() {
+            if !lt_range.contains(&b.intermediate) {
+                return Err(OverflowError::LargeType {
+                    node,
+                    range: b.intermediate,
+                    type_name: type_name::<LT>().to_string(),
+                });
+            }
+
+            if !field_range.contains(&b.intermediate) {
+                return Err(OverflowError::Field {
+                    node,
+                    range: b.intermediate,
+                    field_bits: F::MODULUS_BIT_SIZE,
+                });
+            }
+        }
+
+        Ok(bounds)
+    }
+}
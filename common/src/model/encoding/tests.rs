@@ -0,0 +1,250 @@
+// The derive below expands to trait impls inside an anonymous const, which
+// rustc reports as non-local definitions
+#![allow(non_local_definitions)]
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_ff::{Field, Fp64, MontBackend, MontConfig};
+
+use crate::model::nodes::{bmm::BMMNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode, Node};
+use crate::model::qarray::{QArray, QTypeArray};
+use crate::two_layer_perceptron_mnist::{build_two_layer_perceptron_mnist, parameters::Z_1_O};
+use crate::Ligero;
+
+use super::*;
+
+// Field small enough for the values of simple models to overflow it
+#[derive(MontConfig)]
+#[modulus = "65537"]
+#[generator = "3"]
+struct F65537Config;
+type F65537 = Fp64<MontBackend<F65537Config, 1>>;
+
+// 64-bit field (of order 2^64 - 2^32 + 1), which holds the i32 values of
+// quantised models
+#[derive(MontConfig)]
+#[modulus = "18446744069414584321"]
+#[generator = "7"]
+struct F64Config;
+type F64 = Fp64<MontBackend<F64Config, 1>>;
+
+// Skips the overflow check of BMMNode::new, which relies on the same bounds
+fn single_bmm(weights: Vec<i8>, bias: i32, input_zero_point: i8) -> Model<i8, i32> {
+    let num_rows = weights.len();
+
+    Model::new(
+        vec![num_rows],
+        vec![
+            Node::BMM(BMMNode::new_unchecked(
+                QArray::new(weights, vec![num_rows, 1]),
+                QArray::new(vec![bias], vec![1]),
+                input_zero_point,
+            )),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                1,
+                1.0,
+                input_zero_point,
+                1.0,
+                0,
+                1.0,
+                0,
+            )),
+        ],
+    )
+}
+
+fn range_of<T: QuantisedInteger>(values: &[T]) -> ValueRange {
+    let values: Vec<i128> = values.iter().map(|x| x.to_i128()).collect();
+
+    ValueRange::new(*values.iter().min().unwrap(), *values.iter().max().unwrap())
+}
+
+#[test]
+fn test_encoding_round_trip() {
+    for x in [i8::MIN, -1, 0, 1, i8::MAX] {
+        assert_eq!(<Fr as FieldEncoding<i8>>::decode(&Fr::encode(x)), Some(x));
+        assert_eq!(<F64 as FieldEncoding<i8>>::decode(&F64::encode(x)), Some(x));
+    }
+
+    for x in [i32::MIN, -784 * 127 * 128, -1, 0, 1, i32::MAX] {
+        assert_eq!(<Fr as FieldEncoding<i32>>::decode(&Fr::encode(x)), Some(x));
+        assert_eq!(
+            <F64 as FieldEncoding<i32>>::decode(&F64::encode(x)),
+            Some(x)
+        );
+    }
+
+    for x in [0, 1, u8::MAX] {
+        assert_eq!(<Fr as FieldEncoding<u8>>::decode(&Fr::encode(x)), Some(x));
+    }
+}
+
+#[test]
+fn test_encoding_is_residue() {
+    assert_eq!(<Fr as FieldEncoding<i32>>::encode(-5), -Fr::from(5u64));
+    assert_eq!(
+        <F64 as FieldEncoding<i8>>::encode(-128),
+        F64::from(F64::MODULUS.0[0] - 128)
+    );
+
+    // Field operations on encodings agree with integer ones absent overflows
+    let (a, b): (i32, i32) = (-784 * 127, 128);
+    assert_eq!(
+        F64::encode(a) * F64::encode(b),
+        <F64 as FieldEncoding<i32>>::encode(a * b)
+    );
+}
+
+#[test]
+fn test_decoding_out_of_range() {
+    // Encodings of values which do not fit in the type
+    assert_eq!(
+        <Fr as FieldEncoding<i8>>::decode(&Fr::encode(i8::MAX as i32 + 1)),
+        None
+    );
+    assert_eq!(<Fr as FieldEncoding<u8>>::decode(&Fr::encode(-1i8)), None);
+
+    // Elements far from 0 do not represent any i32
+    assert_eq!(
+        <Fr as FieldEncoding<i32>>::decode(&Fr::from(1u64 << 40).square()),
+        None
+    );
+    assert_eq!(
+        <Fr as FieldEncoding<i32>>::decode(&-Fr::from(1u64 << 40).square()),
+        None
+    );
+}
+
+#[test]
+fn test_max_encodable() {
+    assert_eq!(max_encodable::<F64>(), (1 << 63) - (1 << 31));
+    assert_eq!(max_encodable::<F65537>(), 1 << 15);
+
+    // (p - 1) / 2 does not fit in an i128 for BN254
+    assert_eq!(max_encodable::<Fr>(), i128::MAX);
+}
+
+#[test]
+fn test_accumulate() {
+    let (sum, partial_sums) = accumulate([
+        ValueRange::new(1, 2),
+        ValueRange::new(-10, -5),
+        ValueRange::new(3, 4),
+    ]);
+
+    assert_eq!(sum, ValueRange::new(-6, 1));
+    assert_eq!(partial_sums, ValueRange::new(-10, 6));
+}
+
+#[test]
+fn test_two_layer_perceptron_bounds() {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+
+    let bounds = model.check_bounds::<Fr>().unwrap();
+    assert_eq!(bounds, model.check_bounds::<F64>().unwrap());
+    assert_eq!(bounds.len(), 7);
+
+    // Requantised outputs lie within i8
+    for b in [bounds[3], bounds[4], bounds[6]] {
+        assert!(ValueRange::of::<i8>().contains(&b.output));
+    }
+
+    // ReLU outputs lie above the zero point
+    assert_eq!(bounds[4].output.min, Z_1_O as i128);
+
+    // Every value computed natively lies within the bounds
+    for i in 0..5 {
+        let input = QArray::new(
+            (0..784)
+                .map(|j| ((i * 53 + j * 7) % 256) as u8 as i8)
+                .collect(),
+            vec![28, 28],
+        );
+
+        let mut output = QTypeArray::S(input);
+
+        for (node, b) in model.nodes.iter().zip(bounds.iter().skip(1)) {
+            output = node.evaluate(&output);
+
+            let range = match &output {
+                QTypeArray::S(o) => range_of(o.values()),
+                QTypeArray::L(o) => range_of(o.values()),
+            };
+
+            assert!(b.output.contains(&range));
+            assert!(b.intermediate.contains(&range));
+        }
+    }
+}
+
+#[test]
+fn test_bmm_bounds() {
+    let bounds = single_bmm(vec![1, -2, 3], 10, -128).value_bounds();
+
+    // Shifted inputs range over [0, 255]
+    assert_eq!(
+        bounds[1].output,
+        ValueRange::new(10 - 2 * 255, 10 + 4 * 255)
+    );
+    assert_eq!(
+        bounds[1].intermediate,
+        ValueRange::new(-2 * 255, 10 + 4 * 255)
+    );
+}
+
+#[test]
+fn test_large_type_overflow() {
+    let model = single_bmm(vec![1], i32::MAX - 100, 0);
+
+    assert_eq!(
+        model.check_bounds::<Fr>(),
+        Err(OverflowError::LargeType {
+            node: 1,
+            range: ValueRange::new(-128, i32::MAX as i128 + 27),
+            type_name: "i32".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_field_overflow() {
+    let model = single_bmm(vec![127, 127, 127], 0, 0);
+
+    assert!(model.check_bounds::<Fr>().is_ok());
+    assert_eq!(
+        model.check_bounds::<F65537>(),
+        Err(OverflowError::Field {
+            node: 1,
+            range: ValueRange::new(-3 * 128 * 127, 3 * 127 * 127),
+            field_bits: 17,
+        })
+    );
+}
+
+#[test]
+fn test_relu_bounds() {
+    let node = ReLUNode::new(4, -5i8);
+
+    assert_eq!(
+        node.bounds(ValueRange::new(-100, 50)).output,
+        ValueRange::new(-5, 50)
+    );
+    assert_eq!(
+        node.bounds(ValueRange::new(-100, -10)).output,
+        ValueRange::new(-5, -5)
+    );
+}
+
+#[test]
+fn test_requantise_bounds() {
+    // Scale 1 / 4 and output zero point 3
+    let node = RequantiseBMMNode::<i8>::new(4, 1.0, 0, 0.25, 0, 1.0, 3);
+
+    let bounds = node.bounds(ValueRange::new(-40, 41));
+    assert_eq!(bounds.output, ValueRange::new(-7, 14));
+
+    // Outputs are clamped to the small type
+    let bounds = node.bounds(ValueRange::new(-10_000, 10_000));
+    assert_eq!(bounds.output, ValueRange::of::<i8>());
+    assert_eq!(bounds.intermediate, ValueRange::new(-10_000, 10_000));
+}
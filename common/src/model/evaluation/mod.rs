@@ -0,0 +1,168 @@
+// Native evaluation with overflow detection. Nodes evaluate natively in the
+// large type with its own arithmetic, which panics on overflow in debug
+// builds and wraps around silently in release ones, where the output then
+// disagrees with the field computation of the proof. The evaluation modes
+// below instead perform every operation of the accumulating nodes exactly
+// and report the output entries of the node for which some operation left
+// the range of the large type.
+
+use ark_std::{fmt, marker::PhantomData};
+
+use crate::model::encoding::{QuantisedInteger, ValueRange};
+use crate::model::qarray::{QArray, QTypeArray};
+use crate::model::Model;
+
+#[cfg(test)]
+mod tests;
+
+/// Behaviour of `Model::evaluate_with_mode` and
+/// `Model::padded_evaluate_with_mode` on accumulator overflows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// Fail on the first overflow
+    Checked,
+    /// Clamp every overflowing operation to the range of the large type and
+    /// report all overflows
+    Saturating,
+}
+
+/// Output entry `index` of node `node` overflowed the large type. Nodes are
+/// indexed as in `Model::value_bounds`, i.e. the model input being the 0-th.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccumulatorOverflow {
+    pub node: usize,
+    pub index: usize,
+}
+
+impl fmt::Display for AccumulatorOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Accumulator overflow in entry {} of node {}",
+            self.index, self.node
+        )
+    }
+}
+
+impl std::error::Error for AccumulatorOverflow {}
+
+/// Output of an evaluation with overflow detection, together with the
+/// overflows that were clamped (always empty in `OverflowMode::Checked`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation<ST> {
+    pub output: QArray<ST>,
+    pub overflows: Vec<AccumulatorOverflow>,
+}
+
+// Exact arithmetic on values of T, each result being clamped to the range of
+// T. Records whether any clamping took place.
+pub(crate) struct SaturatingArithmetic<T> {
+    range: ValueRange,
+    overflowed: bool,
+    _type: PhantomData<T>,
+}
+
+impl<T: QuantisedInteger> SaturatingArithmetic<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            range: ValueRange::of::<T>(),
+            overflowed: false,
+            _type: PhantomData,
+        }
+    }
+
+    fn clamp(&mut self, x: i128) -> i128 {
+        if !self.range.contains(&ValueRange::new(x, x)) {
+            self.overflowed = true;
+        }
+
+        x.clamp(self.range.min, self.range.max)
+    }
+
+    pub(crate) fn add(&mut self, a: i128, b: i128) -> i128 {
+        self.clamp(a + b)
+    }
+
+    pub(crate) fn mul(&mut self, a: i128, b: i128) -> i128 {
+        self.clamp(a * b)
+    }
+
+    pub(crate) fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+}
+
+impl<ST, LT> Model<ST, LT>
+where
+    ST: QuantisedInteger + TryFrom<LT>,
+    LT: QuantisedInteger + From<ST>,
+{
+    /// Evaluates the model natively as `evaluate` does, detecting overflows
+    /// of the accumulators of BMM nodes in the large type
+    pub fn evaluate_with_mode(
+        &self,
+        input: QArray<ST>,
+        mode: OverflowMode,
+    ) -> Result<Evaluation<ST>, AccumulatorOverflow> {
+        self.evaluate_nodes(QTypeArray::S(input), mode, false)
+    }
+
+    /// Evaluates the padded model (cf. `ProveModel::padded_evaluate`, whose
+    /// output this agrees with) with overflow detection. The input is
+    /// unpadded, and so is the output.
+    pub fn padded_evaluate_with_mode(
+        &self,
+        input: QArray<ST>,
+        mode: OverflowMode,
+    ) -> Result<Evaluation<ST>, AccumulatorOverflow> {
+        let input = input.compact_resize(
+            self.input_shape
+                .iter()
+                .map(|x| x.next_power_of_two())
+                .collect(),
+            ST::ZERO,
+        );
+
+        let evaluation = self.evaluate_nodes(QTypeArray::S(input), mode, true)?;
+
+        Ok(Evaluation {
+            output: evaluation
+                .output
+                .compact_resize(self.output_shape.clone(), ST::ZERO),
+            overflows: evaluation.overflows,
+        })
+    }
+
+    fn evaluate_nodes(
+        &self,
+        mut output: QTypeArray<ST, LT>,
+        mode: OverflowMode,
+        padded: bool,
+    ) -> Result<Evaluation<ST>, AccumulatorOverflow> {
+        let mut overflows = Vec::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let (node_output, node_overflows) = node.saturating_evaluate(&output, padded);
+
+            let mut node_overflows = node_overflows
+                .into_iter()
+                .map(|index| AccumulatorOverflow { node: i + 1, index });
+
+            match mode {
+                OverflowMode::Checked => {
+                    if let Some(overflow) = node_overflows.next() {
+                        return Err(overflow);
+                    }
+                }
+                OverflowMode::Saturating => overflows.extend(node_overflows),
+            }
+
+            output = node_output;
+        }
+
+        Ok(Evaluation {
+            output: output.unwrap_small(),
+            overflows,
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use crate::model::nodes::{
+    bmm::BMMNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode, sparse_bmm::SparseBMMNode,
+    Node,
+};
+
+use super::*;
+
+// A 5 x 3 weight matrix with some zero and small entries
+fn weights() -> QArray<i8> {
+    QArray::new(
+        vec![3, 0, -1, 0, 0, 0, -7, 2, 0, 0, 1, 5, 0, -2, 0],
+        vec![5, 3],
+    )
+}
+
+fn inputs() -> Vec<QArray<i8>> {
+    vec![
+        QArray::new(vec![1, 2, 3, 4, 5], vec![5]),
+        QArray::new(vec![-128, 127, 0, -5, 9], vec![5]),
+    ]
+}
+
+fn requantise(num_units: usize, input_zero_point: i8) -> Node<i8, i32> {
+    Node::RequantiseBMM(RequantiseBMMNode::new(
+        num_units,
+        0.5,
+        input_zero_point,
+        0.1,
+        0,
+        0.2,
+        4,
+    ))
+}
+
+fn dense_model() -> Model<i8, i32> {
+    Model::new(
+        vec![5],
+        vec![
+            Node::BMM(BMMNode::new(
+                weights(),
+                QArray::new(vec![10, -4, 7], vec![3]),
+                -3,
+            )),
+            requantise(3, -3),
+            Node::ReLU(ReLUNode::new(3, 4)),
+        ],
+    )
+}
+
+// Sparse model whose first (resp. last) accumulator overflows i32 for large
+// enough positive first (resp. negative last) input entries, which skips the
+// overflow check of SparseBMMNode::new
+fn overflowing_model() -> Model<i8, i32> {
+    Model::new(
+        vec![5],
+        vec![
+            Node::SparseBMM(SparseBMMNode::new_unchecked(
+                (5, 3),
+                vec![(0, 0, 127), (1, 1, -1), (4, 2, 127)],
+                QArray::new(vec![i32::MAX - 1000, 0, i32::MIN + 1000], vec![3]),
+                0,
+            )),
+            requantise(3, 0),
+        ],
+    )
+}
+
+#[test]
+fn test_evaluation_without_overflows() {
+    let model = dense_model();
+
+    for input in inputs() {
+        let expected = model.evaluate(input.clone());
+
+        for mode in [OverflowMode::Checked, OverflowMode::Saturating] {
+            for evaluation in [
+                model.evaluate_with_mode(input.clone(), mode),
+                model.padded_evaluate_with_mode(input.clone(), mode),
+            ] {
+                assert_eq!(
+                    evaluation,
+                    Ok(Evaluation {
+                        output: expected.clone(),
+                        overflows: vec![],
+                    })
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_checked_evaluation() {
+    let model = overflowing_model();
+
+    // 127 * 7 does not exceed the margin of the first bias entry
+    let input = QArray::new(vec![7, 0, 0, 0, 0], vec![5]);
+    assert_eq!(
+        model
+            .evaluate_with_mode(input.clone(), OverflowMode::Checked)
+            .unwrap()
+            .output,
+        model.evaluate(input)
+    );
+
+    let input = QArray::new(vec![8, 0, 0, 0, 0], vec![5]);
+    let overflow = AccumulatorOverflow { node: 1, index: 0 };
+
+    assert_eq!(
+        model.evaluate_with_mode(input.clone(), OverflowMode::Checked),
+        Err(overflow)
+    );
+    assert_eq!(
+        model.padded_evaluate_with_mode(input, OverflowMode::Checked),
+        Err(overflow)
+    );
+}
+
+#[test]
+fn test_saturating_evaluation() {
+    let model = overflowing_model();
+
+    // Both the first and the last accumulators overflow
+    let input = QArray::new(vec![100, 5, 0, 0, -128], vec![5]);
+
+    let evaluation = model
+        .evaluate_with_mode(input.clone(), OverflowMode::Saturating)
+        .unwrap();
+
+    assert_eq!(
+        evaluation.overflows,
+        vec![
+            AccumulatorOverflow { node: 1, index: 0 },
+            AccumulatorOverflow { node: 1, index: 2 },
+        ]
+    );
+
+    // The saturated accumulators are requantised to the extremes of i8
+    assert_eq!(evaluation.output.values()[0], i8::MAX);
+    assert_eq!(evaluation.output.values()[2], i8::MIN);
+
+    assert_eq!(
+        model.padded_evaluate_with_mode(input, OverflowMode::Saturating),
+        Ok(evaluation)
+    );
+}
+
+#[test]
+fn test_accumulator_overflow() {
+    let node = BMMNode::new(weights(), QArray::new(vec![10, -4, 7], vec![3]), -3);
+    assert_eq!(node.accumulator_overflow(), None);
+
+    // A column of 2^17 weights -128 on inputs shifted to [0, 255]
+    let node = BMMNode::<i8, i32>::new_unchecked(
+        QArray::new(vec![-128; 1 << 17], vec![1 << 17, 1]),
+        QArray::new(vec![0], vec![1]),
+        -128,
+    );
+
+    assert_eq!(
+        node.accumulator_overflow(),
+        Some(ValueRange::new(-(255 * 128) << 17, 255))
+    );
+}
+
+#[test]
+#[should_panic(expected = "BMM node accumulators range over")]
+fn test_overflowing_bmm_node() {
+    BMMNode::<i8, i32>::new(
+        QArray::new(vec![1, 1], vec![2, 1]),
+        QArray::new(vec![i32::MAX - 200], vec![1]),
+        0,
+    );
+}
+
+#[test]
+fn test_sparse_accumulator_overflow() {
+    let node = SparseBMMNode::<i8, i32>::new(
+        (5, 3),
+        vec![(0, 0, 127), (4, 2, -1)],
+        QArray::new(vec![10, -4, 7], vec![3]),
+        0,
+    );
+    assert_eq!(node.accumulator_overflow(), None);
+
+    let Node::SparseBMM(node) = &overflowing_model().nodes[0] else {
+        unreachable!()
+    };
+    assert!(node.accumulator_overflow().is_some());
+}
+
+#[test]
+#[should_panic(expected = "Sparse BMM node accumulators range over")]
+fn test_overflowing_sparse_bmm_node() {
+    SparseBMMNode::<i8, i32>::from_dense(
+        &QArray::new(vec![1, 1], vec![2, 1]),
+        QArray::new(vec![i32::MAX - 200], vec![1]),
+        0,
+        0,
+    );
+}
@@ -0,0 +1,66 @@
+use ark_std::fmt;
+
+use crate::model::nodes::{bmm::BMMNode, Node};
+use crate::model::qarray::QArray;
+
+pub(crate) mod onnx;
+pub(crate) mod tflite;
+
+/// Errors arising when importing a model from an external format
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// The model file could not be read
+    Io(String),
+    /// The file contents do not follow the expected format
+    Malformed(String),
+    /// The model contains an operator with no corresponding node
+    UnsupportedOperator(String),
+    /// The model contains a tensor type or quantisation scheme which we cannot
+    /// represent
+    UnsupportedTensor(String),
+    /// The model graph is not a sequence of nodes each consuming the output of
+    /// the previous one
+    UnsupportedTopology(String),
+    /// The accumulators of a matrix product may overflow i32 for some inputs
+    AccumulatorOverflow(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "Unable to read model file: {}", e),
+            ImportError::Malformed(e) => write!(f, "Malformed model file: {}", e),
+            ImportError::UnsupportedOperator(e) => write!(f, "Unsupported operator: {}", e),
+            ImportError::UnsupportedTensor(e) => write!(f, "Unsupported tensor: {}", e),
+            ImportError::UnsupportedTopology(e) => write!(f, "Unsupported model topology: {}", e),
+            ImportError::AccumulatorOverflow(e) => write!(f, "Accumulator overflow: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        ImportError::Io(e.to_string())
+    }
+}
+
+// Builds a BMM node, reporting accumulators which may overflow as an error
+// rather than panicking as BMMNode::new does
+fn bmm_node(
+    weights: QArray<i8>,
+    bias: QArray<i32>,
+    input_zero_point: i8,
+) -> Result<Node<i8, i32>, ImportError> {
+    let node = BMMNode::new_unchecked(weights, bias, input_zero_point);
+
+    match node.accumulator_overflow() {
+        Some(range) => Err(ImportError::AccumulatorOverflow(format!(
+            "matrix product of shape {:?} whose accumulators range over {}",
+            node.weights.shape(),
+            range
+        ))),
+        None => Ok(Node::BMM(node)),
+    }
+}
@@ -0,0 +1,1225 @@
+// Importer for quantised ONNX models (.onnx protobufs). Two quantisation
+// styles are supported, as long as the graph is a chain of operators each
+// consuming the output of the previous one:
+// - QOperator graphs, where matrix products are performed by QLinearMatMul
+//   or MatMulInteger (the latter followed by Cast/Mul/Add to rescale and add
+//   the bias, as produced by ONNX Runtime).
+// - QDQ graphs, where float Gemm/MatMul operators are surrounded by
+//   DequantizeLinear and QuantizeLinear operators. Each such pattern is fused
+//   into a BMM node followed by a requantisation node.
+// As in the TF Lite importer, the quantisation of the float graph input and
+// the dequantisation of the graph output are not part of the model. uint8
+// tensors are mapped to int8 by subtracting 128 from both the values and the
+// zero point, which leaves the represented real numbers unchanged.
+
+use std::collections::HashMap;
+
+use crate::model::import::{bmm_node, ImportError};
+use crate::model::nodes::{
+    relu::ReLUNode, requantise_bmm::RequantiseBMMNode, reshape::ReshapeNode, Node,
+};
+use crate::model::qarray::QArray;
+use crate::model::Model;
+use crate::quantization::QScaleType;
+
+use self::protobuf::Message;
+
+mod protobuf;
+
+#[cfg(test)]
+mod tests;
+
+// Element types, cf. TensorProto.DataType in the ONNX schema
+const FLOAT: i64 = 1;
+const UINT8: i64 = 2;
+const INT8: i64 = 3;
+const INT32: i64 = 6;
+const INT64: i64 = 7;
+
+/// Reads a quantised ONNX model from the file at `path`
+pub fn read_onnx_model(path: &str) -> Result<Model<i8, i32>, ImportError> {
+    parse_onnx_model(&std::fs::read(path)?)
+}
+
+/// Parses a quantised ONNX model from the contents of a .onnx file.
+///
+/// Supported operators are QLinearMatMul, MatMulInteger, Gemm and MatMul
+/// (between DequantizeLinear and QuantizeLinear), all of which are mapped to a
+/// BMM node followed by a requantisation node; Relu, Reshape and Flatten.
+/// Constant-valued operators (Constant, DequantizeLinear of an initialiser)
+/// may appear anywhere in the graph.
+pub fn parse_onnx_model(bytes: &[u8]) -> Result<Model<i8, i32>, ImportError> {
+    let model = Message::parse(bytes)?;
+
+    let graph = model
+        .message(7)?
+        .ok_or_else(|| ImportError::Malformed("missing ModelProto.graph".to_string()))?;
+
+    let mut constants = HashMap::new();
+
+    for initialiser in graph.messages(5)? {
+        let tensor = OnnxTensor::parse(&initialiser)?;
+        constants.insert(tensor.name.clone(), Constant::Tensor(tensor));
+    }
+
+    // Older models also list initialisers among the graph inputs
+    let inputs: Vec<Message> = graph
+        .messages(11)?
+        .into_iter()
+        .map(|input| Ok((input.string(1)?.unwrap_or_default(), input)))
+        .collect::<Result<Vec<_>, ImportError>>()?
+        .into_iter()
+        .filter(|(name, _)| !constants.contains_key(name))
+        .map(|(_, input)| input)
+        .collect();
+
+    let outputs = graph.messages(12)?;
+
+    let (input, output) = match (&inputs[..], &outputs[..]) {
+        ([input], [output]) => (input, output),
+        _ => {
+            return Err(ImportError::UnsupportedTopology(format!(
+                "expected a single graph input and output, found {} and {}",
+                inputs.len(),
+                outputs.len()
+            )))
+        }
+    };
+
+    let graph_output = output.string(1)?.unwrap_or_default();
+    let (input_shape, batched) = graph_input_shape(input)?;
+
+    let mut chain = Chain {
+        current: input.string(1)?.unwrap_or_default(),
+        value: match graph_input_type(input)? {
+            FLOAT => Value::Float,
+            data_type @ (INT8 | UINT8) => Value::Quantised {
+                scale: None,
+                zero_point: None,
+                data_type,
+            },
+            other => {
+                return Err(ImportError::UnsupportedTensor(format!(
+                    "graph input has type {}, expected float or an 8-bit integer type",
+                    data_type_name(other)
+                )))
+            }
+        },
+        shape: input_shape.clone(),
+        batched,
+        nodes: Vec::new(),
+    };
+
+    for node in graph.messages(1)? {
+        let node = OnnxNode::parse(&node)?;
+
+        if !node.domain.is_empty() && node.domain != "ai.onnx" {
+            return Err(ImportError::UnsupportedOperator(format!(
+                "{}::{}",
+                node.domain, node.op_type
+            )));
+        }
+
+        if node.input(0) == Some(chain.current.as_str()) {
+            chain.apply(&node, &constants)?;
+        } else {
+            evaluate_constant(&node, &mut constants)?;
+        }
+    }
+
+    if chain.current != graph_output {
+        return Err(ImportError::UnsupportedTopology(
+            "the last operator does not produce the graph output".to_string(),
+        ));
+    }
+
+    match chain.value {
+        Value::Quantised { .. } | Value::Dequantised { .. } => {}
+        _ => {
+            return Err(ImportError::UnsupportedTopology(
+                "the graph output is not quantised".to_string(),
+            ))
+        }
+    }
+
+    if chain.nodes.is_empty() {
+        return Err(ImportError::UnsupportedTopology(
+            "the model contains no nodes".to_string(),
+        ));
+    }
+
+    Model::try_new(input_shape, chain.nodes).map_err(|e| ImportError::Malformed(e.to_string()))
+}
+
+// What is known about the tensor flowing along the chain of operators
+enum Value {
+    // The float graph input, before quantisation
+    Float,
+    // An 8-bit integer tensor of the given element type, whose quantisation
+    // parameters may only become known through the operators consuming it
+    Quantised {
+        scale: Option<QScaleType>,
+        zero_point: Option<i8>,
+        data_type: i64,
+    },
+    // A float tensor obtained by dequantising an 8-bit integer tensor
+    Dequantised {
+        scale: QScaleType,
+        zero_point: i8,
+    },
+    // The result of a matrix product which has not been requantised yet
+    Product(PendingBMM),
+}
+
+struct PendingBMM {
+    weights: QArray<i8>,
+    bias: Vec<i32>,
+    input_zero_point: i8,
+    // Input and weight scales, whose product is the scale of the float
+    // product. Unknown after MatMulInteger until the result is rescaled.
+    scales: Option<(QScaleType, QScaleType)>,
+    // Whether the product has been converted to float
+    float: bool,
+    relu: bool,
+}
+
+struct Chain {
+    // Name of the tensor flowing along the chain
+    current: String,
+    value: Value,
+    // Shape of that tensor, without the batch dimension
+    shape: Vec<usize>,
+    // Whether the graph carries a leading batch dimension (of size 1)
+    batched: bool,
+    nodes: Vec<Node<i8, i32>>,
+}
+
+impl Chain {
+    fn apply(
+        &mut self,
+        node: &OnnxNode,
+        constants: &HashMap<String, Constant>,
+    ) -> Result<(), ImportError> {
+        if node.outputs.len() != 1 {
+            return Err(ImportError::UnsupportedTopology(format!(
+                "operator {} ({}) has {} outputs, expected one",
+                node.name,
+                node.op_type,
+                node.outputs.len()
+            )));
+        }
+
+        let constant = |index: usize| node.constant(index, constants);
+
+        let value = ark_std::mem::replace(&mut self.value, Value::Float);
+
+        self.value = match (node.op_type.as_str(), value) {
+            ("Identity", value) => value,
+            ("QuantizeLinear", value) => {
+                let scale = constant(1)?.scalar_f32()?;
+                let (zero_point, data_type) = optional_zero_point(node, 2, constants)?;
+
+                match value {
+                    // Quantisation of the graph input
+                    Value::Float => {}
+                    Value::Dequantised {
+                        scale: s,
+                        zero_point: z,
+                    } if s == scale && z == zero_point => {}
+                    Value::Product(pending) if pending.float => {
+                        self.nodes.extend(pending.into_nodes(scale, zero_point)?)
+                    }
+                    _ => {
+                        return Err(ImportError::UnsupportedTopology(format!(
+                            "operator {} (QuantizeLinear) does not follow a supported pattern",
+                            node.name
+                        )))
+                    }
+                }
+
+                Value::Quantised {
+                    scale: Some(scale),
+                    zero_point: Some(zero_point),
+                    data_type,
+                }
+            }
+            (
+                "DequantizeLinear",
+                Value::Quantised {
+                    scale: s,
+                    zero_point: z,
+                    ..
+                },
+            ) => {
+                let scale = constant(1)?.scalar_f32()?;
+                let (zero_point, _) = optional_zero_point(node, 2, constants)?;
+
+                if s.is_some_and(|s| s != scale) || z.is_some_and(|z| z != zero_point) {
+                    return Err(ImportError::UnsupportedTopology(format!(
+                        "operator {} (DequantizeLinear) does not match the preceding quantisation",
+                        node.name
+                    )));
+                }
+
+                Value::Dequantised { scale, zero_point }
+            }
+            ("QLinearMatMul", Value::Quantised { .. }) => {
+                let s_i = constant(1)?.scalar_f32()?;
+                let z_i = constant(2)?.scalar_i8()?;
+                let weights = constant(3)?.int8_matrix(false)?;
+                let s_w = constant(4)?.scalar_f32()?;
+                let z_w = constant(5)?.scalar_i8()?;
+                let s_o = constant(6)?.scalar_f32()?;
+                let z_o = constant(7)?.scalar_i8()?;
+
+                check_weight_zero_point(node, z_w)?;
+
+                let pending = self.product(weights, z_i, Some((s_i, s_w)), true)?;
+                self.nodes.extend(pending.into_nodes(s_o, z_o)?);
+
+                Value::Quantised {
+                    scale: Some(s_o),
+                    zero_point: Some(z_o),
+                    data_type: constant(7)?.tensor().data_type,
+                }
+            }
+            ("MatMulInteger", Value::Quantised { data_type, .. }) => {
+                let weights = constant(1)?.int8_matrix(false)?;
+
+                // Absent zero points are the zero of the respective tensor
+                // type, which for uint8 tensors is -128 after conversion
+                let z_i = match node.input(2) {
+                    Some(_) => constant(2)?.scalar_i8()?,
+                    None => default_zero_point(data_type),
+                };
+                let z_w = match node.input(3) {
+                    Some(_) => constant(3)?.scalar_i8()?,
+                    None => default_zero_point(constant(1)?.tensor().data_type),
+                };
+
+                check_weight_zero_point(node, z_w)?;
+
+                Value::Product(self.product(weights, z_i, None, false)?)
+            }
+            ("Gemm", Value::Dequantised { scale, zero_point }) => {
+                for (attribute, expected) in [("alpha", 1.0), ("beta", 1.0)] {
+                    if node.attribute_f32(attribute, 1.0)? != expected {
+                        return Err(ImportError::UnsupportedOperator(format!(
+                            "Gemm with {} other than 1",
+                            attribute
+                        )));
+                    }
+                }
+
+                if node.attribute_int("transA", 0)? != 0 {
+                    return Err(ImportError::UnsupportedOperator(
+                        "Gemm with transposed input".to_string(),
+                    ));
+                }
+
+                let transposed = node.attribute_int("transB", 0)? != 0;
+                let (weights, s_w) = constant(1)?.dequantised_weights(transposed)?;
+
+                let mut pending = self.product(weights, zero_point, Some((scale, s_w)), true)?;
+
+                if node.input(2).is_some() {
+                    pending.add_bias(constant(2)?)?;
+                }
+
+                Value::Product(pending)
+            }
+            ("MatMul", Value::Dequantised { scale, zero_point }) => {
+                let (weights, s_w) = constant(1)?.dequantised_weights(false)?;
+                Value::Product(self.product(weights, zero_point, Some((scale, s_w)), true)?)
+            }
+            ("Add", Value::Product(mut pending)) => {
+                pending.add_bias(constant(1)?)?;
+                Value::Product(pending)
+            }
+            ("Cast", Value::Product(mut pending)) if !pending.float => {
+                if node.attribute_int("to", 0)? != FLOAT {
+                    return Err(ImportError::UnsupportedOperator(
+                        "Cast of a matrix product to a type other than float".to_string(),
+                    ));
+                }
+
+                pending.float = true;
+                Value::Product(pending)
+            }
+            ("Mul", Value::Product(mut pending)) if pending.float && !pending.relu => {
+                let factor = constant(1)?.scalar_f32()?;
+
+                pending.scales = Some(match pending.scales {
+                    Some((s_i, s_w)) => (s_i, s_w * factor),
+                    None => (factor, 1.0),
+                });
+
+                Value::Product(pending)
+            }
+            ("Relu", Value::Product(mut pending)) if pending.float => {
+                pending.relu = true;
+                Value::Product(pending)
+            }
+            ("Relu", Value::Dequantised { scale, zero_point }) => {
+                // Clipping the real numbers at 0 amounts to clipping their
+                // quantised representation at the zero point
+                self.nodes.push(Node::ReLU(ReLUNode::new(
+                    self.one_dimensional_len(node)?,
+                    zero_point,
+                )));
+                Value::Dequantised { scale, zero_point }
+            }
+            ("Relu", value @ Value::Quantised { .. }) => {
+                // Integer Relu acts on the raw values
+                self.nodes.push(Node::ReLU(ReLUNode::new(
+                    self.one_dimensional_len(node)?,
+                    0,
+                )));
+                value
+            }
+            (
+                "Reshape",
+                value @ (Value::Float | Value::Quantised { .. } | Value::Dequantised { .. }),
+            ) => {
+                let target = constant(1)?.ints()?;
+                self.reshape(node, self.resolve_reshape(node, &target)?)?;
+                value
+            }
+            (
+                "Flatten",
+                value @ (Value::Float | Value::Quantised { .. } | Value::Dequantised { .. }),
+            ) => {
+                let axis = node.attribute_int("axis", 1)?;
+                self.reshape(node, self.resolve_flatten(node, axis)?)?;
+                value
+            }
+            (op_type, value) => {
+                if !SUPPORTED_OPERATORS.contains(&op_type) {
+                    return Err(ImportError::UnsupportedOperator(op_type.to_string()));
+                }
+
+                return Err(ImportError::UnsupportedTopology(format!(
+                    "operator {} ({}) cannot be applied to {}",
+                    node.name,
+                    op_type,
+                    value.description()
+                )));
+            }
+        };
+
+        self.current = node.outputs[0].clone();
+
+        Ok(())
+    }
+
+    fn product(
+        &mut self,
+        weights: QArray<i8>,
+        input_zero_point: i8,
+        scales: Option<(QScaleType, QScaleType)>,
+        float: bool,
+    ) -> Result<PendingBMM, ImportError> {
+        if self.shape != vec![weights.shape()[0]] {
+            return Err(ImportError::UnsupportedTensor(format!(
+                "matrix product input has shape {:?}, which does not match the {} rows of the weights",
+                self.shape,
+                weights.shape()[0]
+            )));
+        }
+
+        let out_dim = weights.shape()[1];
+        self.shape = vec![out_dim];
+
+        Ok(PendingBMM {
+            weights,
+            bias: vec![0; out_dim],
+            input_zero_point,
+            scales,
+            float,
+            relu: false,
+        })
+    }
+
+    fn one_dimensional_len(&self, node: &OnnxNode) -> Result<usize, ImportError> {
+        match self.shape[..] {
+            [len] => Ok(len),
+            _ => Err(ImportError::UnsupportedTensor(format!(
+                "input to operator {} ({}) has shape {:?}, expected a single (non-batch) dimension",
+                node.name, node.op_type, self.shape
+            ))),
+        }
+    }
+
+    fn reshape(&mut self, node: &OnnxNode, shape: Vec<usize>) -> Result<(), ImportError> {
+        if shape.iter().product::<usize>() != self.shape.iter().product::<usize>() {
+            return Err(ImportError::Malformed(format!(
+                "operator {} ({}) cannot reshape {:?} into {:?}",
+                node.name, node.op_type, self.shape, shape
+            )));
+        }
+
+        if shape != self.shape {
+            self.nodes.push(Node::Reshape(ReshapeNode::new(
+                self.shape.clone(),
+                shape.clone(),
+            )));
+            self.shape = shape;
+        }
+
+        Ok(())
+    }
+
+    // The full (possibly batched) shape of the current tensor, as seen by the
+    // ONNX operators
+    fn graph_shape(&self) -> Vec<usize> {
+        if self.batched {
+            [vec![1], self.shape.clone()].concat()
+        } else {
+            self.shape.clone()
+        }
+    }
+
+    // Strips the batch dimension from a shape computed by an ONNX operator
+    fn unbatch(&self, node: &OnnxNode, shape: Vec<usize>) -> Result<Vec<usize>, ImportError> {
+        if !self.batched {
+            return Ok(shape);
+        }
+
+        match shape[..] {
+            [1, ref rest @ ..] => Ok(rest.to_vec()),
+            _ => Err(ImportError::UnsupportedTopology(format!(
+                "operator {} ({}) does not preserve the batch dimension",
+                node.name, node.op_type
+            ))),
+        }
+    }
+
+    fn resolve_reshape(&self, node: &OnnxNode, target: &[i64]) -> Result<Vec<usize>, ImportError> {
+        let input = self.graph_shape();
+        let len: usize = input.iter().product();
+
+        let invalid = || {
+            ImportError::Malformed(format!(
+                "operator {} (Reshape) has invalid target shape {:?}",
+                node.name, target
+            ))
+        };
+
+        // 0 copies the corresponding input dimension, -1 is inferred
+        let mut shape = target
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| match d {
+                0 => input.get(i).copied().ok_or_else(invalid),
+                -1 => Ok(0),
+                d if d > 0 => Ok(d as usize),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match target.iter().filter(|&&d| d == -1).count() {
+            0 => {}
+            1 => {
+                let known: usize = shape.iter().filter(|&&d| d != 0).product();
+
+                if known == 0 || !len.is_multiple_of(known) {
+                    return Err(invalid());
+                }
+
+                let position = target.iter().position(|&d| d == -1).unwrap();
+                shape[position] = len / known;
+            }
+            _ => return Err(invalid()),
+        }
+
+        self.unbatch(node, shape)
+    }
+
+    fn resolve_flatten(&self, node: &OnnxNode, axis: i64) -> Result<Vec<usize>, ImportError> {
+        let input = self.graph_shape();
+
+        let axis = if axis < 0 {
+            axis + input.len() as i64
+        } else {
+            axis
+        };
+
+        if axis < 0 || axis as usize > input.len() {
+            return Err(ImportError::Malformed(format!(
+                "operator {} (Flatten) has invalid axis {}",
+                node.name, axis
+            )));
+        }
+
+        let (outer, inner) = input.split_at(axis as usize);
+
+        self.unbatch(node, vec![outer.iter().product(), inner.iter().product()])
+    }
+}
+
+impl Value {
+    fn description(&self) -> &'static str {
+        match self {
+            Value::Float => "the float graph input",
+            Value::Quantised { .. } => "a quantised tensor",
+            Value::Dequantised { .. } => "a dequantised tensor",
+            Value::Product(_) => "a matrix product awaiting requantisation",
+        }
+    }
+}
+
+impl PendingBMM {
+    fn add_bias(&mut self, bias: &Constant) -> Result<(), ImportError> {
+        if self.relu {
+            return Err(ImportError::UnsupportedTopology(
+                "bias added after the activation function".to_string(),
+            ));
+        }
+
+        let out_dim = self.bias.len();
+
+        let bias: Vec<i32> = match (bias, self.float) {
+            (Constant::Tensor(t), false) if t.data_type == INT32 => {
+                t.ints()?.into_iter().map(|b| b as i32).collect()
+            }
+            // Float biases are requantised to the scale of the product, which
+            // is exact for the usual QDQ convention s_bias = s_input * s_weight
+            (bias, true) => {
+                let (s_i, s_w) = self.scales.ok_or_else(|| {
+                    ImportError::UnsupportedTopology(
+                        "float bias added to a product of unknown scale".to_string(),
+                    )
+                })?;
+
+                let scale = s_i as f64 * s_w as f64;
+
+                bias.real_values()?
+                    .into_iter()
+                    .map(|b| (b / scale).round() as i32)
+                    .collect()
+            }
+            _ => {
+                return Err(ImportError::UnsupportedTensor(
+                    "bias of a type incompatible with the matrix product".to_string(),
+                ))
+            }
+        };
+
+        if bias.len() != out_dim {
+            return Err(ImportError::UnsupportedTensor(format!(
+                "bias has {} entries, expected {}",
+                bias.len(),
+                out_dim
+            )));
+        }
+
+        self.bias
+            .iter_mut()
+            .zip(bias)
+            .for_each(|(acc, b)| *acc += b);
+
+        Ok(())
+    }
+
+    fn into_nodes(self, s_o: QScaleType, z_o: i8) -> Result<Vec<Node<i8, i32>>, ImportError> {
+        let (s_i, s_w) = self.scales.ok_or_else(|| {
+            ImportError::UnsupportedTopology(
+                "matrix product requantised without being rescaled".to_string(),
+            )
+        })?;
+
+        let out_dim = self.bias.len();
+
+        let mut nodes = vec![
+            bmm_node(
+                self.weights,
+                QArray::new(self.bias, vec![out_dim]),
+                self.input_zero_point,
+            )?,
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                out_dim,
+                s_i,
+                self.input_zero_point,
+                s_w,
+                0,
+                s_o,
+                z_o,
+            )),
+        ];
+
+        if self.relu {
+            nodes.push(Node::ReLU(ReLUNode::new(out_dim, z_o)));
+        }
+
+        Ok(nodes)
+    }
+}
+
+const SUPPORTED_OPERATORS: [&str; 14] = [
+    "Identity",
+    "QuantizeLinear",
+    "DequantizeLinear",
+    "QLinearMatMul",
+    "MatMulInteger",
+    "Gemm",
+    "MatMul",
+    "Add",
+    "Cast",
+    "Mul",
+    "Relu",
+    "Reshape",
+    "Flatten",
+    "Constant",
+];
+
+// Values known at import time: initialisers, outputs of Constant operators and
+// dequantised initialisers (weights and biases in QDQ graphs)
+enum Constant {
+    Tensor(OnnxTensor),
+    Dequantised {
+        tensor: OnnxTensor,
+        scale: QScaleType,
+        zero_point: i8,
+    },
+}
+
+fn evaluate_constant(
+    node: &OnnxNode,
+    constants: &mut HashMap<String, Constant>,
+) -> Result<(), ImportError> {
+    let constant = match node.op_type.as_str() {
+        "Constant" => {
+            let value = node
+                .attribute("value")?
+                .map(|a| a.message(5))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| {
+                    ImportError::UnsupportedOperator("Constant without a tensor value".to_string())
+                })?;
+
+            Constant::Tensor(OnnxTensor::parse(&value)?)
+        }
+        "DequantizeLinear" => {
+            let tensor = match node.constant(0, constants)? {
+                Constant::Tensor(t) => t.clone(),
+                _ => {
+                    return Err(ImportError::UnsupportedTopology(format!(
+                        "operator {} dequantises a float tensor",
+                        node.name
+                    )))
+                }
+            };
+
+            let scale = node.constant(1, constants)?.scalar_f32()?;
+            let zero_point = match node.input(2) {
+                Some(_) => node.constant(2, constants)?.zero_point(tensor.data_type)?,
+                None => default_zero_point(tensor.data_type),
+            };
+
+            Constant::Dequantised {
+                tensor,
+                scale,
+                zero_point,
+            }
+        }
+        "Identity" => match node.constant(0, constants)? {
+            Constant::Tensor(t) => Constant::Tensor(t.clone()),
+            _ => {
+                return Err(ImportError::UnsupportedTopology(format!(
+                    "operator {} (Identity) does not consume the output of the previous operator",
+                    node.name
+                )))
+            }
+        },
+        op_type if !SUPPORTED_OPERATORS.contains(&op_type) => {
+            return Err(ImportError::UnsupportedOperator(op_type.to_string()))
+        }
+        op_type => {
+            return Err(ImportError::UnsupportedTopology(format!(
+                "operator {} ({}) does not consume the output of the previous operator",
+                node.name, op_type
+            )))
+        }
+    };
+
+    let name = node
+        .outputs
+        .first()
+        .ok_or_else(|| ImportError::Malformed(format!("operator {} has no outputs", node.name)))?;
+
+    constants.insert(name.clone(), constant);
+
+    Ok(())
+}
+
+// The zero point of a QuantizeLinear or DequantizeLinear operator together
+// with its type, which is that of the quantised tensor. It defaults to the
+// uint8 value 0 (i.e. -128 after conversion to int8)
+fn optional_zero_point(
+    node: &OnnxNode,
+    index: usize,
+    constants: &HashMap<String, Constant>,
+) -> Result<(i8, i64), ImportError> {
+    match node.input(index) {
+        Some(_) => {
+            let zero_point = node.constant(index, constants)?;
+            Ok((zero_point.scalar_i8()?, zero_point.tensor().data_type))
+        }
+        None => Ok((default_zero_point(UINT8), UINT8)),
+    }
+}
+
+// The zero of a tensor type, which an absent zero point defaults to, mapped
+// to int8 (cf. `OnnxTensor::int8_values`)
+fn default_zero_point(data_type: i64) -> i8 {
+    match data_type {
+        UINT8 => -128,
+        _ => 0,
+    }
+}
+
+fn check_weight_zero_point(node: &OnnxNode, zero_point: i8) -> Result<(), ImportError> {
+    if zero_point != 0 {
+        return Err(ImportError::UnsupportedTensor(format!(
+            "weights of operator {} have non-zero zero point {}",
+            node.name, zero_point
+        )));
+    }
+    Ok(())
+}
+
+impl Constant {
+    fn tensor(&self) -> &OnnxTensor {
+        match self {
+            Constant::Tensor(t) => t,
+            Constant::Dequantised { tensor, .. } => tensor,
+        }
+    }
+
+    fn scalar_f32(&self) -> Result<QScaleType, ImportError> {
+        match self {
+            Constant::Tensor(t) if t.data_type == FLOAT => match t.floats()?[..] {
+                [value] => Ok(value),
+                _ => Err(ImportError::UnsupportedTensor(format!(
+                    "tensor {} has shape {:?}, expected a scalar (per-axis quantisation is not supported)",
+                    t.name, t.dims
+                ))),
+            },
+            _ => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is not a float constant",
+                self.tensor().name
+            ))),
+        }
+    }
+
+    // A scalar 8-bit integer, mapped to int8
+    fn scalar_i8(&self) -> Result<i8, ImportError> {
+        match self {
+            Constant::Tensor(t) => match t.int8_values()?[..] {
+                [value] => Ok(value),
+                _ => Err(ImportError::UnsupportedTensor(format!(
+                    "tensor {} has shape {:?}, expected a scalar (per-axis quantisation is not supported)",
+                    t.name, t.dims
+                ))),
+            },
+            Constant::Dequantised { tensor, .. } => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is not an integer constant",
+                tensor.name
+            ))),
+        }
+    }
+
+    // The zero point of a tensor of the given type, which for int32 biases
+    // must be 0
+    fn zero_point(&self, data_type: i64) -> Result<i8, ImportError> {
+        if data_type != INT32 {
+            return self.scalar_i8();
+        }
+
+        match self.tensor().ints()?[..] {
+            [0] => Ok(0),
+            _ => Err(ImportError::UnsupportedTensor(format!(
+                "int32 tensor {} has non-zero zero point",
+                self.tensor().name
+            ))),
+        }
+    }
+
+    fn ints(&self) -> Result<Vec<i64>, ImportError> {
+        match self {
+            Constant::Tensor(t) => t.ints(),
+            Constant::Dequantised { tensor, .. } => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is not an integer constant",
+                tensor.name
+            ))),
+        }
+    }
+
+    // The real numbers represented by the constant
+    fn real_values(&self) -> Result<Vec<f64>, ImportError> {
+        match self {
+            Constant::Tensor(t) if t.data_type == FLOAT => {
+                Ok(t.floats()?.into_iter().map(|v| v as f64).collect())
+            }
+            Constant::Dequantised {
+                tensor,
+                scale,
+                zero_point,
+            } => {
+                let values = match tensor.data_type {
+                    INT32 => tensor.ints()?,
+                    _ => tensor
+                        .int8_values()?
+                        .into_iter()
+                        .map(|v| v as i64)
+                        .collect(),
+                };
+
+                Ok(values
+                    .into_iter()
+                    .map(|v| (v - *zero_point as i64) as f64 * *scale as f64)
+                    .collect())
+            }
+            Constant::Tensor(t) => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is neither float nor dequantised",
+                t.name
+            ))),
+        }
+    }
+
+    // An 8-bit integer matrix, returned in the (input, output) layout of
+    // BMMNode
+    fn int8_matrix(&self, transposed: bool) -> Result<QArray<i8>, ImportError> {
+        let tensor = self.tensor();
+        let values = tensor.int8_values()?;
+
+        let (rows, cols) = match tensor.dims[..] {
+            [rows, cols] => (rows, cols),
+            _ => {
+                return Err(ImportError::UnsupportedTensor(format!(
+                    "weight tensor {} has shape {:?}, expected two dimensions",
+                    tensor.name, tensor.dims
+                )))
+            }
+        };
+
+        if !transposed {
+            return Ok(QArray::new(values, vec![rows, cols]));
+        }
+
+        let transposed = (0..cols)
+            .flat_map(|row| (0..rows).map(move |col| (row, col)))
+            .map(|(row, col)| values[col * cols + row])
+            .collect();
+
+        Ok(QArray::new(transposed, vec![cols, rows]))
+    }
+
+    // Weights of a QDQ matrix product, together with their scale
+    fn dequantised_weights(
+        &self,
+        transposed: bool,
+    ) -> Result<(QArray<i8>, QScaleType), ImportError> {
+        match self {
+            Constant::Dequantised {
+                scale, zero_point, ..
+            } => {
+                if *zero_point != 0 {
+                    return Err(ImportError::UnsupportedTensor(format!(
+                        "weight tensor {} has non-zero zero point {}",
+                        self.tensor().name,
+                        zero_point
+                    )));
+                }
+
+                Ok((self.int8_matrix(transposed)?, *scale))
+            }
+            Constant::Tensor(t) => Err(ImportError::UnsupportedTensor(format!(
+                "weight tensor {} is not quantised",
+                t.name
+            ))),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct OnnxTensor {
+    name: String,
+    dims: Vec<usize>,
+    data_type: i64,
+    data: TensorData,
+}
+
+#[derive(Clone)]
+enum TensorData {
+    Ints(Vec<i64>),
+    Floats(Vec<f32>),
+}
+
+impl OnnxTensor {
+    fn parse(tensor: &Message) -> Result<Self, ImportError> {
+        let name = tensor.string(8)?.unwrap_or_default();
+
+        let dims = tensor
+            .ints(1)?
+            .into_iter()
+            .map(|d| {
+                usize::try_from(d).map_err(|_| {
+                    ImportError::Malformed(format!("tensor {} has negative dimension", name))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data_type = tensor.int(2)?.unwrap_or(0);
+
+        // External data (data_location = EXTERNAL) would require access to
+        // further files
+        if tensor.int(14)? == Some(1) {
+            return Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is stored externally",
+                name
+            )));
+        }
+
+        let raw = tensor.bytes(9)?;
+
+        let data = match (data_type, raw) {
+            (FLOAT, Some(raw)) => TensorData::Floats(
+                raw.chunks_exact(4)
+                    .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+                    .collect(),
+            ),
+            (FLOAT, None) => TensorData::Floats(tensor.floats(4)?),
+            (UINT8, Some(raw)) => TensorData::Ints(raw.iter().map(|&b| b as i64).collect()),
+            (INT8, Some(raw)) => TensorData::Ints(raw.iter().map(|&b| b as i8 as i64).collect()),
+            (INT32, Some(raw)) => TensorData::Ints(
+                raw.chunks_exact(4)
+                    .map(|c| i32::from_le_bytes(c.try_into().unwrap()) as i64)
+                    .collect(),
+            ),
+            (INT64, Some(raw)) => TensorData::Ints(
+                raw.chunks_exact(8)
+                    .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+                    .collect(),
+            ),
+            // Narrow integer types are stored in int32_data
+            (UINT8 | INT8 | INT32, None) => TensorData::Ints(
+                tensor
+                    .ints(5)?
+                    .into_iter()
+                    .map(|v| v as i32 as i64)
+                    .collect(),
+            ),
+            (INT64, None) => TensorData::Ints(tensor.ints(7)?),
+            (other, _) => {
+                return Err(ImportError::UnsupportedTensor(format!(
+                    "tensor {} has type {}",
+                    name,
+                    data_type_name(other)
+                )))
+            }
+        };
+
+        let len = match &data {
+            TensorData::Ints(v) => v.len(),
+            TensorData::Floats(v) => v.len(),
+        };
+
+        if len != dims.iter().product::<usize>() {
+            return Err(ImportError::Malformed(format!(
+                "tensor {} has {} entries, expected shape {:?}",
+                name, len, dims
+            )));
+        }
+
+        Ok(Self {
+            name,
+            dims,
+            data_type,
+            data,
+        })
+    }
+
+    fn ints(&self) -> Result<Vec<i64>, ImportError> {
+        match &self.data {
+            TensorData::Ints(v) => Ok(v.clone()),
+            TensorData::Floats(_) => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} has type float, expected an integer type",
+                self.name
+            ))),
+        }
+    }
+
+    fn floats(&self) -> Result<Vec<f32>, ImportError> {
+        match &self.data {
+            TensorData::Floats(v) => Ok(v.clone()),
+            TensorData::Ints(_) => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} has type {}, expected float",
+                self.name,
+                data_type_name(self.data_type)
+            ))),
+        }
+    }
+
+    // The values of an 8-bit integer tensor, with uint8 mapped to int8
+    fn int8_values(&self) -> Result<Vec<i8>, ImportError> {
+        let offset = match self.data_type {
+            INT8 => 0,
+            UINT8 => 128,
+            other => {
+                return Err(ImportError::UnsupportedTensor(format!(
+                    "tensor {} has type {}, expected int8 or uint8",
+                    self.name,
+                    data_type_name(other)
+                )))
+            }
+        };
+
+        Ok(self
+            .ints()?
+            .into_iter()
+            .map(|v| (v - offset) as i8)
+            .collect())
+    }
+}
+
+struct OnnxNode<'a> {
+    name: String,
+    op_type: String,
+    domain: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    attributes: Vec<Message<'a>>,
+}
+
+impl<'a> OnnxNode<'a> {
+    fn parse(node: &Message<'a>) -> Result<Self, ImportError> {
+        let op_type = node.string(4)?.unwrap_or_default();
+
+        Ok(Self {
+            name: node.string(3)?.unwrap_or_else(|| op_type.clone()),
+            op_type,
+            domain: node.string(7)?.unwrap_or_default(),
+            inputs: node.strings(1)?,
+            outputs: node.strings(2)?,
+            attributes: node.messages(5)?,
+        })
+    }
+
+    // Optional inputs are either omitted or given an empty name
+    fn input(&self, index: usize) -> Option<&str> {
+        self.inputs
+            .get(index)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    fn constant<'c>(
+        &self,
+        index: usize,
+        constants: &'c HashMap<String, Constant>,
+    ) -> Result<&'c Constant, ImportError> {
+        let name = self.input(index).ok_or_else(|| {
+            ImportError::Malformed(format!(
+                "operator {} ({}) is missing input {}",
+                self.name, self.op_type, index
+            ))
+        })?;
+
+        constants.get(name).ok_or_else(|| {
+            ImportError::UnsupportedTopology(format!(
+                "input {} of operator {} ({}) is not a constant",
+                name, self.name, self.op_type
+            ))
+        })
+    }
+
+    fn attribute(&self, name: &str) -> Result<Option<&Message<'a>>, ImportError> {
+        for attribute in &self.attributes {
+            if attribute.string(1)?.as_deref() == Some(name) {
+                return Ok(Some(attribute));
+            }
+        }
+        Ok(None)
+    }
+
+    fn attribute_int(&self, name: &str, default: i64) -> Result<i64, ImportError> {
+        Ok(match self.attribute(name)? {
+            Some(attribute) => attribute.int(3)?.unwrap_or(default),
+            None => default,
+        })
+    }
+
+    fn attribute_f32(&self, name: &str, default: f32) -> Result<f32, ImportError> {
+        Ok(match self.attribute(name)? {
+            Some(attribute) => attribute.float(2)?.unwrap_or(default),
+            None => default,
+        })
+    }
+}
+
+fn graph_input_type(input: &Message) -> Result<i64, ImportError> {
+    Ok(tensor_type(input)?.int(1)?.unwrap_or(0))
+}
+
+// Returns the shape of the graph input without its batch dimension, if any,
+// together with whether such dimension was present. The batch dimension is
+// a leading symbolic dimension or, in graphs of rank two or more, a leading 1.
+fn graph_input_shape(input: &Message) -> Result<(Vec<usize>, bool), ImportError> {
+    let shape = tensor_type(input)?
+        .message(2)?
+        .ok_or_else(|| ImportError::UnsupportedTensor("graph input has no shape".to_string()))?;
+
+    let dims = shape
+        .messages(1)?
+        .iter()
+        .map(|dim| match dim.int(1)? {
+            Some(d) if d > 0 => Ok(Some(d as usize)),
+            _ => Ok(None),
+        })
+        .collect::<Result<Vec<_>, ImportError>>()?;
+
+    let (batched, rest) = match &dims[..] {
+        [None | Some(1), rest @ ..] if !rest.is_empty() => (true, rest),
+        _ => (false, &dims[..]),
+    };
+
+    let shape = rest
+        .iter()
+        .map(|d| {
+            d.ok_or_else(|| {
+                ImportError::UnsupportedTensor(
+                    "graph input has symbolic non-batch dimensions".to_string(),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((shape, batched))
+}
+
+fn tensor_type<'a>(input: &Message<'a>) -> Result<Message<'a>, ImportError> {
+    input
+        .message(2)?
+        .and_then(|t| t.message(1).transpose())
+        .transpose()?
+        .ok_or_else(|| ImportError::UnsupportedTensor("graph input is not a tensor".to_string()))
+}
+
+fn data_type_name(data_type: i64) -> String {
+    let name = match data_type {
+        FLOAT => "FLOAT",
+        UINT8 => "UINT8",
+        INT8 => "INT8",
+        4 => "UINT16",
+        5 => "INT16",
+        INT32 => "INT32",
+        INT64 => "INT64",
+        9 => "BOOL",
+        10 => "FLOAT16",
+        11 => "DOUBLE",
+        _ => return format!("TYPE_{}", data_type),
+    };
+    name.to_string()
+}
@@ -0,0 +1,186 @@
+// Minimal decoder for the Protocol Buffers wire format, covering what is
+// needed to walk the ONNX schema. Messages are decoded eagerly into a list of
+// (field number, value) pairs; interpreting the values is left to the caller,
+// who knows the schema.
+
+use crate::model::import::ImportError;
+
+fn malformed(what: &str) -> ImportError {
+    ImportError::Malformed(format!("invalid {} in protobuf message", what))
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum WireValue<'a> {
+    Varint(u64),
+    // 64-bit fixed-size values are skipped, since ONNX only uses them in
+    // double and uint64 tensor data
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+pub(crate) struct Message<'a> {
+    fields: Vec<(u32, WireValue<'a>)>,
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, ImportError> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or_else(|| malformed("varint"))?;
+        *pos += 1;
+
+        value |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(malformed("varint"))
+}
+
+fn read_fixed<const N: usize>(data: &[u8], pos: &mut usize) -> Result<[u8; N], ImportError> {
+    let bytes = data
+        .get(*pos..*pos + N)
+        .ok_or_else(|| malformed("fixed-size field"))?;
+    *pos += N;
+    Ok(bytes.try_into().unwrap())
+}
+
+impl<'a> Message<'a> {
+    pub(crate) fn parse(data: &'a [u8]) -> Result<Self, ImportError> {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let key = read_varint(data, &mut pos)?;
+            let field = (key >> 3) as u32;
+
+            let value = match key & 7 {
+                0 => WireValue::Varint(read_varint(data, &mut pos)?),
+                1 => {
+                    read_fixed::<8>(data, &mut pos)?;
+                    WireValue::Fixed64
+                }
+                2 => {
+                    let len = read_varint(data, &mut pos)? as usize;
+                    let bytes = pos
+                        .checked_add(len)
+                        .and_then(|end| data.get(pos..end))
+                        .ok_or_else(|| malformed("length-delimited field"))?;
+                    pos += len;
+                    WireValue::Bytes(bytes)
+                }
+                5 => WireValue::Fixed32(u32::from_le_bytes(read_fixed(data, &mut pos)?)),
+                _ => return Err(malformed("wire type")),
+            };
+
+            fields.push((field, value));
+        }
+
+        Ok(Self { fields })
+    }
+
+    fn values(&self, field: u32) -> impl Iterator<Item = WireValue<'a>> + '_ {
+        self.fields
+            .iter()
+            .filter(move |(f, _)| *f == field)
+            .map(|(_, v)| *v)
+    }
+
+    /// Last occurrence of a non-repeated field, as per protobuf semantics
+    fn last(&self, field: u32) -> Option<WireValue<'a>> {
+        self.values(field).last()
+    }
+
+    pub(crate) fn bytes(&self, field: u32) -> Result<Option<&'a [u8]>, ImportError> {
+        match self.last(field) {
+            None => Ok(None),
+            Some(WireValue::Bytes(b)) => Ok(Some(b)),
+            _ => Err(malformed("bytes field")),
+        }
+    }
+
+    pub(crate) fn string(&self, field: u32) -> Result<Option<String>, ImportError> {
+        Ok(self
+            .bytes(field)?
+            .map(|b| String::from_utf8_lossy(b).into_owned()))
+    }
+
+    pub(crate) fn strings(&self, field: u32) -> Result<Vec<String>, ImportError> {
+        self.values(field)
+            .map(|v| match v {
+                WireValue::Bytes(b) => Ok(String::from_utf8_lossy(b).into_owned()),
+                _ => Err(malformed("string field")),
+            })
+            .collect()
+    }
+
+    pub(crate) fn int(&self, field: u32) -> Result<Option<i64>, ImportError> {
+        match self.last(field) {
+            None => Ok(None),
+            Some(WireValue::Varint(v)) => Ok(Some(v as i64)),
+            _ => Err(malformed("integer field")),
+        }
+    }
+
+    pub(crate) fn float(&self, field: u32) -> Result<Option<f32>, ImportError> {
+        match self.last(field) {
+            None => Ok(None),
+            Some(WireValue::Fixed32(v)) => Ok(Some(f32::from_bits(v))),
+            _ => Err(malformed("float field")),
+        }
+    }
+
+    pub(crate) fn message(&self, field: u32) -> Result<Option<Message<'a>>, ImportError> {
+        self.bytes(field)?.map(Message::parse).transpose()
+    }
+
+    pub(crate) fn messages(&self, field: u32) -> Result<Vec<Message<'a>>, ImportError> {
+        self.values(field)
+            .map(|v| match v {
+                WireValue::Bytes(b) => Message::parse(b),
+                _ => Err(malformed("message field")),
+            })
+            .collect()
+    }
+
+    /// Repeated integer field, which may be encoded either packed or unpacked
+    pub(crate) fn ints(&self, field: u32) -> Result<Vec<i64>, ImportError> {
+        let mut ints = Vec::new();
+
+        for value in self.values(field) {
+            match value {
+                WireValue::Varint(v) => ints.push(v as i64),
+                WireValue::Bytes(b) => {
+                    let mut pos = 0;
+                    while pos < b.len() {
+                        ints.push(read_varint(b, &mut pos)? as i64);
+                    }
+                }
+                _ => return Err(malformed("repeated integer field")),
+            }
+        }
+
+        Ok(ints)
+    }
+
+    /// Repeated float field, which may be encoded either packed or unpacked
+    pub(crate) fn floats(&self, field: u32) -> Result<Vec<f32>, ImportError> {
+        let mut floats = Vec::new();
+
+        for value in self.values(field) {
+            match value {
+                WireValue::Fixed32(v) => floats.push(f32::from_bits(v)),
+                WireValue::Bytes(b) if b.len() % 4 == 0 => floats.extend(
+                    b.chunks_exact(4)
+                        .map(|c| f32::from_le_bytes(c.try_into().unwrap())),
+                ),
+                _ => return Err(malformed("repeated float field")),
+            }
+        }
+
+        Ok(floats)
+    }
+}
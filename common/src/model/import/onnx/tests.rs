@@ -0,0 +1,770 @@
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+
+use crate::model::nodes::bmm::BMMNode;
+use crate::two_layer_perceptron_mnist::{build_two_layer_perceptron_mnist, parameters::*};
+use crate::{quantise_f32_u8_nne, Ligero};
+
+use super::*;
+
+// There are no ONNX exports of the example models in the repository, so the
+// test models are encoded here from the parameters of the two-layer
+// perceptron, following the structure of ONNX Runtime and PyTorch exports
+
+/********************** Protobuf encoding **********************/
+
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn int_field(buf: &mut Vec<u8>, field: u32, value: i64) {
+    varint(buf, (field as u64) << 3);
+    varint(buf, value as u64);
+}
+
+fn bytes_field(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    varint(buf, ((field as u64) << 3) | 2);
+    varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn tensor_proto(name: &str, dims: &[usize], data_type: i64, raw_data: Vec<u8>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for &d in dims {
+        int_field(&mut buf, 1, d as i64);
+    }
+    int_field(&mut buf, 2, data_type);
+    bytes_field(&mut buf, 8, name.as_bytes());
+    bytes_field(&mut buf, 9, &raw_data);
+    buf
+}
+
+fn float_scalar(name: &str, value: f32) -> Vec<u8> {
+    tensor_proto(name, &[], FLOAT, value.to_le_bytes().to_vec())
+}
+
+fn int8_tensor(name: &str, dims: &[usize], values: &[i8]) -> Vec<u8> {
+    tensor_proto(name, dims, INT8, values.iter().map(|&v| v as u8).collect())
+}
+
+fn uint8_scalar(name: &str, value: u8) -> Vec<u8> {
+    tensor_proto(name, &[], UINT8, vec![value])
+}
+
+fn uint8_tensor(name: &str, dims: &[usize], values: &[u8]) -> Vec<u8> {
+    tensor_proto(name, dims, UINT8, values.to_vec())
+}
+
+// The uint8 encoding of int8 values, i.e. shifted by 128
+fn to_uint8(values: &[i8]) -> Vec<u8> {
+    values.iter().map(|&v| (v as i32 + 128) as u8).collect()
+}
+
+fn int32_tensor(name: &str, dims: &[usize], values: &[i32]) -> Vec<u8> {
+    tensor_proto(
+        name,
+        dims,
+        INT32,
+        values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    )
+}
+
+fn int64_tensor(name: &str, values: &[i64]) -> Vec<u8> {
+    tensor_proto(
+        name,
+        &[values.len()],
+        INT64,
+        values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    )
+}
+
+enum Attribute {
+    Int(&'static str, i64),
+    Tensor(&'static str, Vec<u8>),
+}
+
+fn node_proto(op_type: &str, inputs: &[&str], output: &str, attributes: Vec<Attribute>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for input in inputs {
+        bytes_field(&mut buf, 1, input.as_bytes());
+    }
+    bytes_field(&mut buf, 2, output.as_bytes());
+    bytes_field(&mut buf, 3, output.as_bytes());
+    bytes_field(&mut buf, 4, op_type.as_bytes());
+
+    for attribute in attributes {
+        let mut a = Vec::new();
+        match attribute {
+            Attribute::Int(name, value) => {
+                bytes_field(&mut a, 1, name.as_bytes());
+                int_field(&mut a, 3, value);
+                int_field(&mut a, 20, 2);
+            }
+            Attribute::Tensor(name, tensor) => {
+                bytes_field(&mut a, 1, name.as_bytes());
+                bytes_field(&mut a, 5, &tensor);
+                int_field(&mut a, 20, 4);
+            }
+        }
+        bytes_field(&mut buf, 5, &a);
+    }
+
+    buf
+}
+
+// A leading dimension of None is encoded as the symbolic batch dimension "N"
+fn value_info(name: &str, elem_type: i64, dims: &[Option<usize>]) -> Vec<u8> {
+    let mut shape = Vec::new();
+    for d in dims {
+        let mut dim = Vec::new();
+        match d {
+            Some(d) => int_field(&mut dim, 1, *d as i64),
+            None => bytes_field(&mut dim, 2, b"N"),
+        }
+        bytes_field(&mut shape, 1, &dim);
+    }
+
+    let mut tensor_type = Vec::new();
+    int_field(&mut tensor_type, 1, elem_type);
+    bytes_field(&mut tensor_type, 2, &shape);
+
+    let mut type_proto = Vec::new();
+    bytes_field(&mut type_proto, 1, &tensor_type);
+
+    let mut buf = Vec::new();
+    bytes_field(&mut buf, 1, name.as_bytes());
+    bytes_field(&mut buf, 2, &type_proto);
+    buf
+}
+
+fn model_proto(
+    nodes: Vec<Vec<u8>>,
+    initialisers: Vec<Vec<u8>>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+) -> Vec<u8> {
+    let mut graph = Vec::new();
+    for node in nodes {
+        bytes_field(&mut graph, 1, &node);
+    }
+    bytes_field(&mut graph, 2, b"test_graph");
+    for initialiser in initialisers {
+        bytes_field(&mut graph, 5, &initialiser);
+    }
+    bytes_field(&mut graph, 11, &input);
+    bytes_field(&mut graph, 12, &output);
+
+    let mut model = Vec::new();
+    int_field(&mut model, 1, 8);
+    bytes_field(&mut model, 2, b"hcs-tests");
+    bytes_field(&mut model, 7, &graph);
+
+    let mut opset = Vec::new();
+    int_field(&mut opset, 2, 13);
+    bytes_field(&mut model, 8, &opset);
+
+    model
+}
+
+/********************** Test models **********************/
+
+struct Parameters {
+    w1: QArray<i8>,
+    b1: QArray<i32>,
+    w2: QArray<i8>,
+    b2: QArray<i32>,
+}
+
+// Parameters of the two BMM nodes of the two-layer perceptron
+fn parameters() -> Parameters {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+
+    let (Node::BMM(bmm_1), Node::BMM(bmm_2)) = (&model.nodes[1], &model.nodes[4]) else {
+        panic!("Expected BMM nodes");
+    };
+
+    Parameters {
+        w1: bmm_1.weights.clone(),
+        b1: bmm_1.bias.clone(),
+        w2: bmm_2.weights.clone(),
+        b2: bmm_2.bias.clone(),
+    }
+}
+
+// Transposes a matrix stored in our (input, output) layout into the (output,
+// input) layout of PyTorch Linear weights, which are exported with transB = 1
+fn transposed(weights: &QArray<i8>) -> Vec<i8> {
+    let (rows, cols) = (weights.shape()[0], weights.shape()[1]);
+    (0..cols)
+        .flat_map(|c| (0..rows).map(move |r| weights.values()[r * cols + c]))
+        .collect()
+}
+
+// Two-layer perceptron in the QDQ format produced by PyTorch, with a float
+// input of shape (N, 28, 28)
+fn qdq_two_layer_perceptron() -> Vec<u8> {
+    let p = parameters();
+
+    let nodes = vec![
+        node_proto(
+            "QuantizeLinear",
+            &["input", "s_in", "z_in"],
+            "input_q",
+            vec![],
+        ),
+        node_proto(
+            "DequantizeLinear",
+            &["input_q", "s_in", "z_in"],
+            "input_dq",
+            vec![],
+        ),
+        node_proto(
+            "Flatten",
+            &["input_dq"],
+            "flat",
+            vec![Attribute::Int("axis", 1)],
+        ),
+        node_proto("DequantizeLinear", &["w1", "s_w1", "z_w"], "w1_dq", vec![]),
+        node_proto("DequantizeLinear", &["b1", "s_b1"], "b1_dq", vec![]),
+        node_proto(
+            "Gemm",
+            &["flat", "w1_dq", "b1_dq"],
+            "fc1",
+            vec![Attribute::Int("transB", 1)],
+        ),
+        node_proto("Relu", &["fc1"], "relu", vec![]),
+        node_proto("QuantizeLinear", &["relu", "s_h", "z_h"], "relu_q", vec![]),
+        node_proto(
+            "DequantizeLinear",
+            &["relu_q", "s_h", "z_h"],
+            "relu_dq",
+            vec![],
+        ),
+        node_proto("DequantizeLinear", &["w2", "s_w2", "z_w"], "w2_dq", vec![]),
+        node_proto("DequantizeLinear", &["b2", "s_b2"], "b2_dq", vec![]),
+        node_proto(
+            "Gemm",
+            &["relu_dq", "w2_dq", "b2_dq"],
+            "fc2",
+            vec![Attribute::Int("transB", 1)],
+        ),
+        node_proto(
+            "QuantizeLinear",
+            &["fc2", "s_out", "z_out"],
+            "output_q",
+            vec![],
+        ),
+        node_proto(
+            "DequantizeLinear",
+            &["output_q", "s_out", "z_out"],
+            "output",
+            vec![],
+        ),
+    ];
+
+    let initialisers = vec![
+        float_scalar("s_in", S_1_I),
+        uint8_scalar("z_in", (Z_1_I as i32 + 128) as u8),
+        int8_tensor("w1", &[28, 784], &transposed(&p.w1)),
+        float_scalar("s_w1", S_1_W),
+        int8_tensor("z_w", &[], &[0]),
+        int32_tensor("b1", &[28], p.b1.values()),
+        float_scalar("s_b1", S_1_I * S_1_W),
+        float_scalar("s_h", S_1_O),
+        uint8_scalar("z_h", (Z_1_O as i32 + 128) as u8),
+        int8_tensor("w2", &[10, 28], &transposed(&p.w2)),
+        float_scalar("s_w2", S_2_W),
+        int32_tensor("b2", &[10], p.b2.values()),
+        float_scalar("s_b2", S_1_O * S_2_W),
+        float_scalar("s_out", S_2_O),
+        int8_tensor("z_out", &[], &[Z_2_O]),
+    ];
+
+    model_proto(
+        nodes,
+        initialisers,
+        value_info("input", FLOAT, &[None, Some(28), Some(28)]),
+        value_info("output", FLOAT, &[None, Some(10)]),
+    )
+}
+
+// Bias-free two-layer perceptron built out of QLinearMatMul operators, with an
+// int8 input of shape (1, 28, 28) and a Constant target shape for the Reshape
+fn qlinear_two_layer_perceptron() -> Vec<u8> {
+    let p = parameters();
+
+    let nodes = vec![
+        node_proto(
+            "Constant",
+            &[],
+            "target_shape",
+            vec![Attribute::Tensor(
+                "value",
+                int64_tensor("target_shape", &[-1, 784]),
+            )],
+        ),
+        node_proto("Reshape", &["input", "target_shape"], "flat", vec![]),
+        node_proto(
+            "QLinearMatMul",
+            &[
+                "flat", "s_1_i", "z_1_i", "w1", "s_1_w", "z_w", "s_1_o", "z_1_o",
+            ],
+            "fc1",
+            vec![],
+        ),
+        node_proto(
+            "QLinearMatMul",
+            &[
+                "fc1", "s_2_i", "z_2_i", "w2", "s_2_w", "z_w", "s_2_o", "z_2_o",
+            ],
+            "output",
+            vec![],
+        ),
+    ];
+
+    let initialisers = vec![
+        float_scalar("s_1_i", S_1_I),
+        int8_tensor("z_1_i", &[], &[Z_1_I]),
+        int8_tensor("w1", &[784, 28], p.w1.values()),
+        float_scalar("s_1_w", S_1_W),
+        int8_tensor("z_w", &[], &[0]),
+        float_scalar("s_1_o", S_1_O),
+        int8_tensor("z_1_o", &[], &[Z_1_O]),
+        float_scalar("s_2_i", S_1_O),
+        int8_tensor("z_2_i", &[], &[Z_1_O]),
+        int8_tensor("w2", &[28, 10], p.w2.values()),
+        float_scalar("s_2_w", S_2_W),
+        float_scalar("s_2_o", S_2_O),
+        int8_tensor("z_2_o", &[], &[Z_2_O]),
+    ];
+
+    model_proto(
+        nodes,
+        initialisers,
+        value_info("input", INT8, &[Some(1), Some(28), Some(28)]),
+        value_info("output", INT8, &[Some(1), Some(10)]),
+    )
+}
+
+// Single layer in the MatMulInteger format produced by ONNX Runtime, where the
+// int32 product is rescaled in float before being requantised
+fn matmul_integer_layer() -> Vec<u8> {
+    let p = parameters();
+
+    let nodes = vec![
+        node_proto(
+            "MatMulInteger",
+            &["input", "w2", "z_2_i"],
+            "product",
+            vec![],
+        ),
+        node_proto("Add", &["product", "b2"], "biased", vec![]),
+        node_proto(
+            "Cast",
+            &["biased"],
+            "cast",
+            vec![Attribute::Int("to", FLOAT)],
+        ),
+        node_proto("Mul", &["cast", "scale"], "rescaled", vec![]),
+        node_proto(
+            "QuantizeLinear",
+            &["rescaled", "s_2_o", "z_2_o"],
+            "output",
+            vec![],
+        ),
+    ];
+
+    let initialisers = vec![
+        int8_tensor("z_2_i", &[], &[Z_1_O]),
+        int8_tensor("w2", &[28, 10], p.w2.values()),
+        int32_tensor("b2", &[10], p.b2.values()),
+        float_scalar("scale", S_1_O * S_2_W),
+        float_scalar("s_2_o", S_2_O),
+        int8_tensor("z_2_o", &[], &[Z_2_O]),
+    ];
+
+    model_proto(
+        nodes,
+        initialisers,
+        value_info("input", INT8, &[None, Some(28)]),
+        value_info("output", INT8, &[None, Some(10)]),
+    )
+}
+
+// Same layer as `matmul_integer_layer` with a uint8 input and no zero point
+// inputs, which therefore default to the uint8 zero. The weights are uint8
+// if `uint8_weights` is set, and int8 otherwise.
+fn uint8_matmul_integer_layer(uint8_weights: bool) -> Vec<u8> {
+    let p = parameters();
+
+    let nodes = vec![
+        node_proto("MatMulInteger", &["input", "w2"], "product", vec![]),
+        node_proto("Add", &["product", "b2"], "biased", vec![]),
+        node_proto(
+            "Cast",
+            &["biased"],
+            "cast",
+            vec![Attribute::Int("to", FLOAT)],
+        ),
+        node_proto("Mul", &["cast", "scale"], "rescaled", vec![]),
+        node_proto(
+            "QuantizeLinear",
+            &["rescaled", "s_2_o", "z_2_o"],
+            "output",
+            vec![],
+        ),
+    ];
+
+    let weights = if uint8_weights {
+        uint8_tensor("w2", &[28, 10], &to_uint8(p.w2.values()))
+    } else {
+        int8_tensor("w2", &[28, 10], p.w2.values())
+    };
+
+    let initialisers = vec![
+        weights,
+        int32_tensor("b2", &[10], p.b2.values()),
+        float_scalar("scale", S_1_O * S_2_W),
+        float_scalar("s_2_o", S_2_O),
+        int8_tensor("z_2_o", &[], &[Z_2_O]),
+    ];
+
+    model_proto(
+        nodes,
+        initialisers,
+        value_info("input", UINT8, &[None, Some(28)]),
+        value_info("output", INT8, &[None, Some(10)]),
+    )
+}
+
+// Single QDQ layer with a float input whose uint8 weights are dequantised
+// without a zero point, i.e. with an implicit zero point of 128
+fn uint8_weights_qdq_layer() -> Vec<u8> {
+    let p = parameters();
+
+    let nodes = vec![
+        node_proto("QuantizeLinear", &["input", "s_in"], "input_q", vec![]),
+        node_proto("DequantizeLinear", &["input_q", "s_in"], "input_dq", vec![]),
+        node_proto("DequantizeLinear", &["w2", "s_w2"], "w2_dq", vec![]),
+        node_proto("Gemm", &["input_dq", "w2_dq"], "fc", vec![]),
+        node_proto("QuantizeLinear", &["fc", "s_out"], "output_q", vec![]),
+        node_proto("DequantizeLinear", &["output_q", "s_out"], "output", vec![]),
+    ];
+
+    let initialisers = vec![
+        float_scalar("s_in", S_1_O),
+        uint8_tensor("w2", &[28, 10], &to_uint8(p.w2.values())),
+        float_scalar("s_w2", S_2_W),
+        float_scalar("s_out", S_2_O),
+    ];
+
+    model_proto(
+        nodes,
+        initialisers,
+        value_info("input", FLOAT, &[None, Some(28)]),
+        value_info("output", FLOAT, &[None, Some(10)]),
+    )
+}
+
+fn quantised_inference(model: &Model<i8, i32>, raw_input: QArray<f32>) -> QArray<u8> {
+    let quantised_input: QArray<u8> = QArray::new(
+        quantise_f32_u8_nne(raw_input.values(), S_INPUT, Z_INPUT),
+        raw_input.shape().clone(),
+    );
+
+    let input_i8 = (quantised_input.cast::<i32>() - 128).cast::<i8>();
+
+    (model.evaluate(input_i8).cast::<i32>() + 128).cast()
+}
+
+fn node_types(model: &Model<i8, i32>) -> Vec<&str> {
+    model.nodes.iter().map(|n| n.type_name()).collect()
+}
+
+/********************** Tests **********************/
+
+#[test]
+fn test_import_qdq_two_layer_perceptron_structure() {
+    let model = parse_onnx_model(&qdq_two_layer_perceptron()).unwrap();
+
+    assert_eq!(
+        node_types(&model),
+        vec![
+            "Reshape",
+            "BMM",
+            "RequantiseBMM",
+            "ReLU",
+            "BMM",
+            "RequantiseBMM"
+        ]
+    );
+    assert_eq!(model.input_shape, vec![28, 28]);
+    assert_eq!(model.output_shape, vec![10]);
+}
+
+#[test]
+fn test_import_qdq_two_layer_perceptron_outputs() {
+    let model = parse_onnx_model(&qdq_two_layer_perceptron()).unwrap();
+
+    let raw_inputs: Vec<QArray<f32>> =
+        QArray::read_list("examples/two_layer_perceptron_mnist/data/10_test_inputs.json").unwrap();
+    let expected_outputs: Vec<QArray<u8>> =
+        QArray::read_list("examples/two_layer_perceptron_mnist/data/10_test_outputs.json").unwrap();
+
+    for (raw_input, expected_output) in raw_inputs.into_iter().zip(expected_outputs) {
+        assert_eq!(quantised_inference(&model, raw_input), expected_output);
+    }
+}
+
+#[test]
+fn test_import_qlinear_matmul() {
+    let model = parse_onnx_model(&qlinear_two_layer_perceptron()).unwrap();
+
+    let p = parameters();
+
+    let expected_model = Model::new(
+        vec![28, 28],
+        vec![
+            Node::Reshape(ReshapeNode::new(vec![28, 28], vec![784])),
+            Node::BMM(BMMNode::new(
+                p.w1,
+                QArray::new(vec![0; 28], vec![28]),
+                Z_1_I,
+            )),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                28, S_1_I, Z_1_I, S_1_W, 0, S_1_O, Z_1_O,
+            )),
+            Node::BMM(BMMNode::new(
+                p.w2,
+                QArray::new(vec![0; 10], vec![10]),
+                Z_1_O,
+            )),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                10, S_1_O, Z_1_O, S_2_W, 0, S_2_O, Z_2_O,
+            )),
+        ],
+    );
+
+    assert_eq!(
+        node_types(&model),
+        vec!["Reshape", "BMM", "RequantiseBMM", "BMM", "RequantiseBMM"]
+    );
+
+    let raw_inputs: Vec<QArray<f32>> =
+        QArray::read_list("examples/two_layer_perceptron_mnist/data/10_test_inputs.json").unwrap();
+
+    for raw_input in raw_inputs {
+        let input: QArray<i8> = (QArray::<u8>::new(
+            quantise_f32_u8_nne(raw_input.values(), S_INPUT, Z_INPUT),
+            raw_input.shape().clone(),
+        )
+        .cast::<i32>()
+            - 128)
+            .cast();
+
+        assert_eq!(
+            model.evaluate(input.clone()),
+            expected_model.evaluate(input)
+        );
+    }
+}
+
+#[test]
+fn test_import_matmul_integer() {
+    let model = parse_onnx_model(&matmul_integer_layer()).unwrap();
+
+    let p = parameters();
+
+    let expected_model = Model::new(
+        vec![28],
+        vec![
+            Node::BMM(BMMNode::new(p.w2, p.b2, Z_1_O)),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                10,
+                S_1_O * S_2_W,
+                Z_1_O,
+                1.0,
+                0,
+                S_2_O,
+                Z_2_O,
+            )),
+        ],
+    );
+
+    assert_eq!(node_types(&model), vec!["BMM", "RequantiseBMM"]);
+
+    for i in 0..10 {
+        let input = QArray::new(
+            (0..28)
+                .map(|j| ((i * 37 + j * 11) % 256) as u8 as i8)
+                .collect(),
+            vec![28],
+        );
+
+        assert_eq!(
+            model.evaluate(input.clone()),
+            expected_model.evaluate(input)
+        );
+    }
+}
+
+#[test]
+fn test_import_uint8_default_zero_points() {
+    let model = parse_onnx_model(&uint8_matmul_integer_layer(false)).unwrap();
+
+    let p = parameters();
+
+    // The absent uint8 input zero point is -128 after conversion to int8
+    let expected_model = Model::new(
+        vec![28],
+        vec![
+            Node::BMM(BMMNode::new(p.w2, p.b2, -128)),
+            Node::RequantiseBMM(RequantiseBMMNode::new(
+                10,
+                S_1_O * S_2_W,
+                -128,
+                1.0,
+                0,
+                S_2_O,
+                Z_2_O,
+            )),
+        ],
+    );
+
+    assert_eq!(node_types(&model), vec!["BMM", "RequantiseBMM"]);
+
+    for i in 0..10 {
+        let input = QArray::new(
+            (0..28)
+                .map(|j| ((i * 37 + j * 11) % 256) as u8 as i8)
+                .collect(),
+            vec![28],
+        );
+
+        assert_eq!(
+            model.evaluate(input.clone()),
+            expected_model.evaluate(input)
+        );
+    }
+}
+
+#[test]
+fn test_import_uint8_weights_without_zero_point() {
+    // In both cases, the weights have an implicit non-zero zero point
+    for bytes in [uint8_matmul_integer_layer(true), uint8_weights_qdq_layer()] {
+        assert!(matches!(
+            parse_onnx_model(&bytes),
+            Err(ImportError::UnsupportedTensor(_))
+        ));
+    }
+}
+
+#[test]
+fn test_import_unsupported_operator() {
+    let bytes = model_proto(
+        vec![node_proto("Softmax", &["input"], "output", vec![])],
+        vec![],
+        value_info("input", INT8, &[Some(10)]),
+        value_info("output", INT8, &[Some(10)]),
+    );
+
+    assert!(matches!(
+        parse_onnx_model(&bytes),
+        Err(ImportError::UnsupportedOperator(op)) if op == "Softmax"
+    ));
+}
+
+#[test]
+fn test_import_per_axis_quantisation() {
+    let bytes = model_proto(
+        vec![
+            node_proto("DequantizeLinear", &["w", "s_w"], "w_dq", vec![]),
+            node_proto("QuantizeLinear", &["input", "s", "z"], "q", vec![]),
+            node_proto("DequantizeLinear", &["q", "s", "z"], "dq", vec![]),
+            node_proto("MatMul", &["dq", "w_dq"], "output", vec![]),
+        ],
+        vec![
+            int8_tensor("w", &[2, 2], &[1, 2, 3, 4]),
+            tensor_proto(
+                "s_w",
+                &[2],
+                FLOAT,
+                [0.5f32, 0.25]
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+            ),
+            float_scalar("s", 0.5),
+            int8_tensor("z", &[], &[0]),
+        ],
+        value_info("input", FLOAT, &[Some(2)]),
+        value_info("output", FLOAT, &[Some(2)]),
+    );
+
+    assert!(matches!(
+        parse_onnx_model(&bytes),
+        Err(ImportError::UnsupportedTensor(_))
+    ));
+}
+
+// Matrix product whose bias is so close to i32::MAX that the accumulator
+// overflows for large inputs
+#[test]
+fn test_import_overflowing_matmul() {
+    let bytes = model_proto(
+        vec![
+            node_proto("MatMulInteger", &["input", "w", "z"], "product", vec![]),
+            node_proto("Add", &["product", "b"], "biased", vec![]),
+            node_proto(
+                "Cast",
+                &["biased"],
+                "cast",
+                vec![Attribute::Int("to", FLOAT)],
+            ),
+            node_proto("Mul", &["cast", "scale"], "rescaled", vec![]),
+            node_proto(
+                "QuantizeLinear",
+                &["rescaled", "s_o", "z_o"],
+                "output",
+                vec![],
+            ),
+        ],
+        vec![
+            int8_tensor("z", &[], &[0]),
+            int8_tensor("w", &[2, 1], &[127, 127]),
+            int32_tensor("b", &[1], &[i32::MAX - 100]),
+            float_scalar("scale", 0.5),
+            float_scalar("s_o", 0.5),
+            int8_tensor("z_o", &[], &[0]),
+        ],
+        value_info("input", INT8, &[None, Some(2)]),
+        value_info("output", INT8, &[None, Some(1)]),
+    );
+
+    assert!(matches!(
+        parse_onnx_model(&bytes),
+        Err(ImportError::AccumulatorOverflow(_))
+    ));
+}
+
+#[test]
+fn test_import_truncated_file() {
+    let bytes = qdq_two_layer_perceptron();
+
+    assert!(matches!(
+        parse_onnx_model(&bytes[..bytes.len() / 2]),
+        Err(ImportError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_import_missing_onnx_file() {
+    assert!(matches!(
+        read_onnx_model("nonexistent_model.onnx"),
+        Err(ImportError::Io(_))
+    ));
+}
@@ -0,0 +1,218 @@
+// Minimal read-only access to FlatBuffers binaries, covering only what is
+// needed to walk the TF Lite schema: tables, scalar fields, vectors and
+// strings. All accesses are bounds-checked, so a malformed file results in an
+// error rather than a panic.
+
+use crate::model::import::ImportError;
+
+fn malformed(what: &str) -> ImportError {
+    ImportError::Malformed(format!("out-of-bounds {} in FlatBuffer", what))
+}
+
+fn read_slice<'a>(
+    buf: &'a [u8],
+    pos: usize,
+    len: usize,
+    what: &str,
+) -> Result<&'a [u8], ImportError> {
+    buf.get(pos..pos.checked_add(len).ok_or_else(|| malformed(what))?)
+        .ok_or_else(|| malformed(what))
+}
+
+macro_rules! read_le {
+    ($name:ident, $t:ty) => {
+        fn $name(buf: &[u8], pos: usize) -> Result<$t, ImportError> {
+            let bytes = read_slice(buf, pos, ark_std::mem::size_of::<$t>(), stringify!($t))?;
+            Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+        }
+    };
+}
+
+read_le!(read_u8, u8);
+read_le!(read_u16, u16);
+read_le!(read_u32, u32);
+read_le!(read_i32, i32);
+read_le!(read_i64, i64);
+read_le!(read_u64, u64);
+read_le!(read_f32, f32);
+
+/// Follows the unsigned offset stored at `pos`, which is relative to `pos`
+/// itself
+fn follow(buf: &[u8], pos: usize) -> Result<usize, ImportError> {
+    pos.checked_add(read_u32(buf, pos)? as usize)
+        .ok_or_else(|| malformed("offset"))
+}
+
+/// Returns the root table of a FlatBuffer, checking the file identifier if one
+/// is provided
+pub(crate) fn root<'a>(
+    buf: &'a [u8],
+    identifier: Option<&[u8; 4]>,
+) -> Result<Table<'a>, ImportError> {
+    if let Some(identifier) = identifier {
+        if read_slice(buf, 4, 4, "file identifier")? != identifier {
+            return Err(ImportError::Malformed(format!(
+                "expected file identifier {:?}",
+                String::from_utf8_lossy(identifier)
+            )));
+        }
+    }
+
+    Ok(Table {
+        buf,
+        pos: follow(buf, 0)?,
+    })
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Table<'a> {
+    /// Absolute position of the given field, or None if the field is absent
+    /// (in which case it takes its default value)
+    fn field_pos(&self, field: usize) -> Result<Option<usize>, ImportError> {
+        let vtable = (self.pos as i64) - (read_i32(self.buf, self.pos)? as i64);
+
+        if vtable < 0 {
+            return Err(malformed("vtable"));
+        }
+
+        let vtable = vtable as usize;
+        let vtable_len = read_u16(self.buf, vtable)? as usize;
+        let entry = 4 + 2 * field;
+
+        if entry + 2 > vtable_len {
+            return Ok(None);
+        }
+
+        match read_u16(self.buf, vtable + entry)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    pub(crate) fn u8(&self, field: usize, default: u8) -> Result<u8, ImportError> {
+        self.field_pos(field)?
+            .map_or(Ok(default), |p| read_u8(self.buf, p))
+    }
+
+    pub(crate) fn i32(&self, field: usize, default: i32) -> Result<i32, ImportError> {
+        self.field_pos(field)?
+            .map_or(Ok(default), |p| read_i32(self.buf, p))
+    }
+
+    pub(crate) fn u32(&self, field: usize, default: u32) -> Result<u32, ImportError> {
+        self.field_pos(field)?
+            .map_or(Ok(default), |p| read_u32(self.buf, p))
+    }
+
+    pub(crate) fn u64(&self, field: usize, default: u64) -> Result<u64, ImportError> {
+        self.field_pos(field)?
+            .map_or(Ok(default), |p| read_u64(self.buf, p))
+    }
+
+    pub(crate) fn table(&self, field: usize) -> Result<Option<Table<'a>>, ImportError> {
+        self.field_pos(field)?
+            .map(|p| {
+                Ok(Table {
+                    buf: self.buf,
+                    pos: follow(self.buf, p)?,
+                })
+            })
+            .transpose()
+    }
+
+    pub(crate) fn vector(&self, field: usize) -> Result<Option<Vector<'a>>, ImportError> {
+        self.field_pos(field)?
+            .map(|p| {
+                let pos = follow(self.buf, p)?;
+                Ok(Vector {
+                    buf: self.buf,
+                    pos: pos + 4,
+                    len: read_u32(self.buf, pos)? as usize,
+                })
+            })
+            .transpose()
+    }
+
+    pub(crate) fn string(&self, field: usize) -> Result<Option<String>, ImportError> {
+        self.vector(field)?
+            .map(|v| Ok(String::from_utf8_lossy(v.bytes()?).into_owned()))
+            .transpose()
+    }
+
+    /// The underlying buffer, needed to resolve data stored outside the
+    /// FlatBuffer proper (e.g. large TF Lite buffers)
+    pub(crate) fn buffer(&self) -> &'a [u8] {
+        self.buf
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Vector<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    len: usize,
+}
+
+impl<'a> Vector<'a> {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn table(&self, index: usize) -> Result<Table<'a>, ImportError> {
+        self.check_index(index)?;
+        Ok(Table {
+            buf: self.buf,
+            pos: follow(self.buf, self.pos + 4 * index)?,
+        })
+    }
+
+    pub(crate) fn i32(&self, index: usize) -> Result<i32, ImportError> {
+        self.check_index(index)?;
+        read_i32(self.buf, self.pos + 4 * index)
+    }
+
+    pub(crate) fn i64(&self, index: usize) -> Result<i64, ImportError> {
+        self.check_index(index)?;
+        read_i64(self.buf, self.pos + 8 * index)
+    }
+
+    pub(crate) fn f32(&self, index: usize) -> Result<f32, ImportError> {
+        self.check_index(index)?;
+        read_f32(self.buf, self.pos + 4 * index)
+    }
+
+    /// Interprets the vector as raw bytes (a [ubyte] vector or a string)
+    pub(crate) fn bytes(&self) -> Result<&'a [u8], ImportError> {
+        read_slice(self.buf, self.pos, self.len, "byte vector")
+    }
+
+    pub(crate) fn i32s(&self) -> Result<Vec<i32>, ImportError> {
+        (0..self.len).map(|i| self.i32(i)).collect()
+    }
+
+    pub(crate) fn i64s(&self) -> Result<Vec<i64>, ImportError> {
+        (0..self.len).map(|i| self.i64(i)).collect()
+    }
+
+    pub(crate) fn f32s(&self) -> Result<Vec<f32>, ImportError> {
+        (0..self.len).map(|i| self.f32(i)).collect()
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), ImportError> {
+        if index >= self.len {
+            return Err(malformed("vector index"));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a range of an arbitrary buffer, with the same error reporting as
+/// FlatBuffer accesses
+pub(crate) fn bytes_at(buf: &[u8], pos: usize, len: usize) -> Result<&[u8], ImportError> {
+    read_slice(buf, pos, len, "external buffer")
+}
@@ -0,0 +1,525 @@
+// Importer for quantised TF Lite models (.tflite FlatBuffers). Only sequential
+// int8 graphs built out of the operators listed below are supported; anything
+// else results in an ImportError. The uint8 <-> int8 QUANTIZE operators which
+// TF Lite places at the boundaries of the graph are skipped, since the
+// quantisation of model inputs and outputs happens outside of our models (cf.
+// the examples).
+
+use crate::model::import::{bmm_node, ImportError};
+use crate::model::nodes::{
+    relu::ReLUNode, requantise_bmm::RequantiseBMMNode, reshape::ReshapeNode, Node,
+};
+use crate::model::qarray::QArray;
+use crate::model::Model;
+use crate::quantization::QScaleType;
+
+use self::flatbuffer::{Table, Vector};
+
+mod flatbuffer;
+
+#[cfg(test)]
+mod tests;
+
+const TFLITE_FILE_IDENTIFIER: &[u8; 4] = b"TFL3";
+
+// Builtin operator codes, cf. BuiltinOperator in the TF Lite schema
+const DEQUANTIZE: i32 = 6;
+const FULLY_CONNECTED: i32 = 9;
+const RELU: i32 = 19;
+const RESHAPE: i32 = 22;
+const CUSTOM: i32 = 32;
+const QUANTIZE: i32 = 114;
+
+// Tensor types, cf. TensorType in the TF Lite schema
+const TENSOR_INT32: u8 = 2;
+const TENSOR_UINT8: u8 = 3;
+const TENSOR_INT8: u8 = 9;
+
+// Fused activation functions, cf. ActivationFunctionType in the TF Lite schema
+const ACTIVATION_NONE: u8 = 0;
+const ACTIVATION_RELU: u8 = 1;
+
+/// Reads a quantised TF Lite model from the file at `path`
+pub fn read_tflite_model(path: &str) -> Result<Model<i8, i32>, ImportError> {
+    parse_tflite_model(&std::fs::read(path)?)
+}
+
+/// Parses a quantised TF Lite model from the contents of a .tflite file.
+///
+/// Supported operators are FULLY_CONNECTED (with optional fused ReLU), which is
+/// mapped to a BMM node followed by a requantisation node; RESHAPE and RELU.
+/// QUANTIZE and DEQUANTIZE are only allowed at the graph boundaries.
+pub fn parse_tflite_model(bytes: &[u8]) -> Result<Model<i8, i32>, ImportError> {
+    let model = flatbuffer::root(bytes, Some(TFLITE_FILE_IDENTIFIER))?;
+
+    let operator_codes = required_vector(&model, 1, "Model.operator_codes")?;
+    let operator_codes = (0..operator_codes.len())
+        .map(|i| parse_operator_code(&operator_codes.table(i)?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let buffers = required_vector(&model, 4, "Model.buffers")?;
+    let subgraphs = required_vector(&model, 2, "Model.subgraphs")?;
+
+    if subgraphs.len() != 1 {
+        return Err(ImportError::UnsupportedTopology(format!(
+            "expected a single subgraph, found {}",
+            subgraphs.len()
+        )));
+    }
+
+    let subgraph = subgraphs.table(0)?;
+
+    let tensors = required_vector(&subgraph, 0, "SubGraph.tensors")?;
+    let tensors = (0..tensors.len())
+        .map(|i| TFLiteTensor::parse(&tensors.table(i)?, &buffers))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let graph_input = single_index(&subgraph, 1, "input")?;
+    let graph_output = single_index(&subgraph, 2, "output")?;
+
+    let operators = required_vector(&subgraph, 3, "SubGraph.operators")?;
+
+    let mut nodes = Vec::new();
+    let mut input_shape = None;
+    let mut current = graph_input;
+
+    for i in 0..operators.len() {
+        let operator = operators.table(i)?;
+
+        let opcode_index = operator.u32(0, 0)? as usize;
+        let (code, name) = operator_codes.get(opcode_index).cloned().ok_or_else(|| {
+            ImportError::Malformed(format!("operator code index {} out of range", opcode_index))
+        })?;
+
+        let inputs = operator.vector(1)?.map_or(Ok(vec![]), |v| v.i32s())?;
+        let outputs = operator.vector(2)?.map_or(Ok(vec![]), |v| v.i32s())?;
+
+        if inputs.first() != Some(&(current as i32)) || outputs.len() != 1 {
+            return Err(ImportError::UnsupportedTopology(format!(
+                "operator {} ({}) does not consume the output of the previous operator",
+                i, name
+            )));
+        }
+
+        let input = tensor(&tensors, current)?;
+        let output_index = outputs[0] as usize;
+        let output = tensor(&tensors, output_index)?;
+
+        let is_first = nodes.is_empty() && current == graph_input;
+        let is_last = output_index == graph_output;
+
+        current = output_index;
+
+        // Boundary (de)quantisation is not part of the model
+        if (code == QUANTIZE && (is_first || is_last)) || (code == DEQUANTIZE && is_last) {
+            continue;
+        }
+
+        if input_shape.is_none() {
+            input.check_type(TENSOR_INT8)?;
+            input_shape = Some(input.unbatched_shape());
+        }
+
+        match code {
+            FULLY_CONNECTED => {
+                let weights = tensor_from_index(&tensors, inputs.get(1))?.ok_or_else(|| {
+                    ImportError::Malformed(format!("operator {} has no weight tensor", i))
+                })?;
+                let bias = tensor_from_index(&tensors, inputs.get(2))?;
+
+                let fused_activation = match operator.table(4)? {
+                    Some(options) => options.u8(0, ACTIVATION_NONE)?,
+                    None => ACTIVATION_NONE,
+                };
+
+                nodes.extend(fully_connected_nodes(
+                    input,
+                    weights,
+                    bias,
+                    output,
+                    fused_activation,
+                )?);
+            }
+            RESHAPE => {
+                let (from, to) = (input.unbatched_shape(), output.unbatched_shape());
+
+                if from != to {
+                    nodes.push(Node::Reshape(ReshapeNode::new(from, to)));
+                }
+            }
+            RELU => {
+                let (s_i, z_i) = input.quantisation()?;
+                let (s_o, z_o) = output.quantisation()?;
+
+                if s_i != s_o || z_i != z_o {
+                    return Err(ImportError::UnsupportedOperator(
+                        "RELU with different input and output quantisation".to_string(),
+                    ));
+                }
+
+                nodes.push(Node::ReLU(ReLUNode::new(input.one_dimensional_len()?, z_i)));
+            }
+            _ => return Err(ImportError::UnsupportedOperator(name)),
+        }
+    }
+
+    if current != graph_output {
+        return Err(ImportError::UnsupportedTopology(
+            "the last operator does not produce the graph output".to_string(),
+        ));
+    }
+
+    if nodes.is_empty() {
+        return Err(ImportError::UnsupportedTopology(
+            "the model contains no nodes".to_string(),
+        ));
+    }
+
+    Model::try_new(input_shape.unwrap(), nodes).map_err(|e| ImportError::Malformed(e.to_string()))
+}
+
+// Converts a quantised FULLY_CONNECTED operator, whose weight matrix is stored
+// as (output, input) by TF Lite, into our vec-by-mat BMM and requantisation
+// nodes, possibly followed by a ReLU
+fn fully_connected_nodes(
+    input: &TFLiteTensor,
+    weights: &TFLiteTensor,
+    bias: Option<&TFLiteTensor>,
+    output: &TFLiteTensor,
+    fused_activation: u8,
+) -> Result<Vec<Node<i8, i32>>, ImportError> {
+    weights.check_type(TENSOR_INT8)?;
+    output.check_type(TENSOR_INT8)?;
+
+    let (s_i, z_i) = input.quantisation()?;
+    let (s_w, z_w) = weights.quantisation()?;
+    let (s_o, z_o) = output.quantisation()?;
+
+    if z_w != 0 {
+        return Err(ImportError::UnsupportedTensor(format!(
+            "weight tensor {} has non-zero zero point {}",
+            weights.name, z_w
+        )));
+    }
+
+    let (out_dim, in_dim) = match weights.shape[..] {
+        [out_dim, in_dim] => (out_dim, in_dim),
+        _ => {
+            return Err(ImportError::UnsupportedTensor(format!(
+                "weight tensor {} has shape {:?}, expected two dimensions",
+                weights.name, weights.shape
+            )))
+        }
+    };
+
+    if input.one_dimensional_len()? != in_dim {
+        return Err(ImportError::UnsupportedTensor(format!(
+            "input tensor {} does not match the {} columns of weight tensor {}",
+            input.name, in_dim, weights.name
+        )));
+    }
+
+    let w = weights.data_i8(out_dim * in_dim)?;
+
+    // Transposing into the row-major (input, output) layout used by BMMNode
+    let transposed = (0..in_dim)
+        .flat_map(|row| (0..out_dim).map(move |col| (row, col)))
+        .map(|(row, col)| w[col * in_dim + row])
+        .collect();
+
+    let bias = match bias {
+        Some(bias) => {
+            bias.check_type(TENSOR_INT32)?;
+            bias.data_i32(out_dim)?
+        }
+        None => vec![0; out_dim],
+    };
+
+    let mut nodes = vec![
+        bmm_node(
+            QArray::new(transposed, vec![in_dim, out_dim]),
+            QArray::new(bias, vec![out_dim]),
+            z_i,
+        )?,
+        Node::RequantiseBMM(RequantiseBMMNode::new(
+            out_dim, s_i, z_i, s_w, z_w, s_o, z_o,
+        )),
+    ];
+
+    match fused_activation {
+        ACTIVATION_NONE => {}
+        ACTIVATION_RELU => nodes.push(Node::ReLU(ReLUNode::new(out_dim, z_o))),
+        other => {
+            return Err(ImportError::UnsupportedOperator(format!(
+                "FULLY_CONNECTED with fused activation function {}",
+                activation_name(other)
+            )))
+        }
+    }
+
+    Ok(nodes)
+}
+
+struct TFLiteTensor {
+    name: String,
+    shape: Vec<usize>,
+    tensor_type: u8,
+    scale: Vec<f32>,
+    zero_point: Vec<i64>,
+    data: Vec<u8>,
+}
+
+impl TFLiteTensor {
+    fn parse(table: &Table, buffers: &Vector) -> Result<Self, ImportError> {
+        let name = table.string(3)?.unwrap_or_default();
+
+        let shape = table
+            .vector(0)?
+            .map_or(Ok(vec![]), |v| v.i32s())?
+            .into_iter()
+            .map(|d| {
+                usize::try_from(d).map_err(|_| {
+                    ImportError::UnsupportedTensor(format!("tensor {} has dynamic shape", name))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tensor_type = table.u8(1, 0)?;
+
+        let (scale, zero_point) = match table.table(4)? {
+            Some(q) => (
+                q.vector(2)?.map_or(Ok(vec![]), |v| v.f32s())?,
+                q.vector(3)?.map_or(Ok(vec![]), |v| v.i64s())?,
+            ),
+            None => (vec![], vec![]),
+        };
+
+        // Buffer 0 is, by convention, the empty buffer
+        let buffer_index = table.u32(2, 0)? as usize;
+
+        if buffer_index >= buffers.len() {
+            return Err(ImportError::Malformed(format!(
+                "tensor {} refers to non-existent buffer {}",
+                name, buffer_index
+            )));
+        }
+
+        let data = buffer_data(&buffers.table(buffer_index)?)?;
+
+        Ok(Self {
+            name,
+            shape,
+            tensor_type,
+            scale,
+            zero_point,
+            data,
+        })
+    }
+
+    // TF Lite activations carry a leading batch dimension of 1, which our
+    // models do not have
+    fn unbatched_shape(&self) -> Vec<usize> {
+        match self.shape[..] {
+            [1, ref rest @ ..] if !rest.is_empty() => rest.to_vec(),
+            _ => self.shape.clone(),
+        }
+    }
+
+    fn one_dimensional_len(&self) -> Result<usize, ImportError> {
+        match self.unbatched_shape()[..] {
+            [len] => Ok(len),
+            ref shape => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} has shape {:?}, expected a single (non-batch) dimension",
+                self.name, shape
+            ))),
+        }
+    }
+
+    fn check_type(&self, expected: u8) -> Result<(), ImportError> {
+        if self.tensor_type != expected {
+            return Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} has type {}, expected {}",
+                self.name,
+                tensor_type_name(self.tensor_type),
+                tensor_type_name(expected)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Per-tensor quantisation parameters. Per-axis quantisation is not
+    /// supported.
+    fn quantisation(&self) -> Result<(QScaleType, i8), ImportError> {
+        match (&self.scale[..], &self.zero_point[..]) {
+            ([scale], [zero_point]) => {
+                let zero_point = i8::try_from(*zero_point).map_err(|_| {
+                    ImportError::UnsupportedTensor(format!(
+                        "tensor {} has zero point {} outside the i8 range",
+                        self.name, zero_point
+                    ))
+                })?;
+                Ok((*scale, zero_point))
+            }
+            ([], _) => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} is not quantised",
+                self.name
+            ))),
+            _ => Err(ImportError::UnsupportedTensor(format!(
+                "tensor {} uses per-axis quantisation",
+                self.name
+            ))),
+        }
+    }
+
+    fn data_i8(&self, len: usize) -> Result<Vec<i8>, ImportError> {
+        self.check_data_len(len, 1)?;
+        Ok(self.data.iter().map(|b| *b as i8).collect())
+    }
+
+    fn data_i32(&self, len: usize) -> Result<Vec<i32>, ImportError> {
+        self.check_data_len(len, 4)?;
+        Ok(self
+            .data
+            .chunks_exact(4)
+            .map(|c| i32::from_le_bytes(c.try_into().unwrap()))
+            .collect())
+    }
+
+    fn check_data_len(&self, len: usize, size: usize) -> Result<(), ImportError> {
+        if self.data.len() != len * size {
+            return Err(ImportError::Malformed(format!(
+                "tensor {} has {} bytes of data, expected {}",
+                self.name,
+                self.data.len(),
+                len * size
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Buffers are either stored inline or, for large models, appended after the
+// FlatBuffer and referenced by (offset, size)
+fn buffer_data(buffer: &Table) -> Result<Vec<u8>, ImportError> {
+    if let Some(data) = buffer.vector(0)? {
+        return Ok(data.bytes()?.to_vec());
+    }
+
+    let offset = buffer.u64(1, 0)? as usize;
+    let size = buffer.u64(2, 0)? as usize;
+
+    if offset > 1 {
+        Ok(flatbuffer::bytes_at(buffer.buffer(), offset, size)?.to_vec())
+    } else {
+        Ok(vec![])
+    }
+}
+
+// Returns the builtin code and a human-readable name of an operator
+fn parse_operator_code(table: &Table) -> Result<(i32, String), ImportError> {
+    // Older files only populate the deprecated 8-bit code, newer ones store
+    // the actual code in a separate 32-bit field
+    let deprecated_code = table.u8(0, 0)? as i8 as i32;
+    let code = table.i32(3, 0)?.max(deprecated_code);
+
+    let name = if code == CUSTOM {
+        format!("CUSTOM ({})", table.string(1)?.unwrap_or_default())
+    } else {
+        builtin_operator_name(code)
+    };
+
+    Ok((code, name))
+}
+
+fn required_vector<'a>(
+    table: &Table<'a>,
+    field: usize,
+    name: &str,
+) -> Result<Vector<'a>, ImportError> {
+    table
+        .vector(field)?
+        .ok_or_else(|| ImportError::Malformed(format!("missing {}", name)))
+}
+
+fn single_index(subgraph: &Table, field: usize, name: &str) -> Result<usize, ImportError> {
+    let indices = subgraph.vector(field)?.map_or(Ok(vec![]), |v| v.i32s())?;
+
+    match indices[..] {
+        [index] if index >= 0 => Ok(index as usize),
+        _ => Err(ImportError::UnsupportedTopology(format!(
+            "expected a single graph {}, found {}",
+            name,
+            indices.len()
+        ))),
+    }
+}
+
+fn tensor(tensors: &[TFLiteTensor], index: usize) -> Result<&TFLiteTensor, ImportError> {
+    tensors
+        .get(index)
+        .ok_or_else(|| ImportError::Malformed(format!("tensor index {} out of range", index)))
+}
+
+// Optional operator inputs are marked with the index -1
+fn tensor_from_index<'a>(
+    tensors: &'a [TFLiteTensor],
+    index: Option<&i32>,
+) -> Result<Option<&'a TFLiteTensor>, ImportError> {
+    match index {
+        Some(&index) if index >= 0 => tensor(tensors, index as usize).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn builtin_operator_name(code: i32) -> String {
+    let name = match code {
+        0 => "ADD",
+        1 => "AVERAGE_POOL_2D",
+        2 => "CONCATENATION",
+        3 => "CONV_2D",
+        4 => "DEPTHWISE_CONV_2D",
+        DEQUANTIZE => "DEQUANTIZE",
+        FULLY_CONNECTED => "FULLY_CONNECTED",
+        14 => "LOGISTIC",
+        17 => "MAX_POOL_2D",
+        18 => "MUL",
+        RELU => "RELU",
+        20 => "RELU_N1_TO_1",
+        21 => "RELU6",
+        RESHAPE => "RESHAPE",
+        25 => "SOFTMAX",
+        28 => "TANH",
+        40 => "MEAN",
+        43 => "SQUEEZE",
+        QUANTIZE => "QUANTIZE",
+        _ => return format!("BUILTIN_{}", code),
+    };
+    name.to_string()
+}
+
+fn tensor_type_name(tensor_type: u8) -> String {
+    let name = match tensor_type {
+        0 => "FLOAT32",
+        1 => "FLOAT16",
+        TENSOR_INT32 => "INT32",
+        TENSOR_UINT8 => "UINT8",
+        4 => "INT64",
+        7 => "INT16",
+        TENSOR_INT8 => "INT8",
+        _ => return format!("TYPE_{}", tensor_type),
+    };
+    name.to_string()
+}
+
+fn activation_name(activation: u8) -> String {
+    let name = match activation {
+        ACTIVATION_NONE => "NONE",
+        ACTIVATION_RELU => "RELU",
+        2 => "RELU_N1_TO_1",
+        3 => "RELU6",
+        4 => "TANH",
+        5 => "SIGN_BIT",
+        _ => return format!("ACTIVATION_{}", activation),
+    };
+    name.to_string()
+}
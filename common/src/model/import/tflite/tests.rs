@@ -0,0 +1,172 @@
+use super::*;
+
+use crate::quantise_f32_u8_nne;
+
+const S_INPUT: f32 = 0.003921568859368563;
+const Z_INPUT: u8 = 0;
+
+macro_rules! MODEL_PATH {
+    () => {
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../exploring_tf_lite/models/{}"
+        )
+    };
+}
+
+// Returns a copy of a TF Lite file in which every operator with the builtin
+// code `from` is replaced by one with code `to`, by overwriting both the
+// deprecated 8-bit and the 32-bit code fields of its OperatorCode table
+fn replace_operator_code(bytes: &[u8], from: i32, to: i32) -> Vec<u8> {
+    let read_u32 = |pos: usize| u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap());
+    let follow = |pos: usize| pos + read_u32(pos) as usize;
+
+    // Absolute position of a field of the table at `table`, if present
+    let field_pos = |table: usize, field: usize| {
+        let soffset = i32::from_le_bytes(bytes[table..table + 4].try_into().unwrap());
+        let vtable = (table as i64 - soffset as i64) as usize;
+        let vtable_len = u16::from_le_bytes(bytes[vtable..vtable + 2].try_into().unwrap()) as usize;
+        let entry = 4 + 2 * field;
+
+        if entry + 2 > vtable_len {
+            return None;
+        }
+
+        match u16::from_le_bytes(
+            bytes[vtable + entry..vtable + entry + 2]
+                .try_into()
+                .unwrap(),
+        ) {
+            0 => None,
+            offset => Some(table + offset as usize),
+        }
+    };
+
+    let mut patched = bytes.to_vec();
+
+    let operator_codes = follow(field_pos(follow(0), 1).unwrap());
+
+    for i in 0..read_u32(operator_codes) as usize {
+        let table = follow(operator_codes + 4 + 4 * i);
+        let deprecated_code = field_pos(table, 0);
+        let code = field_pos(table, 3);
+
+        let current = code
+            .map_or(0, |p| read_u32(p) as i32)
+            .max(deprecated_code.map_or(0, |p| bytes[p] as i8 as i32));
+
+        if current == from {
+            if let Some(p) = deprecated_code {
+                patched[p] = to as u8;
+            }
+            if let Some(p) = code {
+                patched[p..p + 4].copy_from_slice(&to.to_le_bytes());
+            }
+        }
+    }
+
+    patched
+}
+
+fn quantised_inference(model: &Model<i8, i32>, raw_input: QArray<f32>) -> QArray<u8> {
+    let quantised_input: QArray<u8> = QArray::new(
+        quantise_f32_u8_nne(raw_input.values(), S_INPUT, Z_INPUT),
+        raw_input.shape().clone(),
+    );
+
+    let input_i8 = (quantised_input.cast::<i32>() - 128).cast::<i8>();
+
+    (model.evaluate(input_i8).cast::<i32>() + 128).cast()
+}
+
+#[test]
+fn test_import_two_layer_perceptron_structure() {
+    let model = read_tflite_model(&format!(MODEL_PATH!(), "two_layer_perceptron.tflite")).unwrap();
+
+    let node_types: Vec<&str> = model.nodes.iter().map(|n| n.type_name()).collect();
+
+    assert_eq!(
+        node_types,
+        vec![
+            "Reshape",
+            "BMM",
+            "RequantiseBMM",
+            "ReLU",
+            "BMM",
+            "RequantiseBMM"
+        ]
+    );
+    assert_eq!(model.input_shape, vec![28, 28]);
+    assert_eq!(model.output_shape, vec![10]);
+}
+
+#[test]
+fn test_import_two_layer_perceptron_outputs() {
+    let model = read_tflite_model(&format!(MODEL_PATH!(), "two_layer_perceptron.tflite")).unwrap();
+
+    let raw_inputs: Vec<QArray<f32>> =
+        QArray::read_list("examples/two_layer_perceptron_mnist/data/10_test_inputs.json").unwrap();
+    let expected_outputs: Vec<QArray<u8>> =
+        QArray::read_list("examples/two_layer_perceptron_mnist/data/10_test_outputs.json").unwrap();
+
+    for (raw_input, expected_output) in raw_inputs.into_iter().zip(expected_outputs) {
+        assert_eq!(quantised_inference(&model, raw_input), expected_output);
+    }
+}
+
+#[test]
+fn test_import_simple_perceptron_outputs() {
+    let model = read_tflite_model(&format!(MODEL_PATH!(), "simple_model_quant.tflite")).unwrap();
+
+    let raw_inputs: Vec<QArray<f32>> =
+        QArray::read_list("examples/simple_perceptron_mnist/data/10_test_inputs.json").unwrap();
+    let expected_outputs: Vec<QArray<u8>> =
+        QArray::read_list("examples/simple_perceptron_mnist/data/10_test_outputs.json").unwrap();
+
+    for (raw_input, expected_output) in raw_inputs.into_iter().zip(expected_outputs) {
+        assert_eq!(quantised_inference(&model, raw_input), expected_output);
+    }
+}
+
+#[test]
+fn test_import_unsupported_operator() {
+    let bytes = std::fs::read(format!(MODEL_PATH!(), "two_layer_perceptron.tflite")).unwrap();
+
+    for (code, name) in [(3, "CONV_2D"), (25, "SOFTMAX")] {
+        let patched = replace_operator_code(&bytes, FULLY_CONNECTED, code);
+
+        assert!(matches!(
+            parse_tflite_model(&patched),
+            Err(ImportError::UnsupportedOperator(op)) if op == name
+        ));
+    }
+}
+
+#[test]
+fn test_import_wrong_identifier() {
+    let mut bytes = std::fs::read(format!(MODEL_PATH!(), "simple_model_quant.tflite")).unwrap();
+    bytes[4..8].copy_from_slice(b"ONNX");
+
+    assert!(matches!(
+        parse_tflite_model(&bytes),
+        Err(ImportError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_import_truncated() {
+    let bytes = std::fs::read(format!(MODEL_PATH!(), "simple_model_quant.tflite")).unwrap();
+
+    assert!(matches!(
+        parse_tflite_model(&bytes[..bytes.len() / 2]),
+        Err(ImportError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_import_missing_file() {
+    assert!(matches!(
+        read_tflite_model("non_existent.tflite"),
+        Err(ImportError::Io(_))
+    ));
+}
@@ -2,49 +2,276 @@ use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
 use ark_poly::DenseMultilinearExtension;
 use ark_poly_commit::{LabeledCommitment, LabeledPolynomial, PolynomialCommitment};
-use ark_std::rand::RngCore;
+use ark_std::{log2, rand::RngCore};
 
+use crate::model::config::{ProofConfig, ProofConfigError, SecurePCS};
+use crate::model::encoding::FieldEncoding;
 use crate::model::nodes::Node;
+use crate::model::setup::{SetupError, SetupKeys, UniversalSetup};
 
 use self::qarray::InnerType;
 use self::qarray::QTypeArray;
-use self::{nodes::NodeProof, qarray::QArray};
+use self::{
+    nodes::{NodeProof, NodeReductionProof},
+    qarray::QArray,
+};
 
+pub mod builder;
+pub mod config;
+pub mod encoding;
+pub mod evaluation;
+pub(crate) mod import;
 pub mod nodes;
+pub mod optimisation;
 pub mod qarray;
+pub(crate) mod serialisation;
+pub mod setup;
+pub mod validation;
 
 pub type Poly<F> = DenseMultilinearExtension<F>;
 pub type LabeledPoly<F> = LabeledPolynomial<F, DenseMultilinearExtension<F>>;
 
-pub struct InferenceProof<F, S, PCS, ST, LT>
+/// Proof of a batch of inferences (possibly of size one) of a model. Node
+/// values are committed to with `PCS`, whereas the node parameters are
+/// committed to with `WPCS` (cf. `Model::setup_weight_keys`).
+pub struct InferenceProof<F, S, PCS, WPCS, ST, LT>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    // Model input tensors in plain
+    // Model input tensors in plain, one for each inference in the batch
     pub inputs: Vec<QTypeArray<ST, LT>>,
 
-    // Model output tensors in plain
+    // Model output tensors in plain, one for each inference in the batch
     pub outputs: Vec<QTypeArray<ST, LT>>,
 
-    // Commitments to each of the node values
+    // Commitments to the node values (the model input included). The values
+    // of a node across the batch are committed to as a single MLE whose last
+    // variables index the inference. There is either one commitment for each
+    // node value or, if the prover packed them (cf. NodeValueCommitment), a
+    // single one
     pub node_value_commitments: Vec<LabeledCommitment<PCS::Commitment>>,
 
     // Proofs of evaluation of each of the model's nodes
-    pub node_proofs: Vec<NodeProof<F, S, PCS>>,
+    pub node_proofs: Vec<NodeProof<F, S, PCS, WPCS>>,
 
-    // Proofs of opening of each of the model's inputs
+    // Proofs of opening of each of the model's inputs (across the batch)
     pub input_opening_proofs: Vec<PCS::Proof>,
 
-    // Proofs of opening of each of the model's outputs
+    // Proofs of opening of each of the model's outputs (across the batch)
     pub output_opening_proofs: Vec<PCS::Proof>,
 }
 
+/// Layered (GKR-style) proof of a batch of inferences (possibly of size one)
+/// of a model. A claim about the model output is reduced node by node to one
+/// about the model input, which the verifier checks in plain. The values at
+/// the boundaries of nodes without a claim reduction are committed to (cf.
+/// `Model::layer_boundaries`): a claim reaching one of these is checked by
+/// opening the commitment, and the reduction of the preceding nodes starts
+/// from an opening at a fresh challenge point. Since `ReLUNode` and
+/// `RequantiseBMMNode` have no claim reduction, a model alternating BMMs,
+/// requantisations and ReLUs still commits to the values on both sides of
+/// every requantisation. As in `InferenceProof`, `WPCS` is the PCS of the
+/// node parameters.
+pub struct LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    // Model input tensors in plain, one for each inference in the batch
+    pub inputs: Vec<QTypeArray<ST, LT>>,
+
+    // Model output tensors in plain, one for each inference in the batch
+    pub outputs: Vec<QTypeArray<ST, LT>>,
+
+    // Commitments to the node values at the layer boundaries, in the order
+    // of Model::layer_boundaries. As in InferenceProof, the values of a node
+    // across the batch are committed to as a single MLE.
+    pub boundary_commitments: Vec<LabeledCommitment<PCS::Commitment>>,
+
+    // Proofs of the claim reduction of each of the model's nodes, or None for
+    // those which do not have one
+    pub node_proofs: Vec<Option<NodeReductionProof<F, S, PCS, WPCS>>>,
+
+    // Values of each of the committed node values at the point of its only
+    // claim and proofs of opening, in the same order as the commitments
+    pub boundary_opening_values: Vec<F>,
+    pub boundary_opening_proofs: Vec<PCS::Proof>,
+}
+
+/// Commitment through which the values of a node (across the batch) are
+/// opened. The values are either committed to on their own, or packed
+/// together with those of all other nodes into a single MLE: the values of
+/// the i-th node (the model input being the 0-th) are padded with zeros to the
+/// largest number of variables and placed in the i-th slot, indexed by the
+/// last (selector) variables. Opening the values at a point then amounts to
+/// opening the packed MLE at that point extended by `point_suffix`: the zeros
+/// of the padding followed by the bits of i.
+pub struct NodeValueCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub com: LabeledCommitment<PCS::Commitment>,
+    pub point_suffix: Vec<F>,
+}
+
+impl<F, S, PCS> NodeValueCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// Node value commitments given by separate commitments to each MLE
+    pub fn separate(coms: &[LabeledCommitment<PCS::Commitment>]) -> Vec<Self> {
+        coms.iter()
+            .map(|com| Self {
+                com: com.clone(),
+                point_suffix: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Node value commitments given by a commitment to the packed MLE of
+    /// values with the given numbers of variables
+    pub fn packed(com: &LabeledCommitment<PCS::Commitment>, value_num_vars: &[usize]) -> Vec<Self> {
+        let slot_num_vars = value_num_vars.iter().max().unwrap();
+        let num_selector_vars = log2(value_num_vars.len()) as usize;
+
+        value_num_vars
+            .iter()
+            .enumerate()
+            .map(|(i, num_vars)| Self {
+                com: com.clone(),
+                point_suffix: (*num_vars..*slot_num_vars)
+                    .map(|_| F::zero())
+                    .chain((0..num_selector_vars).map(|j| F::from(((i >> j) & 1) as u64)))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Number of variables of the packed MLE of values with the given numbers
+    /// of variables
+    pub fn packed_num_vars(value_num_vars: &[usize]) -> usize {
+        value_num_vars.iter().max().unwrap() + log2(value_num_vars.len()) as usize
+    }
+
+    /// Point at which the committed MLE must be opened to obtain the
+    /// evaluation of the node values at `point`
+    pub fn point(&self, point: &[F]) -> Vec<F> {
+        point
+            .iter()
+            .chain(self.point_suffix.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+/// A PCS opening which the verifier has yet to check: the claim that the
+/// committed polynomials evaluate to `values` at `point`, as attested by
+/// `proof`. Opening proofs are computed on a fork of the transcript, whose
+/// state at that point is kept in `sponge`, so their checks can be deferred
+/// until the rest of the proof (or of several proofs) has been verified, and
+/// then batched (cf. `SecurePCS::batch_check`).
+pub struct OpeningClaim<F, S, PCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub commitments: Vec<LabeledCommitment<PCS::Commitment>>,
+    pub point: Vec<F>,
+    pub values: Vec<F>,
+    pub proof: PCS::Proof,
+    pub sponge: S,
+}
+
+impl<F, S, PCS> OpeningClaim<F, S, PCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// Records the claim, forking the transcript at its current state
+    pub fn new(
+        commitments: Vec<LabeledCommitment<PCS::Commitment>>,
+        point: Vec<F>,
+        values: Vec<F>,
+        proof: PCS::Proof,
+        sponge: &S,
+    ) -> Self {
+        Self {
+            commitments,
+            point,
+            values,
+            proof,
+            sponge: sponge.clone(),
+        }
+    }
+
+    /// Records the claim that the node values committed to through `com`
+    /// evaluate to `value` at `point`, which refers to the node values alone
+    /// (cf. `NodeValueCommitment::point`)
+    pub fn node_value(
+        com: &NodeValueCommitment<F, S, PCS>,
+        point: &[F],
+        value: F,
+        proof: PCS::Proof,
+        sponge: &S,
+    ) -> Self {
+        Self::new(
+            vec![com.com.clone()],
+            com.point(point),
+            vec![value],
+            proof,
+            sponge,
+        )
+    }
+
+    /// Checks the opening proof. Malformed claims (e.g. with a point of the
+    /// wrong length) are rejected rather than reported as errors.
+    pub fn check(&self, vk: &PCS::VerifierKey) -> bool {
+        // TODO possibly rng, not None
+        PCS::check(
+            vk,
+            &self.commitments,
+            &self.point,
+            self.values.iter().cloned(),
+            &self.proof,
+            &mut self.sponge.clone(),
+            None,
+        )
+        .unwrap_or(false)
+    }
+}
+
+// Sets up keys of PCS for MLEs in up to num_vars variables after checking
+// that the field and PCS meet the security level of config
+fn setup_pcs_keys<F, S, PCS, R>(
+    config: &ProofConfig,
+    num_vars: usize,
+    rng: &mut R,
+) -> Result<(PCS::CommitterKey, PCS::VerifierKey), ProofConfigError>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+    R: RngCore,
+{
+    let setup = UniversalSetup::<F, S, PCS>::new(config, num_vars, rng)?;
+
+    PCS::trim_to_num_vars(setup.params(), num_vars)
+        .map_err(|e| ProofConfigError::PCSSetup(e.to_string()))
+}
+
 // TODO change the functions that receive vectors to receive slices instead whenever it makes sense
 
-// TODO: for now, we require all nodes to use the same PCS; this might change
-// in the future
 pub struct Model<ST, LT> {
     pub input_shape: Vec<usize>,
     pub output_shape: Vec<usize>,
@@ -56,40 +283,189 @@ where
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
+    /// Creates a model with the given input shape and nodes, panicking if
+    /// they are inconsistent (cf. `try_new` and `validate`). An empty model,
+    /// for instance, would cause panics later down the line e.g. when
+    /// determining the number of variables needed to commit to it.
     pub fn new(input_shape: Vec<usize>, nodes: Vec<Node<ST, LT>>) -> Self {
-        // An empty model would cause panics later down the line e.g. when
-        // determining the number of variables needed to commit to it.
-        assert!(!nodes.is_empty(), "A model cannot have no nodes",);
-
-        Self {
-            input_shape,
-            output_shape: nodes.last().unwrap().shape(),
-            nodes,
-        }
+        Self::try_new(input_shape, nodes).unwrap_or_else(|e| panic!("Invalid model: {}", e))
     }
 
     pub fn input_shape(&self) -> &Vec<usize> {
         &self.input_shape
     }
 
+    /// Sets up keys for the PCS of node values at the security level of
+    /// `config` (cf. `setup_keys_for_batch`)
     pub fn setup_keys<F, S, PCS, R>(
         &self,
+        config: &ProofConfig,
         rng: &mut R,
-    ) -> Result<(PCS::CommitterKey, PCS::VerifierKey), PCS::Error>
+    ) -> Result<(PCS::CommitterKey, PCS::VerifierKey), ProofConfigError>
     where
-        F: PrimeField + Absorb + From<ST> + From<LT>,
+        F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
         S: CryptographicSponge,
-        PCS: PolynomialCommitment<F, Poly<F>, S>,
+        PCS: SecurePCS<F, S>,
+        R: RngCore,
+    {
+        self.setup_keys_for_batch::<F, S, PCS, R>(config, 1, rng)
+    }
+
+    /// Sets up keys for the PCS of node values which can be used to prove and
+    /// verify batches of up to `max_batch_size` inferences at once, whose
+    /// node values are committed to as MLEs with log2(max_batch_size)
+    /// additional variables. These also cover the other polynomials committed
+    /// to in each proof, some of which (e.g. the lookups of sparse nodes) are
+    /// as large as the node parameters. The field and the PCS must meet the
+    /// security level of `config`, for which the PCS parameters are derived.
+    pub fn setup_keys_for_batch<F, S, PCS, R>(
+        &self,
+        config: &ProofConfig,
+        max_batch_size: usize,
+        rng: &mut R,
+    ) -> Result<(PCS::CommitterKey, PCS::VerifierKey), ProofConfigError>
+    where
+        F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
         R: RngCore,
     {
-        let num_vars = self.nodes.iter().map(|n| n.com_num_vars()).max().unwrap();
+        let num_vars = self.value_key_num_vars::<F, S, PCS>(max_batch_size);
+
+        setup_pcs_keys::<F, S, PCS, R>(config, num_vars, rng)
+    }
+
+    /// Sets up keys for the PCS of the node parameters, which are committed
+    /// to once per model (cf. `ProveModel::proving_key`) and opened in every
+    /// proof. This PCS may differ from that of the node values, e.g. to trade
+    /// a trusted setup for shorter proofs and cheaper verification of the
+    /// parameter openings. As for node values, it must meet the security
+    /// level of `config`.
+    pub fn setup_weight_keys<F, S, WPCS, R>(
+        &self,
+        config: &ProofConfig,
+        rng: &mut R,
+    ) -> Result<(WPCS::CommitterKey, WPCS::VerifierKey), ProofConfigError>
+    where
+        F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+        S: CryptographicSponge,
+        WPCS: SecurePCS<F, S>,
+        R: RngCore,
+    {
+        setup_pcs_keys::<F, S, WPCS, R>(config, self.weight_key_num_vars(), rng)
+    }
+
+    /// Number of variables which the keys for the PCS of node values must
+    /// support to prove and verify batches of up to `max_batch_size`
+    /// inferences (cf. `setup_keys_for_batch`)
+    pub fn value_key_num_vars<F, S, PCS>(&self, max_batch_size: usize) -> usize
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: PolynomialCommitment<F, Poly<F>, S>,
+    {
+        // Node values may be packed into a single MLE (cf.
+        // NodeValueCommitment)
+        let value_num_vars = NodeValueCommitment::<F, S, PCS>::packed_num_vars(
+            &self.node_value_num_vars(log2(max_batch_size.next_power_of_two()) as usize),
+        );
+
+        self.nodes
+            .iter()
+            .map(|n| n.com_num_vars())
+            .chain([value_num_vars])
+            .max()
+            .unwrap()
+    }
+
+    /// Number of variables which the keys for the PCS of the node parameters
+    /// must support (cf. `setup_weight_keys`)
+    pub fn weight_key_num_vars(&self) -> usize {
+        self.nodes.iter().map(|n| n.com_num_vars()).max().unwrap()
+    }
+
+    /// Checks that keys for the PCS of node values supporting batches of up
+    /// to `max_batch_size` inferences can be trimmed from `setup`
+    pub fn check_setup<F, S, PCS>(
+        &self,
+        setup: &UniversalSetup<F, S, PCS>,
+        max_batch_size: usize,
+    ) -> Result<(), SetupError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
+    {
+        let required = self.value_key_num_vars::<F, S, PCS>(max_batch_size);
+
+        if required > setup.num_vars() {
+            return Err(SetupError::TooSmall {
+                required,
+                supported: setup.num_vars(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Trims keys for the PCS of node values supporting batches of up to
+    /// `max_batch_size` inferences from a universal setup shared across
+    /// models, rather than generating fresh parameters as
+    /// `setup_keys_for_batch` does. The keys record the setup they come from.
+    pub fn keys_from_setup<F, S, PCS>(
+        &self,
+        setup: &UniversalSetup<F, S, PCS>,
+        max_batch_size: usize,
+    ) -> Result<SetupKeys<F, S, PCS>, SetupError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        PCS: SecurePCS<F, S>,
+    {
+        setup.trim(self.value_key_num_vars::<F, S, PCS>(max_batch_size))
+    }
+
+    /// Trims keys for the PCS of the node parameters from a universal setup
+    /// shared across models (cf. `keys_from_setup`)
+    pub fn weight_keys_from_setup<F, S, WPCS>(
+        &self,
+        setup: &UniversalSetup<F, S, WPCS>,
+    ) -> Result<SetupKeys<F, S, WPCS>, SetupError>
+    where
+        F: PrimeField,
+        S: CryptographicSponge,
+        WPCS: SecurePCS<F, S>,
+    {
+        setup.trim(self.weight_key_num_vars())
+    }
+
+    /// Indices of the node values (the model input being the 0-th) which must
+    /// be committed to in layered proofs (cf. `LayeredInferenceProof`): those
+    /// which are the input or the output, but not both, of a node without a
+    /// claim reduction. The model input and output are never committed to,
+    /// as the verifier receives them in plain.
+    pub fn layer_boundaries(&self) -> Vec<usize> {
+        (1..self.nodes.len())
+            .filter(|i| {
+                self.nodes[i - 1].has_claim_reduction() != self.nodes[*i].has_claim_reduction()
+            })
+            .collect()
+    }
 
-        let pp = PCS::setup(1, Some(num_vars), rng).unwrap();
+    /// Numbers of variables of the MLEs of the values of each node (the model
+    /// input first) across a batch of 2^num_batch_vars inferences
+    pub fn node_value_num_vars(&self, num_batch_vars: usize) -> Vec<usize> {
+        let input_num_vars: usize = self
+            .input_shape
+            .iter()
+            .map(|x| log2(x.next_power_of_two()) as usize)
+            .sum();
 
-        // Make sure supported_degree, supported_hiding_bound and
-        // enforced_degree_bounds have a consistent meaning across ML PCSs and
-        // we are using them securely.
-        PCS::trim(&pp, 0, 0, None)
+        [input_num_vars]
+            .into_iter()
+            .chain(self.nodes.iter().map(|n| n.padded_num_units_log()))
+            .map(|num_vars| num_vars + num_batch_vars)
+            .collect()
     }
 
     pub fn evaluate(&self, input: QArray<ST>) -> QArray<ST> {
@@ -0,0 +1,188 @@
+use ark_std::log2;
+
+use crate::model::encoding::{NodeBounds, QuantisedInteger, ValueRange};
+use crate::model::qarray::{QArray, QTypeArray};
+
+use super::bmm::BMMNode;
+use super::custom::{CustomNodeOps, ValueType};
+
+/// Batch normalisation of the accumulators of the preceding BMM node, as a
+/// custom node (cf. `CustomNodeOps`) on values of the large type. Each entry
+/// x_j is mapped to scale_j * x_j + shift_j, where the integer scale and shift
+/// of channel j fold the batch statistics and the affine parameters of the
+/// normalisation, expressed in the quantisation of the accumulators:
+///     scale_j * x_j + shift_j ~ (gamma_j * (x_j - mean_j) / sqrt(var_j + eps) + beta_j) / s
+/// for a common scale s of the outputs. The node has no proof of its own and
+/// is meant to be removed by `FoldBatchNorm`, which folds it into the weights
+/// and bias of the preceding `BMMNode`.
+pub struct BatchNormNode<LT> {
+    scale: QArray<LT>,
+    shift: QArray<LT>,
+    log_num_units: usize,
+}
+
+impl<LT: QuantisedInteger> BatchNormNode<LT> {
+    /// Creates a node with the given per-channel scale and shift, which must
+    /// have the same length
+    pub fn new(scale: QArray<LT>, shift: QArray<LT>) -> Self {
+        assert_eq!(
+            scale.len(),
+            shift.len(),
+            "BatchNorm node scale and shift have different lengths"
+        );
+
+        let log_num_units = log2(scale.len().next_power_of_two()) as usize;
+
+        Self {
+            scale,
+            shift,
+            log_num_units,
+        }
+    }
+
+    pub fn scale(&self) -> &QArray<LT> {
+        &self.scale
+    }
+
+    pub fn shift(&self) -> &QArray<LT> {
+        &self.shift
+    }
+
+    // Normalises the entries of the given channels, leaving any others (i.e.
+    // the padding) unchanged
+    fn normalise(&self, input: &QArray<LT>) -> QArray<LT> {
+        let values = input
+            .values()
+            .iter()
+            .enumerate()
+            .map(
+                |(j, x)| match (self.scale.values().get(j), self.shift.values().get(j)) {
+                    (Some(scale), Some(shift)) => *scale * *x + *shift,
+                    _ => *x,
+                },
+            )
+            .collect();
+
+        QArray::new(values, input.shape().clone())
+    }
+
+    /// BMM node computing the composition of `bmm` and this node, which
+    /// scales column j of the weights and the bias entry j by scale_j and adds
+    /// shift_j to the latter. Returns None if the number of columns of `bmm`
+    /// differs from the number of channels, if some scaled weight does not fit
+    /// in ST or the folded bias in LT, or if the accumulators of the folded
+    /// node may overflow LT (cf. `BMMNode::accumulator_overflow`), in which
+    /// cases the node cannot be folded exactly.
+    pub fn fold_into<ST: QuantisedInteger>(
+        &self,
+        bmm: &BMMNode<ST, LT>,
+    ) -> Option<BMMNode<ST, LT>> {
+        let num_cols = bmm.weights.shape()[1];
+
+        if num_cols != self.scale.len() {
+            return None;
+        }
+
+        let scale = self.scale.values();
+
+        let weights = bmm
+            .weights
+            .values()
+            .iter()
+            .enumerate()
+            .map(|(i, w)| ST::try_from_i128(w.to_i128() * scale[i % num_cols].to_i128()))
+            .collect::<Option<Vec<_>>>()?;
+
+        let bias = bmm
+            .bias
+            .values()
+            .iter()
+            .zip(scale.iter().zip(self.shift.values()))
+            .map(|(b, (scale, shift))| {
+                LT::try_from_i128(b.to_i128() * scale.to_i128() + shift.to_i128())
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let folded = BMMNode::new_unchecked(
+            QArray::new(weights, bmm.weights.shape().clone()),
+            QArray::new(bias, vec![num_cols]),
+            bmm.input_zero_point,
+        );
+
+        folded.accumulator_overflow().is_none().then_some(folded)
+    }
+}
+
+impl<ST, LT> CustomNodeOps<ST, LT> for BatchNormNode<LT>
+where
+    ST: 'static,
+    LT: QuantisedInteger,
+{
+    fn type_name(&self) -> &'static str {
+        "BatchNorm"
+    }
+
+    fn input_type(&self) -> ValueType {
+        ValueType::Large
+    }
+
+    fn output_type(&self) -> ValueType {
+        ValueType::Large
+    }
+
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.scale.len()]
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.scale.len()]
+    }
+
+    fn padded_shape_log(&self) -> Vec<usize> {
+        vec![self.log_num_units]
+    }
+
+    fn com_num_vars(&self) -> usize {
+        0
+    }
+
+    fn evaluate(&self, input: &QTypeArray<ST, LT>) -> QTypeArray<ST, LT> {
+        QTypeArray::L(self.normalise(input.ref_large()))
+    }
+
+    // The padding is left unchanged, so that it stays zero after a BMM node
+    // (cf. BMMNode::padded_evaluate), as it does after the folded node
+    fn padded_evaluate(&self, input: &QTypeArray<ST, LT>) -> QTypeArray<ST, LT> {
+        QTypeArray::L(self.normalise(input.ref_large()))
+    }
+
+    fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let output = self
+            .scale
+            .values()
+            .iter()
+            .zip(self.shift.values())
+            .map(|(scale, shift)| input.scale(scale.to_i128()).shift(shift.to_i128()))
+            .reduce(|a, b| a.hull(&b))
+            .unwrap_or(input);
+
+        let scaled = self.scale.values().iter().fold(input, |range, scale| {
+            range.hull(&input.scale(scale.to_i128()))
+        });
+
+        NodeBounds {
+            output,
+            intermediate: scaled.hull(&output),
+        }
+    }
+
+    // Scale and shift of each channel, as 16-byte little-endian integers
+    fn encode_parameters(&self) -> Vec<u8> {
+        self.scale
+            .values()
+            .iter()
+            .chain(self.shift.values())
+            .flat_map(|x| x.to_i128().to_le_bytes())
+            .collect()
+    }
+}
@@ -1,26 +1,36 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
 use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
-use ark_std::log2;
+use ark_std::{any::type_name, log2};
 
 use ark_sumcheck::ml_sumcheck::Proof;
 
+use crate::model::encoding::{accumulate, NodeBounds, QuantisedInteger, ValueRange};
+use crate::model::evaluation::SaturatingArithmetic;
 use crate::model::qarray::{InnerType, QArray};
-use crate::model::Poly;
+use crate::model::{LabeledPoly, Poly};
+use crate::utils::mle::eq_evaluation;
 use crate::{Commitment, CommitmentState};
 
-use super::{NodeOpsNative, NodeOpsPadded};
+use super::{sparse_bmm::SparseBMMNode, NodeOpsNative, NodeOpsPadded};
 
 // TODO convention: input, bias and output are rows, the op is vec-by-mat (in that order)
 
 /// Start with 2D matrices, and Mat-by-vector multiplication only
+///
+/// For proving, the weight matrix is not padded as a whole. Instead, each of
+/// its dimensions is split into blocks whose sizes are the powers of two in
+/// its binary expansion (e.g. 784 = 512 + 256 + 16), and each of the resulting
+/// blocks of the matrix is committed to and proved separately. The sizes of
+/// the weight commitments and the prover's work therefore track the number of
+/// actual parameters. Since each block starts at a multiple of its size, it
+/// corresponds to a subcube of the padded hypercube, which allows the padded
+/// input and output MLEs to be used as they are.
 pub struct BMMNode<ST, LT> {
     /// The row-major flattened unpadded vector of weights
-    weights: QArray<ST>,
-    /// The padded weight vector
-    pub padded_weights: QArray<ST>,
+    pub(crate) weights: QArray<ST>,
     /// The unpadded vector of biases
-    bias: QArray<LT>,
+    pub(crate) bias: QArray<LT>,
     /// The padded bias vector
     pub padded_bias: QArray<LT>,
     /// Unpadded imensions (rows, columns)
@@ -31,18 +41,34 @@ pub struct BMMNode<ST, LT> {
     pub input_zero_point: ST,
 }
 
-/// Commitment to a BMM node, consisting of a commitment to the *dual* of the
-/// weight MLE and one to the *dual* of the bias MLE
+/// Commitment to a BMM node, consisting of commitments to the *duals* of the
+/// MLEs of each block of the weight matrix (cf. `BMMNode::weight_blocks`) and
+/// one to the *dual* of the bias MLE
 pub struct BMMNodeCommitment<F, S, PCS>
 where
     F: PrimeField,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    pub weight_com: LabeledCommitment<PCS::Commitment>,
+    pub weight_coms: Vec<LabeledCommitment<PCS::Commitment>>,
     pub bias_com: LabeledCommitment<PCS::Commitment>,
 }
 
+// Derived Clone would require S and PCS to be Clone
+impl<F, S, PCS> Clone for BMMNodeCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            weight_coms: self.weight_coms.clone(),
+            bias_com: self.bias_com.clone(),
+        }
+    }
+}
+
 impl<F, S, PCS> Commitment for BMMNodeCommitment<F, S, PCS>
 where
     F: PrimeField,
@@ -51,15 +77,15 @@ where
 {
 }
 
-/// Commitment states associated to a BMMNodeCommitment: one for the weight and
-/// one for the bias
+/// Commitment states associated to a BMMNodeCommitment: one for each weight
+/// block and one for the bias
 pub struct BMMNodeCommitmentState<F, S, PCS>
 where
     F: PrimeField,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    pub weight_com_state: PCS::CommitmentState,
+    pub weight_com_states: Vec<PCS::CommitmentState>,
     pub bias_com_state: PCS::CommitmentState,
 }
 
@@ -71,35 +97,95 @@ where
 {
 }
 
-/// Proof of execution of a BMM node, consisting of a sumcheck proof and four
-/// PCS opening proofs
+/// MLEs of the weight blocks and padded bias of a BMM node, whose evaluations
+/// are the field-element conversions of the entries. These are computed once
+/// as part of the prover's key, rather than on each proof.
+pub struct BMMNodeMLEs<F: PrimeField> {
+    /// Duals of the MLEs of the row-major flattenings of the weight blocks,
+    /// in the order of `BMMNode::weight_blocks`
+    pub weight_mles: Vec<LabeledPoly<F>>,
+    /// Dual of the MLE of the bias vector
+    pub bias_mle: LabeledPoly<F>,
+}
+
+/// Proof of execution of a BMM node. Denoting by W_{j, l} the block of the
+/// weight matrix in the j-th row block and l-th column block, by r a
+/// challenge point of the padded output hypercube, by r_l and gamma_l the
+/// challenges it yields for the l-th column block (cf.
+/// `BMMNode::col_block_challenges`) and by v^ the dual of the MLE of v, the
+/// proof reduces the claim
+///     output^(r) - bias^(r)
+///   = sum_j sum_x (input^(x, j) - zero_point) * sum_l gamma_l * W_{j, l}^(r_l, x)
+/// to one sumcheck for each row block j, where (., j) stands for the fixed
+/// high-order variables of the block. Since the output is opened at r itself,
+/// rather than at each column block, its padding is bound to be zero. Values
+/// indexed by row block or by weight block (in row-major order) follow the
+/// order of `BMMNode::row_blocks` and `BMMNode::weight_blocks`.
 pub struct BMMNodeProof<
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 > {
-    /// Sumcheck protocol proof for the polynomial
-    /// g(x) = (input - zero_point)^(x) * W^(r, x),
-    /// where v^ denotes the dual of the MLE of v and r is a challenge point
-    pub sumcheck_proof: Proof<F>,
+    /// Claimed sum of the sumcheck polynomial of each row block
+    pub block_sums: Vec<F>,
+
+    /// Sumcheck proof for each row block. These are empty for blocks
+    /// consisting of a single row, whose polynomial is a constant.
+    pub sumcheck_proofs: Vec<Proof<F>>,
+
+    /// Values of the *dual* of the input MLE at the sumcheck challenge point
+    /// of each row block (together with the batch challenge point, if any)
+    /// and proofs of opening
+    pub input_opening_proofs: Vec<PCS::Proof>,
+    pub input_opening_values: Vec<F>,
+
+    /// Values of the *dual* of each weight block MLE at r_l || s_j, where s_j
+    /// is the sumcheck challenge point of its row block, and proofs of opening
+    pub weight_opening_proofs: Vec<WPCS::Proof>,
+    pub weight_opening_values: Vec<F>,
+
+    /// Value of the *dual* of the output MLE at r (together with the batch
+    /// challenge point, if any) and proof of opening
+    pub output_opening_proof: PCS::Proof,
+    pub output_opening_value: F,
 
-    /// Value of the *dual* of the input MLE at the challenge point s and proof
-    /// of opening
-    pub input_opening_proof: PCS::Proof,
-    pub input_opening_value: F,
+    /// Value of the *dual* of the bias MLE at r and proof of opening
+    pub bias_opening_proof: WPCS::Proof,
+    pub bias_opening_value: F,
+}
 
-    /// Value of the *dual* of the weight MLE at the challenge point r || s and proof of
-    /// opening
-    pub weight_opening_proof: PCS::Proof,
-    pub weight_opening_value: F,
+/// Proof reducing a claim about the output of a BMM node at a point r (and
+/// the batch point rho, if any) to one about its input. Denoting by r_l the
+/// low-order variables of r corresponding to the l-th column block and by
+/// gamma_l the evaluation of eq at its high-order ones and the bits of the
+/// block, the claim
+///     output^(r) - bias^(r)
+///   = sum_x (input^(x) - zero_point) * sum_j eq(x, j) * sum_l gamma_l * W_{j, l}^(r_l, x)
+/// is proved by a single sumcheck over the padded input hypercube, at whose
+/// challenge point s the input claim is made (cf. `BMMNodeProof` for the
+/// notation). Only node parameters are opened, so `PCS` is the scheme they are
+/// committed to with.
+pub struct BMMNodeReductionProof<
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+> {
+    /// Sumcheck proof, empty if the padded input consists of a single entry
+    pub sumcheck_proof: Proof<F>,
 
-    /// Proof of opening of the *duals* of the output and bias MLEs at the
-    // challenge point
-    pub output_bias_opening_proof: PCS::Proof,
+    /// Claimed value of the *dual* of the input MLE at s (together with the
+    /// batch challenge point, if any)
+    pub input_value: F,
 
-    /// Value of the *dual* of the weight MLE at the challenge point and proof of
-    /// opening
-    pub output_opening_value: F,
+    /// Values of the *dual* of each weight block MLE at r_l || s_j, where s_j
+    /// are the low-order variables of s corresponding to its row block, and
+    /// proofs of opening
+    pub weight_opening_proofs: Vec<PCS::Proof>,
+    pub weight_opening_values: Vec<F>,
+
+    /// Value of the *dual* of the bias MLE at r and proof of opening
+    pub bias_opening_proof: PCS::Proof,
     pub bias_opening_value: F,
 }
 
@@ -108,6 +194,10 @@ where
     ST: InnerType,
     LT: InnerType + From<ST>,
 {
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.dims.0]
+    }
+
     fn shape(&self) -> Vec<usize> {
         vec![self.dims.1]
     }
@@ -130,24 +220,11 @@ where
 
         let input: QArray<LT> = input.cast();
 
-        // TODO this is a bigger question: can this overflow an i8? Supposedly the point of quantisation
-        // is that input-by-weight products can be computed in i8. To be safe, let us use the large type here
+        // Input-by-weight products are computed in the large type, where the
+        // check in `new` guarantees that no value overflows for any input
         let shifted_input = input - LT::from(self.input_zero_point);
 
-        let mut accumulators = self.bias.values().clone();
-
-        // TODO this can be made more elegant (efficient?) using addition of QArrays after defining suitable operators
-
-        // TODO since we have acumulators, this can be done more efficiently going row-wise to avoid re-caching the input
-        for col in 0..self.dims.1 {
-            // TODO does the compiler realise it doesn't need to access accumulators[col] on every iteration of the inner loop? ow change
-            for row in 0..self.dims.0 {
-                accumulators[col] +=
-                    shifted_input[row] * LT::from(self.weights[row * self.dims.1 + col])
-            }
-        }
-
-        QArray::new(accumulators, vec![self.dims.1])
+        &shifted_input.matmul(&self.weights.cast()) + &self.bias
     }
 }
 
@@ -161,13 +238,16 @@ where
     }
 
     fn com_num_vars(&self) -> usize {
-        self.padded_dims_log.0 + self.padded_dims_log.1
+        // The largest weight block and the padded bias
+        let largest_block_log = |n: usize| log2(n + 1) as usize - 1;
+
+        (largest_block_log(self.dims.0) + largest_block_log(self.dims.1))
+            .max(self.padded_dims_log.1)
     }
 
-    // This function naively computes entries which are known to be zero. It is
-    // meant to exactly mirror the proof-system multiplication proved by the
-    // sumcheck argument. Requantisation and shifting are also applied to these
-    // trivial entries, as the proof system does.
+    // Only the actual input entries are multiplied by the weights, as is the
+    // case in the proof system, which ignores the input padding. The padded
+    // output entries are zero.
     fn padded_evaluate(&self, input: &QArray<ST>) -> QArray<LT> {
         let padded_dims = (1 << self.padded_dims_log.0, 1 << self.padded_dims_log.1);
 
@@ -187,35 +267,46 @@ where
             input.len()
         );
 
-        let input: QArray<LT> = input.cast();
+        let input: QArray<LT> =
+            QArray::new(input.values()[..self.dims.0].to_vec(), vec![self.dims.0]).cast();
 
-        // TODO this is a bigger question: can this overflow an i8? Supposedly the point of quantisation
-        // is that input-by-weight products can be computed in i8. To be safe, let us use the large type here
+        // Input-by-weight products are computed in the large type, where the
+        // check in `new` guarantees that no value overflows for any input
         let shifted_input = input - LT::from(self.input_zero_point);
 
-        let mut accumulators = self.padded_bias.values().clone();
-
-        // TODO this can be made more elegant (efficient?) using addition of QArrays after defining suitable operators
-
-        // TODO since we have acumulators, this can be done more efficiently going row-wise to avoid re-caching the input
-        for col in 0..padded_dims.1 {
-            // TODO does the compiler realise it doesn't need to access accumulators[col] on every iteration of the inner loop? ow change
-            for row in 0..padded_dims.0 {
-                accumulators[col] +=
-                    shifted_input[row] * LT::from(self.padded_weights[row * padded_dims.1 + col])
-            }
-        }
-
-        QArray::new(accumulators, vec![padded_dims.1])
+        (&shifted_input.matmul(&self.weights.cast()) + &self.bias)
+            .compact_resize(vec![padded_dims.1], LT::ZERO)
     }
 }
 
+// Splits a dimension of size n into blocks of decreasing power-of-two sizes,
+// each given by its offset and the log of its size
+fn power_of_two_blocks(n: usize) -> Vec<(usize, usize)> {
+    let mut offset = 0;
+
+    (0..usize::BITS as usize)
+        .rev()
+        .filter(|i| n & (1 << i) != 0)
+        .map(|i| {
+            let block = (offset, i);
+            offset += 1 << i;
+            block
+        })
+        .collect()
+}
+
 impl<ST, LT> BMMNode<ST, LT>
 where
     ST: InnerType,
     LT: InnerType,
 {
-    pub fn new(weights: QArray<ST>, bias: QArray<LT>, input_zero_point: ST) -> Self {
+    // Constructor without the overflow check of `new`, for callers which
+    // report overflows as errors rather than panicking
+    pub(crate) fn new_unchecked(
+        weights: QArray<ST>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+    ) -> Self {
         let dims = (weights.shape()[0], weights.shape()[1]);
 
         assert_eq!(
@@ -229,19 +320,13 @@ where
             log2(dims.1.next_power_of_two()) as usize,
         );
 
-        // Padding the weights and bias
-        let padded_weights = weights.clone().compact_resize(
-            vec![dims.0.next_power_of_two(), dims.1.next_power_of_two()],
-            ST::ZERO,
-        );
-
+        // Padding the bias
         let padded_bias = bias
             .clone()
             .compact_resize(vec![dims.1.next_power_of_two()], LT::ZERO);
 
         Self {
             weights,
-            padded_weights,
             bias,
             padded_bias,
             dims,
@@ -250,6 +335,17 @@ where
         }
     }
 
+    /// Row-major flattening of the weight block in the given row and column
+    /// blocks
+    pub fn weight_block(&self, row_block: (usize, usize), col_block: (usize, usize)) -> QArray<ST> {
+        let ((row_offset, row_log), (col_offset, col_log)) = (row_block, col_block);
+
+        self.weights.slice(&[
+            row_offset..row_offset + (1 << row_log),
+            col_offset..col_offset + (1 << col_log),
+        ])
+    }
+
     #[allow(dead_code)]
     pub(crate) fn padded_dims_log(&self) -> (usize, usize) {
         self.padded_dims_log
@@ -260,5 +356,213 @@ where
         self.input_zero_point
     }
 }
+
+impl<ST, LT> BMMNode<ST, LT>
+where
+    ST: QuantisedInteger,
+    LT: QuantisedInteger,
+{
+    /// Creates a node with the given weights (of shape (rows, columns)), bias
+    /// and input zero point. Panics if the accumulators may overflow LT for
+    /// some input (cf. `accumulator_overflow`).
+    pub fn new(weights: QArray<ST>, bias: QArray<LT>, input_zero_point: ST) -> Self {
+        let node = Self::new_unchecked(weights, bias, input_zero_point);
+
+        if let Some(range) = node.accumulator_overflow() {
+            panic!(
+                "BMM node accumulators range over {} for some inputs, which does not fit in {}",
+                range,
+                type_name::<LT>()
+            );
+        }
+
+        node
+    }
+
+    /// Sparse node with the same parameters, pruning all weights whose
+    /// absolute value is at most `threshold` (cf. `SparseBMMNode::from_dense`)
+    pub fn to_sparse(&self, threshold: ST) -> SparseBMMNode<ST, LT> {
+        SparseBMMNode::from_dense(
+            &self.weights,
+            self.bias.clone(),
+            self.input_zero_point,
+            threshold,
+        )
+    }
+
+    /// Worst-case check of the accumulators based on the magnitudes of the
+    /// weights: returns the range of the values computed by the node over
+    /// all inputs (cf. `bounds`) if it does not fit in LT
+    pub fn accumulator_overflow(&self) -> Option<ValueRange> {
+        let range = self.bounds(ValueRange::of::<ST>()).intermediate;
+
+        (!ValueRange::of::<LT>().contains(&range)).then_some(range)
+    }
+
+    /// Evaluates the node (or its padded version if `padded` is set) as
+    /// `evaluate` and `padded_evaluate` do, but clamping every operation to
+    /// the range of LT instead of overflowing. Returns the indices of the
+    /// output entries for which some operation was clamped.
+    pub(crate) fn saturating_evaluate(
+        &self,
+        input: &QArray<ST>,
+        padded: bool,
+    ) -> (QArray<LT>, Vec<usize>) {
+        let input_len = if padded {
+            1 << self.padded_dims_log.0
+        } else {
+            self.dims.0
+        };
+
+        assert_eq!(
+            input.num_dims(),
+            1,
+            "Incorrect shape: BMM node expects a 1-dimensional input array"
+        );
+        assert_eq!(
+            input_len,
+            input.len(),
+            "Length mismatch: BMM node expects input with {} elements, got {} elements instead",
+            input_len,
+            input.len()
+        );
+
+        let input = input.values();
+        let weights = self.weights.values();
+        let zero_point = self.input_zero_point.to_i128();
+
+        let mut overflows = Vec::new();
+
+        let output = self
+            .bias
+            .values()
+            .iter()
+            .enumerate()
+            .map(|(col, bias)| {
+                let mut arithmetic = SaturatingArithmetic::<LT>::new();
+                let mut acc = 0;
+
+                for row in 0..self.dims.0 {
+                    let shifted_input = arithmetic.add(input[row].to_i128(), -zero_point);
+                    let product =
+                        arithmetic.mul(shifted_input, weights[row * self.dims.1 + col].to_i128());
+                    acc = arithmetic.add(acc, product);
+                }
+
+                acc = arithmetic.add(acc, bias.to_i128());
+
+                if arithmetic.overflowed() {
+                    overflows.push(col);
+                }
+
+                LT::try_from_i128(acc).unwrap()
+            })
+            .collect();
+
+        let output = QArray::new(output, vec![self.dims.1]);
+
+        let output = if padded {
+            output.compact_resize(vec![1 << self.padded_dims_log.1], LT::ZERO)
+        } else {
+            output
+        };
+
+        (output, overflows)
+    }
+
+    /// Bounds on the values of the node for inputs in the given range (cf.
+    /// `Model::value_bounds`). Each output entry accumulates the products of
+    /// the shifted input entries by a column of weights, and then the bias.
+    pub(crate) fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let shifted_input = input.shift(-self.input_zero_point.to_i128());
+        let weights = self.weights.values();
+
+        let mut output: Option<ValueRange> = None;
+        let mut intermediate = input.hull(&shifted_input);
+
+        for (col, bias) in self.bias.values().iter().enumerate() {
+            let (sum, partial_sums) = accumulate(
+                (0..self.dims.0)
+                    .map(|row| shifted_input.scale(weights[row * self.dims.1 + col].to_i128())),
+            );
+
+            let col_output = sum.shift(bias.to_i128());
+
+            output = Some(output.map_or(col_output, |o| o.hull(&col_output)));
+            intermediate = intermediate.hull(&partial_sums).hull(&col_output);
+        }
+
+        NodeBounds {
+            output: output.unwrap(),
+            intermediate,
+        }
+    }
+}
+// Block decomposition used for proving, which does not depend on the
+// quantisation types
+impl<ST, LT> BMMNode<ST, LT> {
+    /// Blocks into which the rows of the weight matrix (i.e. the input
+    /// entries) are split, each given by its offset and the log of its size
+    pub fn row_blocks(&self) -> Vec<(usize, usize)> {
+        power_of_two_blocks(self.dims.0)
+    }
+
+    /// Blocks into which the columns of the weight matrix (i.e. the output
+    /// entries) are split, each given by its offset and the log of its size
+    pub fn col_blocks(&self) -> Vec<(usize, usize)> {
+        power_of_two_blocks(self.dims.1)
+    }
+
+    /// Blocks of the weight matrix, given by the indices of their row and
+    /// column blocks, in row-major order
+    pub fn weight_blocks(&self) -> Vec<(usize, usize)> {
+        let num_col_blocks = self.col_blocks().len();
+
+        (0..self.row_blocks().len())
+            .flat_map(|j| (0..num_col_blocks).map(move |l| (j, l)))
+            .collect()
+    }
+
+    /// Point of the padded hypercube of `num_vars` variables consisting of
+    /// `low` followed by the bits of the block with the given offset and log
+    /// size. This is the point at which the padded MLE must be evaluated to
+    /// obtain the evaluation at `low` of the MLE of the block alone.
+    pub fn block_point<F: PrimeField>(low: &[F], block: (usize, usize), num_vars: usize) -> Vec<F> {
+        let (offset, log_size) = block;
+
+        low.iter()
+            .cloned()
+            .chain((log_size..num_vars).map(|i| {
+                if offset & (1 << i) != 0 {
+                    F::one()
+                } else {
+                    F::zero()
+                }
+            }))
+            .collect()
+    }
+
+    /// Splits a point r of the padded output hypercube into the low-order
+    /// variables r_l of each column block and the coefficients gamma_l, i.e.
+    /// the evaluations of eq at the high-order variables of r and the bits of
+    /// the block. The MLE of a padded output vector evaluates at r to
+    ///     sum_l gamma_l * output_l^(r_l),
+    /// where output_l is its l-th block, if and only if its padding is zero.
+    pub fn col_block_challenges<'a, F: PrimeField>(&self, r: &'a [F]) -> (Vec<&'a [F]>, Vec<F>) {
+        self.col_blocks()
+            .into_iter()
+            .map(|block| {
+                (
+                    &r[..block.1],
+                    eq_evaluation(
+                        &r[block.1..],
+                        &Self::block_point::<F>(&[], block, self.padded_dims_log.1),
+                    ),
+                )
+            })
+            .unzip()
+    }
+}
+
 // TODO in constructor, add quantisation information checks? (s_bias = s_input * s_weight, z_bias = 0, z_weight = 0, etc.)
 // TODO in constructor, check bias length matches appropriate matrix dimension
@@ -0,0 +1,128 @@
+use ark_std::any::Any;
+use ark_std::ops::Deref;
+use std::sync::Arc;
+
+use crate::model::encoding::{NodeBounds, ValueRange};
+use crate::model::qarray::QTypeArray;
+
+/// Type (small or large) of the values received or produced by a custom node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueType {
+    Small,
+    Large,
+}
+
+/// Native and padded operations of a node type defined outside this crate,
+/// which makes up a `Node::Custom`. Its SNARK operations are implemented
+/// through the traits `NodeOpsProve` and `NodeOpsVerify` of the prover and
+/// verifier crates, for which the node type must be registered with each of
+/// them (cf. `register_custom_prover` and `register_custom_verifier`). Its
+/// proofs, commitments and parameter MLEs are opaque to the rest of the
+/// library (cf. `CustomNodeData`).
+pub trait CustomNodeOps<ST, LT>: Any + Send + Sync {
+    /// Name of the node type, which identifies it in model files and error
+    /// messages
+    fn type_name(&self) -> &'static str;
+
+    /// Type of the values received by the node
+    fn input_type(&self) -> ValueType;
+
+    /// Type of the values produced by the node
+    fn output_type(&self) -> ValueType;
+
+    /// Returns the shape of the node's input tensor
+    fn input_shape(&self) -> Vec<usize>;
+
+    /// Returns the shape of the node's output tensor
+    fn shape(&self) -> Vec<usize>;
+
+    /// Returns the element-wise base-two logarithm of the padded node's
+    /// output shape (cf. `NodeOpsPadded::padded_shape_log`)
+    fn padded_shape_log(&self) -> Vec<usize>;
+
+    /// The log of the number of output units of the padded node
+    fn padded_num_units_log(&self) -> usize {
+        self.padded_shape_log().iter().sum()
+    }
+
+    /// Returns the maximum number of variables of the MLEs committed to as
+    /// part of this nodes's commitment
+    fn com_num_vars(&self) -> usize;
+
+    /// Evaluate the node natively (without padding)
+    fn evaluate(&self, input: &QTypeArray<ST, LT>) -> QTypeArray<ST, LT>;
+
+    /// Evaluate the padded node natively
+    fn padded_evaluate(&self, input: &QTypeArray<ST, LT>) -> QTypeArray<ST, LT>;
+
+    /// Bounds on the values computed by the node for inputs in the given
+    /// range (cf. `Model::value_bounds`)
+    fn bounds(&self, input: ValueRange) -> NodeBounds;
+
+    /// Whether a claim about the node's output can be reduced to one about
+    /// its input (cf. `Node::has_claim_reduction`)
+    fn has_claim_reduction(&self) -> bool {
+        false
+    }
+
+    /// Zero point the node assumes its input to have, if any, which is
+    /// checked on model construction (cf. `Model::validate`)
+    fn input_zero_point(&self) -> Option<ST> {
+        None
+    }
+
+    /// Zero point of the node's output, if known
+    fn output_zero_point(&self) -> Option<ST> {
+        None
+    }
+
+    /// Encoding of the node parameters, which is written to model files and
+    /// bound by the content hash of the model
+    fn encode_parameters(&self) -> Vec<u8>;
+}
+
+/// Node of a type defined outside this crate (cf. `CustomNodeOps`)
+pub struct CustomNode<ST, LT>(Box<dyn CustomNodeOps<ST, LT>>);
+
+impl<ST: 'static, LT: 'static> CustomNode<ST, LT> {
+    pub fn new(node: impl CustomNodeOps<ST, LT>) -> Self {
+        Self(Box::new(node))
+    }
+
+    /// Returns the node as a dynamically typed value, from which the prover
+    /// and verifier recover the node type they have been registered for
+    pub fn as_any(&self) -> &dyn Any {
+        let node: &dyn CustomNodeOps<ST, LT> = &*self.0;
+        node
+    }
+
+    /// Returns the underlying node if it is of type `N`
+    pub fn downcast_ref<N: CustomNodeOps<ST, LT>>(&self) -> Option<&N> {
+        self.as_any().downcast_ref()
+    }
+}
+
+impl<ST, LT> Deref for CustomNode<ST, LT> {
+    type Target = dyn CustomNodeOps<ST, LT>;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+/// Proof, commitment, commitment state or parameter MLEs of a custom node,
+/// whose type only the node itself knows. These are shared rather than
+/// copied when cloned (e.g. along with a `NodeCommitment`).
+#[derive(Clone)]
+pub struct CustomNodeData(Arc<dyn Any + Send + Sync>);
+
+impl CustomNodeData {
+    pub fn new<T: Any + Send + Sync>(data: T) -> Self {
+        Self(Arc::new(data))
+    }
+
+    /// Returns the data if it is of type `T`
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
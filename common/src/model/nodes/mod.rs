@@ -1,6 +1,7 @@
 use ark_crypto_primitives::sponge::Absorb;
 use ark_ff::PrimeField;
 use ark_poly_commit::PolynomialCommitment;
+use ark_std::borrow::Cow;
 
 use crate::{
     model::{
@@ -11,22 +12,34 @@ use crate::{
 };
 
 use self::{
-    bmm::{BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeProof},
+    bmm::{
+        BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeMLEs, BMMNodeProof, BMMNodeReductionProof,
+    },
+    custom::{CustomNode, CustomNodeData},
     requantise_bmm::{
         RequantiseBMMNode, RequantiseBMMNodeCommitment, RequantiseBMMNodeCommitmentState,
         RequantiseBMMNodeProof,
     },
-    reshape::ReshapeNode,
+    reshape::{ReshapeNode, ReshapeNodeReductionProof},
+    sparse_bmm::{
+        SparseBMMNode, SparseBMMNodeCommitment, SparseBMMNodeCommitmentState, SparseBMMNodeMLEs,
+        SparseBMMNodeProof, SparseBMMNodeReductionProof,
+    },
 };
 
+use super::encoding::{NodeBounds, QuantisedInteger, ValueRange};
 use super::qarray::{InnerType, QTypeArray};
 
+pub(crate) mod batch_norm;
 pub(crate) mod bmm;
+pub(crate) mod custom;
 pub(crate) mod relu;
 pub(crate) mod requantise_bmm;
 pub(crate) mod reshape;
+pub(crate) mod sparse_bmm;
 
-// mod parser;
+#[cfg(test)]
+mod tests;
 
 // TODO: batched methods (e.g. for multiple evaluations)
 // TODO: issue: missing info about size of the next output? Or reduplicate it?
@@ -37,6 +50,9 @@ pub(crate) mod reshape;
 /// applicable), but not about about the specific values of its nodes: these
 /// are handled by the methods only.
 pub trait NodeOpsNative<I, O> {
+    /// Returns the shape of the node's input tensor
+    fn input_shape(&self) -> Vec<usize>;
+
     /// Returns the shape of the node's output tensor
     fn shape(&self) -> Vec<usize>;
 
@@ -87,21 +103,49 @@ pub trait NodeOpsPadded<I, O>: NodeOpsNative<I, O> {
 
 pub enum Node<ST, LT> {
     BMM(BMMNode<ST, LT>),
+    SparseBMM(SparseBMMNode<ST, LT>),
     RequantiseBMM(RequantiseBMMNode<ST>),
     ReLU(ReLUNode<ST>),
     Reshape(ReshapeNode),
+    Custom(CustomNode<ST, LT>),
 }
 
-pub enum NodeProof<F, S, PCS>
+/// Proof of execution of a node. Openings of the node values (and of any
+/// other polynomials committed to as part of the proof) are proofs of the
+/// PCS `PCS`, whereas openings of the node parameters, which are committed to
+/// once per model (cf. `NodeCommitment`), are proofs of the PCS `WPCS`. Both
+/// are linked through the evaluations at the challenge points of the
+/// sumchecks, so the two schemes need not be the same.
+pub enum NodeProof<F, S, PCS, WPCS>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    BMM(BMMNodeProof<F, S, PCS>),
+    BMM(BMMNodeProof<F, S, PCS, WPCS>),
+    SparseBMM(SparseBMMNodeProof<F, S, PCS, WPCS>),
     RequantiseBMM(RequantiseBMMNodeProof),
     ReLU(()),
     Reshape(()),
+    Custom(CustomNodeData),
+}
+
+/// Proof reducing a claim about the output of a node to one about its input,
+/// which only some nodes support (cf. `Node::has_claim_reduction`). These
+/// make up layered inference proofs (cf. `LayeredInferenceProof`). As in
+/// `NodeProof`, `WPCS` is the PCS of the node parameters.
+pub enum NodeReductionProof<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    BMM(BMMNodeReductionProof<F, S, WPCS>),
+    SparseBMM(Box<SparseBMMNodeReductionProof<F, S, PCS, WPCS>>),
+    Reshape(ReshapeNodeReductionProof<F>),
+    Custom(CustomNodeData),
 }
 
 pub enum NodeCommitment<F, S, PCS>
@@ -111,9 +155,30 @@ where
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
     BMM(BMMNodeCommitment<F, S, PCS>),
+    SparseBMM(SparseBMMNodeCommitment<F, S, PCS>),
     RequantiseBMM(RequantiseBMMNodeCommitment),
     ReLU(()),
     Reshape(()),
+    Custom(CustomNodeData),
+}
+
+// Cf. the Clone implementation of BMMNodeCommitment
+impl<F, S, PCS> Clone for NodeCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    fn clone(&self) -> Self {
+        match self {
+            NodeCommitment::BMM(c) => NodeCommitment::BMM(c.clone()),
+            NodeCommitment::SparseBMM(c) => NodeCommitment::SparseBMM(c.clone()),
+            NodeCommitment::RequantiseBMM(c) => NodeCommitment::RequantiseBMM(c.clone()),
+            NodeCommitment::ReLU(()) => NodeCommitment::ReLU(()),
+            NodeCommitment::Reshape(()) => NodeCommitment::Reshape(()),
+            NodeCommitment::Custom(c) => NodeCommitment::Custom(c.clone()),
+        }
+    }
 }
 
 pub enum NodeCommitmentState<F, S, PCS>
@@ -123,9 +188,25 @@ where
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
     BMM(BMMNodeCommitmentState<F, S, PCS>),
+    SparseBMM(SparseBMMNodeCommitmentState<F, S, PCS>),
     RequantiseBMM(RequantiseBMMNodeCommitmentState),
     ReLU(()),
     Reshape(()),
+    Custom(CustomNodeData),
+}
+
+/// Field-element MLEs of the parameters of a node, which the prover computes
+/// once per model (and commits to) rather than on each proof
+pub enum NodeMLEs<F>
+where
+    F: PrimeField,
+{
+    BMM(BMMNodeMLEs<F>),
+    SparseBMM(Box<SparseBMMNodeMLEs<F>>),
+    RequantiseBMM(()),
+    ReLU(()),
+    Reshape(()),
+    Custom(CustomNodeData),
 }
 
 // A lot of this overlaps with the NodeOps trait and could be handled more
@@ -140,24 +221,81 @@ where
     pub fn type_name(&self) -> &'static str {
         match self {
             Node::BMM(_) => "BMM",
+            Node::SparseBMM(_) => "SparseBMM",
             Node::RequantiseBMM(_r) => "RequantiseBMM",
             Node::ReLU(_) => "ReLU",
             Node::Reshape(_) => "Reshape",
+            Node::Custom(c) => c.type_name(),
+        }
+    }
+
+    /// Whether a claim about the node's output can be reduced to one about
+    /// its input (cf. `NodeReductionProof`). Nodes which are not linear in
+    /// their input cannot do this without lookup arguments, which are yet to
+    /// be added.
+    pub fn has_claim_reduction(&self) -> bool {
+        match self {
+            Node::BMM(_) | Node::SparseBMM(_) | Node::Reshape(_) => true,
+            Node::RequantiseBMM(_) | Node::ReLU(_) => false,
+            Node::Custom(c) => c.has_claim_reduction(),
         }
     }
 
+    /// Returns the shape of the node's input tensor
+    pub fn input_shape(&self) -> Vec<usize> {
+        node_op!(self, input_shape, NodeOpsNative)
+    }
+
     /// Returns the shape of the node's output tensor
     pub fn shape(&self) -> Vec<usize> {
         node_op!(self, shape, NodeOpsNative)
     }
 
+    // Reshapes the input into the (padded, if `padded` is set) shape the node
+    // expects if it only agrees with it after padding (cf.
+    // `reshape::agree_after_padding`), which leaves its padded values as they
+    // are
+    fn conform_input<'a>(
+        &self,
+        input: &'a QTypeArray<I, O>,
+        padded: bool,
+    ) -> Cow<'a, QTypeArray<I, O>> {
+        let mut shape = self.input_shape();
+
+        if padded {
+            shape = shape.iter().map(|x| x.next_power_of_two()).collect();
+        }
+
+        let input_shape = match input {
+            QTypeArray::S(s) => s.shape(),
+            QTypeArray::L(l) => l.shape(),
+        };
+
+        if *input_shape == shape {
+            return Cow::Borrowed(input);
+        }
+
+        let mut input = input.clone();
+
+        match &mut input {
+            QTypeArray::S(s) => s.reshape(shape),
+            QTypeArray::L(l) => l.reshape(shape),
+        }
+
+        Cow::Owned(input)
+    }
+
     /// Evaluate the node natively (without padding)
     pub fn evaluate(&self, input: &QTypeArray<I, O>) -> QTypeArray<I, O> {
-        match (self, input) {
+        let input = self.conform_input(input, false);
+
+        match (self, &*input) {
             (Node::BMM(fc), QTypeArray::S(input)) => QTypeArray::L(fc.evaluate(input)),
+            (Node::SparseBMM(fc), QTypeArray::S(input)) => QTypeArray::L(fc.evaluate(input)),
             (Node::RequantiseBMM(r), QTypeArray::L(input)) => QTypeArray::S(r.evaluate(input)),
             (Node::ReLU(r), QTypeArray::S(input)) => QTypeArray::S(r.evaluate(input)),
             (Node::Reshape(r), QTypeArray::S(input)) => QTypeArray::S(r.evaluate(input)),
+            (Node::Custom(c), input) if c.input_type() == input.value_type() => c.evaluate(input),
             _ => panic!(
                 "Type mismatch: node of type {} received input of type {}",
                 self.type_name(),
@@ -166,22 +304,82 @@ where
         }
     }
 
+    /// Returns the log of the shape of the node's padded output tensor
+    pub fn padded_shape_log(&self) -> Vec<usize> {
+        node_op!(self, padded_shape_log, NodeOpsPadded)
+    }
+
     pub fn com_num_vars(&self) -> usize {
         node_op!(self, com_num_vars, NodeOpsPadded)
     }
 
+    /// The log of the number of output units of the padded node
+    pub fn padded_num_units_log(&self) -> usize {
+        node_op!(self, padded_num_units_log, NodeOpsPadded)
+    }
+
     /// Here we perform matching without sanity checks. By design, the input type of the
     /// next node in the model is the same as the output type of the current node,
     /// so hiccups should never occur.
     pub fn padded_evaluate(&self, input: &QTypeArray<I, O>) -> QTypeArray<I, O> {
-        match (self, input) {
+        let input = self.conform_input(input, true);
+
+        match (self, &*input) {
             (Node::BMM(fc), QTypeArray::S(input)) => QTypeArray::L(fc.padded_evaluate(input)),
+            (Node::SparseBMM(fc), QTypeArray::S(input)) => QTypeArray::L(fc.padded_evaluate(input)),
             (Node::RequantiseBMM(r), QTypeArray::L(input)) => {
                 QTypeArray::S(r.padded_evaluate(input))
             }
             (Node::ReLU(r), QTypeArray::S(input)) => QTypeArray::S(r.padded_evaluate(input)),
             (Node::Reshape(r), QTypeArray::S(input)) => QTypeArray::S(r.padded_evaluate(input)),
+            (Node::Custom(c), input) => c.padded_evaluate(input),
             _ => panic!("Invalid input type for node"),
         }
     }
 }
+
+impl<I, O> Node<I, O>
+where
+    I: QuantisedInteger + TryFrom<O>,
+    O: QuantisedInteger + From<I>,
+{
+    /// Bounds on the values computed by the node for inputs in the given
+    /// range (cf. `Model::value_bounds`)
+    pub fn bounds(&self, input: ValueRange) -> NodeBounds {
+        match self {
+            Node::BMM(fc) => fc.bounds(input),
+            Node::SparseBMM(fc) => fc.bounds(input),
+            Node::RequantiseBMM(r) => r.bounds(input),
+            Node::ReLU(r) => r.bounds(input),
+            Node::Reshape(_) => NodeBounds::output_only(input),
+            Node::Custom(c) => c.bounds(input),
+        }
+    }
+
+    /// Evaluates the node natively (or its padded version if `padded` is
+    /// set), clamping every operation of its accumulators to the range of
+    /// the large type. Returns the output together with the indices of the
+    /// entries for which some operation was clamped (cf.
+    /// `Model::evaluate_with_mode`).
+    pub fn saturating_evaluate(
+        &self,
+        input: &QTypeArray<I, O>,
+        padded: bool,
+    ) -> (QTypeArray<I, O>, Vec<usize>) {
+        let input = self.conform_input(input, padded);
+
+        match (self, &*input) {
+            (Node::BMM(fc), QTypeArray::S(input)) => {
+                let (output, overflows) = fc.saturating_evaluate(input, padded);
+                (QTypeArray::L(output), overflows)
+            }
+            (Node::SparseBMM(fc), QTypeArray::S(input)) => {
+                let (output, overflows) = fc.saturating_evaluate(input, padded);
+                (QTypeArray::L(output), overflows)
+            }
+            // The remaining nodes cannot overflow
+            _ if padded => (self.padded_evaluate(&input), vec![]),
+            _ => (self.evaluate(&input), vec![]),
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use ark_std::log2;
 
+use crate::model::encoding::{NodeBounds, QuantisedInteger, ValueRange};
 use crate::{model::qarray::InnerType, QArray};
 
 use super::{NodeOpsNative, NodeOpsPadded};
 
 // Rectified linear unit node performing x |-> max(0, x).
 pub struct ReLUNode<ST> {
-    num_units: usize,
+    pub(crate) num_units: usize,
     log_num_units: usize,
     pub zero_point: ST,
 }
@@ -15,6 +16,10 @@ impl<ST> NodeOpsNative<ST, ST> for ReLUNode<ST>
 where
     ST: InnerType,
 {
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.num_units]
+    }
+
     fn shape(&self) -> Vec<usize> {
         vec![self.num_units]
     }
@@ -57,3 +62,17 @@ impl<ST> ReLUNode<ST> {
         }
     }
 }
+
+impl<ST: QuantisedInteger> ReLUNode<ST> {
+    /// Bounds on the values of the node for inputs in the given range (cf.
+    /// `Model::value_bounds`)
+    pub(crate) fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let zero_point = self.zero_point.to_i128();
+        let output = ValueRange::new(input.min.max(zero_point), input.max.max(zero_point));
+
+        NodeBounds {
+            output,
+            intermediate: input.hull(&output),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use ark_std::log2;
 
+use crate::model::encoding::{NodeBounds, QuantisedInteger, ValueRange};
 use crate::model::qarray::{InnerType, QArray};
-use crate::quantization::{requantise_fc, BMMQInfo, QInfo, QScaleType, RoundingScheme};
+use crate::quantization::{
+    requantisation_scale, requantise_fc, BMMQInfo, QInfo, QScaleType, RoundingScheme,
+};
 use crate::{Commitment, CommitmentState};
 
 use super::{NodeOpsNative, NodeOpsPadded};
@@ -11,15 +14,20 @@ use super::{NodeOpsNative, NodeOpsPadded};
 /// Apply requantisation after a BMM argument
 pub struct RequantiseBMMNode<ST> {
     // Number of units
-    size: usize,
+    pub(crate) size: usize,
 
     // log2 of the number of units
     pub padded_size_log: usize,
 
     /// Quantisation info associated to the input BMM result
     pub q_info: BMMQInfo<ST>,
+
+    // Whether a ReLU is applied to the requantised output, i.e. whether it
+    // is clamped below by the output zero point (cf. `with_relu`)
+    pub(crate) relu: bool,
 }
 
+#[derive(Clone)]
 pub struct RequantiseBMMNodeCommitment();
 
 impl Commitment for RequantiseBMMNodeCommitment {}
@@ -37,6 +45,10 @@ where
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.size]
+    }
+
     fn shape(&self) -> Vec<usize> {
         vec![self.size]
     }
@@ -64,7 +76,7 @@ where
         )
         .into();
 
-        output
+        self.activate(output)
     }
 }
 
@@ -106,7 +118,8 @@ where
             RoundingScheme::NearestTiesEven,
         )
         .into();
-        output
+
+        self.activate(output)
     }
 }
 
@@ -120,8 +133,6 @@ impl<ST> RequantiseBMMNode<ST> {
         s_o: QScaleType,
         z_o: ST,
     ) -> Self {
-        let padded_size_log = log2(size.next_power_of_two()) as usize;
-
         // TODO not all of these are needed
         let q_info = BMMQInfo {
             input_info: QInfo {
@@ -138,11 +149,79 @@ impl<ST> RequantiseBMMNode<ST> {
             },
         };
 
+        Self::from_q_info(size, q_info)
+    }
+
+    /// Creates a node requantising `size` values from the quantisation info
+    /// of the input, weights and output of the preceding BMM node
+    pub fn from_q_info(size: usize, q_info: BMMQInfo<ST>) -> Self {
+        let padded_size_log = log2(size.next_power_of_two()) as usize;
+
         Self {
             size,
             padded_size_log,
             q_info,
+            relu: false,
+        }
+    }
+
+    /// Fuses a ReLU node with the same zero point as the output into this
+    /// node, which then outputs the values that ReLU node would
+    pub fn with_relu(self) -> Self {
+        Self { relu: true, ..self }
+    }
+
+    /// Whether a ReLU is fused into the node (cf. `with_relu`)
+    pub fn has_relu(&self) -> bool {
+        self.relu
+    }
+}
+
+impl<ST: InnerType> RequantiseBMMNode<ST> {
+    // Applies the fused ReLU, if any, to the requantised output
+    fn activate(&self, output: QArray<ST>) -> QArray<ST> {
+        if self.relu {
+            output.maximum(self.q_info.output_info.zero_point)
+        } else {
+            output
+        }
+    }
+}
+impl<ST: QuantisedInteger> RequantiseBMMNode<ST> {
+    /// Bounds on the values of the node for inputs in the given range (cf.
+    /// `Model::value_bounds`). Each input entry is scaled and rounded in
+    /// floating point, shifted by the output zero point in the large type
+    /// and clamped to the small one. Rounding down the smallest and up the
+    /// largest scaled values bounds both rounding schemes.
+    pub(crate) fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let s = requantisation_scale(&self.q_info);
+        let scaled = [input.min as QScaleType * s, input.max as QScaleType * s];
+
+        let requantised = ValueRange::new(
+            scaled[0].min(scaled[1]).floor() as i128,
+            scaled[0].max(scaled[1]).ceil() as i128,
+        )
+        .shift(self.q_info.output_info.zero_point.to_i128());
+
+        // The fused ReLU, if any, raises the lower end of the clamping range
+        // to the output zero point
+        let st_min = if self.relu {
+            self.q_info.output_info.zero_point.to_i128()
+        } else {
+            ST::MIN.to_i128()
+        };
+        let st_max = ST::MAX.to_i128();
+
+        let output = ValueRange::new(
+            requantised.min.clamp(st_min, st_max),
+            requantised.max.clamp(st_min, st_max),
+        );
+
+        NodeBounds {
+            output,
+            intermediate: input.hull(&requantised).hull(&output),
         }
     }
 }
+
 // TODO in constructor, add quantisation information checks? (e.g. z_weight = 0, etc.)
@@ -1,4 +1,6 @@
+use ark_ff::PrimeField;
 use ark_std::log2;
+use ark_sumcheck::ml_sumcheck::Proof;
 
 use crate::{model::qarray::InnerType, QArray};
 
@@ -11,10 +13,30 @@ pub struct ReshapeNode {
     pub padded_output_shape_log: Vec<usize>,
 }
 
+/// Proof reducing a claim about the output of a reshape node at a point r (and
+/// the batch point rho, if any) to one about its input. Denoting by M the
+/// matrix sending each entry of the padded input to its position in the
+/// padded output (cf. `ReshapeNode::padded_index_map`), the claim
+///     output^(r) = sum_y input^(y) * M^(r, y)
+/// is proved by a sumcheck over the padded input hypercube. The verifier
+/// evaluates M^ at the challenge point itself.
+pub struct ReshapeNodeReductionProof<F: PrimeField> {
+    /// Sumcheck proof, empty if the padded input consists of a single entry
+    pub sumcheck_proof: Proof<F>,
+
+    /// Claimed value of the *dual* of the input MLE at the sumcheck challenge
+    /// point (together with the batch challenge point, if any)
+    pub input_value: F,
+}
+
 impl<ST> NodeOpsNative<ST, ST> for ReshapeNode
 where
     ST: InnerType,
 {
+    fn input_shape(&self) -> Vec<usize> {
+        self.input_shape.clone()
+    }
+
     fn shape(&self) -> Vec<usize> {
         self.output_shape.clone()
     }
@@ -104,6 +126,60 @@ impl ReshapeNode {
             padded_output_shape_log,
         }
     }
+
+    /// Pairs of positions (in the flattened padded input and output) of each
+    /// entry of the unpadded input and its image under the reshape
+    pub fn padded_index_map(&self) -> Vec<(usize, usize)> {
+        let num_entries: usize = self.input_shape.iter().product();
+
+        (0..num_entries)
+            .map(|k| {
+                (
+                    padded_position(k, &self.input_shape, &self.padded_input_shape_log),
+                    padded_position(k, &self.output_shape, &self.padded_output_shape_log),
+                )
+            })
+            .collect()
+    }
+
+    /// Whether the padded output coincides with the padded input entry by
+    /// entry, so that the reshape only changes the unpadded shape, e.g. by
+    /// adding or removing dimensions of size one or splitting dimensions whose
+    /// sizes are powers of two (cf. `agree_after_padding`)
+    pub fn is_padded_no_op(&self) -> bool {
+        self.padded_input_shape_log.iter().sum::<usize>()
+            == self.padded_output_shape_log.iter().sum::<usize>()
+            && self
+                .padded_index_map()
+                .into_iter()
+                .all(|(input_position, output_position)| input_position == output_position)
+    }
+}
+
+/// Whether values of the first shape can be passed to a node expecting the
+/// second one as they are, i.e. the shapes have the same number of entries
+/// and reshaping from one to the other is a no-op after padding (cf.
+/// `ReshapeNode::is_padded_no_op`). Nodes reshape such inputs themselves.
+pub fn agree_after_padding(shape: &[usize], expected_shape: &[usize]) -> bool {
+    shape == expected_shape
+        || (shape.iter().product::<usize>() == expected_shape.iter().product::<usize>()
+            && ReshapeNode::new(shape.to_vec(), expected_shape.to_vec()).is_padded_no_op())
+}
+
+// Position in the flattened padded array of the entry with the given position
+// in the flattened unpadded one
+fn padded_position(index: usize, shape: &[usize], padded_shape_log: &[usize]) -> usize {
+    let mut index = index;
+    let mut position = 0;
+    let mut stride_log = 0;
+
+    for (dim, dim_log) in shape.iter().zip(padded_shape_log.iter()).rev() {
+        position += (index % dim) << stride_log;
+        index /= dim;
+        stride_log += dim_log;
+    }
+
+    position
 }
 
 // TODO in constructor, add quantisation information checks? (s_bias = s_input * s_weight, z_bias = 0, z_weight = 0, etc.)
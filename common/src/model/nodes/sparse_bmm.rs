@@ -0,0 +1,596 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_std::{any::type_name, log2};
+
+use ark_sumcheck::ml_sumcheck::Proof;
+
+use crate::model::encoding::{accumulate, NodeBounds, QuantisedInteger, ValueRange};
+use crate::model::evaluation::SaturatingArithmetic;
+use crate::model::qarray::{InnerType, QArray};
+use crate::model::{LabeledPoly, Poly};
+use crate::{Commitment, CommitmentState};
+
+use super::{NodeOpsNative, NodeOpsPadded};
+
+/// BMM node whose weight matrix is stored and committed to as the list of its
+/// non-zero entries, as is convenient for pruned models. Evaluation and
+/// proving costs scale with the number of non-zero entries (and the input and
+/// output sizes) rather than with the size of the matrix.
+///
+/// For proving, the entries (padded to a power of two with zero entries at
+/// position (0, 0)) are described by three MLEs: their values, rows and
+/// columns. Following Spark, the evaluation of the weight MLE at the output
+/// challenge point r is expressed as a sum over the entries of
+///     value * (input[row] - zero_point) * eq(r, col),
+/// where the values input[row] and eq(r, col) are committed to by the prover
+/// and shown to be correct lookups into the input and the table of eq(r, .)
+/// with a log-derivative argument. The number of times each row and column is
+/// looked up only depends on the sparsity pattern, and is committed to along
+/// with the weights.
+pub struct SparseBMMNode<ST, LT> {
+    /// The non-zero entries of the unpadded weight matrix as
+    /// (row, column, value), in row-major order
+    pub(crate) entries: Vec<(usize, usize, ST)>,
+    /// The unpadded vector of biases
+    pub(crate) bias: QArray<LT>,
+    /// The padded bias vector
+    pub padded_bias: QArray<LT>,
+    /// Unpadded dimensions (rows, columns)
+    pub(crate) dims: (usize, usize),
+    /// The logarithm of the padded dimensions (rows, columns)
+    pub padded_dims_log: (usize, usize),
+    /// The logarithm of the number of entries padded to a power of two
+    pub padded_num_entries_log: usize,
+    /// Zero-point quantisation parameter of the input
+    pub input_zero_point: ST,
+}
+
+/// Commitment to a sparse BMM node, consisting of commitments to the MLEs of
+/// the values, rows and columns of the entries, to those of the number of
+/// times each row and column is looked up and to the *dual* of the bias MLE
+pub struct SparseBMMNodeCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub value_com: LabeledCommitment<PCS::Commitment>,
+    pub row_com: LabeledCommitment<PCS::Commitment>,
+    pub col_com: LabeledCommitment<PCS::Commitment>,
+    pub row_count_com: LabeledCommitment<PCS::Commitment>,
+    pub col_count_com: LabeledCommitment<PCS::Commitment>,
+    pub bias_com: LabeledCommitment<PCS::Commitment>,
+}
+
+// Cf. the Clone implementation of BMMNodeCommitment
+impl<F, S, PCS> Clone for SparseBMMNodeCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value_com: self.value_com.clone(),
+            row_com: self.row_com.clone(),
+            col_com: self.col_com.clone(),
+            row_count_com: self.row_count_com.clone(),
+            col_count_com: self.col_count_com.clone(),
+            bias_com: self.bias_com.clone(),
+        }
+    }
+}
+
+impl<F, S, PCS> Commitment for SparseBMMNodeCommitment<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+}
+
+/// Commitment states associated to a SparseBMMNodeCommitment
+pub struct SparseBMMNodeCommitmentState<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub value_com_state: PCS::CommitmentState,
+    pub row_com_state: PCS::CommitmentState,
+    pub col_com_state: PCS::CommitmentState,
+    pub row_count_com_state: PCS::CommitmentState,
+    pub col_count_com_state: PCS::CommitmentState,
+    pub bias_com_state: PCS::CommitmentState,
+}
+
+impl<F, S, PCS> CommitmentState for SparseBMMNodeCommitmentState<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+}
+
+/// MLEs of the parameters of a sparse BMM node, computed once as part of the
+/// prover's key
+pub struct SparseBMMNodeMLEs<F: PrimeField> {
+    /// MLEs of the values, rows and columns of the padded list of entries
+    pub value_mle: LabeledPoly<F>,
+    pub row_mle: LabeledPoly<F>,
+    pub col_mle: LabeledPoly<F>,
+    /// MLEs of the number of entries in each row of the padded input and
+    /// each column of the padded output
+    pub row_count_mle: LabeledPoly<F>,
+    pub col_count_mle: LabeledPoly<F>,
+    /// Dual of the MLE of the bias vector
+    pub bias_mle: LabeledPoly<F>,
+}
+
+/// Proof of execution of a sparse BMM node. Denoting by r the output
+/// challenge point, by a_k and b_k the claimed values of input[row_k] and
+/// eq(r, col_k) for the k-th entry and by (alpha, beta) the lookup
+/// challenges, the proof consists of:
+///  - commitments to a and b;
+///  - commitments to the inverses h_row = 1 / (beta - row - alpha * a) and
+///    h_col = 1 / (beta - col - alpha * b) over the entries, and to
+///    q_input = row_count / (beta - y - alpha * input(y)) and
+///    q_eq = col_count / (beta - y - alpha * eq(r, y)) over the tables;
+///  - the sums of h_row and h_col, which must match those of q_input and
+///    q_eq;
+///  - one sumcheck over the entries, which proves
+///    output^(r) - bias^(r) = sum_k value_k * (a_k - zero_point) * b_k
+///    together with the correctness of h_row, h_col and their sums; and one
+///    over each table, which proves the correctness of q_input and q_eq and
+///    their sums;
+///  - the openings needed to check the final claims of the sumchecks.
+///
+/// All of this except for the openings of the input and output is shared with
+/// the reduction of a claim about the output to one about the input (cf.
+/// `SparseBMMNodeReductionProof`), where the output claim is given instead.
+pub struct SparseBMMNodeProof<
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+> {
+    /// Proof reducing the claimed value of the output at r to that of the
+    /// input at the challenge point of the input table sumcheck
+    pub reduction_proof: SparseBMMNodeReductionProof<F, S, PCS, WPCS>,
+
+    /// Proof of opening of the *dual* of the input MLE at the point of the
+    /// reduced claim (together with the batch challenge point, if any)
+    pub input_opening_proof: PCS::Proof,
+
+    /// Value of the *dual* of the output MLE at r (together with the batch
+    /// challenge point, if any) and proof of opening
+    pub output_opening_proof: PCS::Proof,
+    pub output_opening_value: F,
+}
+
+/// Proof reducing a claim about the output of a sparse BMM node at a point
+/// r to one about its input (cf. `SparseBMMNodeProof`)
+pub struct SparseBMMNodeReductionProof<
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+> {
+    /// Commitments to a and b (in that order)
+    pub lookup_coms: Vec<LabeledCommitment<PCS::Commitment>>,
+
+    /// Commitments to h_row, h_col, q_input and q_eq (in that order)
+    pub inverse_coms: Vec<LabeledCommitment<PCS::Commitment>>,
+
+    /// Sums of h_row and h_col
+    pub inverse_sums: Vec<F>,
+
+    /// Sumcheck proofs over the entries, the input table and the eq table.
+    /// These are empty if the corresponding hypercube has no variables.
+    pub entry_sumcheck_proof: Proof<F>,
+    pub input_table_sumcheck_proof: Proof<F>,
+    pub eq_table_sumcheck_proof: Proof<F>,
+
+    /// Values of the MLEs of the entry values, rows and columns, a, b, h_row
+    /// and h_col (in that order) at the challenge point of the entry sumcheck
+    /// and proofs of opening. As the former three are node parameters and the
+    /// latter four are committed to in the proof, they are opened separately.
+    pub entry_weight_opening_proof: WPCS::Proof,
+    pub entry_opening_proof: PCS::Proof,
+    pub entry_opening_values: Vec<F>,
+
+    /// Values of q_input and the row counts at the challenge point of the
+    /// input table sumcheck and proofs of opening (separate as above)
+    pub input_table_opening_proof: PCS::Proof,
+    pub row_count_opening_proof: WPCS::Proof,
+    pub input_table_opening_values: Vec<F>,
+
+    /// Claimed value of the *dual* of the input MLE at the challenge point of
+    /// the input table sumcheck (together with the batch challenge point, if
+    /// any)
+    pub input_value: F,
+
+    /// Values of q_eq and the column counts at the challenge point of the eq
+    /// table sumcheck and proofs of opening (separate as above)
+    pub eq_table_opening_proof: PCS::Proof,
+    pub col_count_opening_proof: WPCS::Proof,
+    pub eq_table_opening_values: Vec<F>,
+
+    /// Value of the *dual* of the bias MLE at r and proof of opening
+    pub bias_opening_proof: WPCS::Proof,
+    pub bias_opening_value: F,
+}
+
+impl<ST, LT> NodeOpsNative<ST, LT> for SparseBMMNode<ST, LT>
+where
+    ST: InnerType,
+    LT: InnerType + From<ST>,
+{
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.dims.0]
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.dims.1]
+    }
+
+    fn evaluate(&self, input: &QArray<ST>) -> QArray<LT> {
+        // Sanity checks
+        // TODO systematise
+        assert_eq!(
+            input.num_dims(),
+            1,
+            "Incorrect shape: Sparse BMM node expects a 1-dimensional input array"
+        );
+        assert_eq!(
+            self.dims.0,
+            input.len(),
+            "Length mismatch: Sparse BMM node expects input with {} elements, got {} elements instead",
+            self.dims.0,
+            input.len()
+        );
+
+        self.sparse_matmul(input.values())
+    }
+}
+
+impl<ST, LT> NodeOpsPadded<ST, LT> for SparseBMMNode<ST, LT>
+where
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    fn padded_shape_log(&self) -> Vec<usize> {
+        vec![self.padded_dims_log.1]
+    }
+
+    fn com_num_vars(&self) -> usize {
+        // The entry MLEs, the row counts and the column counts and bias
+        self.padded_num_entries_log
+            .max(self.padded_dims_log.0)
+            .max(self.padded_dims_log.1)
+    }
+
+    // As for the dense BMM node, only the actual input entries are used and
+    // the padded output entries are zero
+    fn padded_evaluate(&self, input: &QArray<ST>) -> QArray<LT> {
+        let padded_dims = (1 << self.padded_dims_log.0, 1 << self.padded_dims_log.1);
+
+        // Sanity checks
+        // TODO systematise
+        assert_eq!(
+            input.num_dims(),
+            1,
+            "Incorrect shape: Sparse BMM node expects a 1-dimensional input array"
+        );
+
+        assert_eq!(
+            padded_dims.0,
+            input.len(),
+            "Length mismatch: Padded sparse BMM node expected input with {} elements, got {} elements instead",
+            padded_dims.0,
+            input.len()
+        );
+
+        self.sparse_matmul(&input.values()[..self.dims.0])
+            .compact_resize(vec![padded_dims.1], LT::ZERO)
+    }
+}
+
+impl<ST, LT> SparseBMMNode<ST, LT>
+where
+    ST: InnerType,
+    LT: InnerType,
+{
+    // Constructor without the overflow check of `new` (cf.
+    // `BMMNode::new_unchecked`)
+    pub(crate) fn new_unchecked(
+        dims: (usize, usize),
+        entries: Vec<(usize, usize, ST)>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+    ) -> Self {
+        assert_eq!(
+            bias.len(),
+            dims.1,
+            "Bias vector length does not match the number of columns"
+        );
+
+        let mut entries: Vec<(usize, usize, ST)> = entries
+            .into_iter()
+            .filter(|(_, _, w)| *w != ST::ZERO)
+            .collect();
+
+        entries.sort_unstable_by_key(|(i, j, _)| (*i, *j));
+
+        assert!(
+            entries.iter().all(|(i, j, _)| *i < dims.0 && *j < dims.1),
+            "Sparse weight matrix entry out of bounds for dimensions {:?}",
+            dims
+        );
+
+        assert!(
+            entries
+                .windows(2)
+                .all(|e| (e[0].0, e[0].1) != (e[1].0, e[1].1)),
+            "Sparse weight matrix contains repeated entries"
+        );
+
+        let padded_dims_log: (usize, usize) = (
+            log2(dims.0.next_power_of_two()) as usize,
+            log2(dims.1.next_power_of_two()) as usize,
+        );
+
+        let padded_num_entries_log = log2(entries.len().max(1).next_power_of_two()) as usize;
+
+        // Padding the bias
+        let padded_bias = bias
+            .clone()
+            .compact_resize(vec![dims.1.next_power_of_two()], LT::ZERO);
+
+        Self {
+            entries,
+            bias,
+            padded_bias,
+            dims,
+            padded_dims_log,
+            padded_num_entries_log,
+            input_zero_point,
+        }
+    }
+
+    // Same as `from_dense`, without its overflow check
+    pub(crate) fn from_dense_unchecked(
+        weights: &QArray<ST>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+        threshold: ST,
+    ) -> Self {
+        assert_eq!(
+            weights.num_dims(),
+            2,
+            "Incorrect shape: Sparse BMM node expects a 2-dimensional weight matrix"
+        );
+
+        assert!(
+            threshold >= ST::ZERO,
+            "Sparsity threshold must be non-negative"
+        );
+
+        let dims = (weights.shape()[0], weights.shape()[1]);
+
+        // The negative bound is only computed for negative weights, so that
+        // unsigned types do not underflow
+        let entries = weights
+            .values()
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w > threshold || (**w < ST::ZERO && **w < ST::ZERO - threshold))
+            .map(|(k, w)| (k / dims.1, k % dims.1, *w))
+            .collect();
+
+        Self::new_unchecked(dims, entries, bias, input_zero_point)
+    }
+
+    /// Number of non-zero entries of the weight matrix
+    pub fn num_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The entries of the weight matrix padded with zero entries at position
+    /// (0, 0) up to 2^padded_num_entries_log, as committed to by the prover
+    pub fn padded_entries(&self) -> Vec<(usize, usize, ST)> {
+        self.entries
+            .iter()
+            .cloned()
+            .chain(ark_std::iter::repeat((0, 0, ST::ZERO)))
+            .take(1 << self.padded_num_entries_log)
+            .collect()
+    }
+
+    /// Dense row-major weight matrix
+    pub fn to_dense(&self) -> QArray<ST> {
+        let mut weights = vec![ST::ZERO; self.dims.0 * self.dims.1];
+
+        for (i, j, w) in &self.entries {
+            weights[i * self.dims.1 + j] = *w;
+        }
+
+        QArray::new(weights, vec![self.dims.0, self.dims.1])
+    }
+
+    // Computes (input - zero_point) * weights + bias for an unpadded input,
+    // iterating over the non-zero entries only
+    fn sparse_matmul(&self, input: &[ST]) -> QArray<LT>
+    where
+        LT: From<ST>,
+    {
+        let mut output = self.bias.values().clone();
+
+        // Input-by-weight products are computed in the large type, whose
+        // range is checked on construction (cf. `accumulator_overflow`)
+        for (i, j, w) in &self.entries {
+            output[*j] += (LT::from(input[*i]) - LT::from(self.input_zero_point)) * LT::from(*w);
+        }
+
+        QArray::new(output, vec![self.dims.1])
+    }
+}
+
+impl<ST, LT> SparseBMMNode<ST, LT>
+where
+    ST: QuantisedInteger,
+    LT: QuantisedInteger,
+{
+    /// Creates a node with the given unpadded dimensions (rows, columns) of
+    /// the weight matrix and its entries as (row, column, value). These need
+    /// not be sorted, but no position may appear more than once. Entries with
+    /// value zero are discarded. Panics if the accumulators may overflow LT
+    /// for some input (cf. `accumulator_overflow`).
+    pub fn new(
+        dims: (usize, usize),
+        entries: Vec<(usize, usize, ST)>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+    ) -> Self {
+        Self::new_unchecked(dims, entries, bias, input_zero_point).checked()
+    }
+
+    /// Creates a node from a dense weight matrix, pruning all weights whose
+    /// absolute value is at most `threshold` (which must be non-negative). A
+    /// threshold of zero results in a node equivalent to the dense one.
+    /// Panics if the accumulators may overflow LT, as `new` does.
+    pub fn from_dense(
+        weights: &QArray<ST>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+        threshold: ST,
+    ) -> Self {
+        Self::from_dense_unchecked(weights, bias, input_zero_point, threshold).checked()
+    }
+
+    /// Worst-case check of the accumulators based on the magnitudes of the
+    /// non-zero weights (cf. `BMMNode::accumulator_overflow`)
+    pub fn accumulator_overflow(&self) -> Option<ValueRange> {
+        let range = self.bounds(ValueRange::of::<ST>()).intermediate;
+
+        (!ValueRange::of::<LT>().contains(&range)).then_some(range)
+    }
+
+    fn checked(self) -> Self {
+        if let Some(range) = self.accumulator_overflow() {
+            panic!(
+                "Sparse BMM node accumulators range over {} for some inputs, which does not fit in {}",
+                range,
+                type_name::<LT>()
+            );
+        }
+
+        self
+    }
+
+    /// Saturating evaluation of the node (or its padded version if `padded`
+    /// is set), cf. `BMMNode::saturating_evaluate`
+    pub(crate) fn saturating_evaluate(
+        &self,
+        input: &QArray<ST>,
+        padded: bool,
+    ) -> (QArray<LT>, Vec<usize>) {
+        let input_len = if padded {
+            1 << self.padded_dims_log.0
+        } else {
+            self.dims.0
+        };
+
+        assert_eq!(
+            input.num_dims(),
+            1,
+            "Incorrect shape: Sparse BMM node expects a 1-dimensional input array"
+        );
+        assert_eq!(
+            input_len,
+            input.len(),
+            "Length mismatch: Sparse BMM node expects input with {} elements, got {} elements instead",
+            input_len,
+            input.len()
+        );
+
+        let input = input.values();
+        let zero_point = self.input_zero_point.to_i128();
+
+        let mut arithmetic: Vec<SaturatingArithmetic<LT>> = (0..self.dims.1)
+            .map(|_| SaturatingArithmetic::new())
+            .collect();
+        let mut acc: Vec<i128> = self.bias.values().iter().map(|b| b.to_i128()).collect();
+
+        for (i, j, w) in &self.entries {
+            let shifted_input = arithmetic[*j].add(input[*i].to_i128(), -zero_point);
+            let product = arithmetic[*j].mul(shifted_input, w.to_i128());
+            acc[*j] = arithmetic[*j].add(acc[*j], product);
+        }
+
+        let overflows = (0..self.dims.1)
+            .filter(|j| arithmetic[*j].overflowed())
+            .collect();
+
+        let output = QArray::new(
+            acc.into_iter()
+                .map(|x| LT::try_from_i128(x).unwrap())
+                .collect(),
+            vec![self.dims.1],
+        );
+
+        let output = if padded {
+            output.compact_resize(vec![1 << self.padded_dims_log.1], LT::ZERO)
+        } else {
+            output
+        };
+
+        (output, overflows)
+    }
+
+    /// Bounds on the values of the node for inputs in the given range (cf.
+    /// `Model::value_bounds`). Each output entry starts from the bias and
+    /// accumulates the products of the shifted input entries by the non-zero
+    /// weights of its column. The proof additionally encodes the positions of
+    /// the entries and the number of times each of them is looked up.
+    pub(crate) fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let shifted_input = input.shift(-self.input_zero_point.to_i128());
+
+        let mut col_terms: Vec<Vec<ValueRange>> = self
+            .bias
+            .values()
+            .iter()
+            .map(|b| vec![ValueRange::new(b.to_i128(), b.to_i128())])
+            .collect();
+
+        for (_, j, w) in &self.entries {
+            col_terms[*j].push(shifted_input.scale(w.to_i128()));
+        }
+
+        let lookup_sizes = [
+            self.padded_num_entries_log,
+            self.padded_dims_log.0,
+            self.padded_dims_log.1,
+        ];
+
+        let mut output: Option<ValueRange> = None;
+        let mut intermediate = input
+            .hull(&shifted_input)
+            .hull(&ValueRange::new(0, 1 << lookup_sizes.iter().max().unwrap()));
+
+        for terms in col_terms {
+            let (col_output, partial_sums) = accumulate(terms);
+
+            output = Some(output.map_or(col_output, |o| o.hull(&col_output)));
+            intermediate = intermediate.hull(&partial_sums);
+        }
+
+        NodeBounds {
+            output: output.unwrap(),
+            intermediate,
+        }
+    }
+}
+
+// TODO in constructor, add quantisation information checks? (s_bias = s_input * s_weight, z_bias = 0, z_weight = 0, etc.)
@@ -0,0 +1,244 @@
+use crate::model::{serialisation::SerialisationError, validation::ModelError, Model};
+
+use super::custom::{CustomNodeOps, ValueType};
+use super::*;
+
+// A 5 x 3 weight matrix with some zero and small entries
+fn weights() -> QArray<i8> {
+    QArray::new(
+        vec![3, 0, -1, 0, 0, 0, -7, 2, 0, 0, 1, 5, 0, -2, 0],
+        vec![5, 3],
+    )
+}
+
+fn bias() -> QArray<i32> {
+    QArray::new(vec![10, -4, 7], vec![3])
+}
+
+fn inputs() -> Vec<QArray<i8>> {
+    vec![
+        QArray::new(vec![1, 2, 3, 4, 5], vec![5]),
+        QArray::new(vec![-128, 127, 0, -5, 9], vec![5]),
+    ]
+}
+
+#[test]
+fn test_sparse_bmm_matches_dense() {
+    let dense = BMMNode::new(weights(), bias(), -3);
+    let sparse = SparseBMMNode::from_dense(&weights(), bias(), -3, 0);
+
+    assert_eq!(sparse.num_entries(), 7);
+    assert_eq!(sparse.to_dense(), weights());
+
+    for input in inputs() {
+        assert_eq!(sparse.evaluate(&input), dense.evaluate(&input));
+
+        let padded_input = input.compact_resize(vec![8], 0);
+        assert_eq!(
+            sparse.padded_evaluate(&padded_input),
+            dense.padded_evaluate(&padded_input)
+        );
+    }
+}
+
+#[test]
+fn test_sparse_bmm_pruning() {
+    let sparse = BMMNode::new(weights(), bias(), -3).to_sparse(2);
+
+    assert_eq!(sparse.entries, vec![(0, 0, 3), (2, 0, -7), (3, 2, 5)],);
+
+    let pruned = QArray::new(
+        vec![3, 0, 0, 0, 0, 0, -7, 0, 0, 0, 0, 5, 0, 0, 0],
+        vec![5, 3],
+    );
+
+    for input in inputs() {
+        assert_eq!(
+            sparse.evaluate(&input),
+            BMMNode::new(pruned.clone(), bias(), -3).evaluate(&input)
+        );
+    }
+}
+
+#[test]
+fn test_sparse_bmm_padded_entries() {
+    let sparse: SparseBMMNode<i8, i32> =
+        SparseBMMNode::new((5, 3), vec![(4, 1, -2), (0, 2, 6), (1, 1, 0)], bias(), 0);
+
+    // Zero entries are discarded and the rest sorted in row-major order
+    assert_eq!(sparse.padded_num_entries_log, 1);
+    assert_eq!(sparse.padded_entries(), vec![(0, 2, 6), (4, 1, -2)]);
+
+    let empty: SparseBMMNode<i8, i32> = SparseBMMNode::new((5, 3), vec![], bias(), 0);
+
+    assert_eq!(empty.padded_num_entries_log, 0);
+    assert_eq!(empty.padded_entries(), vec![(0, 0, 0)]);
+    assert_eq!(empty.evaluate(&inputs()[0]), bias());
+}
+
+#[test]
+#[should_panic(expected = "repeated entries")]
+fn test_sparse_bmm_repeated_entries() {
+    SparseBMMNode::<i8, i32>::new((5, 3), vec![(1, 1, 2), (1, 1, 3)], bias(), 0);
+}
+
+#[test]
+fn test_reshape_padded_index_map() {
+    // The rows of the input are padded from 3 to 4 entries, whereas the
+    // output needs no padding
+    let reshape = ReshapeNode::new(vec![2, 3], vec![3, 2]);
+
+    assert_eq!(
+        reshape.padded_index_map(),
+        vec![(0, 0), (1, 1), (2, 2), (4, 3), (5, 4), (6, 5)]
+    );
+}
+
+#[test]
+fn test_reshape_padded_no_op() {
+    for (input_shape, output_shape) in [(vec![1, 5], vec![5]), (vec![8], vec![2, 4])] {
+        assert!(ReshapeNode::new(input_shape.clone(), output_shape.clone()).is_padded_no_op());
+        assert!(reshape::agree_after_padding(&input_shape, &output_shape));
+    }
+
+    // Entries are moved by the padding, even when the padded shapes coincide
+    for (input_shape, output_shape) in [(vec![2, 3], vec![6]), (vec![5, 6], vec![6, 5])] {
+        assert!(!ReshapeNode::new(input_shape.clone(), output_shape.clone()).is_padded_no_op());
+        assert!(!reshape::agree_after_padding(&input_shape, &output_shape));
+    }
+
+    assert!(!reshape::agree_after_padding(&[5], &[2, 4]));
+}
+
+// Custom node clamping its input to [min, max] element-wise
+struct ClampNode {
+    num_units: usize,
+    min: i8,
+    max: i8,
+}
+
+impl CustomNodeOps<i8, i32> for ClampNode {
+    fn type_name(&self) -> &'static str {
+        "Clamp"
+    }
+
+    fn input_type(&self) -> ValueType {
+        ValueType::Small
+    }
+
+    fn output_type(&self) -> ValueType {
+        ValueType::Small
+    }
+
+    fn input_shape(&self) -> Vec<usize> {
+        vec![self.num_units]
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.num_units]
+    }
+
+    fn padded_shape_log(&self) -> Vec<usize> {
+        vec![self.num_units.next_power_of_two().trailing_zeros() as usize]
+    }
+
+    fn com_num_vars(&self) -> usize {
+        0
+    }
+
+    fn evaluate(&self, input: &QTypeArray<i8, i32>) -> QTypeArray<i8, i32> {
+        QTypeArray::S(input.ref_small().maximum(self.min).minimum(self.max))
+    }
+
+    fn padded_evaluate(&self, input: &QTypeArray<i8, i32>) -> QTypeArray<i8, i32> {
+        self.evaluate(input)
+    }
+
+    fn bounds(&self, input: ValueRange) -> NodeBounds {
+        let (min, max) = (self.min as i128, self.max as i128);
+
+        NodeBounds {
+            output: ValueRange::new(input.min.clamp(min, max), input.max.clamp(min, max)),
+            intermediate: input.hull(&ValueRange::new(min, max)),
+        }
+    }
+
+    fn encode_parameters(&self) -> Vec<u8> {
+        vec![self.min as u8, self.max as u8]
+    }
+}
+
+fn clamp(min: i8, max: i8) -> Node<i8, i32> {
+    Node::Custom(CustomNode::new(ClampNode {
+        num_units: 3,
+        min,
+        max,
+    }))
+}
+
+fn custom_model(min: i8, max: i8) -> Result<Model<i8, i32>, ModelError> {
+    Model::try_new(
+        vec![5],
+        vec![
+            Node::BMM(BMMNode::new(weights(), bias(), -3)),
+            Node::RequantiseBMM(RequantiseBMMNode::new(3, 0.5, -3, 0.1, 0, 0.2, 4)),
+            clamp(min, max),
+        ],
+    )
+}
+
+#[test]
+fn test_custom_node_evaluate() {
+    let model = custom_model(-10, 10).unwrap();
+    let reference = Model::new(vec![5], model.nodes.into_iter().take(2).collect());
+
+    let Node::Custom(node) = clamp(-10, 10) else {
+        unreachable!()
+    };
+
+    assert_eq!(node.type_name(), "Clamp");
+    assert_eq!(node.downcast_ref::<ClampNode>().unwrap().max, 10);
+
+    for input in inputs() {
+        let expected = reference.evaluate(input.clone()).maximum(-10).minimum(10);
+
+        assert_eq!(custom_model(-10, 10).unwrap().evaluate(input), expected);
+    }
+}
+
+#[test]
+fn test_custom_node_validation() {
+    // The clamp node receives the output of the BMM node
+    let nodes = vec![
+        Node::BMM(BMMNode::new(weights(), bias(), -3)),
+        clamp(-10, 10),
+    ];
+
+    assert_eq!(
+        Model::try_new(vec![5], nodes).err(),
+        Some(ModelError::TypeMismatch {
+            node: 2,
+            expected: "small",
+            found: "large",
+        })
+    );
+}
+
+#[test]
+fn test_custom_node_serialisation() {
+    let model = custom_model(-10, 10).unwrap();
+
+    // The parameters of custom nodes are bound by the content hash, but they
+    // cannot be decoded
+    assert_ne!(
+        model.content_hash(),
+        custom_model(-10, 11).unwrap().content_hash()
+    );
+
+    assert_eq!(
+        Model::<i8, i32>::from_json(&model.to_json().unwrap()).err(),
+        Some(SerialisationError::InvalidModel(
+            "custom node of type Clamp cannot be loaded from a model file".to_string()
+        ))
+    );
+}
@@ -0,0 +1,156 @@
+// Rewriting of the node list of a model into an equivalent one which is
+// cheaper to prove. Every pass preserves the output of `Model::evaluate` (and
+// that of the padded evaluation) exactly, i.e. the optimised model computes
+// the same function as the original one.
+
+use crate::model::encoding::QuantisedInteger;
+use crate::model::nodes::{batch_norm::BatchNormNode, reshape::ReshapeNode, Node};
+use crate::model::qarray::InnerType;
+use crate::model::validation::ModelError;
+use crate::model::Model;
+
+#[cfg(test)]
+mod tests;
+
+/// Rewriting of the nodes of a model which preserves its output (cf.
+/// `Model::optimise_with`)
+pub trait OptimisationPass<ST, LT> {
+    /// Returns the rewritten node list
+    fn apply(&self, nodes: Vec<Node<ST, LT>>) -> Vec<Node<ST, LT>>;
+}
+
+/// Replaces every run of consecutive reshape nodes by a single one and drops
+/// those which leave the shape unchanged (unless the model consists of that
+/// node alone), as well as those which are no-ops after padding (cf.
+/// `ReshapeNode::is_padded_no_op`) unless they determine the output shape of
+/// the model. The node following a dropped reshape receives values which
+/// agree with its input shape after padding (cf. `Model::validate`).
+pub struct MergeReshapes;
+
+/// Fuses every ReLU node following a requantisation node into the latter
+/// (cf. `RequantiseBMMNode::with_relu`), which saves committing to the
+/// values between them
+pub struct FuseRequantiseReLU;
+
+/// Folds every batch normalisation (cf. `BatchNormNode`) following a BMM node
+/// into the weights and bias of the latter (cf. `BatchNormNode::fold_into`).
+/// Nodes which cannot be folded exactly are left as they are.
+pub struct FoldBatchNorm;
+
+impl<ST, LT> OptimisationPass<ST, LT> for MergeReshapes {
+    fn apply(&self, nodes: Vec<Node<ST, LT>>) -> Vec<Node<ST, LT>> {
+        let mut merged: Vec<Node<ST, LT>> = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            match (node, merged.pop()) {
+                (Node::Reshape(r), Some(Node::Reshape(previous))) => merged.push(Node::Reshape(
+                    ReshapeNode::new(previous.input_shape, r.output_shape),
+                )),
+                (node, previous) => {
+                    merged.extend(previous);
+                    merged.push(node);
+                }
+            }
+        }
+
+        if merged.len() == 1 {
+            return merged;
+        }
+
+        let last = merged.len() - 1;
+
+        merged
+            .into_iter()
+            .enumerate()
+            .filter(|(i, node)| match node {
+                Node::Reshape(r) => {
+                    r.input_shape != r.output_shape && (*i == last || !r.is_padded_no_op())
+                }
+                _ => true,
+            })
+            .map(|(_, node)| node)
+            .collect()
+    }
+}
+
+impl<ST: InnerType, LT> OptimisationPass<ST, LT> for FuseRequantiseReLU {
+    fn apply(&self, nodes: Vec<Node<ST, LT>>) -> Vec<Node<ST, LT>> {
+        let mut fused: Vec<Node<ST, LT>> = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            match (node, fused.pop()) {
+                (Node::ReLU(relu), Some(Node::RequantiseBMM(requantise)))
+                    if !requantise.has_relu()
+                        && relu.zero_point == requantise.q_info.output_info.zero_point =>
+                {
+                    fused.push(Node::RequantiseBMM(requantise.with_relu()))
+                }
+                (node, previous) => {
+                    fused.extend(previous);
+                    fused.push(node);
+                }
+            }
+        }
+
+        fused
+    }
+}
+
+impl<ST, LT> OptimisationPass<ST, LT> for FoldBatchNorm
+where
+    ST: QuantisedInteger,
+    LT: QuantisedInteger,
+{
+    fn apply(&self, nodes: Vec<Node<ST, LT>>) -> Vec<Node<ST, LT>> {
+        let mut folded: Vec<Node<ST, LT>> = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            let fold = match (&node, folded.last()) {
+                (Node::Custom(custom), Some(Node::BMM(bmm))) => custom
+                    .downcast_ref::<BatchNormNode<LT>>()
+                    .and_then(|batch_norm| batch_norm.fold_into(bmm)),
+                _ => None,
+            };
+
+            match fold {
+                Some(bmm) => *folded.last_mut().unwrap() = Node::BMM(bmm),
+                None => folded.push(node),
+            }
+        }
+
+        folded
+    }
+}
+
+impl<ST, LT> Model<ST, LT>
+where
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    /// Applies the optimisation passes of the library (cf. `optimise_with`),
+    /// i.e. `MergeReshapes`, `FoldBatchNorm` and `FuseRequantiseReLU`
+    pub fn optimise(self) -> Self
+    where
+        ST: QuantisedInteger,
+        LT: QuantisedInteger,
+    {
+        self.optimise_with(&[&MergeReshapes, &FoldBatchNorm, &FuseRequantiseReLU])
+            .unwrap_or_else(|e| panic!("Invalid optimised model: {}", e))
+    }
+
+    /// Applies the given passes to the nodes of the model in order and checks
+    /// the resulting model (cf. `Model::validate`). The output of `evaluate`
+    /// is preserved as long as each pass preserves it.
+    pub fn optimise_with(
+        self,
+        passes: &[&dyn OptimisationPass<ST, LT>],
+    ) -> Result<Self, ModelError> {
+        let Model {
+            input_shape, nodes, ..
+        } = self;
+
+        let nodes = passes.iter().fold(nodes, |nodes, pass| pass.apply(nodes));
+
+        Model::try_new(input_shape, nodes)
+    }
+}
@@ -0,0 +1,282 @@
+use crate::model::evaluation::OverflowMode;
+use crate::model::nodes::{
+    bmm::BMMNode, custom::CustomNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode,
+};
+use crate::model::qarray::QArray;
+
+use super::*;
+
+const Z_I: i8 = -5;
+const Z_1: i8 = 3;
+const Z_2: i8 = -10;
+
+fn bmm(rows: usize, cols: usize, input_zero_point: i8) -> Node<i8, i32> {
+    let weights = (0..rows * cols)
+        .map(|i| ((7 * i as i32) % 23 - 11) as i8)
+        .collect();
+    let bias = (0..cols).map(|i| 40 * i as i32 - 60).collect();
+
+    Node::BMM(BMMNode::new(
+        QArray::new(weights, vec![rows, cols]),
+        QArray::new(bias, vec![cols]),
+        input_zero_point,
+    ))
+}
+
+fn requantise(size: usize, input_zero_point: i8, output_zero_point: i8) -> Node<i8, i32> {
+    Node::RequantiseBMM(RequantiseBMMNode::new(
+        size,
+        0.05,
+        input_zero_point,
+        0.1,
+        0,
+        0.04,
+        output_zero_point,
+    ))
+}
+
+fn reshape(input_shape: Vec<usize>, output_shape: Vec<usize>) -> Node<i8, i32> {
+    Node::Reshape(ReshapeNode::new(input_shape, output_shape))
+}
+
+// Two-layer perceptron with redundant reshapes around its layers, as produced
+// by some importers
+fn unoptimised_model() -> Model<i8, i32> {
+    Model::new(
+        vec![2, 3],
+        vec![
+            reshape(vec![2, 3], vec![3, 2]),
+            reshape(vec![3, 2], vec![6]),
+            reshape(vec![6], vec![6]),
+            bmm(6, 4, Z_I),
+            requantise(4, Z_I, Z_1),
+            Node::ReLU(ReLUNode::new(4, Z_1)),
+            reshape(vec![4], vec![2, 2]),
+            reshape(vec![2, 2], vec![4]),
+            bmm(4, 3, Z_1),
+            requantise(3, Z_1, Z_2),
+            Node::ReLU(ReLUNode::new(3, Z_2)),
+        ],
+    )
+}
+
+fn inputs() -> Vec<QArray<i8>> {
+    vec![
+        QArray::new(vec![1, 2, 3, 4, 5, 6], vec![2, 3]),
+        QArray::new(vec![-128, 127, 0, -5, 9, 64], vec![2, 3]),
+        QArray::new(vec![100, -100, 50, -50, 25, -25], vec![2, 3]),
+        QArray::new(vec![-5; 6], vec![2, 3]),
+    ]
+}
+
+#[test]
+fn test_optimised_nodes() {
+    let model = unoptimised_model().optimise();
+
+    assert_eq!(model.nodes.len(), 5);
+
+    match &model.nodes[0] {
+        Node::Reshape(r) => {
+            assert_eq!(r.input_shape, vec![2, 3]);
+            assert_eq!(r.output_shape, vec![6]);
+        }
+        _ => panic!("Expected a reshape node"),
+    }
+
+    for i in [2, 4] {
+        match &model.nodes[i] {
+            Node::RequantiseBMM(r) => assert!(r.has_relu()),
+            _ => panic!("Expected a requantisation node"),
+        }
+    }
+
+    assert_eq!(model.output_shape, vec![3]);
+}
+
+#[test]
+fn test_optimisation_preserves_outputs() {
+    let unoptimised = unoptimised_model();
+    let optimised = unoptimised_model().optimise();
+
+    for input in inputs() {
+        assert_eq!(
+            optimised.evaluate(input.clone()),
+            unoptimised.evaluate(input.clone())
+        );
+
+        assert_eq!(
+            optimised
+                .padded_evaluate_with_mode(input.clone(), OverflowMode::Checked)
+                .unwrap(),
+            unoptimised
+                .padded_evaluate_with_mode(input, OverflowMode::Checked)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_individual_passes_preserve_outputs() {
+    let unoptimised = unoptimised_model();
+
+    for pass in [
+        &MergeReshapes as &dyn OptimisationPass<i8, i32>,
+        &FuseRequantiseReLU,
+    ] {
+        let optimised = unoptimised_model().optimise_with(&[pass]).unwrap();
+
+        assert!(optimised.nodes.len() < unoptimised.nodes.len());
+
+        for input in inputs() {
+            assert_eq!(
+                optimised.evaluate(input.clone()),
+                unoptimised.evaluate(input)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fused_relu_round_trip() {
+    let model = unoptimised_model().optimise();
+    let loaded = Model::<i8, i32>::from_bytes(&model.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(loaded.content_hash(), model.content_hash());
+    assert_ne!(loaded.content_hash(), unoptimised_model().content_hash());
+
+    for input in inputs() {
+        assert_eq!(loaded.evaluate(input.clone()), model.evaluate(input));
+    }
+}
+
+// Perceptron with reshapes which are no-ops after padding: a flattening of
+// the model input, a split of the hidden layer into a square and a final
+// reshape determining the output shape
+fn padded_no_op_model() -> Model<i8, i32> {
+    Model::new(
+        vec![1, 6],
+        vec![
+            reshape(vec![1, 6], vec![6]),
+            bmm(6, 4, Z_I),
+            requantise(4, Z_I, Z_1),
+            reshape(vec![4], vec![2, 2]),
+            Node::ReLU(ReLUNode::new(4, Z_1)),
+            bmm(4, 3, Z_1),
+            requantise(3, Z_1, Z_2),
+            reshape(vec![3], vec![1, 3]),
+        ],
+    )
+}
+
+#[test]
+fn test_padded_no_op_reshapes_are_dropped() {
+    let unoptimised = padded_no_op_model();
+    let optimised = padded_no_op_model()
+        .optimise_with(&[&MergeReshapes])
+        .unwrap();
+
+    assert_eq!(optimised.nodes.len(), 6);
+    assert!(matches!(optimised.nodes[0], Node::BMM(_)));
+    assert!(matches!(optimised.nodes[3], Node::BMM(_)));
+    assert!(matches!(optimised.nodes[5], Node::Reshape(_)));
+    assert_eq!(optimised.output_shape, vec![1, 3]);
+
+    for input in inputs() {
+        let input = QArray::new(input.values().clone(), vec![1, 6]);
+
+        assert_eq!(
+            optimised.evaluate(input.clone()),
+            unoptimised.evaluate(input.clone())
+        );
+
+        assert_eq!(
+            optimised
+                .padded_evaluate_with_mode(input.clone(), OverflowMode::Checked)
+                .unwrap(),
+            unoptimised
+                .padded_evaluate_with_mode(input, OverflowMode::Checked)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_single_identity_reshape_is_kept() {
+    let model = Model::<i8, i32>::new(vec![4], vec![reshape(vec![4], vec![4])]).optimise();
+
+    assert_eq!(model.nodes.len(), 1);
+    assert_eq!(
+        model.evaluate(QArray::new(vec![1, -2, 3, -4], vec![4])),
+        QArray::new(vec![1, -2, 3, -4], vec![4])
+    );
+}
+
+fn batch_norm(scale: Vec<i32>, shift: Vec<i32>) -> Node<i8, i32> {
+    let len = scale.len();
+
+    Node::Custom(CustomNode::new(BatchNormNode::new(
+        QArray::new(scale, vec![len]),
+        QArray::new(shift, vec![len]),
+    )))
+}
+
+// Perceptron with a batch normalisation after each BMM node, whose scales
+// (at most 3 in absolute value) keep the folded weights within i8
+fn batch_norm_model(first_scale: Vec<i32>) -> Model<i8, i32> {
+    Model::new(
+        vec![6],
+        vec![
+            bmm(6, 4, Z_I),
+            batch_norm(first_scale, vec![7, -300, 0, 25]),
+            requantise(4, Z_I, Z_1),
+            Node::ReLU(ReLUNode::new(4, Z_1)),
+            bmm(4, 3, Z_1),
+            batch_norm(vec![-1, 1, 2], vec![0, 50, -50]),
+            requantise(3, Z_1, Z_2),
+        ],
+    )
+}
+
+#[test]
+fn test_batch_norm_is_folded() {
+    let unoptimised = batch_norm_model(vec![2, 3, -3, 1]);
+    let optimised = batch_norm_model(vec![2, 3, -3, 1])
+        .optimise_with(&[&FoldBatchNorm])
+        .unwrap();
+
+    assert_eq!(optimised.nodes.len(), 5);
+    assert!(!optimised
+        .nodes
+        .iter()
+        .any(|node| matches!(node, Node::Custom(_))));
+
+    for input in inputs() {
+        let input = QArray::new(input.values().clone(), vec![6]);
+
+        assert_eq!(
+            optimised.evaluate(input.clone()),
+            unoptimised.evaluate(input.clone())
+        );
+
+        assert_eq!(
+            optimised
+                .padded_evaluate_with_mode(input.clone(), OverflowMode::Checked)
+                .unwrap(),
+            unoptimised
+                .padded_evaluate_with_mode(input, OverflowMode::Checked)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_unfoldable_batch_norm_is_kept() {
+    // Scaling the weights (of absolute value up to 11) by 20 overflows i8
+    let optimised = batch_norm_model(vec![2, 20, -3, 1])
+        .optimise_with(&[&FoldBatchNorm])
+        .unwrap();
+
+    assert_eq!(optimised.nodes.len(), 6);
+    assert!(matches!(optimised.nodes[1], Node::Custom(_)));
+    assert!(matches!(optimised.nodes[4], Node::BMM(_)));
+}
@@ -0,0 +1,400 @@
+// Tensor operations on QArrays: element-wise arithmetic between arrays with
+// numpy-style broadcasting, multi-dimensional indexing, slicing, axis
+// permutations, concatenation and matrix multiplication. These are reference
+// implementations meant for node evaluation, not optimised kernels, except for
+// the matrix product, which is parallelised under the `parallel` feature.
+
+use ark_std::cfg_chunks_mut;
+use ark_std::ops::{Add, Div, Index, IndexMut, Mul, Range, Sub};
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{InnerType, QArray};
+
+/// Returns the shape resulting from broadcasting arrays of shapes `a` and `b`
+/// against each other, or `None` if they are incompatible. As in numpy, shapes
+/// are aligned on their last dimension and any dimension of size 1 (or
+/// missing) is stretched to match the other one.
+pub fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let len = a.len().max(b.len());
+
+    // Dimension i of the shape s left-padded with 1s to length len
+    let dim = |s: &[usize], i: usize| {
+        if i + s.len() < len {
+            1
+        } else {
+            s[i + s.len() - len]
+        }
+    };
+
+    (0..len)
+        .map(|i| match (dim(a, i), dim(b, i)) {
+            (x, y) if x == y || y == 1 => Some(x),
+            (1, y) => Some(y),
+            _ => None,
+        })
+        .collect()
+}
+
+impl<T: InnerType> QArray<T> {
+    /************************ Indexing ************************/
+
+    /// Returns the entry at the given n-dimensional index
+    pub fn get(&self, index: &[usize]) -> T {
+        self.flattened[self.flatten_index(index)]
+    }
+
+    /// Sets the entry at the given n-dimensional index
+    pub fn set(&mut self, index: &[usize], value: T) {
+        let i = self.flatten_index(index);
+        self.flattened[i] = value;
+    }
+
+    // Collects the entries at positions offset + Σ index[i] * strides[i] for
+    // all multi-indices within `shape`, in row-major order
+    fn strided_values(&self, shape: &[usize], strides: &[usize], offset: usize) -> Vec<T> {
+        let len = shape.iter().product::<usize>();
+        let mut values = Vec::with_capacity(len);
+
+        if len == 0 {
+            return values;
+        }
+
+        let mut index = vec![0; shape.len()];
+        let mut position = offset;
+
+        for _ in 0..len {
+            values.push(self.flattened[position]);
+
+            // Incrementing the multi-index, starting from the last dimension
+            for d in (0..shape.len()).rev() {
+                index[d] += 1;
+                position += strides[d];
+
+                if index[d] < shape[d] {
+                    break;
+                }
+
+                index[d] = 0;
+                position -= strides[d] * shape[d];
+            }
+        }
+
+        values
+    }
+
+    /// Returns the sub-array determined by one range for each of the leading
+    /// dimensions. Dimensions beyond `ranges.len()` are kept in full, and
+    /// the number of dimensions is preserved.
+    pub fn slice(&self, ranges: &[Range<usize>]) -> QArray<T> {
+        assert!(
+            ranges.len() <= self.num_dims(),
+            "Cannot slice a {}-dimensional array along {} dimensions",
+            self.num_dims(),
+            ranges.len()
+        );
+
+        let shape: Vec<usize> = self
+            .shape
+            .iter()
+            .enumerate()
+            .map(|(i, d)| match ranges.get(i) {
+                Some(range) => {
+                    assert!(
+                        range.start <= range.end && range.end <= *d,
+                        "Range {:?} is out of bounds for dimension {} of size {}",
+                        range,
+                        i,
+                        d
+                    );
+                    range.end - range.start
+                }
+                None => *d,
+            })
+            .collect();
+
+        let offset = ranges
+            .iter()
+            .zip(self.cumulative_dimensions.iter())
+            .map(|(range, c)| range.start * c)
+            .sum();
+
+        let flattened = self.strided_values(&shape, &self.cumulative_dimensions, offset);
+
+        QArray::new(flattened, shape)
+    }
+
+    /************************ Reordering ************************/
+
+    /// Permutes the dimensions of the array, so that dimension `i` of the
+    /// result is dimension `permutation[i]` of `self`
+    pub fn permute(&self, permutation: &[usize]) -> QArray<T> {
+        let mut sorted = permutation.to_vec();
+        sorted.sort_unstable();
+
+        assert!(
+            sorted.iter().copied().eq(0..self.num_dims()),
+            "{:?} is not a permutation of the {} dimensions of the array",
+            permutation,
+            self.num_dims()
+        );
+
+        let shape: Vec<usize> = permutation.iter().map(|p| self.shape[*p]).collect();
+        let strides: Vec<usize> = permutation
+            .iter()
+            .map(|p| self.cumulative_dimensions[*p])
+            .collect();
+
+        QArray::new(self.strided_values(&shape, &strides, 0), shape)
+    }
+
+    /// Reverses the order of the dimensions of the array (as `numpy.transpose`
+    /// does). For matrices, this is the usual transpose.
+    pub fn transpose(&self) -> QArray<T> {
+        let permutation: Vec<usize> = (0..self.num_dims()).rev().collect();
+        self.permute(&permutation)
+    }
+
+    /// Concatenates arrays along the dimension `axis`, along which their sizes
+    /// may differ. All other dimensions must coincide.
+    pub fn concat(arrays: &[&QArray<T>], axis: usize) -> QArray<T> {
+        assert!(!arrays.is_empty(), "Cannot concatenate an empty list");
+
+        let first = arrays[0];
+
+        assert!(
+            axis < first.num_dims(),
+            "Cannot concatenate {}-dimensional arrays along axis {}",
+            first.num_dims(),
+            axis
+        );
+
+        for array in arrays {
+            assert!(
+                array.num_dims() == first.num_dims()
+                    && (0..first.num_dims()).all(|i| i == axis || array.shape[i] == first.shape[i]),
+                "Cannot concatenate arrays of shapes {:?} and {:?} along axis {}",
+                first.shape,
+                array.shape,
+                axis
+            );
+        }
+
+        let mut shape = first.shape.clone();
+        shape[axis] = arrays.iter().map(|a| a.shape[axis]).sum();
+
+        // Each array consists of the same number of contiguous blocks (one for
+        // each index of the dimensions before axis), which are interleaved
+        let num_blocks = first.shape[..axis].iter().product::<usize>();
+
+        let mut flattened = Vec::with_capacity(shape.iter().product());
+
+        for block in 0..num_blocks {
+            for array in arrays {
+                let block_len = array.shape[axis] * array.cumulative_dimensions[axis];
+                flattened.extend_from_slice(
+                    &array.flattened[block * block_len..(block + 1) * block_len],
+                );
+            }
+        }
+
+        QArray::new(flattened, shape)
+    }
+
+    /************************ Broadcasting ************************/
+
+    // Values of the array broadcast to `shape`, which is assumed to be a valid
+    // broadcast target
+    fn broadcast_values(&self, shape: &[usize]) -> Vec<T> {
+        let extra_dims = shape.len() - self.num_dims();
+
+        // Broadcast dimensions are traversed with stride zero
+        let strides: Vec<usize> = (0..shape.len())
+            .map(|i| match i.checked_sub(extra_dims) {
+                Some(j) if self.shape[j] != 1 => self.cumulative_dimensions[j],
+                _ => 0,
+            })
+            .collect();
+
+        self.strided_values(shape, &strides, 0)
+    }
+
+    /// Stretches the array to the given shape following numpy's broadcasting
+    /// rules (cf. `broadcast_shape`)
+    pub fn broadcast_to(&self, shape: Vec<usize>) -> QArray<T> {
+        assert!(
+            broadcast_shape(&self.shape, &shape).as_ref() == Some(&shape),
+            "Cannot broadcast an array of shape {:?} to {:?}",
+            self.shape,
+            shape
+        );
+
+        QArray::new(self.broadcast_values(&shape), shape)
+    }
+
+    /// Applies `f` to each pair of entries of `self` and `other` after
+    /// broadcasting them to a common shape
+    pub fn zip_with(&self, other: &QArray<T>, f: impl Fn(T, T) -> T) -> QArray<T> {
+        // Fast path avoiding any index computations
+        if self.shape == other.shape {
+            let flattened = self
+                .flattened
+                .iter()
+                .zip(other.flattened.iter())
+                .map(|(x, y)| f(*x, *y))
+                .collect();
+
+            return QArray {
+                flattened,
+                shape: self.shape.clone(),
+                cumulative_dimensions: self.cumulative_dimensions.clone(),
+            };
+        }
+
+        let shape = broadcast_shape(&self.shape, &other.shape).unwrap_or_else(|| {
+            panic!(
+                "Arrays of shapes {:?} and {:?} cannot be broadcast together",
+                self.shape, other.shape
+            )
+        });
+
+        let flattened = self
+            .broadcast_values(&shape)
+            .into_iter()
+            .zip(other.broadcast_values(&shape))
+            .map(|(x, y)| f(x, y))
+            .collect();
+
+        QArray::new(flattened, shape)
+    }
+
+    /// Element-wise maximum of two arrays (with broadcasting)
+    pub fn max(&self, other: &QArray<T>) -> QArray<T> {
+        self.zip_with(other, |x, y| if x >= y { x } else { y })
+    }
+
+    /// Element-wise minimum of two arrays (with broadcasting)
+    pub fn min(&self, other: &QArray<T>) -> QArray<T> {
+        self.zip_with(other, |x, y| if x <= y { x } else { y })
+    }
+
+    /************************ Matrix product ************************/
+
+    // Maximum number of output columns computed by each (parallel) task of
+    // the matrix product
+    const MATMUL_COLUMN_BLOCK: usize = 256;
+
+    /// Matrix product of two arrays with at most two dimensions, following
+    /// `numpy.matmul`: a one-dimensional left (resp. right) operand is treated
+    /// as a row (resp. column) vector, and the corresponding dimension is
+    /// removed from the result (the product of two vectors has shape [1]).
+    /// Entries are accumulated in `T`, so arrays of a small type should be
+    /// cast to a larger one beforehand.
+    pub fn matmul(&self, other: &QArray<T>) -> QArray<T> {
+        assert!(
+            self.num_dims() <= 2 && other.num_dims() <= 2,
+            "Matrix product is only supported for arrays with at most two dimensions, got shapes {:?} and {:?}",
+            self.shape,
+            other.shape
+        );
+
+        let (m, k) = match self.shape[..] {
+            [k] => (1, k),
+            [m, k] => (m, k),
+            _ => unreachable!(),
+        };
+
+        let (k_other, n) = match other.shape[..] {
+            [k] => (k, 1),
+            [k, n] => (k, n),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            k, k_other,
+            "Inner dimension mismatch in matrix product of arrays of shapes {:?} and {:?}",
+            self.shape, other.shape
+        );
+
+        let mut flattened = vec![T::ZERO; m * n];
+
+        // Output rows are split into blocks of columns, which are computed
+        // independently. This parallelises the (most common) vector-by-matrix
+        // case too, where there is only one row.
+        cfg_chunks_mut!(flattened, n.max(1))
+            .enumerate()
+            .for_each(|(row, output_row)| {
+                let lhs_row = &self.flattened[row * k..(row + 1) * k];
+
+                cfg_chunks_mut!(output_row, Self::MATMUL_COLUMN_BLOCK)
+                    .enumerate()
+                    .for_each(|(block, output_block)| {
+                        let start = block * Self::MATMUL_COLUMN_BLOCK;
+
+                        // Traversing other row-wise is cache-friendlier than
+                        // column-wise
+                        for (x, other_row) in lhs_row.iter().zip(other.flattened.chunks_exact(n)) {
+                            for (acc, y) in output_block.iter_mut().zip(&other_row[start..]) {
+                                *acc += *x * *y;
+                            }
+                        }
+                    });
+            });
+
+        let shape = match (self.num_dims(), other.num_dims()) {
+            (1, 1) => vec![1],
+            (1, _) => vec![n],
+            (_, 1) => vec![m],
+            _ => vec![m, n],
+        };
+
+        QArray::new(flattened, shape)
+    }
+}
+
+impl<T: InnerType, const N: usize> Index<[usize; N]> for QArray<T> {
+    type Output = T;
+
+    fn index(&self, index: [usize; N]) -> &Self::Output {
+        &self.flattened[self.flatten_index(&index)]
+    }
+}
+
+impl<T: InnerType, const N: usize> IndexMut<[usize; N]> for QArray<T> {
+    fn index_mut(&mut self, index: [usize; N]) -> &mut Self::Output {
+        let i = self.flatten_index(&index);
+        &mut self.flattened[i]
+    }
+}
+
+/******************* Element-wise operators *******************/
+
+// Unlike the scalar operators, these are implemented for both owned arrays
+// and references, the latter avoiding clones when operands are reused
+macro_rules! impl_elementwise_op {
+    ($op_trait:ident, $op_fn:ident, $op:tt) => {
+        impl<T: InnerType> $op_trait<&QArray<T>> for &QArray<T> {
+            type Output = QArray<T>;
+
+            fn $op_fn(self, rhs: &QArray<T>) -> QArray<T> {
+                self.zip_with(rhs, |x, y| x $op y)
+            }
+        }
+
+        impl<T: InnerType> $op_trait<QArray<T>> for QArray<T> {
+            type Output = QArray<T>;
+
+            fn $op_fn(self, rhs: QArray<T>) -> QArray<T> {
+                self.zip_with(&rhs, |x, y| x $op y)
+            }
+        }
+    };
+}
+
+impl_elementwise_op!(Add, add, +);
+impl_elementwise_op!(Sub, sub, -);
+impl_elementwise_op!(Mul, mul, *);
+impl_elementwise_op!(Div, div, /);
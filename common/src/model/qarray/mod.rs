@@ -13,15 +13,22 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use crate::model::nodes::custom::ValueType;
 use crate::quantization::QScaleType;
 
+mod algebra;
+mod npy;
+
+pub use algebra::broadcast_shape;
+
 #[cfg(test)]
 mod tests;
 
 const QARRAY_NESTED_TAB: &str = "    ";
 
 pub trait InnerType:
-    Copy
+    'static
+    + Copy
     + Debug
     + PartialEq
     + PartialOrd
@@ -35,6 +42,8 @@ pub trait InnerType:
     + DivAssign
     + Serialize
     + DeserializeOwned
+    + Send
+    + Sync
 {
     const ZERO: Self;
     const MIN: Self;
@@ -44,6 +53,16 @@ pub trait InnerType:
     // types, this will change
     fn from_qscaletype(x: QScaleType) -> Self;
     fn to_qscaletype(&self) -> QScaleType;
+
+    /// NumPy type descriptor of the type, as in `numpy.dtype.str`
+    const NPY_DESCR: &'static str;
+
+    /// Reads a value from its little-endian byte representation, which must
+    /// have length `size_of::<Self>()`
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Appends the little-endian byte representation of the value
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>);
 }
 
 impl InnerType for i8 {
@@ -58,6 +77,16 @@ impl InnerType for i8 {
     fn to_qscaletype(&self) -> QScaleType {
         *self as QScaleType
     }
+
+    const NPY_DESCR: &'static str = "|i1";
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
 }
 impl InnerType for i32 {
     const ZERO: Self = 0;
@@ -71,6 +100,16 @@ impl InnerType for i32 {
     fn to_qscaletype(&self) -> QScaleType {
         *self as QScaleType
     }
+
+    const NPY_DESCR: &'static str = "<i4";
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
 }
 impl InnerType for u8 {
     const ZERO: Self = 0;
@@ -84,6 +123,16 @@ impl InnerType for u8 {
     fn to_qscaletype(&self) -> QScaleType {
         *self as QScaleType
     }
+
+    const NPY_DESCR: &'static str = "|u1";
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
 }
 
 impl InnerType for f32 {
@@ -98,18 +147,140 @@ impl InnerType for f32 {
     fn to_qscaletype(&self) -> QScaleType {
         *self as QScaleType
     }
+
+    const NPY_DESCR: &'static str = "<f4";
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        Self::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
 }
 
+// The cumulative dimensions are not serialised, since they are determined by
+// the shape. They are ignored if present (as in older JSON files) and
+// recomputed on deserialisation.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "QArrayData<T>")]
 pub struct QArray<T> {
     #[serde(rename = "f")]
     flattened: Vec<T>,
     #[serde(rename = "s")]
     shape: Vec<usize>,
-    #[serde(rename = "c")]
+    #[serde(skip_serializing)]
     cumulative_dimensions: Vec<usize>,
 }
 
+#[derive(Deserialize)]
+struct QArrayData<T> {
+    #[serde(rename = "f")]
+    flattened: Vec<T>,
+    #[serde(rename = "s")]
+    shape: Vec<usize>,
+}
+
+impl<T> TryFrom<QArrayData<T>> for QArray<T> {
+    type Error = QArrayError;
+
+    fn try_from(data: QArrayData<T>) -> Result<Self, Self::Error> {
+        check_shape(data.flattened.len(), &data.shape)?;
+
+        Ok(QArray {
+            cumulative_dimensions: cumulative_dimensions(&data.shape),
+            flattened: data.flattened,
+            shape: data.shape,
+        })
+    }
+}
+
+/// Errors arising when reading or writing QArrays
+#[derive(Debug, Clone, PartialEq)]
+pub enum QArrayError {
+    /// The file could not be read or written
+    Io(String),
+    /// The contents could not be (de)serialised
+    Encoding(String),
+    /// The shape does not match the number of entries
+    Shape(String),
+    /// The stored element type is not the requested one
+    TypeMismatch { expected: String, found: String },
+}
+
+impl fmt::Display for QArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QArrayError::Io(e) => write!(f, "Unable to access QArray file: {}", e),
+            QArrayError::Encoding(e) => write!(f, "Unable to (de)serialise QArray: {}", e),
+            QArrayError::Shape(e) => write!(f, "Invalid QArray shape: {}", e),
+            QArrayError::TypeMismatch { expected, found } => write!(
+                f,
+                "QArray element type {} does not match the expected {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QArrayError {}
+
+impl From<std::io::Error> for QArrayError {
+    fn from(e: std::io::Error) -> Self {
+        QArrayError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for QArrayError {
+    fn from(e: serde_json::Error) -> Self {
+        QArrayError::Encoding(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for QArrayError {
+    fn from(e: bincode::Error) -> Self {
+        QArrayError::Encoding(e.to_string())
+    }
+}
+
+// Checks the conditions asserted by QArray::new
+fn check_shape(len: usize, shape: &[usize]) -> Result<(), QArrayError> {
+    if shape.is_empty() {
+        return Err(QArrayError::Shape(
+            "arrays cannot be zero-dimensional".to_string(),
+        ));
+    }
+
+    if shape.iter().product::<usize>() != len {
+        return Err(QArrayError::Shape(format!(
+            "incorrect shape {:?} for data of length {}",
+            shape, len
+        )));
+    }
+
+    Ok(())
+}
+
+// Computes the number of entries spanned by a step along each dimension
+fn cumulative_dimensions(shape: &[usize]) -> Vec<usize> {
+    let mut cumulative_dimensions = Vec::with_capacity(shape.len());
+
+    let mut acc = 1;
+
+    for dim in shape.iter().rev() {
+        cumulative_dimensions.push(acc);
+        acc *= dim;
+    }
+
+    cumulative_dimensions.reverse();
+
+    cumulative_dimensions
+}
+
+// Header of the binary encoding, followed by the NumPy descriptor of the
+// element type and the bincode encoding of the array
+const QARRAY_BINARY_MAGIC: &[u8; 4] = b"HCSQ";
+
 #[derive(Clone)]
 pub enum QTypeArray<ST, LT> {
     S(QArray<ST>),
@@ -205,16 +376,7 @@ impl<T: InnerType> QArray<T> {
             flattened.len()
         );
 
-        let mut cumulative_dimensions = Vec::with_capacity(shape.len());
-
-        let mut acc = 1;
-
-        for dim in shape.iter().rev() {
-            cumulative_dimensions.push(acc);
-            acc *= dim;
-        }
-
-        cumulative_dimensions.reverse();
+        let cumulative_dimensions = cumulative_dimensions(&shape);
 
         Self {
             flattened,
@@ -226,14 +388,20 @@ impl<T: InnerType> QArray<T> {
     /// Takes an n-dimensional index and returns the corresponding flattened
     /// index. E.g. for a 3x3 matrix, the index (1, 2) corresponds
     /// to the flattened index 5.
-    #[allow(dead_code)]
-    fn flatten_index(&self, index: Vec<usize>) -> usize {
-        debug_assert_eq!(
+    fn flatten_index(&self, index: &[usize]) -> usize {
+        assert_eq!(
             index.len(),
             self.num_dims(),
             "Index has the wrong number of dimensions"
         );
 
+        assert!(
+            index.iter().zip(self.shape.iter()).all(|(i, d)| i < d),
+            "Index {:?} is out of bounds for shape {:?}",
+            index,
+            self.shape
+        );
+
         index
             .iter()
             .zip(self.cumulative_dimensions.iter())
@@ -241,11 +409,6 @@ impl<T: InnerType> QArray<T> {
             .sum()
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn get(&self, index: Vec<usize>) -> T {
-        self.flattened[self.flatten_index(index)]
-    }
-
     /// For each dimension of self.shape, either pad the QArray with `value`
     /// (if the new size is larger than the original one) or truncate it (if
     /// the new size is smaller than or equal to the original one).
@@ -284,34 +447,74 @@ impl<T: InnerType> QArray<T> {
         QArray::new(flattened, new_shape)
     }
 
-    pub fn write(&self, path: &str) {
-        let mut writer = std::fs::File::create(path).unwrap();
-        serde_json::to_writer(&mut writer, self).unwrap();
+    /// Writes the array to the file at `path` as JSON
+    pub fn write(&self, path: &str) -> Result<(), QArrayError> {
+        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        Ok(serde_json::to_writer(writer, self)?)
     }
 
-    pub fn read(path: &str) -> QArray<T> {
-        let reader = std::fs::File::open(path).unwrap();
-        serde_json::from_reader(reader).unwrap()
+    /// Reads an array from a JSON file
+    pub fn read(path: &str) -> Result<QArray<T>, QArrayError> {
+        let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
     }
 
-    pub fn write_multiple(qarrays: &[&QArray<T>], paths: &[&str]) {
+    pub fn write_multiple(qarrays: &[&QArray<T>], paths: &[&str]) -> Result<(), QArrayError> {
         for (qarray, path) in qarrays.iter().zip(paths.iter()) {
-            qarray.write(path);
+            qarray.write(path)?;
         }
+        Ok(())
     }
 
-    pub fn read_multiple(paths: &[&str]) -> Vec<QArray<T>> {
+    pub fn read_multiple(paths: &[&str]) -> Result<Vec<QArray<T>>, QArrayError> {
         paths.iter().map(|path| QArray::read(path)).collect()
     }
 
-    pub fn write_list(qarrays: &[&QArray<T>], path: &str) {
-        let mut writer = std::fs::File::create(path).unwrap();
-        serde_json::to_writer(&mut writer, qarrays).unwrap();
+    /// Writes a list of arrays to the file at `path` as a JSON list
+    pub fn write_list(qarrays: &[&QArray<T>], path: &str) -> Result<(), QArrayError> {
+        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        Ok(serde_json::to_writer(writer, qarrays)?)
+    }
+
+    /// Reads a list of arrays from a JSON file
+    pub fn read_list(path: &str) -> Result<Vec<QArray<T>>, QArrayError> {
+        let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
     }
 
-    pub fn read_list(path: &str) -> Vec<QArray<T>> {
-        let reader = std::fs::File::open(path).unwrap();
-        serde_json::from_reader(reader).unwrap()
+    /// Compact binary encoding of the array, which records its element type
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = QARRAY_BINARY_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &(T::NPY_DESCR, self)).unwrap();
+        bytes
+    }
+
+    /// Decodes an array from its compact binary encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<QArray<T>, QArrayError> {
+        let body = bytes
+            .strip_prefix(QARRAY_BINARY_MAGIC)
+            .ok_or_else(|| QArrayError::Encoding("missing binary QArray header".to_string()))?;
+
+        let (descr, qarray): (String, QArray<T>) = bincode::deserialize(body)?;
+
+        if descr != T::NPY_DESCR {
+            return Err(QArrayError::TypeMismatch {
+                expected: T::NPY_DESCR.to_string(),
+                found: descr,
+            });
+        }
+
+        Ok(qarray)
+    }
+
+    /// Writes the array to the file at `path` in the compact binary encoding
+    pub fn write_binary(&self, path: &str) -> Result<(), QArrayError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    /// Reads an array from a file in the compact binary encoding
+    pub fn read_binary(path: &str) -> Result<QArray<T>, QArrayError> {
+        Self::from_bytes(&std::fs::read(path)?)
     }
 }
 
@@ -536,7 +739,7 @@ fn print_flat_data<T: InnerType>(
 
 // We follow the convention (e.g. in numpy) that `maximum` and `minimum`
 // compare an array to a single element (element-wise); whereas `max` and `min`
-// (not implemented) compare two equally sized arrays element-wise.
+// (cf. algebra.rs) compare two arrays element-wise, with broadcasting.
 impl<T: InnerType + PartialOrd> QArray<T> {
     pub fn maximum(&self, x: T) -> QArray<T> {
         let flattened_max: Vec<T> = self
@@ -613,4 +816,12 @@ impl<ST, LT> QTypeArray<ST, LT> {
             QTypeArray::L(_) => "QTypeArray::L",
         }
     }
+    /// Type (small or large) of the array's values
+    #[inline]
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            QTypeArray::S(_) => ValueType::Small,
+            QTypeArray::L(_) => ValueType::Large,
+        }
+    }
 }
@@ -0,0 +1,443 @@
+// Reading and writing of NumPy .npy files (cf. numpy.lib.format) and .npz
+// archives thereof, so that tensors can be exchanged with Python directly.
+// .npz archives are plain ZIP files, of which only the uncompressed variant
+// written by numpy.savez is supported (not numpy.savez_compressed).
+
+use ark_std::mem::size_of;
+
+use super::{check_shape, InnerType, QArray, QArrayError};
+
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+// The header (including magic string, version and length) is padded to a
+// multiple of this
+const NPY_HEADER_ALIGNMENT: usize = 64;
+
+fn npy_error(e: &str) -> QArrayError {
+    QArrayError::Encoding(format!("invalid .npy data: {}", e))
+}
+
+fn npz_error(e: &str) -> QArrayError {
+    QArrayError::Encoding(format!("invalid .npz archive: {}", e))
+}
+
+impl<T: InnerType> QArray<T> {
+    /// Encodes the array in the .npy format
+    pub fn to_npy_bytes(&self) -> Vec<u8> {
+        let shape = match &self.shape[..] {
+            [len] => format!("({},)", len),
+            shape => format!(
+                "({})",
+                shape
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            T::NPY_DESCR,
+            shape
+        );
+
+        // Magic string, two version bytes and two header length bytes
+        let prefix_len = NPY_MAGIC.len() + 4;
+        let unpadded_len = prefix_len + header.len() + 1;
+        let padding =
+            (NPY_HEADER_ALIGNMENT - unpadded_len % NPY_HEADER_ALIGNMENT) % NPY_HEADER_ALIGNMENT;
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(prefix_len + header.len() + self.len() * size_of::<T>());
+        bytes.extend_from_slice(NPY_MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+
+        for value in &self.flattened {
+            value.extend_le_bytes(&mut bytes);
+        }
+
+        bytes
+    }
+
+    /// Decodes an array from .npy data, which must have an element type
+    /// matching `T` (in either byte order)
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<QArray<T>, QArrayError> {
+        let rest = bytes
+            .strip_prefix(NPY_MAGIC)
+            .ok_or_else(|| npy_error("missing magic string"))?;
+
+        let (header_len, rest) = match rest {
+            [1, _, l0, l1, rest @ ..] => (u16::from_le_bytes([*l0, *l1]) as usize, rest),
+            [2 | 3, _, l0, l1, l2, l3, rest @ ..] => {
+                (u32::from_le_bytes([*l0, *l1, *l2, *l3]) as usize, rest)
+            }
+            _ => return Err(npy_error("unsupported version")),
+        };
+
+        if rest.len() < header_len {
+            return Err(npy_error("truncated header"));
+        }
+
+        let (header, data) = rest.split_at(header_len);
+        let header = ark_std::str::from_utf8(header).map_err(|_| npy_error("non-UTF-8 header"))?;
+
+        let descr = header_string(header, "descr")?;
+        let fortran_order = header_value(header, "fortran_order")?.starts_with("True");
+        let shape = header_shape(header)?;
+
+        // The first character of the descriptor is the byte order, which is
+        // irrelevant for single-byte types
+        let (byte_order, type_code) = descr.split_at(1);
+
+        if *type_code != T::NPY_DESCR[1..] {
+            return Err(QArrayError::TypeMismatch {
+                expected: T::NPY_DESCR.to_string(),
+                found: descr.to_string(),
+            });
+        }
+
+        // The shape comes from the file, so its product may overflow
+        let size = size_of::<T>();
+        let num_bytes = shape
+            .iter()
+            .try_fold(size, |acc, d| acc.checked_mul(*d))
+            .ok_or_else(|| npy_error("shape too large"))?;
+
+        if data.len() != num_bytes {
+            return Err(npy_error(&format!(
+                "expected {} bytes of data, found {}",
+                num_bytes,
+                data.len()
+            )));
+        }
+
+        let values: Vec<T> = data
+            .chunks_exact(size)
+            .map(|chunk| match byte_order {
+                ">" => T::from_le_slice(&chunk.iter().rev().copied().collect::<Vec<_>>()),
+                _ => T::from_le_slice(chunk),
+            })
+            .collect();
+
+        // NumPy scalars are zero-dimensional, which QArrays cannot be, so they
+        // are read as arrays of length 1
+        let shape = if shape.is_empty() { vec![1] } else { shape };
+
+        check_shape(values.len(), &shape)?;
+
+        let values = if fortran_order {
+            fortran_to_row_major(values, &shape)
+        } else {
+            values
+        };
+
+        Ok(QArray::new(values, shape))
+    }
+
+    /// Writes the array to the file at `path` in the .npy format
+    pub fn write_npy(&self, path: &str) -> Result<(), QArrayError> {
+        Ok(std::fs::write(path, self.to_npy_bytes())?)
+    }
+
+    /// Reads an array from a .npy file, as written by `numpy.save`
+    pub fn read_npy(path: &str) -> Result<QArray<T>, QArrayError> {
+        Self::from_npy_bytes(&std::fs::read(path)?)
+    }
+
+    /// Writes a list of named arrays to the file at `path` as an .npz archive,
+    /// which can be read with `numpy.load`
+    pub fn write_npz(qarrays: &[(&str, &QArray<T>)], path: &str) -> Result<(), QArrayError> {
+        let entries: Vec<(String, Vec<u8>)> = qarrays
+            .iter()
+            .map(|(name, qarray)| (format!("{}.npy", name), qarray.to_npy_bytes()))
+            .collect();
+
+        Ok(std::fs::write(path, zip_stored(&entries))?)
+    }
+
+    /// Reads all arrays in an .npz archive, as written by `numpy.savez`,
+    /// together with their names. All arrays must have an element type
+    /// matching `T`.
+    pub fn read_npz(path: &str) -> Result<Vec<(String, QArray<T>)>, QArrayError> {
+        unzip_stored(&std::fs::read(path)?)?
+            .into_iter()
+            .map(|(name, data)| {
+                let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+                Ok((name, Self::from_npy_bytes(data)?))
+            })
+            .collect()
+    }
+}
+
+// Returns the raw text following `'key':` in a .npy header dictionary
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, QArrayError> {
+    for quote in ['\'', '"'] {
+        let pattern = format!("{}{}{}", quote, key, quote);
+
+        if let Some(position) = header.find(&pattern) {
+            let rest = header[position + pattern.len()..].trim_start();
+            return rest
+                .strip_prefix(':')
+                .map(str::trim_start)
+                .ok_or_else(|| npy_error("malformed header"));
+        }
+    }
+
+    Err(npy_error(&format!("header has no key {}", key)))
+}
+
+fn header_string<'a>(header: &'a str, key: &str) -> Result<&'a str, QArrayError> {
+    let value = header_value(header, key)?;
+
+    let quote = value
+        .chars()
+        .next()
+        .filter(|c| *c == '\'' || *c == '"')
+        .ok_or_else(|| npy_error(&format!("{} is not a string", key)))?;
+
+    value[1..]
+        .split(quote)
+        .next()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| npy_error(&format!("{} is not a string", key)))
+}
+
+fn header_shape(header: &str) -> Result<Vec<usize>, QArrayError> {
+    let value = header_value(header, "shape")?;
+
+    let tuple = value
+        .strip_prefix('(')
+        .and_then(|v| v.split(')').next())
+        .ok_or_else(|| npy_error("shape is not a tuple"))?;
+
+    tuple
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse().map_err(|_| npy_error("invalid shape entry")))
+        .collect()
+}
+
+// Converts values stored in column-major (Fortran) order into row-major order
+fn fortran_to_row_major<T: Copy>(values: Vec<T>, shape: &[usize]) -> Vec<T> {
+    let mut row_major = Vec::with_capacity(values.len());
+    let mut index = vec![0; shape.len()];
+
+    for _ in 0..values.len() {
+        let position = index
+            .iter()
+            .zip(shape)
+            .rev()
+            .fold(0, |acc, (i, d)| acc * d + i);
+        row_major.push(values[position]);
+
+        // Incrementing the row-major multi-index
+        for (i, d) in index.iter_mut().zip(shape).rev() {
+            *i += 1;
+            if *i < *d {
+                break;
+            }
+            *i = 0;
+        }
+    }
+
+    row_major
+}
+
+/************************* ZIP archives *************************/
+
+const ZIP_LOCAL_HEADER: u32 = 0x04034b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x02014b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const ZIP_VERSION: u16 = 20;
+// 1980-01-01, the earliest date representable in ZIP files
+const ZIP_DATE: u16 = (1 << 5) | 1;
+
+fn crc32(data: &[u8]) -> u32 {
+    let table: Vec<u32> = (0..256u32)
+        .map(|n| {
+            (0..8).fold(n, |c, _| {
+                if c & 1 == 1 {
+                    0xedb88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                }
+            })
+        })
+        .collect();
+
+    !data.iter().fold(!0u32, |crc, b| {
+        table[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+// Builds a ZIP archive storing the given files without compression
+fn zip_stored(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, data) in entries {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+
+        // Fields common to the local and central headers: version needed,
+        // flags, compression method, time, date, CRC, compressed and
+        // uncompressed sizes, name length and extra field length
+        let mut common = Vec::new();
+        common.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&ZIP_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        archive.extend_from_slice(&ZIP_LOCAL_HEADER.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        // Version made by, the common fields, comment length, disk number,
+        // internal and external attributes and local header offset
+        central_directory.extend_from_slice(&ZIP_CENTRAL_HEADER.to_le_bytes());
+        central_directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        central_directory.extend_from_slice(&common);
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+
+    archive.extend_from_slice(&ZIP_END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&central_directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+
+    archive
+}
+
+// Sizes and offsets are read from the archive, so the range they define may
+// overflow
+fn read_bytes(bytes: &[u8], pos: usize, len: usize) -> Result<&[u8], QArrayError> {
+    pos.checked_add(len)
+        .and_then(|end| bytes.get(pos..end))
+        .ok_or_else(|| npz_error("truncated archive"))
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Result<u16, QArrayError> {
+    read_bytes(bytes, pos, 2).map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, QArrayError> {
+    read_bytes(bytes, pos, 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, QArrayError> {
+    read_bytes(bytes, pos, 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+// Extracts the files of a ZIP archive whose entries are stored without
+// compression, in the order of its central directory
+fn unzip_stored(archive: &[u8]) -> Result<Vec<(String, &[u8])>, QArrayError> {
+    // The end-of-central-directory record is at least 22 bytes long and may be
+    // followed by a comment
+    let end = (0..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| read_u32(archive, pos).ok() == Some(ZIP_END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| npz_error("missing end of central directory"))?;
+
+    let num_entries = read_u16(archive, end + 10)? as usize;
+    let mut pos = read_u32(archive, end + 16)? as usize;
+
+    let mut files = Vec::with_capacity(num_entries);
+
+    for _ in 0..num_entries {
+        if read_u32(archive, pos)? != ZIP_CENTRAL_HEADER {
+            return Err(npz_error("invalid central directory entry"));
+        }
+
+        let method = read_u16(archive, pos + 10)?;
+        let crc = read_u32(archive, pos + 16)?;
+        let mut size = read_u32(archive, pos + 20)? as u64;
+        let name_len = read_u16(archive, pos + 28)? as usize;
+        let extra_len = read_u16(archive, pos + 30)? as usize;
+        let comment_len = read_u16(archive, pos + 32)? as usize;
+        let mut offset = read_u32(archive, pos + 42)? as u64;
+
+        let name = read_bytes(archive, pos + 46, name_len)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        if method != 0 {
+            return Err(npz_error(&format!(
+                "entry {} is compressed, which is not supported (use numpy.savez rather than numpy.savez_compressed)",
+                name
+            )));
+        }
+
+        // Sizes and offsets which do not fit in 32 bits are stored in the
+        // ZIP64 extra field, in this order, when the regular field is saturated
+        let mut extra = pos + 46 + name_len;
+        let extra_end = extra + extra_len;
+
+        while extra + 4 <= extra_end {
+            let (id, len) = (
+                read_u16(archive, extra)?,
+                read_u16(archive, extra + 2)? as usize,
+            );
+
+            if id == 1 {
+                let mut field = extra + 4;
+
+                if read_u32(archive, pos + 24)? == u32::MAX {
+                    field += 8;
+                }
+                if size == u32::MAX as u64 {
+                    size = read_u64(archive, field)?;
+                    field += 8;
+                }
+                if offset == u32::MAX as u64 {
+                    offset = read_u64(archive, field)?;
+                }
+            }
+
+            extra += 4 + len;
+        }
+
+        let offset = usize::try_from(offset).map_err(|_| npz_error("truncated archive"))?;
+        let size = usize::try_from(size).map_err(|_| npz_error("truncated archive"))?;
+
+        if read_u32(archive, offset)? != ZIP_LOCAL_HEADER {
+            return Err(npz_error("invalid local file header"));
+        }
+
+        // The local header starts within the archive, so the following sums
+        // of small lengths cannot overflow
+        let data_start = offset
+            + 30
+            + read_u16(archive, offset + 26)? as usize
+            + read_u16(archive, offset + 28)? as usize;
+
+        let data = read_bytes(archive, data_start, size)?;
+
+        if crc32(data) != crc {
+            return Err(npz_error(&format!("checksum mismatch in entry {}", name)));
+        }
+
+        files.push((name, data));
+        pos = extra_end + comment_len;
+    }
+
+    Ok(files)
+}
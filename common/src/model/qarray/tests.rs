@@ -4,7 +4,7 @@ use super::*;
 fn test_flatten_index_trivial() {
     let q = QArray::from(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
     for i in 0..9 {
-        assert_eq!(q.flatten_index(vec![i]), i);
+        assert_eq!(q.flatten_index(&[i]), i);
     }
 }
 
@@ -13,13 +13,13 @@ fn test_flatten_index_2d() {
     let shape = vec![3, 3];
     let flattened: Vec<i32> = (1..=9).collect();
     let q = QArray::new(flattened, shape);
-    assert_eq!(q.flatten_index(vec![0, 0]), 0);
-    assert_eq!(q.flatten_index(vec![0, 1]), 1);
-    assert_eq!(q.flatten_index(vec![0, 2]), 2);
-    assert_eq!(q.flatten_index(vec![1, 0]), 3);
-    assert_eq!(q.flatten_index(vec![1, 2]), 5);
-    assert_eq!(q.flatten_index(vec![2, 0]), 6);
-    assert_eq!(q.flatten_index(vec![2, 2]), 8);
+    assert_eq!(q.flatten_index(&[0, 0]), 0);
+    assert_eq!(q.flatten_index(&[0, 1]), 1);
+    assert_eq!(q.flatten_index(&[0, 2]), 2);
+    assert_eq!(q.flatten_index(&[1, 0]), 3);
+    assert_eq!(q.flatten_index(&[1, 2]), 5);
+    assert_eq!(q.flatten_index(&[2, 0]), 6);
+    assert_eq!(q.flatten_index(&[2, 2]), 8);
 }
 
 #[test]
@@ -27,12 +27,12 @@ fn test_flatten_index_3d() {
     let shape = vec![2, 3, 4];
     let flattened: Vec<i32> = (1..=24).collect();
     let q = QArray::new(flattened, shape);
-    assert_eq!(q.flatten_index(vec![0, 0, 0]), 0);
-    assert_eq!(q.flatten_index(vec![0, 0, 1]), 1);
-    assert_eq!(q.flatten_index(vec![0, 0, 2]), 2);
-    assert_eq!(q.flatten_index(vec![0, 1, 0]), 4);
-    assert_eq!(q.flatten_index(vec![1, 0, 0]), 12);
-    assert_eq!(q.flatten_index(vec![1, 2, 3]), 23);
+    assert_eq!(q.flatten_index(&[0, 0, 0]), 0);
+    assert_eq!(q.flatten_index(&[0, 0, 1]), 1);
+    assert_eq!(q.flatten_index(&[0, 0, 2]), 2);
+    assert_eq!(q.flatten_index(&[0, 1, 0]), 4);
+    assert_eq!(q.flatten_index(&[1, 0, 0]), 12);
+    assert_eq!(q.flatten_index(&[1, 2, 3]), 23);
 }
 
 #[test]
@@ -188,3 +188,538 @@ fn test_minimum() {
     // Do it here and elsewhere
     assert_eq!(qarray.minimum(3).move_values(), vec![-1, 2, 3, 3, -5, 3]);
 }
+
+macro_rules! FIXTURES_PATH {
+    () => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/src/model/qarray/fixtures/{}")
+    };
+}
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("hcs_qarray_test_{}", name))
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+fn npy_bytes(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+    let header = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}\n",
+        descr,
+        if fortran_order { "True" } else { "False" },
+        shape
+    );
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn test_json_round_trip() {
+    let qarray: QArray<i32> = QArray::new((-5..7).collect(), vec![2, 3, 2]);
+    let path = temp_path("round_trip.json");
+
+    qarray.write(&path).unwrap();
+    let read = QArray::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(qarray, read);
+}
+
+#[test]
+fn test_json_legacy_cumulative_dimensions() {
+    let qarray: QArray<i8> =
+        serde_json::from_str(r#"{"f":[1,2,3,4,5,6],"s":[2,3],"c":[3,1]}"#).unwrap();
+
+    assert_eq!(qarray, QArray::new(vec![1, 2, 3, 4, 5, 6], vec![2, 3]));
+}
+
+#[test]
+fn test_json_invalid_shape() {
+    assert!(serde_json::from_str::<QArray<i8>>(r#"{"f":[1,2,3],"s":[2,2]}"#).is_err());
+    assert!(serde_json::from_str::<QArray<i8>>(r#"{"f":[1],"s":[]}"#).is_err());
+}
+
+#[test]
+fn test_read_errors() {
+    assert!(matches!(
+        QArray::<i8>::read(&temp_path("missing.json")),
+        Err(QArrayError::Io(_))
+    ));
+
+    let path = temp_path("malformed.json");
+    std::fs::write(&path, "{\"f\": [1, 2").unwrap();
+    let result = QArray::<i8>::read_list(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(QArrayError::Encoding(_))));
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let qarray: QArray<i8> = QArray::new(
+        (0..784).map(|i| (i % 256) as u8 as i8).collect(),
+        vec![28, 28],
+    );
+    let bytes = qarray.to_bytes();
+
+    assert_eq!(QArray::from_bytes(&bytes).unwrap(), qarray);
+    assert!(bytes.len() < serde_json::to_string(&qarray).unwrap().len() / 3);
+
+    let path = temp_path("round_trip.bin");
+    qarray.write_binary(&path).unwrap();
+    let read = QArray::read_binary(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(qarray, read);
+}
+
+#[test]
+fn test_binary_errors() {
+    let bytes = QArray::new(vec![1u8, 2, 3], vec![3]).to_bytes();
+
+    assert!(matches!(
+        QArray::<i8>::from_bytes(&bytes),
+        Err(QArrayError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        QArray::<u8>::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(QArrayError::Encoding(_))
+    ));
+    assert!(matches!(
+        QArray::<u8>::from_bytes(&bytes[1..]),
+        Err(QArrayError::Encoding(_))
+    ));
+}
+
+#[test]
+fn test_npy_round_trip() {
+    fn round_trip<T: InnerType>(qarray: QArray<T>) {
+        let bytes = qarray.to_npy_bytes();
+
+        // The header is padded to a multiple of 64 bytes
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+
+        assert_eq!(QArray::from_npy_bytes(&bytes).unwrap(), qarray);
+    }
+
+    round_trip(QArray::new(vec![-128i8, -1, 0, 127], vec![4]));
+    round_trip(QArray::new(
+        (-6..6).map(|x| x * 1000).collect::<Vec<i32>>(),
+        vec![2, 3, 2],
+    ));
+    round_trip(QArray::new((0..6).collect::<Vec<u8>>(), vec![3, 2]));
+    round_trip(QArray::new(vec![0.5f32, -1.25, 3.0], vec![1, 3]));
+
+    let qarray = QArray::new(vec![1i32, 2, 3, 4], vec![2, 2]);
+    let path = temp_path("round_trip.npy");
+    qarray.write_npy(&path).unwrap();
+    let read = QArray::read_npy(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(qarray, read);
+}
+
+#[test]
+fn test_npy_shape_header() {
+    let bytes = QArray::new(vec![1i8, 2, 3], vec![3]).to_npy_bytes();
+    let header = String::from_utf8_lossy(&bytes[10..]);
+
+    assert!(header.starts_with("{'descr': '|i1', 'fortran_order': False, 'shape': (3,), }"));
+}
+
+#[test]
+fn test_npy_fortran_order() {
+    // The matrix [[1, 2, 3], [4, 5, 6]] stored column by column
+    let bytes = npy_bytes("|i1", true, "(2, 3)", &[1, 4, 2, 5, 3, 6]);
+
+    assert_eq!(
+        QArray::<i8>::from_npy_bytes(&bytes).unwrap(),
+        QArray::new(vec![1, 2, 3, 4, 5, 6], vec![2, 3])
+    );
+}
+
+#[test]
+fn test_npy_big_endian() {
+    let data: Vec<u8> = [1i32, -2, 300]
+        .iter()
+        .flat_map(|x| x.to_be_bytes())
+        .collect();
+    let bytes = npy_bytes(">i4", false, "(3,)", &data);
+
+    assert_eq!(
+        QArray::<i32>::from_npy_bytes(&bytes).unwrap(),
+        QArray::new(vec![1, -2, 300], vec![3])
+    );
+}
+
+#[test]
+fn test_npy_errors() {
+    let bytes = npy_bytes("<f8", false, "(1,)", &[0; 8]);
+    assert!(matches!(
+        QArray::<f32>::from_npy_bytes(&bytes),
+        Err(QArrayError::TypeMismatch { .. })
+    ));
+
+    let bytes = npy_bytes("<i4", false, "(2, 2)", &[0; 12]);
+    assert!(matches!(
+        QArray::<i32>::from_npy_bytes(&bytes),
+        Err(QArrayError::Encoding(_))
+    ));
+
+    let bytes = QArray::new(vec![1i8, 2], vec![2]).to_npy_bytes();
+    assert!(QArray::<i8>::from_npy_bytes(&bytes[1..]).is_err());
+    assert!(QArray::<i8>::from_npy_bytes(&bytes[..20]).is_err());
+}
+
+#[test]
+fn test_npy_oversized_shape() {
+    // The number of entries overflows usize
+    let bytes = npy_bytes("<i4", false, "(4294967296, 4294967296, 4294967296)", &[]);
+    assert!(matches!(
+        QArray::<i32>::from_npy_bytes(&bytes),
+        Err(QArrayError::Encoding(_))
+    ));
+
+    // The number of entries fits in usize, but not the number of bytes
+    let bytes = npy_bytes("<i4", false, "(4611686018427387904,)", &[]);
+    assert!(matches!(
+        QArray::<i32>::from_npy_bytes(&bytes),
+        Err(QArrayError::Encoding(_))
+    ));
+}
+
+// The fixtures follow the exact layout of numpy.save and numpy.savez, which
+// differs from that of `to_npy_bytes` and `write_npz`: the header is padded
+// with room for the first dimension to grow, and archive entries have ZIP64
+// local headers
+#[test]
+fn test_npy_numpy_fixture() {
+    assert_eq!(
+        QArray::<i32>::read_npy(&format!(FIXTURES_PATH!(), "numpy_weights.npy")).unwrap(),
+        QArray::new((-6..6).collect(), vec![3, 4])
+    );
+}
+
+#[test]
+fn test_npz_numpy_fixture() {
+    assert_eq!(
+        QArray::<i32>::read_npz(&format!(FIXTURES_PATH!(), "numpy_savez.npz")).unwrap(),
+        vec![
+            (
+                "weights".to_string(),
+                QArray::new((-6..6).collect(), vec![3, 4])
+            ),
+            ("bias".to_string(), QArray::new(vec![-1, 1], vec![2])),
+        ]
+    );
+}
+
+#[test]
+fn test_npz_round_trip() {
+    let w = QArray::new((0..12).collect::<Vec<i32>>(), vec![3, 4]);
+    let b = QArray::new(vec![-1i32, 1], vec![2]);
+    let path = temp_path("round_trip.npz");
+
+    QArray::write_npz(&[("weights", &w), ("bias", &b)], &path).unwrap();
+    let read = QArray::<i32>::read_npz(&path);
+    let mismatched = QArray::<i8>::read_npz(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        read.unwrap(),
+        vec![("weights".to_string(), w), ("bias".to_string(), b)]
+    );
+    assert!(matches!(mismatched, Err(QArrayError::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_npz_errors() {
+    let path = temp_path("invalid.npz");
+    std::fs::write(&path, b"PK\x03\x04 definitely not an archive").unwrap();
+    let result = QArray::<i32>::read_npz(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(QArrayError::Encoding(_))));
+}
+
+#[test]
+fn test_npz_oversized_entry() {
+    // Archive with a single empty entry whose size, stored in the ZIP64 extra
+    // field of its central directory entry, is the largest possible
+    let mut archive = Vec::new();
+    archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 22]);
+    archive.extend_from_slice(&1u16.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive.push(b'a');
+
+    let central_directory = archive.len() as u32;
+    archive.extend_from_slice(&0x02014b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 12]);
+    archive.extend_from_slice(&0u32.to_le_bytes());
+    archive.extend_from_slice(&u32::MAX.to_le_bytes());
+    archive.extend_from_slice(&u32::MAX.to_le_bytes());
+    archive.extend_from_slice(&1u16.to_le_bytes());
+    archive.extend_from_slice(&20u16.to_le_bytes());
+    archive.extend_from_slice(&[0; 10]);
+    archive.extend_from_slice(&0u32.to_le_bytes());
+    archive.push(b'a');
+    archive.extend_from_slice(&1u16.to_le_bytes());
+    archive.extend_from_slice(&16u16.to_le_bytes());
+    archive.extend_from_slice(&u64::MAX.to_le_bytes());
+    archive.extend_from_slice(&u64::MAX.to_le_bytes());
+
+    let central_directory_len = archive.len() as u32 - central_directory;
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&1u16.to_le_bytes());
+    archive.extend_from_slice(&1u16.to_le_bytes());
+    archive.extend_from_slice(&central_directory_len.to_le_bytes());
+    archive.extend_from_slice(&central_directory.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+
+    let path = temp_path("oversized.npz");
+    std::fs::write(&path, &archive).unwrap();
+    let result = QArray::<i32>::read_npz(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(QArrayError::Encoding(_))));
+}
+
+#[test]
+fn test_broadcast_shape() {
+    assert_eq!(broadcast_shape(&[2, 3], &[2, 3]), Some(vec![2, 3]));
+    assert_eq!(broadcast_shape(&[2, 3], &[3]), Some(vec![2, 3]));
+    assert_eq!(broadcast_shape(&[4, 1, 3], &[2, 1]), Some(vec![4, 2, 3]));
+    assert_eq!(broadcast_shape(&[1], &[5, 4]), Some(vec![5, 4]));
+    assert_eq!(broadcast_shape(&[2, 3], &[2]), None);
+    assert_eq!(broadcast_shape(&[3, 2], &[2, 2]), None);
+}
+
+#[test]
+fn test_elementwise_same_shape() {
+    let a = QArray::new(vec![1, 2, 3, 4, 5, 6], vec![2, 3]);
+    let b = QArray::new(vec![6, 5, 4, 3, 2, 1], vec![2, 3]);
+
+    assert_eq!((&a + &b).move_values(), vec![7; 6]);
+    assert_eq!((&a - &b).move_values(), vec![-5, -3, -1, 1, 3, 5]);
+    assert_eq!((&a * &b).move_values(), vec![6, 10, 12, 12, 10, 6]);
+    assert_eq!((&b / &a).move_values(), vec![6, 2, 1, 0, 0, 0]);
+    assert_eq!((a.clone() + b.clone()).shape(), &vec![2, 3]);
+}
+
+#[test]
+fn test_elementwise_broadcast() {
+    let matrix: QArray<i32> = QArray::new((0..6).collect(), vec![2, 3]);
+    let row = QArray::new(vec![10, 20, 30], vec![3]);
+    let column = QArray::new(vec![100, 200], vec![2, 1]);
+
+    assert_eq!(
+        &matrix + &row,
+        QArray::new(vec![10, 21, 32, 13, 24, 35], vec![2, 3])
+    );
+    assert_eq!(
+        &column + &matrix,
+        QArray::new(vec![100, 101, 102, 203, 204, 205], vec![2, 3])
+    );
+    assert_eq!(
+        &column * &row,
+        QArray::new(vec![1000, 2000, 3000, 2000, 4000, 6000], vec![2, 3])
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_elementwise_incompatible_shapes() {
+    let _ = QArray::new(vec![1, 2, 3], vec![3]) + QArray::new(vec![1, 2], vec![2]);
+}
+
+#[test]
+fn test_broadcast_to() {
+    let qarray: QArray<u8> = QArray::new(vec![1, 2], vec![2, 1]);
+
+    assert_eq!(
+        qarray.broadcast_to(vec![2, 2, 3]),
+        QArray::new(vec![1, 1, 1, 2, 2, 2, 1, 1, 1, 2, 2, 2], vec![2, 2, 3])
+    );
+}
+
+#[test]
+fn test_max_min() {
+    let a: QArray<i8> = QArray::new(vec![-1, 5, 3, -7], vec![2, 2]);
+    let b = QArray::new(vec![0, 4], vec![2]);
+
+    assert_eq!(a.max(&b).move_values(), vec![0, 5, 3, 4]);
+    assert_eq!(a.min(&b).move_values(), vec![-1, 4, 0, -7]);
+}
+
+#[test]
+fn test_multidimensional_indexing() {
+    let mut qarray: QArray<i32> = QArray::new((0..24).collect(), vec![2, 3, 4]);
+
+    assert_eq!(qarray.get(&[1, 2, 3]), 23);
+    assert_eq!(qarray[[0, 1, 2]], 6);
+
+    qarray.set(&[1, 0, 0], -1);
+    qarray[[0, 0, 1]] = -2;
+
+    assert_eq!(qarray[12], -1);
+    assert_eq!(qarray[1], -2);
+}
+
+#[test]
+#[should_panic]
+fn test_index_out_of_bounds() {
+    // Without the bounds check, this would silently return the entry (1, 0)
+    let qarray: QArray<i32> = QArray::new((0..6).collect(), vec![2, 3]);
+    qarray.get(&[0, 3]);
+}
+
+#[test]
+fn test_slice() {
+    let qarray: QArray<i32> = QArray::new((0..24).collect(), vec![2, 3, 4]);
+
+    assert_eq!(
+        qarray.slice(&[1..2, 0..3, 1..3]),
+        QArray::new(vec![13, 14, 17, 18, 21, 22], vec![1, 3, 2])
+    );
+    assert_eq!(
+        qarray.slice(&[0..2, 2..3]),
+        QArray::new(vec![8, 9, 10, 11, 20, 21, 22, 23], vec![2, 1, 4])
+    );
+    assert_eq!(qarray.slice(&[]), qarray);
+    assert_eq!(qarray.slice(&[0..2, 1..1]).len(), 0);
+}
+
+#[test]
+fn test_transpose() {
+    let qarray: QArray<i32> = QArray::new((1..=6).collect(), vec![2, 3]);
+    let transposed = qarray.transpose();
+
+    assert_eq!(transposed, QArray::new(vec![1, 4, 2, 5, 3, 6], vec![3, 2]));
+    assert_eq!(transposed.transpose(), qarray);
+
+    let vector = QArray::new(vec![1, 2, 3], vec![3]);
+    assert_eq!(vector.transpose(), vector);
+}
+
+#[test]
+fn test_permute() {
+    let qarray: QArray<i32> = QArray::new((0..24).collect(), vec![2, 3, 4]);
+    let permuted = qarray.permute(&[2, 0, 1]);
+
+    assert_eq!(permuted.shape(), &vec![4, 2, 3]);
+
+    for i in 0..2 {
+        for j in 0..3 {
+            for k in 0..4 {
+                assert_eq!(permuted[[k, i, j]], qarray[[i, j, k]]);
+            }
+        }
+    }
+
+    assert_eq!(permuted.permute(&[1, 2, 0]), qarray);
+}
+
+#[test]
+#[should_panic]
+fn test_permute_invalid() {
+    QArray::new((0..6).collect::<Vec<i32>>(), vec![2, 3]).permute(&[0, 0]);
+}
+
+#[test]
+fn test_concat() {
+    let a: QArray<i32> = QArray::new((0..6).collect(), vec![2, 3]);
+    let b = QArray::new(vec![10, 11, 12], vec![1, 3]);
+    let c = QArray::new(vec![20, 21], vec![2, 1]);
+
+    assert_eq!(
+        QArray::concat(&[&a, &b], 0),
+        QArray::new(vec![0, 1, 2, 3, 4, 5, 10, 11, 12], vec![3, 3])
+    );
+    assert_eq!(
+        QArray::concat(&[&a, &c, &a], 1),
+        QArray::new(vec![0, 1, 2, 20, 0, 1, 2, 3, 4, 5, 21, 3, 4, 5], vec![2, 7])
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_concat_mismatched_shapes() {
+    let a: QArray<i32> = QArray::new((0..6).collect(), vec![2, 3]);
+    let b = QArray::new(vec![10, 11], vec![1, 2]);
+
+    QArray::concat(&[&a, &b], 0);
+}
+
+#[test]
+fn test_matmul() {
+    let a: QArray<i32> = QArray::new((1..=6).collect(), vec![2, 3]);
+    let b: QArray<i32> = QArray::new((1..=12).collect(), vec![3, 4]);
+
+    assert_eq!(
+        a.matmul(&b),
+        QArray::new(vec![38, 44, 50, 56, 83, 98, 113, 128], vec![2, 4])
+    );
+
+    let v = QArray::new(vec![1, -1, 2], vec![3]);
+
+    // Vector-by-matrix, matrix-by-vector and vector-by-vector
+    assert_eq!(v.matmul(&b), QArray::new(vec![14, 16, 18, 20], vec![4]));
+    assert_eq!(a.matmul(&v), QArray::new(vec![5, 11], vec![2]));
+    assert_eq!(v.matmul(&v), QArray::new(vec![6], vec![1]));
+}
+
+#[test]
+fn test_matmul_wide() {
+    // Wide enough for the output rows to be split into several column blocks
+    let (m, k, n) = (3, 5, 600);
+
+    let a: QArray<i32> = QArray::new((0..m * k).map(|x| x as i32 - 7).collect(), vec![m, k]);
+    let b: QArray<i32> = QArray::new(
+        (0..k * n).map(|x| (x as i32 % 11) - 5).collect(),
+        vec![k, n],
+    );
+
+    let expected: Vec<i32> = (0..m * n)
+        .map(|x| {
+            let (row, col) = (x / n, x % n);
+            (0..k).map(|i| a.get(&[row, i]) * b.get(&[i, col])).sum()
+        })
+        .collect();
+
+    assert_eq!(a.matmul(&b), QArray::new(expected, vec![m, n]));
+
+    let mut v = a.slice(&[0..1, 0..k]);
+    v.reshape(vec![k]);
+
+    let mut first_row = a.matmul(&b).slice(&[0..1, 0..n]);
+    first_row.reshape(vec![n]);
+
+    assert_eq!(v.matmul(&b), first_row);
+}
+
+#[test]
+fn test_matmul_matches_transpose() {
+    let a: QArray<i8> = QArray::new(vec![1, -2, 3, 0, 5, -6], vec![3, 2]);
+    let b: QArray<i8> = QArray::new(vec![2, 1, -1, 3], vec![2, 2]);
+
+    // (AB)^T = B^T A^T
+    assert_eq!(
+        a.matmul(&b).transpose(),
+        b.transpose().matmul(&a.transpose())
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_matmul_dimension_mismatch() {
+    let a: QArray<i32> = QArray::new((1..=6).collect(), vec![2, 3]);
+    a.matmul(&a);
+}
@@ -0,0 +1,456 @@
+// Native file format for models. A model file contains every node together
+// with its parameters and quantisation information, so that a Model can be
+// saved and loaded as a unit. Two variants are provided: human-readable JSON
+// and a compact binary encoding (a magic header followed by the bincode
+// encoding of the same structure). Both carry a content hash, which is checked
+// on load and identifies the model independently of the variant used.
+
+use ark_std::any::type_name;
+use ark_std::fmt;
+use blake2::{Blake2s256, Digest};
+use serde::{Deserialize, Serialize};
+
+use crate::model::encoding::QuantisedInteger;
+use crate::model::nodes::{
+    bmm::BMMNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode, reshape::ReshapeNode,
+    sparse_bmm::SparseBMMNode, Node,
+};
+use crate::model::qarray::{InnerType, QArray};
+use crate::model::Model;
+use crate::quantization::BMMQInfo;
+
+#[cfg(test)]
+mod tests;
+
+/// Current version of the model file format
+pub const MODEL_FORMAT_VERSION: u32 = 1;
+
+// Header identifying the binary variant
+const BINARY_MAGIC: &[u8; 4] = b"HCSM";
+
+/// Variant of the model file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Json,
+    Binary,
+}
+
+/// Errors arising when saving or loading a model file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerialisationError {
+    /// The model file could not be read or written
+    Io(String),
+    /// The file contents could not be (de)serialised
+    Encoding(String),
+    /// The file was written by an incompatible version of the format
+    UnsupportedVersion(u32),
+    /// The file contains a model with different small and large types
+    TypeMismatch { expected: String, found: String },
+    /// The hash stored in the file does not match its contents
+    HashMismatch { expected: String, found: String },
+    /// The file contents do not describe a valid model
+    InvalidModel(String),
+}
+
+impl fmt::Display for SerialisationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerialisationError::Io(e) => write!(f, "Unable to access model file: {}", e),
+            SerialisationError::Encoding(e) => write!(f, "Unable to (de)serialise model: {}", e),
+            SerialisationError::UnsupportedVersion(v) => write!(
+                f,
+                "Unsupported model format version {} (expected {})",
+                v, MODEL_FORMAT_VERSION
+            ),
+            SerialisationError::TypeMismatch { expected, found } => write!(
+                f,
+                "Model types {} do not match the expected {}",
+                found, expected
+            ),
+            SerialisationError::HashMismatch { expected, found } => write!(
+                f,
+                "Model content hash {} does not match the stored hash {}",
+                found, expected
+            ),
+            SerialisationError::InvalidModel(e) => write!(f, "Invalid model: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerialisationError {}
+
+impl From<std::io::Error> for SerialisationError {
+    fn from(e: std::io::Error) -> Self {
+        SerialisationError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SerialisationError {
+    fn from(e: serde_json::Error) -> Self {
+        SerialisationError::Encoding(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for SerialisationError {
+    fn from(e: bincode::Error) -> Self {
+        SerialisationError::Encoding(e.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModelFile<ST, LT> {
+    version: u32,
+    // Names of the small and large types, e.g. "i8" and "i32"
+    types: (String, String),
+    // Hex-encoded content hash of the model
+    hash: String,
+    model: ModelDescription<ST, LT>,
+}
+
+// The constructor arguments of a model and its nodes, from which all derived
+// data (e.g. padded parameters) is recomputed on load
+#[derive(Serialize, Deserialize)]
+struct ModelDescription<ST, LT> {
+    input_shape: Vec<usize>,
+    nodes: Vec<NodeDescription<ST, LT>>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum NodeDescription<ST, LT> {
+    BMM {
+        weights: QArray<ST>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+    },
+    RequantiseBMM {
+        size: usize,
+        q_info: BMMQInfo<ST>,
+    },
+    ReLU {
+        num_units: usize,
+        zero_point: ST,
+    },
+    Reshape {
+        input_shape: Vec<usize>,
+        output_shape: Vec<usize>,
+    },
+    SparseBMM {
+        dims: (usize, usize),
+        entries: Vec<(usize, usize, ST)>,
+        bias: QArray<LT>,
+        input_zero_point: ST,
+    },
+    // Custom nodes are only described by their own encoding of their
+    // parameters, which this crate cannot decode (cf.
+    // CustomNodeOps::encode_parameters)
+    Custom {
+        name: String,
+        parameters: Vec<u8>,
+    },
+    // Requantisation node with a fused ReLU (cf. RequantiseBMMNode::with_relu)
+    RequantiseBMMReLU {
+        size: usize,
+        q_info: BMMQInfo<ST>,
+    },
+}
+
+impl<ST, LT> Model<ST, LT>
+where
+    ST: QuantisedInteger + TryFrom<LT>,
+    LT: QuantisedInteger + From<ST>,
+{
+    /// BLAKE2s hash of the model's contents: its small and large types, input
+    /// shape and nodes (including their parameters and quantisation info)
+    pub fn content_hash(&self) -> [u8; 32] {
+        content_hash::<ST, LT>(&self.description())
+    }
+
+    /// Serialises the model in the JSON variant of the model file format
+    pub fn to_json(&self) -> Result<String, SerialisationError> {
+        Ok(serde_json::to_string(&self.to_file())?)
+    }
+
+    /// Deserialises a model from the JSON variant of the model file format,
+    /// checking its content hash
+    pub fn from_json(json: &str) -> Result<Self, SerialisationError> {
+        Self::from_file(serde_json::from_str(json)?)
+    }
+
+    /// Serialises the model in the binary variant of the model file format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerialisationError> {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &self.to_file())?;
+        Ok(bytes)
+    }
+
+    /// Deserialises a model from the binary variant of the model file format,
+    /// checking its content hash
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerialisationError> {
+        match bytes.strip_prefix(BINARY_MAGIC) {
+            Some(body) => Self::from_file(bincode::deserialize(body)?),
+            None => Err(SerialisationError::Encoding(
+                "missing binary model header".to_string(),
+            )),
+        }
+    }
+
+    /// Writes the model to the file at `path` in the given format variant
+    pub fn write(&self, path: &str, format: ModelFormat) -> Result<(), SerialisationError> {
+        let bytes = match format {
+            ModelFormat::Json => self.to_json()?.into_bytes(),
+            ModelFormat::Binary => self.to_bytes()?,
+        };
+
+        Ok(std::fs::write(path, bytes)?)
+    }
+
+    /// Reads a model from the file at `path`, which may be in either format
+    /// variant
+    pub fn read(path: &str) -> Result<Self, SerialisationError> {
+        let bytes = std::fs::read(path)?;
+
+        if bytes.starts_with(BINARY_MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            Self::from_json(
+                ark_std::str::from_utf8(&bytes)
+                    .map_err(|e| SerialisationError::Encoding(e.to_string()))?,
+            )
+        }
+    }
+
+    fn description(&self) -> ModelDescription<ST, LT> {
+        ModelDescription {
+            input_shape: self.input_shape.clone(),
+            nodes: self.nodes.iter().map(NodeDescription::from).collect(),
+        }
+    }
+
+    fn to_file(&self) -> ModelFile<ST, LT> {
+        let model = self.description();
+
+        ModelFile {
+            version: MODEL_FORMAT_VERSION,
+            types: types::<ST, LT>(),
+            hash: hex(&content_hash::<ST, LT>(&model)),
+            model,
+        }
+    }
+
+    fn from_file(file: ModelFile<ST, LT>) -> Result<Self, SerialisationError> {
+        if file.version != MODEL_FORMAT_VERSION {
+            return Err(SerialisationError::UnsupportedVersion(file.version));
+        }
+
+        let expected_types = types::<ST, LT>();
+
+        if file.types != expected_types {
+            return Err(SerialisationError::TypeMismatch {
+                expected: format!("{:?}", expected_types),
+                found: format!("{:?}", file.types),
+            });
+        }
+
+        let hash = hex(&content_hash::<ST, LT>(&file.model));
+
+        if hash != file.hash {
+            return Err(SerialisationError::HashMismatch {
+                expected: file.hash,
+                found: hash,
+            });
+        }
+
+        if file.model.nodes.is_empty() {
+            return Err(SerialisationError::InvalidModel(
+                "the model contains no nodes".to_string(),
+            ));
+        }
+
+        let nodes = file
+            .model
+            .nodes
+            .into_iter()
+            .map(NodeDescription::into_node)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Model::try_new(file.model.input_shape, nodes)
+            .map_err(|e| SerialisationError::InvalidModel(e.to_string()))
+    }
+}
+
+impl<ST, LT> From<&Node<ST, LT>> for NodeDescription<ST, LT>
+where
+    ST: InnerType,
+    LT: InnerType,
+{
+    fn from(node: &Node<ST, LT>) -> Self {
+        match node {
+            Node::BMM(n) => NodeDescription::BMM {
+                weights: n.weights.clone(),
+                bias: n.bias.clone(),
+                input_zero_point: n.input_zero_point,
+            },
+            Node::SparseBMM(n) => NodeDescription::SparseBMM {
+                dims: n.dims,
+                entries: n.entries.clone(),
+                bias: n.bias.clone(),
+                input_zero_point: n.input_zero_point,
+            },
+            Node::RequantiseBMM(n) if n.relu => NodeDescription::RequantiseBMMReLU {
+                size: n.size,
+                q_info: n.q_info.clone(),
+            },
+            Node::RequantiseBMM(n) => NodeDescription::RequantiseBMM {
+                size: n.size,
+                q_info: n.q_info.clone(),
+            },
+            Node::ReLU(n) => NodeDescription::ReLU {
+                num_units: n.num_units,
+                zero_point: n.zero_point,
+            },
+            Node::Reshape(n) => NodeDescription::Reshape {
+                input_shape: n.input_shape.clone(),
+                output_shape: n.output_shape.clone(),
+            },
+            Node::Custom(n) => NodeDescription::Custom {
+                name: n.type_name().to_string(),
+                parameters: n.encode_parameters(),
+            },
+        }
+    }
+}
+
+impl<ST, LT> NodeDescription<ST, LT>
+where
+    ST: QuantisedInteger,
+    LT: QuantisedInteger,
+{
+    // Checks the conditions asserted by the node constructors, so that a
+    // malformed file results in an error rather than a panic
+    fn into_node(self) -> Result<Node<ST, LT>, SerialisationError> {
+        let invalid = |e: String| Err(SerialisationError::InvalidModel(e));
+
+        match self {
+            NodeDescription::BMM {
+                weights,
+                bias,
+                input_zero_point,
+            } => {
+                if weights.num_dims() != 2 || bias.num_dims() != 1 {
+                    return invalid(format!(
+                        "BMM node with weight shape {:?} and bias shape {:?}",
+                        weights.shape(),
+                        bias.shape()
+                    ));
+                }
+
+                if bias.len() != weights.shape()[1] {
+                    return invalid(format!(
+                        "BMM node with {} bias entries for {} columns",
+                        bias.len(),
+                        weights.shape()[1]
+                    ));
+                }
+
+                let node = BMMNode::new_unchecked(weights, bias, input_zero_point);
+
+                if let Some(range) = node.accumulator_overflow() {
+                    return invalid(format!(
+                        "BMM node whose accumulators range over {}, which does not fit in {}",
+                        range,
+                        type_name::<LT>()
+                    ));
+                }
+
+                Ok(Node::BMM(node))
+            }
+            NodeDescription::RequantiseBMM { size, q_info } => Ok(Node::RequantiseBMM(
+                RequantiseBMMNode::from_q_info(size, q_info),
+            )),
+            NodeDescription::RequantiseBMMReLU { size, q_info } => Ok(Node::RequantiseBMM(
+                RequantiseBMMNode::from_q_info(size, q_info).with_relu(),
+            )),
+            NodeDescription::ReLU {
+                num_units,
+                zero_point,
+            } => Ok(Node::ReLU(ReLUNode::new(num_units, zero_point))),
+            NodeDescription::Reshape {
+                input_shape,
+                output_shape,
+            } => {
+                if input_shape.iter().product::<usize>() != output_shape.iter().product::<usize>() {
+                    return invalid(format!(
+                        "Reshape node from {:?} to {:?}",
+                        input_shape, output_shape
+                    ));
+                }
+
+                Ok(Node::Reshape(ReshapeNode::new(input_shape, output_shape)))
+            }
+            NodeDescription::SparseBMM {
+                dims,
+                entries,
+                bias,
+                input_zero_point,
+            } => {
+                if bias.num_dims() != 1 || bias.len() != dims.1 {
+                    return invalid(format!(
+                        "Sparse BMM node with bias shape {:?} for {} columns",
+                        bias.shape(),
+                        dims.1
+                    ));
+                }
+
+                let mut positions: Vec<(usize, usize)> =
+                    entries.iter().map(|(i, j, _)| (*i, *j)).collect();
+                positions.sort_unstable();
+
+                if positions.iter().any(|(i, j)| *i >= dims.0 || *j >= dims.1)
+                    || positions.windows(2).any(|p| p[0] == p[1])
+                {
+                    return invalid(format!(
+                        "Sparse BMM node with out-of-bounds or repeated entries for dimensions {:?}",
+                        dims
+                    ));
+                }
+
+                let node = SparseBMMNode::new_unchecked(dims, entries, bias, input_zero_point);
+
+                if let Some(range) = node.accumulator_overflow() {
+                    return invalid(format!(
+                        "Sparse BMM node whose accumulators range over {}, which does not fit in {}",
+                        range,
+                        type_name::<LT>()
+                    ));
+                }
+
+                Ok(Node::SparseBMM(node))
+            }
+            NodeDescription::Custom { name, .. } => invalid(format!(
+                "custom node of type {} cannot be loaded from a model file",
+                name
+            )),
+        }
+    }
+}
+
+fn types<ST, LT>() -> (String, String) {
+    (type_name::<ST>().to_string(), type_name::<LT>().to_string())
+}
+
+// The hash is computed over the bincode encoding of the model description,
+// which is deterministic, rather than over either file variant
+fn content_hash<ST, LT>(model: &ModelDescription<ST, LT>) -> [u8; 32]
+where
+    ST: InnerType,
+    LT: InnerType,
+{
+    let mut hasher = Blake2s256::new();
+    hasher.update(bincode::serialize(&types::<ST, LT>()).unwrap());
+    hasher.update(bincode::serialize(model).unwrap());
+    hasher.finalize().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
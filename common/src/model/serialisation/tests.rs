@@ -0,0 +1,154 @@
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+
+use crate::two_layer_perceptron_mnist::{
+    build_two_layer_perceptron_mnist,
+    parameters::{S_1_I, S_1_O, S_1_W, Z_1_I, Z_1_O},
+};
+use crate::Ligero;
+
+use super::*;
+
+fn test_inputs() -> Vec<QArray<i8>> {
+    (0..5)
+        .map(|i| {
+            QArray::new(
+                (0..784)
+                    .map(|j| ((i * 53 + j * 7) % 256) as u8 as i8)
+                    .collect(),
+                vec![28, 28],
+            )
+        })
+        .collect()
+}
+
+fn assert_same_model(model: &Model<i8, i32>, loaded: &Model<i8, i32>) {
+    assert_eq!(model.content_hash(), loaded.content_hash());
+    assert_eq!(model.input_shape, loaded.input_shape);
+    assert_eq!(model.output_shape, loaded.output_shape);
+
+    let types =
+        |m: &Model<i8, i32>| -> Vec<&str> { m.nodes.iter().map(|n| n.type_name()).collect() };
+    assert_eq!(types(model), types(loaded));
+
+    for input in test_inputs() {
+        assert_eq!(model.evaluate(input.clone()), loaded.evaluate(input));
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+    let loaded = Model::from_json(&model.to_json().unwrap()).unwrap();
+
+    assert_same_model(&model, &loaded);
+}
+
+#[test]
+fn test_sparse_round_trip() {
+    let mut nodes = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>().nodes;
+
+    for node in nodes.iter_mut() {
+        if let Node::BMM(bmm) = node {
+            *node = Node::SparseBMM(bmm.to_sparse(3));
+        }
+    }
+
+    let model = Model::new(vec![28, 28], nodes);
+
+    assert_same_model(
+        &model,
+        &Model::from_json(&model.to_json().unwrap()).unwrap(),
+    );
+    assert_same_model(
+        &model,
+        &Model::from_bytes(&model.to_bytes().unwrap()).unwrap(),
+    );
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+    let bytes = model.to_bytes().unwrap();
+    let loaded = Model::from_bytes(&bytes).unwrap();
+
+    assert_same_model(&model, &loaded);
+    assert!(bytes.len() < model.to_json().unwrap().len());
+}
+
+#[test]
+fn test_file_round_trip() {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+
+    for (format, extension) in [(ModelFormat::Json, "json"), (ModelFormat::Binary, "bin")] {
+        let path = std::env::temp_dir().join(format!("hcs_serialisation_test.{}", extension));
+        let path = path.to_str().unwrap();
+
+        model.write(path, format).unwrap();
+        let loaded = Model::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_same_model(&model, &loaded);
+    }
+}
+
+#[test]
+fn test_content_hash_depends_on_parameters() {
+    let model = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>();
+
+    let mut nodes = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>().nodes;
+    nodes[2] = Node::RequantiseBMM(RequantiseBMMNode::new(
+        28,
+        S_1_I,
+        Z_1_I,
+        S_1_W,
+        0,
+        2.0 * S_1_O,
+        Z_1_O,
+    ));
+    let modified = Model::new(vec![28, 28], nodes);
+
+    assert_ne!(model.content_hash(), modified.content_hash());
+}
+
+#[test]
+fn test_tampered_file() {
+    let model: Model<i8, i32> = Model::new(vec![2], vec![Node::ReLU(ReLUNode::new(2, 5))]);
+
+    let json = model.to_json().unwrap();
+    let tampered = json.replace("\"zero_point\":5", "\"zero_point\":6");
+    assert_ne!(json, tampered);
+
+    assert!(matches!(
+        Model::<i8, i32>::from_json(&tampered),
+        Err(SerialisationError::HashMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_type_mismatch() {
+    let bytes = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>()
+        .to_bytes()
+        .unwrap();
+
+    assert!(matches!(
+        Model::<u8, i32>::from_bytes(&bytes),
+        Err(SerialisationError::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_invalid_binary() {
+    let bytes = build_two_layer_perceptron_mnist::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>()
+        .to_bytes()
+        .unwrap();
+
+    assert!(matches!(
+        Model::<i8, i32>::from_bytes(&bytes[..bytes.len() / 2]),
+        Err(SerialisationError::Encoding(_))
+    ));
+    assert!(matches!(
+        Model::<i8, i32>::from_bytes(&bytes[4..]),
+        Err(SerialisationError::Encoding(_))
+    ));
+}
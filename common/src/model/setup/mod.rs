@@ -0,0 +1,289 @@
+// Universal setups shared across models. The public parameters of a PCS are
+// generated once for a maximum number of variables at the security level of a
+// ProofConfig, persisted, and the keys of each model are trimmed from them.
+// A setup is identified by the BLAKE2s hash of its serialisation, which the
+// keys trimmed from it record.
+
+use ark_crypto_primitives::sponge::CryptographicSponge;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{fmt, marker::PhantomData, rand::RngCore};
+use blake2::{Blake2s256, Digest};
+
+use crate::model::config::{ProofConfig, ProofConfigError, SecurePCS};
+
+#[cfg(test)]
+mod tests;
+
+/// Current version of the setup file format
+pub const SETUP_FORMAT_VERSION: u32 = 1;
+
+// Header identifying setup files
+const SETUP_MAGIC: &[u8; 4] = b"HCSU";
+
+/// Errors arising when generating, persisting or trimming universal setups
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetupError {
+    /// The setup file could not be read or written
+    Io(String),
+    /// The setup could not be (de)serialised
+    Encoding(String),
+    /// The file was written by an incompatible version of the format
+    UnsupportedVersion(u32),
+    /// The field or PCS do not meet the configuration of the setup
+    Config(ProofConfigError),
+    /// The setup does not support as many variables as required
+    TooSmall { required: usize, supported: usize },
+    /// The PCS failed to trim the setup
+    Trim(String),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::Io(e) => write!(f, "Unable to access setup file: {}", e),
+            SetupError::Encoding(e) => write!(f, "Unable to (de)serialise setup: {}", e),
+            SetupError::UnsupportedVersion(v) => write!(
+                f,
+                "Setup format version {} is not supported (expected {})",
+                v, SETUP_FORMAT_VERSION
+            ),
+            SetupError::Config(e) => write!(f, "Invalid setup configuration: {}", e),
+            SetupError::TooSmall {
+                required,
+                supported,
+            } => write!(
+                f,
+                "Setup supports {} variables, but {} are required",
+                supported, required
+            ),
+            SetupError::Trim(e) => write!(f, "Unable to trim setup: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+impl From<std::io::Error> for SetupError {
+    fn from(e: std::io::Error) -> Self {
+        SetupError::Io(e.to_string())
+    }
+}
+
+impl From<ark_serialize::SerializationError> for SetupError {
+    fn from(e: ark_serialize::SerializationError) -> Self {
+        SetupError::Encoding(e.to_string())
+    }
+}
+
+impl From<ProofConfigError> for SetupError {
+    fn from(e: ProofConfigError) -> Self {
+        SetupError::Config(e)
+    }
+}
+
+/// Universal parameters of `PCS` supporting MLEs in up to `num_vars`
+/// variables at the security level of a `ProofConfig`. These are generated
+/// once and the keys of any model fitting in them (cf. `Model::check_setup`)
+/// are trimmed from them, e.g. by `Model::keys_from_setup`.
+pub struct UniversalSetup<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+{
+    config: ProofConfig,
+    num_vars: usize,
+    params: PCS::UniversalParams,
+    id: [u8; 32],
+    _field_sponge: PhantomData<(F, S)>,
+}
+
+/// Committer and verifier keys trimmed from a `UniversalSetup`, together
+/// with the identifier and configuration of the latter
+pub struct SetupKeys<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+{
+    pub ck: PCS::CommitterKey,
+    pub vk: PCS::VerifierKey,
+
+    /// Identifier of the setup the keys were trimmed from (cf.
+    /// `UniversalSetup::id`)
+    pub setup_id: [u8; 32],
+
+    /// Configuration of the setup the keys were trimmed from
+    pub config: ProofConfig,
+}
+
+impl<F, S, PCS> UniversalSetup<F, S, PCS>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+{
+    /// Generates universal parameters for MLEs in up to `num_vars` variables
+    /// at the security level of `config`, which the field and PCS must meet
+    pub fn new<R: RngCore>(
+        config: &ProofConfig,
+        num_vars: usize,
+        rng: &mut R,
+    ) -> Result<Self, ProofConfigError> {
+        config.check_field::<F>()?;
+        config.check_pcs::<F, S, PCS>()?;
+
+        let params = PCS::setup_for_config(config, num_vars, rng)
+            .map_err(|e| ProofConfigError::PCSSetup(e.to_string()))?;
+
+        Ok(Self::from_parts(*config, num_vars, params))
+    }
+
+    fn from_parts(config: ProofConfig, num_vars: usize, params: PCS::UniversalParams) -> Self {
+        let id = Blake2s256::digest(setup_body::<F, S, PCS>(&config, num_vars, &params)).into();
+
+        Self {
+            config,
+            num_vars,
+            params,
+            id,
+            _field_sponge: PhantomData,
+        }
+    }
+
+    pub fn config(&self) -> &ProofConfig {
+        &self.config
+    }
+
+    /// Maximum number of variables of the MLEs supported by the setup
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    pub fn params(&self) -> &PCS::UniversalParams {
+        &self.params
+    }
+
+    /// BLAKE2s hash of the configuration, number of variables and parameters
+    /// of the setup, which identifies it
+    pub fn id(&self) -> [u8; 32] {
+        self.id
+    }
+
+    /// Trims committer and verifier keys for MLEs in up to `num_vars`
+    /// variables from the setup
+    pub fn trim(&self, num_vars: usize) -> Result<SetupKeys<F, S, PCS>, SetupError> {
+        if num_vars > self.num_vars {
+            return Err(SetupError::TooSmall {
+                required: num_vars,
+                supported: self.num_vars,
+            });
+        }
+
+        let (ck, vk) = PCS::trim_to_num_vars(&self.params, num_vars)
+            .map_err(|e| SetupError::Trim(e.to_string()))?;
+
+        Ok(SetupKeys {
+            ck,
+            vk,
+            setup_id: self.id,
+            config: self.config,
+        })
+    }
+
+    /// Serialises the setup: a header, the format version and the
+    /// (compressed) canonical serialisation of its contents
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = SETUP_MAGIC.to_vec();
+        bytes.extend(SETUP_FORMAT_VERSION.to_le_bytes());
+        bytes.extend(setup_body::<F, S, PCS>(
+            &self.config,
+            self.num_vars,
+            &self.params,
+        ));
+        bytes
+    }
+
+    /// Deserialises a setup as serialised by `to_bytes`, validating the
+    /// parameters. The identifier is recomputed from the contents.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SetupError> {
+        let body = bytes
+            .strip_prefix(SETUP_MAGIC)
+            .ok_or_else(|| SetupError::Encoding("missing setup header".to_string()))?;
+
+        if body.len() < 4 {
+            return Err(SetupError::Encoding("missing setup version".to_string()));
+        }
+
+        let (version, mut body) = body.split_at(4);
+        let version = u32::from_le_bytes(version.try_into().unwrap());
+
+        if version != SETUP_FORMAT_VERSION {
+            return Err(SetupError::UnsupportedVersion(version));
+        }
+
+        let (security_bits, ligero_rho_inv, num_vars) =
+            <(u64, u64, u64)>::deserialize_compressed(&mut body)?;
+        let params = PCS::UniversalParams::deserialize_compressed(&mut body)?;
+
+        if !body.is_empty() {
+            return Err(SetupError::Encoding(
+                "trailing bytes after setup".to_string(),
+            ));
+        }
+
+        if ligero_rho_inv < 2 {
+            return Err(SetupError::Encoding(format!(
+                "invalid Ligero code rate 1/{}",
+                ligero_rho_inv
+            )));
+        }
+
+        let config =
+            ProofConfig::new(security_bits as usize).with_ligero_rho_inv(ligero_rho_inv as usize);
+
+        // The field and PCS may have changed since the setup was written
+        config.check_field::<F>()?;
+        config.check_pcs::<F, S, PCS>()?;
+
+        Ok(Self::from_parts(config, num_vars as usize, params))
+    }
+
+    /// Writes the setup to the file at `path`
+    pub fn write(&self, path: &str) -> Result<(), SetupError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    /// Reads a setup from the file at `path`
+    pub fn read(path: &str) -> Result<Self, SetupError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+// Canonical serialisation of the contents of a setup, which is both what is
+// persisted (after the header and version) and what is hashed into its id
+fn setup_body<F, S, PCS>(
+    config: &ProofConfig,
+    num_vars: usize,
+    params: &PCS::UniversalParams,
+) -> Vec<u8>
+where
+    F: PrimeField,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+{
+    let mut body = Vec::new();
+
+    (
+        config.security_bits() as u64,
+        config.ligero_rho_inv() as u64,
+        num_vars as u64,
+    )
+        .serialize_compressed(&mut body)
+        .unwrap();
+
+    params.serialize_compressed(&mut body).unwrap();
+
+    body
+}
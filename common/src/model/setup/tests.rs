@@ -0,0 +1,197 @@
+use ark_bn254::{Bn254, Fr};
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_poly_commit::{LabeledPolynomial, PolynomialCommitment};
+use ark_std::{test_rng, UniformRand};
+
+use crate::{poseidon_sponge, Ligero, Model, MultilinearKZG, Node, Poly, ReLUNode};
+
+use super::*;
+
+type S = PoseidonSponge<Fr>;
+
+fn relu_model() -> Model<i8, i32> {
+    Model::new(vec![16], vec![Node::ReLU(ReLUNode::new(16, -128))])
+}
+
+#[test]
+fn test_byte_round_trip() {
+    let setup =
+        UniversalSetup::<Fr, S, Ligero<Fr>>::new(&ProofConfig::default(), 10, &mut test_rng())
+            .unwrap();
+
+    let loaded = UniversalSetup::<Fr, S, Ligero<Fr>>::from_bytes(&setup.to_bytes()).unwrap();
+
+    assert_eq!(loaded.id(), setup.id());
+    assert_eq!(loaded.num_vars(), 10);
+    assert_eq!(loaded.config(), setup.config());
+}
+
+#[test]
+fn test_file_round_trip() {
+    let setup = UniversalSetup::<Fr, S, MultilinearKZG<Bn254>>::new(
+        &ProofConfig::new(100),
+        6,
+        &mut test_rng(),
+    )
+    .unwrap();
+
+    let path = std::env::temp_dir().join("hcs_setup_test.bin");
+    let path = path.to_str().unwrap();
+
+    setup.write(path).unwrap();
+    let loaded = UniversalSetup::<Fr, S, MultilinearKZG<Bn254>>::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded.id(), setup.id());
+    assert_eq!(loaded.to_bytes(), setup.to_bytes());
+}
+
+#[test]
+fn test_id_depends_on_contents() {
+    let config = ProofConfig::default();
+
+    let setup_10 = UniversalSetup::<Fr, S, Ligero<Fr>>::new(&config, 10, &mut test_rng()).unwrap();
+    let setup_11 = UniversalSetup::<Fr, S, Ligero<Fr>>::new(&config, 11, &mut test_rng()).unwrap();
+    let setup_rho = UniversalSetup::<Fr, S, Ligero<Fr>>::new(
+        &config.with_ligero_rho_inv(8),
+        10,
+        &mut test_rng(),
+    )
+    .unwrap();
+
+    assert_ne!(setup_10.id(), setup_11.id());
+    assert_ne!(setup_10.id(), setup_rho.id());
+}
+
+#[test]
+fn test_invalid_bytes() {
+    let setup =
+        UniversalSetup::<Fr, S, Ligero<Fr>>::new(&ProofConfig::default(), 10, &mut test_rng())
+            .unwrap();
+    let bytes = setup.to_bytes();
+
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(
+        UniversalSetup::<Fr, S, Ligero<Fr>>::from_bytes(&wrong_magic),
+        Err(SetupError::Encoding(_))
+    ));
+
+    let mut wrong_version = bytes.clone();
+    wrong_version[4] = 2;
+    assert_eq!(
+        UniversalSetup::<Fr, S, Ligero<Fr>>::from_bytes(&wrong_version).err(),
+        Some(SetupError::UnsupportedVersion(2))
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        UniversalSetup::<Fr, S, Ligero<Fr>>::from_bytes(&trailing),
+        Err(SetupError::Encoding(_))
+    ));
+}
+
+#[test]
+fn test_setup_config_checked() {
+    // KZG over BN254 falls short of 128 bits of security
+    assert!(matches!(
+        UniversalSetup::<Fr, S, MultilinearKZG<Bn254>>::new(
+            &ProofConfig::new(128),
+            6,
+            &mut test_rng()
+        ),
+        Err(ProofConfigError::PCSTooWeak { .. })
+    ));
+}
+
+#[test]
+fn test_model_fits_setup() {
+    let model = relu_model();
+    let required = model.value_key_num_vars::<Fr, S, Ligero<Fr>>(4);
+    let config = ProofConfig::default();
+
+    let small =
+        UniversalSetup::<Fr, S, Ligero<Fr>>::new(&config, required - 1, &mut test_rng()).unwrap();
+
+    assert_eq!(
+        model.check_setup(&small, 4),
+        Err(SetupError::TooSmall {
+            required,
+            supported: required - 1
+        })
+    );
+    assert!(model.keys_from_setup(&small, 4).is_err());
+
+    // A single inference requires fewer variables than a batch of four
+    assert_eq!(model.check_setup(&small, 1), Ok(()));
+
+    let large =
+        UniversalSetup::<Fr, S, Ligero<Fr>>::new(&config, required + 4, &mut test_rng()).unwrap();
+
+    assert_eq!(model.check_setup(&large, 4), Ok(()));
+
+    let keys = model.keys_from_setup(&large, 4).unwrap();
+    assert_eq!(keys.setup_id, large.id());
+    assert_eq!(keys.config, config);
+
+    let weight_keys = model.weight_keys_from_setup(&large).unwrap();
+    assert_eq!(weight_keys.setup_id, large.id());
+}
+
+#[test]
+fn test_kzg_keys_trimmed_from_larger_setup() {
+    let rng = &mut test_rng();
+    let setup = UniversalSetup::<Fr, S, MultilinearKZG<Bn254>>::new(&ProofConfig::new(100), 8, rng)
+        .unwrap();
+
+    let keys = setup.trim(3).unwrap();
+    assert_eq!(keys.ck.0.nv, 3);
+
+    let polynomial = LabeledPolynomial::new(
+        "p".to_string(),
+        Poly::from_evaluations_vec(3, (0..8).map(|_| Fr::rand(rng)).collect()),
+        None,
+        None,
+    );
+    let point: Vec<Fr> = (0..3).map(|_| Fr::rand(rng)).collect();
+
+    let (coms, states) = <MultilinearKZG<Bn254> as PolynomialCommitment<Fr, Poly<Fr>, S>>::commit(
+        &keys.ck,
+        [&polynomial],
+        None,
+    )
+    .unwrap();
+
+    let mut sponge: S = poseidon_sponge();
+    let proof = MultilinearKZG::<Bn254>::open(
+        &keys.ck,
+        [&polynomial],
+        &coms,
+        &point,
+        &mut sponge,
+        &states,
+        None,
+    )
+    .unwrap();
+
+    let mut sponge: S = poseidon_sponge();
+    assert!(MultilinearKZG::<Bn254>::check(
+        &keys.vk,
+        &coms,
+        &point,
+        [polynomial.evaluate(&point)],
+        &proof,
+        &mut sponge,
+        None,
+    )
+    .unwrap());
+
+    assert!(matches!(
+        setup.trim(9),
+        Err(SetupError::TooSmall {
+            required: 9,
+            supported: 8
+        })
+    ));
+}
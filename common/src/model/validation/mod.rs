@@ -0,0 +1,217 @@
+// Static validation of models. A model is a chain of nodes, each consuming the
+// output of the previous one (the first one consuming the model input), which
+// must therefore agree on the type (small or large) and shape of the values
+// passed between them, as well as on their quantisation. Violations of the
+// first two would otherwise only surface as panics during evaluation or
+// proving, and of the latter as silently wrong outputs. Shapes which only
+// differ by a reshape that is a no-op after padding (e.g. [1, 8] and [8])
+// agree, since the padded values passed between the nodes are the same.
+
+use ark_std::fmt;
+
+use crate::model::nodes::{custom::ValueType, reshape::agree_after_padding, Node};
+use crate::model::qarray::InnerType;
+use crate::model::Model;
+
+#[cfg(test)]
+mod tests;
+
+const SMALL_TYPE: &str = "small";
+const LARGE_TYPE: &str = "large";
+
+fn type_name(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::Small => SMALL_TYPE,
+        ValueType::Large => LARGE_TYPE,
+    }
+}
+
+/// Inconsistencies in the structure of a model. Nodes are indexed as in
+/// `Model::value_bounds`, i.e. the model input being the 0-th.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelError {
+    /// The model has no nodes
+    NoNodes,
+    /// The node expects values of one type (small or large) but receives
+    /// those of the other
+    TypeMismatch {
+        node: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The node expects an input of a different shape from that it receives
+    ShapeMismatch {
+        node: usize,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+    /// The node assumes a zero point of its input different from that of the
+    /// values it receives
+    ZeroPointMismatch {
+        node: usize,
+        expected: String,
+        found: String,
+    },
+    /// The last node outputs values of the large type, whereas the model
+    /// output is of the small one
+    LargeOutput,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::NoNodes => write!(f, "A model cannot have no nodes"),
+            ModelError::TypeMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {} expects an input of the {} type, but receives one of the {} type",
+                node, expected, found
+            ),
+            ModelError::ShapeMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {} expects an input of shape {:?}, but receives one of shape {:?}",
+                node, expected, found
+            ),
+            ModelError::ZeroPointMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {} expects an input with zero point {}, but receives one with zero point {}",
+                node, expected, found
+            ),
+            ModelError::LargeOutput => {
+                write!(f, "The last node of a model must output the small type")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl<ST, LT> Model<ST, LT>
+where
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    /// Creates a model after checking it with `validate`
+    pub fn try_new(input_shape: Vec<usize>, nodes: Vec<Node<ST, LT>>) -> Result<Self, ModelError> {
+        let output_shape = nodes.last().ok_or(ModelError::NoNodes)?.shape();
+
+        let model = Self {
+            input_shape,
+            output_shape,
+            nodes,
+        };
+
+        model.validate()?;
+
+        Ok(model)
+    }
+
+    /// Checks that consecutive nodes agree on the type (small or large) and
+    /// shape (up to padding, cf. `ReshapeNode::is_padded_no_op`) of the
+    /// values passed between them, starting from the model
+    /// input, and that the input zero points assumed by BMM, requantisation
+    /// and ReLU nodes are the zero points of the values they receive (when
+    /// these are known, i.e. not for the model input)
+    pub fn validate(&self) -> Result<(), ModelError> {
+        if self.nodes.is_empty() {
+            return Err(ModelError::NoNodes);
+        }
+
+        let mut value_type = SMALL_TYPE;
+        let mut shape = self.input_shape.clone();
+
+        // Zero point of the small-type values received by the node, and
+        // input zero point of the last BMM node (which its requantisation
+        // node must share)
+        let mut zero_point: Option<ST> = None;
+        let mut bmm_zero_point: Option<ST> = None;
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let index = i + 1;
+
+            let (input_type, output_type) = match node {
+                Node::BMM(_) | Node::SparseBMM(_) => (SMALL_TYPE, LARGE_TYPE),
+                Node::RequantiseBMM(_) => (LARGE_TYPE, SMALL_TYPE),
+                Node::ReLU(_) | Node::Reshape(_) => (SMALL_TYPE, SMALL_TYPE),
+                Node::Custom(c) => (type_name(c.input_type()), type_name(c.output_type())),
+            };
+
+            if input_type != value_type {
+                return Err(ModelError::TypeMismatch {
+                    node: index,
+                    expected: input_type,
+                    found: value_type,
+                });
+            }
+
+            if !agree_after_padding(&shape, &node.input_shape()) {
+                return Err(ModelError::ShapeMismatch {
+                    node: index,
+                    expected: node.input_shape(),
+                    found: shape,
+                });
+            }
+
+            let (expected_zero_point, received_zero_point, output_zero_point) = match node {
+                Node::BMM(fc) => {
+                    bmm_zero_point = Some(fc.input_zero_point());
+                    (Some(fc.input_zero_point()), zero_point, None)
+                }
+                Node::SparseBMM(fc) => {
+                    bmm_zero_point = Some(fc.input_zero_point);
+                    (Some(fc.input_zero_point), zero_point, None)
+                }
+                Node::RequantiseBMM(r) => (
+                    Some(r.q_info.input_info.zero_point),
+                    bmm_zero_point,
+                    Some(r.q_info.output_info.zero_point),
+                ),
+                Node::ReLU(r) => (Some(r.zero_point), zero_point, Some(r.zero_point)),
+                Node::Reshape(_) => (None, None, zero_point),
+                Node::Custom(c) => {
+                    let received_zero_point = match c.input_type() {
+                        ValueType::Small => zero_point,
+                        ValueType::Large => bmm_zero_point,
+                    };
+
+                    (
+                        c.input_zero_point(),
+                        received_zero_point,
+                        c.output_zero_point(),
+                    )
+                }
+            };
+
+            if let (Some(expected), Some(found)) = (expected_zero_point, received_zero_point) {
+                if expected != found {
+                    return Err(ModelError::ZeroPointMismatch {
+                        node: index,
+                        expected: format!("{:?}", expected),
+                        found: format!("{:?}", found),
+                    });
+                }
+            }
+
+            value_type = output_type;
+            shape = node.shape();
+            zero_point = output_zero_point;
+        }
+
+        if value_type != SMALL_TYPE {
+            return Err(ModelError::LargeOutput);
+        }
+
+        Ok(())
+    }
+}
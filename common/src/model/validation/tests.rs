@@ -0,0 +1,214 @@
+use crate::model::evaluation::OverflowMode;
+use crate::model::nodes::{
+    bmm::BMMNode, relu::ReLUNode, requantise_bmm::RequantiseBMMNode, reshape::ReshapeNode,
+    sparse_bmm::SparseBMMNode,
+};
+use crate::model::qarray::QArray;
+
+use super::*;
+
+const Z_I: i8 = -3;
+const Z_O: i8 = 4;
+
+fn reshape() -> Node<i8, i32> {
+    Node::Reshape(ReshapeNode::new(vec![1, 5], vec![5]))
+}
+
+fn bmm(input_zero_point: i8) -> Node<i8, i32> {
+    Node::BMM(BMMNode::new(
+        QArray::new((0..15).map(|x| x - 7).collect(), vec![5, 3]),
+        QArray::new(vec![10, -4, 7], vec![3]),
+        input_zero_point,
+    ))
+}
+
+fn requantise(input_zero_point: i8) -> Node<i8, i32> {
+    Node::RequantiseBMM(RequantiseBMMNode::new(
+        3,
+        0.5,
+        input_zero_point,
+        0.1,
+        0,
+        0.2,
+        Z_O,
+    ))
+}
+
+fn relu(num_units: usize, zero_point: i8) -> Node<i8, i32> {
+    Node::ReLU(ReLUNode::new(num_units, zero_point))
+}
+
+fn second_bmm(input_zero_point: i8) -> Node<i8, i32> {
+    Node::SparseBMM(SparseBMMNode::new(
+        (3, 2),
+        vec![(0, 1, 2), (2, 0, -5)],
+        QArray::new(vec![0, 1], vec![2]),
+        input_zero_point,
+    ))
+}
+
+fn valid_nodes() -> Vec<Node<i8, i32>> {
+    vec![
+        reshape(),
+        bmm(Z_I),
+        requantise(Z_I),
+        relu(3, Z_O),
+        second_bmm(Z_O),
+        Node::RequantiseBMM(RequantiseBMMNode::new(2, 0.5, Z_O, 0.1, 0, 0.2, 0)),
+    ]
+}
+
+fn try_new(nodes: Vec<Node<i8, i32>>) -> Result<Model<i8, i32>, ModelError> {
+    Model::try_new(vec![1, 5], nodes)
+}
+
+#[test]
+fn test_valid_model() {
+    let model = try_new(valid_nodes()).unwrap();
+
+    assert_eq!(model.output_shape, vec![2]);
+    assert_eq!(model.validate(), Ok(()));
+}
+
+#[test]
+fn test_no_nodes() {
+    assert_eq!(try_new(vec![]).err(), Some(ModelError::NoNodes));
+}
+
+#[test]
+fn test_type_mismatch() {
+    // ReLU directly after a BMM node
+    let mut nodes = valid_nodes();
+    nodes[2] = relu(3, Z_O);
+
+    assert_eq!(
+        try_new(nodes).err(),
+        Some(ModelError::TypeMismatch {
+            node: 3,
+            expected: "small",
+            found: "large",
+        })
+    );
+
+    // Requantisation of the model input
+    assert_eq!(
+        try_new(vec![requantise(Z_I)]).err(),
+        Some(ModelError::TypeMismatch {
+            node: 1,
+            expected: "large",
+            found: "small",
+        })
+    );
+}
+
+#[test]
+fn test_large_output() {
+    assert_eq!(
+        try_new(valid_nodes().into_iter().take(2).collect()).err(),
+        Some(ModelError::LargeOutput)
+    );
+}
+
+#[test]
+fn test_shape_mismatch() {
+    // The model input has more entries than the first BMM node expects
+    assert_eq!(
+        Model::try_new(vec![5, 2], valid_nodes().into_iter().skip(1).collect()).err(),
+        Some(ModelError::ShapeMismatch {
+            node: 1,
+            expected: vec![5],
+            found: vec![5, 2],
+        })
+    );
+
+    let mut nodes = valid_nodes();
+    nodes[3] = relu(4, Z_O);
+
+    assert_eq!(
+        try_new(nodes).err(),
+        Some(ModelError::ShapeMismatch {
+            node: 4,
+            expected: vec![4],
+            found: vec![3],
+        })
+    );
+}
+
+#[test]
+fn test_shapes_agreeing_after_padding() {
+    // The model input is not reshaped, but only differs from the input of
+    // the first BMM node by a dimension of size one
+    let model = try_new(valid_nodes().into_iter().skip(1).collect()).unwrap();
+    let reference = try_new(valid_nodes()).unwrap();
+
+    let input = QArray::new(vec![1, -7, 30, 0, 4], vec![1, 5]);
+
+    assert_eq!(
+        model.evaluate(input.clone()),
+        reference.evaluate(input.clone())
+    );
+    assert_eq!(
+        model
+            .padded_evaluate_with_mode(input.clone(), OverflowMode::Checked)
+            .unwrap(),
+        reference
+            .padded_evaluate_with_mode(input, OverflowMode::Checked)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_zero_point_mismatch() {
+    // The zero point of the model input is not known
+    let mut nodes = valid_nodes();
+    nodes[1] = bmm(Z_I + 1);
+    nodes[2] = requantise(Z_I + 1);
+    assert!(try_new(nodes).is_ok());
+
+    // The requantisation node disagrees with its BMM node
+    let mut nodes = valid_nodes();
+    nodes[2] = requantise(Z_I + 1);
+
+    assert_eq!(
+        try_new(nodes).err(),
+        Some(ModelError::ZeroPointMismatch {
+            node: 3,
+            expected: "-2".to_string(),
+            found: "-3".to_string(),
+        })
+    );
+
+    // The ReLU and sparse BMM nodes disagree with the requantisation output
+    let mut nodes = valid_nodes();
+    nodes[3] = relu(3, Z_O - 1);
+
+    assert_eq!(
+        try_new(nodes).err(),
+        Some(ModelError::ZeroPointMismatch {
+            node: 4,
+            expected: "3".to_string(),
+            found: "4".to_string(),
+        })
+    );
+
+    let mut nodes = valid_nodes();
+    nodes[4] = second_bmm(0);
+
+    assert_eq!(
+        try_new(nodes).err(),
+        Some(ModelError::ZeroPointMismatch {
+            node: 5,
+            expected: "0".to_string(),
+            found: "4".to_string(),
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid model: Node 3 expects an input of the small type")]
+fn test_new_panics() {
+    let mut nodes = valid_nodes();
+    nodes[2] = relu(3, Z_O);
+
+    Model::new(vec![1, 5], nodes);
+}
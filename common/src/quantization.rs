@@ -1,3 +1,9 @@
+use ark_std::cfg_iter;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use crate::model::qarray::InnerType;
 
 // TODO if we decide to make the model generic on the quantisation process
@@ -8,14 +14,22 @@ pub(crate) type QScaleType = f32;
 // Larger precision type to compute the requantisation scale in some schemes
 pub(crate) type QScaleComputationType = f64;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct QInfo<ST> {
     pub scale: QScaleType,
     pub zero_point: ST,
 }
 
+impl<ST> QInfo<ST> {
+    pub fn new(scale: QScaleType, zero_point: ST) -> Self {
+        Self { scale, zero_point }
+    }
+}
+
 // TODO: this will probably change to inference-ready requantisation info
 // Even what is being done now could be optimised by precomputing outside the
 // evaluate function
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BMMQInfo<ST> {
     pub input_info: QInfo<ST>,
     pub weight_info: QInfo<ST>,
@@ -50,23 +64,10 @@ where
     LT: InnerType + From<ST>,
 {
     // 1. Computing scale
-    // TODO In actual schemes, this will be decomposed as (int, shift)
-    let (s_i, s_w, s_o) = (
-        q_info.input_info.scale,
-        q_info.weight_info.scale,
-        q_info.output_info.scale,
-    );
-    let (s_i, s_w, s_o) = (
-        s_i as QScaleComputationType,
-        s_w as QScaleComputationType,
-        s_o as QScaleComputationType,
-    );
-    let s = (s_i * s_w / s_o) as QScaleType;
+    let s = requantisation_scale(q_info);
 
     // 2. Requantise
-    // TODO add rayon for parallelisation?
-    output
-        .iter()
+    cfg_iter!(output)
         .map(|x| {
             let x = LT::to_qscaletype(x) * s;
             let mut x = LT::from_qscaletype(x.round());
@@ -78,6 +79,22 @@ where
         .collect()
 }
 
+// Scale by which the BMM output is multiplied when requantising it
+// TODO In actual schemes, this will be decomposed as (int, shift)
+pub(crate) fn requantisation_scale<ST>(q_info: &BMMQInfo<ST>) -> QScaleType {
+    let (s_i, s_w, s_o) = (
+        q_info.input_info.scale,
+        q_info.weight_info.scale,
+        q_info.output_info.scale,
+    );
+    let (s_i, s_w, s_o) = (
+        s_i as QScaleComputationType,
+        s_w as QScaleComputationType,
+        s_o as QScaleComputationType,
+    );
+    (s_i * s_w / s_o) as QScaleType
+}
+
 // The (unstable) method clamp comes from the trait Ord, which we cannot
 // restrict InnerType to as we need f32 to implement the latter. Note that this
 // method is not meaningfully defined for classes that genuinely do not
@@ -99,23 +116,10 @@ where
     LT: InnerType + From<ST>,
 {
     // 1. Computing scale
-    // TODO In actual schemes, this will be decomposed as (int, shift)
-    let (s_i, s_w, s_o) = (
-        q_info.input_info.scale,
-        q_info.weight_info.scale,
-        q_info.output_info.scale,
-    );
-    let (s_i, s_w, s_o) = (
-        s_i as QScaleComputationType,
-        s_w as QScaleComputationType,
-        s_o as QScaleComputationType,
-    );
-    let s = (s_i * s_w / s_o) as QScaleType;
+    let s = requantisation_scale(q_info);
 
     // 2. Requantise
-    // TODO add rayon for parallelisation?
-    output
-        .iter()
+    cfg_iter!(output)
         .map(|x| {
             let x = LT::to_qscaletype(x) * s;
             let mut x = LT::from_qscaletype(x.round_ties_even()); // TODO which type to pick here? Should we check for overflows?
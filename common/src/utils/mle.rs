@@ -0,0 +1,35 @@
+use ark_ff::PrimeField;
+
+// MLEs and evaluations shared by the node proofs. As everywhere else, the
+// first variable is the least significant bit of the index.
+
+/// Evaluations over the hypercube of the MLE of eq(point, .)
+pub fn eq_evaluations<F: PrimeField>(point: &[F]) -> Vec<F> {
+    let mut evaluations = vec![F::one()];
+
+    for p in point.iter().rev() {
+        evaluations = evaluations
+            .into_iter()
+            .flat_map(|e| [e - e * p, e * p])
+            .collect();
+    }
+
+    evaluations
+}
+
+/// Evaluation of eq(x, y) = prod_i (x_i * y_i + (1 - x_i) * (1 - y_i))
+pub fn eq_evaluation<F: PrimeField>(x: &[F], y: &[F]) -> F {
+    x.iter()
+        .zip(y.iter())
+        .map(|(x_i, y_i)| *x_i * y_i + (F::one() - x_i) * (F::one() - y_i))
+        .product()
+}
+
+/// Evaluation of the MLE of the map sending each point of the hypercube to
+/// the integer it represents
+pub fn index_evaluation<F: PrimeField>(point: &[F]) -> F {
+    point
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, p| acc.double() + p)
+}
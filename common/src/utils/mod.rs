@@ -1,5 +1,6 @@
-#[cfg(feature = "test-types")]
+pub mod mle;
 pub mod pcs_types;
+pub mod sponge;
 
 #[cfg(feature = "test-types")]
 pub mod test_sponge;
@@ -8,9 +9,11 @@ macro_rules! node_op {
     ($self:expr, $method:ident, $trait:ident) => {
         match $self {
             Node::BMM(node) => node.$method(),
+            Node::SparseBMM(node) => node.$method(),
             Node::RequantiseBMM(node) => node.$method(),
             Node::ReLU(node) => node.$method(),
             Node::Reshape(node) => $trait::<I, _>::$method(node),
+            Node::Custom(node) => node.$method(),
         }
     };
 }
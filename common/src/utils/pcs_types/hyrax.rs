@@ -0,0 +1,243 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+use ark_poly_commit::{hyrax::HyraxPC, LabeledCommitment, LabeledPolynomial, PolynomialCommitment};
+use ark_std::{
+    marker::PhantomData,
+    rand::{rngs::StdRng, RngCore, SeedableRng},
+};
+
+use crate::model::config::SecurePCS;
+use crate::Poly;
+
+type HyraxInner<G, S> = HyraxPC<G, DenseMultilinearExtension<<G as AffineRepr>::ScalarField>, S>;
+
+type HyraxField<G> = <G as AffineRepr>::ScalarField;
+
+/// Hyrax PCS over the group G (whose scalar field is that of the MLEs). This
+/// wraps `HyraxPC` from ark-poly-commit in order to lift two of its
+/// restrictions, which node values and parameters do not meet:
+/// - Only MLEs with an even number of variables are supported. An MLE p in
+///   an odd number of variables is committed to as p'(x, y) = p(x) instead,
+///   and opened at the point extended with y = 0. The setup is likewise
+///   rounded up to an even number of variables.
+/// - Committing and opening require a source of randomness, which the rest of
+///   the crate does not always provide (cf. the TODOs on hiding). A fixed one
+///   is used in that case, which keeps commitments binding but not hiding.
+pub struct Hyrax<G>(PhantomData<G>);
+
+impl<G, S> PolynomialCommitment<HyraxField<G>, Poly<HyraxField<G>>, S> for Hyrax<G>
+where
+    G: AffineRepr,
+    HyraxField<G>: Absorb,
+    S: CryptographicSponge,
+    HyraxInner<G, S>: PolynomialCommitment<HyraxField<G>, Poly<HyraxField<G>>, S>,
+{
+    type UniversalParams = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::UniversalParams;
+    type CommitterKey = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::CommitterKey;
+    type VerifierKey = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::VerifierKey;
+    type Commitment = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::Commitment;
+    type CommitmentState = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::CommitmentState;
+    type Proof =
+        <HyraxInner<G, S> as PolynomialCommitment<HyraxField<G>, Poly<HyraxField<G>>, S>>::Proof;
+    type BatchProof = <HyraxInner<G, S> as PolynomialCommitment<
+        HyraxField<G>,
+        Poly<HyraxField<G>>,
+        S,
+    >>::BatchProof;
+    type Error =
+        <HyraxInner<G, S> as PolynomialCommitment<HyraxField<G>, Poly<HyraxField<G>>, S>>::Error;
+
+    fn setup<R: RngCore>(
+        max_degree: usize,
+        num_vars: Option<usize>,
+        rng: &mut R,
+    ) -> Result<Self::UniversalParams, Self::Error> {
+        HyraxInner::<G, S>::setup(max_degree, num_vars.map(|n| n + n % 2), rng)
+    }
+
+    fn trim(
+        pp: &Self::UniversalParams,
+        supported_degree: usize,
+        supported_hiding_bound: usize,
+        enforced_degree_bounds: Option<&[usize]>,
+    ) -> Result<(Self::CommitterKey, Self::VerifierKey), Self::Error> {
+        HyraxInner::<G, S>::trim(
+            pp,
+            supported_degree,
+            supported_hiding_bound,
+            enforced_degree_bounds,
+        )
+    }
+
+    fn commit<'a>(
+        ck: &Self::CommitterKey,
+        polynomials: impl IntoIterator<Item = &'a LabeledPolynomial<HyraxField<G>, Poly<HyraxField<G>>>>,
+        rng: Option<&mut dyn RngCore>,
+    ) -> Result<
+        (
+            Vec<LabeledCommitment<Self::Commitment>>,
+            Vec<Self::CommitmentState>,
+        ),
+        Self::Error,
+    >
+    where
+        Poly<HyraxField<G>>: 'a,
+    {
+        let polynomials: Vec<_> = polynomials.into_iter().map(lift_polynomial).collect();
+
+        let mut fixed_rng;
+        let rng: &mut dyn RngCore = match rng {
+            Some(rng) => rng,
+            None => {
+                fixed_rng = StdRng::seed_from_u64(0);
+                &mut fixed_rng
+            }
+        };
+
+        HyraxInner::<G, S>::commit(ck, &polynomials, Some(rng))
+    }
+
+    fn open<'a>(
+        ck: &Self::CommitterKey,
+        labeled_polynomials: impl IntoIterator<
+            Item = &'a LabeledPolynomial<HyraxField<G>, Poly<HyraxField<G>>>,
+        >,
+        commitments: impl IntoIterator<Item = &'a LabeledCommitment<Self::Commitment>>,
+        point: &'a Vec<HyraxField<G>>,
+        sponge: &mut S,
+        states: impl IntoIterator<Item = &'a Self::CommitmentState>,
+        rng: Option<&mut dyn RngCore>,
+    ) -> Result<Self::Proof, Self::Error>
+    where
+        Poly<HyraxField<G>>: 'a,
+        Self::CommitmentState: 'a,
+        Self::Commitment: 'a,
+    {
+        let polynomials: Vec<_> = labeled_polynomials
+            .into_iter()
+            .map(lift_polynomial)
+            .collect();
+        let point = lift_point(point);
+
+        // Collected so that their lifetimes shrink to that of the lifted point
+        let commitments: Vec<_> = commitments.into_iter().collect();
+        let states: Vec<_> = states.into_iter().collect();
+
+        let mut fixed_rng;
+        let rng: &mut dyn RngCore = match rng {
+            Some(rng) => rng,
+            None => {
+                fixed_rng = StdRng::seed_from_u64(0);
+                &mut fixed_rng
+            }
+        };
+
+        HyraxInner::<G, S>::open(
+            ck,
+            &polynomials,
+            commitments.iter().copied(),
+            &point,
+            sponge,
+            states.iter().copied(),
+            Some(rng),
+        )
+    }
+
+    fn check<'a>(
+        vk: &Self::VerifierKey,
+        commitments: impl IntoIterator<Item = &'a LabeledCommitment<Self::Commitment>>,
+        point: &'a Vec<HyraxField<G>>,
+        values: impl IntoIterator<Item = HyraxField<G>>,
+        proof: &Self::Proof,
+        sponge: &mut S,
+        rng: Option<&mut dyn RngCore>,
+    ) -> Result<bool, Self::Error>
+    where
+        Self::Commitment: 'a,
+    {
+        let point = lift_point(point);
+        let commitments: Vec<_> = commitments.into_iter().collect();
+
+        HyraxInner::<G, S>::check(
+            vk,
+            commitments.iter().copied(),
+            &point,
+            values,
+            proof,
+            sponge,
+            rng,
+        )
+    }
+}
+
+// The commitments are binding as long as discrete logarithms in G are hard,
+// which takes about sqrt(|G|) operations with Pollard's rho. Although they
+// are homomorphic, the opening proofs are dot-product arguments with
+// challenges of their own, which cannot be combined once produced, so claims
+// are checked one by one (cf. `SecurePCS::batch_check`).
+impl<G, S> SecurePCS<HyraxField<G>, S> for Hyrax<G>
+where
+    G: AffineRepr,
+    HyraxField<G>: Absorb,
+    S: CryptographicSponge,
+    HyraxInner<G, S>: PolynomialCommitment<HyraxField<G>, Poly<HyraxField<G>>, S>,
+{
+    fn security_bits() -> usize {
+        HyraxField::<G>::MODULUS_BIT_SIZE as usize / 2
+    }
+}
+
+// Lifts an MLE in an odd number of variables to one in an additional
+// variable on which it does not depend
+fn lift_polynomial<F: PrimeField>(
+    polynomial: &LabeledPolynomial<F, Poly<F>>,
+) -> LabeledPolynomial<F, Poly<F>> {
+    let num_vars = polynomial.num_vars();
+
+    if num_vars % 2 == 0 {
+        return polynomial.clone();
+    }
+
+    let evaluations = polynomial.to_evaluations();
+
+    LabeledPolynomial::new(
+        polynomial.label().clone(),
+        Poly::from_evaluations_vec(num_vars + 1, [evaluations.clone(), evaluations].concat()),
+        polynomial.degree_bound(),
+        polynomial.hiding_bound(),
+    )
+}
+
+// Extends a point in an odd number of variables to the additional variable
+// of the lifted MLEs (cf. lift_polynomial)
+fn lift_point<F: PrimeField>(point: &[F]) -> Vec<F> {
+    let mut point = point.to_vec();
+
+    if point.len() % 2 == 1 {
+        point.push(F::zero());
+    }
+
+    point
+}
@@ -0,0 +1,200 @@
+use ark_crypto_primitives::{
+    crh::{sha256::Sha256, CRHScheme, TwoToOneCRHScheme},
+    merkle_tree::{ByteDigestConverter, Config},
+    sponge::{poseidon::PoseidonSponge, Absorb},
+    Error,
+};
+use ark_ff::PrimeField;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{borrow::Borrow, marker::PhantomData, rand::RngCore};
+
+use ark_poly_commit::{
+    linear_codes::{
+        BrakedownPCParams, LigeroPCParams, LinCodeParametersInfo, LinearCodePCS,
+        MultilinearBrakedown, MultilinearLigero,
+    },
+    PolynomialCommitment,
+};
+use blake2::{Blake2s256, Digest};
+
+use crate::model::config::{ProofConfig, SecurePCS};
+use crate::Poly;
+
+mod hyrax;
+mod multilinear_kzg;
+
+#[cfg(test)]
+mod tests;
+
+pub use hyrax::Hyrax;
+pub use multilinear_kzg::MultilinearKZG;
+
+/// Largest security level, in bits, of the linear-code PCSs (Ligero and
+/// Brakedown), which is that of the hashes of their Merkle trees (SHA-256)
+/// and columns (BLAKE2s). Their `setup` fixes the code parameters for it:
+/// Ligero uses a code of rate 1/4 and Brakedown the code of the Brakedown
+/// paper, and in both cases the number of columns opened by the verifier is
+/// derived from this level. Ligero can be set up for other levels and rates
+/// through `SecurePCS::setup_for_config`.
+pub const LINEAR_CODE_SECURITY_LEVEL: usize = 128;
+
+// Merkle tree configuration shared by the linear-code PCSs (Ligero and
+// Brakedown). Leaves are column hashes already, so they are not hashed again.
+pub struct MerkleTreeParams;
+type LeafH = LeafIdentityHasher;
+type CompressH = Sha256;
+impl Config for MerkleTreeParams {
+    type Leaf = Vec<u8>;
+
+    type LeafDigest = <LeafH as CRHScheme>::Output;
+    type LeafInnerDigestConverter = ByteDigestConverter<Self::LeafDigest>;
+    type InnerDigest = <CompressH as TwoToOneCRHScheme>::Output;
+
+    type LeafHash = LeafH;
+    type TwoToOneHash = CompressH;
+}
+
+/// Leaf "hash" of the Merkle tree, which returns its input unchanged
+pub struct LeafIdentityHasher;
+
+impl CRHScheme for LeafIdentityHasher {
+    type Input = Vec<u8>;
+    type Output = Vec<u8>;
+    type Parameters = ();
+
+    fn setup<R: RngCore>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        _: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        Ok(input.borrow().to_vec())
+    }
+}
+
+/// Column hash of the linear-code PCSs: the digest D of the serialised column
+pub struct FieldToBytesColHasher<F: PrimeField, D: Digest>(PhantomData<(F, D)>);
+
+impl<F: PrimeField, D: Digest> CRHScheme for FieldToBytesColHasher<F, D> {
+    type Input = Vec<F>;
+    type Output = Vec<u8>;
+    type Parameters = ();
+
+    fn setup<R: RngCore>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        _: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let mut bytes = Vec::new();
+        input.borrow().serialize_compressed(&mut bytes)?;
+
+        Ok(D::digest(bytes).to_vec())
+    }
+}
+
+type MTConfig = MerkleTreeParams;
+type ColHasher<F> = FieldToBytesColHasher<F, Blake2s256>;
+
+// The backends below offer different trade-offs, all of them with transparent
+// setup except for MultilinearKZG:
+// - Ligero and Brakedown: hash-based, fast proving but large proofs (of size
+//   O(sqrt(n))), Brakedown's linear-time encoding proving fastest;
+// - Hyrax: discrete-log-based, with O(sqrt(n))-sized commitments and proofs;
+// - MultilinearKZG: pairing-based (PST13), with constant-sized commitments
+//   and O(log(n))-sized proofs, but a trusted setup.
+
+/// Ligero PCS over the scalar field F
+pub type Ligero<F> = LinearCodePCS<
+    MultilinearLigero<F, MTConfig, PoseidonSponge<F>, DenseMultilinearExtension<F>, ColHasher<F>>,
+    F,
+    DenseMultilinearExtension<F>,
+    PoseidonSponge<F>,
+    MTConfig,
+    ColHasher<F>,
+>;
+
+/// Brakedown PCS over the scalar field F
+pub type Brakedown<F> = LinearCodePCS<
+    MultilinearBrakedown<
+        F,
+        MTConfig,
+        PoseidonSponge<F>,
+        DenseMultilinearExtension<F>,
+        ColHasher<F>,
+    >,
+    F,
+    DenseMultilinearExtension<F>,
+    PoseidonSponge<F>,
+    MTConfig,
+    ColHasher<F>,
+>;
+
+impl<F> SecurePCS<F, PoseidonSponge<F>> for Ligero<F>
+where
+    F: PrimeField + Absorb,
+    Ligero<F>: PolynomialCommitment<
+        F,
+        Poly<F>,
+        PoseidonSponge<F>,
+        UniversalParams = LigeroPCParams<F, MTConfig, ColHasher<F>>,
+        VerifierKey = LigeroPCParams<F, MTConfig, ColHasher<F>>,
+    >,
+{
+    fn security_bits() -> usize {
+        LINEAR_CODE_SECURITY_LEVEL
+    }
+
+    // The number of columns opened is derived from the security level and the
+    // code rate, whereas the parameters are independent of the number of
+    // variables
+    fn setup_for_config<R: RngCore>(
+        config: &ProofConfig,
+        _num_vars: usize,
+        _rng: &mut R,
+    ) -> Result<Self::UniversalParams, Self::Error> {
+        Ok(LigeroPCParams::new(
+            config.security_bits(),
+            config.ligero_rho_inv(),
+            true,
+            (),
+            (),
+            (),
+        ))
+    }
+
+    // The verifier derives the number of columns to open from the security
+    // level and code rate of its key, so a key for a lower level (or another
+    // rate than that bound to the proofs) would accept weaker proofs
+    fn check_verifier_key(config: &ProofConfig, vk: &Self::VerifierKey) -> bool {
+        vk.check_well_formedness()
+            && vk.sec_param() >= config.security_bits()
+            && vk.distance() == (config.ligero_rho_inv() - 1, config.ligero_rho_inv())
+    }
+}
+
+impl<F> SecurePCS<F, PoseidonSponge<F>> for Brakedown<F>
+where
+    F: PrimeField + Absorb,
+    Brakedown<F>: PolynomialCommitment<
+        F,
+        Poly<F>,
+        PoseidonSponge<F>,
+        VerifierKey = BrakedownPCParams<F, MTConfig, ColHasher<F>>,
+    >,
+{
+    fn security_bits() -> usize {
+        LINEAR_CODE_SECURITY_LEVEL
+    }
+
+    // As for Ligero, the number of columns opened is derived from the
+    // security level of the key, whose code is fixed
+    fn check_verifier_key(config: &ProofConfig, vk: &Self::VerifierKey) -> bool {
+        vk.check_well_formedness() && vk.sec_param() >= config.security_bits()
+    }
+}
@@ -0,0 +1,429 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::{Field, PrimeField};
+use ark_poly::MultilinearExtension;
+use ark_poly_commit::{
+    multilinear_pc::{
+        data_structures::{Commitment, CommitterKey, Proof, UniversalParams, VerifierKey},
+        MultilinearPC,
+    },
+    Error, LabeledCommitment, LabeledPolynomial, PCCommitment, PCCommitmentState, PCCommitterKey,
+    PCUniversalParams, PCVerifierKey, PolynomialCommitment,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{marker::PhantomData, rand::RngCore, Zero};
+
+use crate::model::config::SecurePCS;
+use crate::{OpeningClaim, Poly};
+
+/// Multilinear KZG PCS (PST13) over the pairing E. This adapts
+/// `MultilinearPC` from ark-poly-commit, which only handles MLEs in exactly
+/// the number of variables of the key, to the `PolynomialCommitment`
+/// interface: MLEs in fewer variables are padded with zeros in the
+/// additional (last) variables and opened at the point extended with zeros.
+/// Commitments have constant size and proofs one G2 element per variable of
+/// the key, but the setup is trusted: its trapdoor must be discarded.
+pub struct MultilinearKZG<E: Pairing>(PhantomData<E>);
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultilinearKZGUniversalParams<E: Pairing>(pub UniversalParams<E>);
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultilinearKZGCommitterKey<E: Pairing>(pub CommitterKey<E>);
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultilinearKZGVerifierKey<E: Pairing>(pub VerifierKey<E>);
+
+/// Commitment to an MLE, together with its number of variables (which may be
+/// smaller than that of the key)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultilinearKZGCommitment<E: Pairing> {
+    pub num_vars: usize,
+    pub g_product: E::G1Affine,
+}
+
+/// Multilinear KZG commitments are deterministic and need no state
+#[derive(Clone, Debug, Default, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultilinearKZGCommitmentState;
+
+impl<E: Pairing> PCUniversalParams for MultilinearKZGUniversalParams<E> {
+    fn max_degree(&self) -> usize {
+        1
+    }
+}
+
+impl<E: Pairing> PCCommitterKey for MultilinearKZGCommitterKey<E> {
+    fn max_degree(&self) -> usize {
+        1
+    }
+
+    fn supported_degree(&self) -> usize {
+        1
+    }
+}
+
+impl<E: Pairing> PCVerifierKey for MultilinearKZGVerifierKey<E> {
+    fn max_degree(&self) -> usize {
+        1
+    }
+
+    fn supported_degree(&self) -> usize {
+        1
+    }
+}
+
+impl<E: Pairing> Default for MultilinearKZGCommitment<E> {
+    fn default() -> Self {
+        Self {
+            num_vars: 0,
+            g_product: E::G1Affine::default(),
+        }
+    }
+}
+
+impl<E: Pairing> PCCommitment for MultilinearKZGCommitment<E> {
+    fn empty() -> Self {
+        Self::default()
+    }
+
+    fn has_degree_bound(&self) -> bool {
+        false
+    }
+}
+
+impl PCCommitmentState for MultilinearKZGCommitmentState {
+    type Randomness = ();
+
+    fn empty() -> Self {
+        Self
+    }
+
+    fn rand<R: RngCore>(
+        _num_queries: usize,
+        _has_degree_bound: bool,
+        _num_vars: Option<usize>,
+        _rng: &mut R,
+    ) -> Self::Randomness {
+    }
+}
+
+impl<E, S> PolynomialCommitment<E::ScalarField, Poly<E::ScalarField>, S> for MultilinearKZG<E>
+where
+    E: Pairing,
+    S: CryptographicSponge,
+{
+    type UniversalParams = MultilinearKZGUniversalParams<E>;
+    type CommitterKey = MultilinearKZGCommitterKey<E>;
+    type VerifierKey = MultilinearKZGVerifierKey<E>;
+    type Commitment = MultilinearKZGCommitment<E>;
+    type CommitmentState = MultilinearKZGCommitmentState;
+    // One proof for each of the MLEs opened at once
+    type Proof = Vec<Proof<E>>;
+    type BatchProof = Vec<Self::Proof>;
+    type Error = Error;
+
+    fn setup<R: RngCore>(
+        _max_degree: usize,
+        num_vars: Option<usize>,
+        rng: &mut R,
+    ) -> Result<Self::UniversalParams, Self::Error> {
+        let num_vars = num_vars.expect("MultilinearKZG requires the number of variables");
+
+        Ok(MultilinearKZGUniversalParams(MultilinearPC::setup(
+            num_vars, rng,
+        )))
+    }
+
+    fn trim(
+        pp: &Self::UniversalParams,
+        _supported_degree: usize,
+        _supported_hiding_bound: usize,
+        _enforced_degree_bounds: Option<&[usize]>,
+    ) -> Result<(Self::CommitterKey, Self::VerifierKey), Self::Error> {
+        let (ck, vk) = MultilinearPC::trim(&pp.0, pp.0.num_vars);
+
+        Ok((
+            MultilinearKZGCommitterKey(ck),
+            MultilinearKZGVerifierKey(vk),
+        ))
+    }
+
+    fn commit<'a>(
+        ck: &Self::CommitterKey,
+        polynomials: impl IntoIterator<
+            Item = &'a LabeledPolynomial<E::ScalarField, Poly<E::ScalarField>>,
+        >,
+        _rng: Option<&mut dyn RngCore>,
+    ) -> Result<
+        (
+            Vec<LabeledCommitment<Self::Commitment>>,
+            Vec<Self::CommitmentState>,
+        ),
+        Self::Error,
+    >
+    where
+        Poly<E::ScalarField>: 'a,
+    {
+        Ok(polynomials
+            .into_iter()
+            .map(|polynomial| {
+                let Commitment { g_product, .. } =
+                    MultilinearPC::commit(&ck.0, &pad_polynomial(polynomial, ck.0.nv));
+
+                let com = MultilinearKZGCommitment {
+                    num_vars: polynomial.num_vars(),
+                    g_product,
+                };
+
+                (
+                    LabeledCommitment::new(polynomial.label().clone(), com, None),
+                    MultilinearKZGCommitmentState,
+                )
+            })
+            .unzip())
+    }
+
+    fn open<'a>(
+        ck: &Self::CommitterKey,
+        labeled_polynomials: impl IntoIterator<
+            Item = &'a LabeledPolynomial<E::ScalarField, Poly<E::ScalarField>>,
+        >,
+        _commitments: impl IntoIterator<Item = &'a LabeledCommitment<Self::Commitment>>,
+        point: &'a Vec<E::ScalarField>,
+        _sponge: &mut S,
+        _states: impl IntoIterator<Item = &'a Self::CommitmentState>,
+        _rng: Option<&mut dyn RngCore>,
+    ) -> Result<Self::Proof, Self::Error>
+    where
+        Poly<E::ScalarField>: 'a,
+        Self::CommitmentState: 'a,
+        Self::Commitment: 'a,
+    {
+        let point = pad_point(point, ck.0.nv);
+
+        Ok(labeled_polynomials
+            .into_iter()
+            .map(|polynomial| {
+                MultilinearPC::open(&ck.0, &pad_polynomial(polynomial, ck.0.nv), &point)
+            })
+            .collect())
+    }
+
+    fn check<'a>(
+        vk: &Self::VerifierKey,
+        commitments: impl IntoIterator<Item = &'a LabeledCommitment<Self::Commitment>>,
+        point: &'a Vec<E::ScalarField>,
+        values: impl IntoIterator<Item = E::ScalarField>,
+        proof: &Self::Proof,
+        _sponge: &mut S,
+        _rng: Option<&mut dyn RngCore>,
+    ) -> Result<bool, Self::Error>
+    where
+        Self::Commitment: 'a,
+    {
+        let commitments: Vec<_> = commitments.into_iter().collect();
+        let values: Vec<_> = values.into_iter().collect();
+
+        // The point must refer to the variables of the committed MLEs, and
+        // there must be one value and proof for each of them
+        if commitments.len() != values.len()
+            || commitments.len() != proof.len()
+            || point.len() > vk.0.nv
+            || commitments
+                .iter()
+                .any(|com| com.commitment().num_vars != point.len())
+        {
+            return Ok(false);
+        }
+
+        let point = pad_point(point, vk.0.nv);
+
+        Ok(commitments
+            .into_iter()
+            .zip(values)
+            .zip(proof.iter())
+            .all(|((com, value), proof)| {
+                let com = Commitment {
+                    nv: vk.0.nv,
+                    g_product: com.commitment().g_product,
+                };
+
+                MultilinearPC::check(&vk.0, &com, &point, value, proof)
+            }))
+    }
+}
+
+// Besides discrete logarithms in the source groups (cf. Hyrax), the security
+// of pairing-based schemes rests on those in the target group, a subgroup of
+// the multiplicative group of an extension field, for which the tower number
+// field sieve is much faster than generic algorithms. The estimates follow
+// Guillevic (https://eprint.iacr.org/2019/1371): about 100 bits for BN254
+// (whose target field has 3048 bits) and 120 for BLS12-381 (4572 bits).
+impl<E, S> SecurePCS<E::ScalarField, S> for MultilinearKZG<E>
+where
+    E: Pairing,
+    S: CryptographicSponge,
+{
+    fn security_bits() -> usize {
+        let target_field_bits = <E::TargetField as Field>::BasePrimeField::MODULUS_BIT_SIZE
+            as usize
+            * E::TargetField::extension_degree() as usize;
+
+        let target_group_bits = match target_field_bits {
+            0..=3100 => 100,
+            3101..=4600 => 120,
+            _ => 128,
+        };
+
+        (E::ScalarField::MODULUS_BIT_SIZE as usize / 2).min(target_group_bits)
+    }
+
+    // Keys only hold the powers for the variables they support, so trimming
+    // them to those of a given model saves space and commitment time
+    fn trim_to_num_vars(
+        pp: &Self::UniversalParams,
+        num_vars: usize,
+    ) -> Result<(Self::CommitterKey, Self::VerifierKey), Self::Error> {
+        assert!(
+            num_vars <= pp.0.num_vars,
+            "MultilinearKZG: cannot trim to more variables than supported by the parameters"
+        );
+
+        let (ck, vk) = MultilinearPC::trim(&pp.0, num_vars);
+
+        Ok((
+            MultilinearKZGCommitterKey(ck),
+            MultilinearKZGVerifierKey(vk),
+        ))
+    }
+
+    // The opening of a commitment C to v at x by a proof (pi_1, ..., pi_n)
+    // is checked as
+    //   e(C - v * g, h) = prod_i e(g_i - x_i * g, pi_i),
+    // where g_i are the powers of the trapdoor in the key. Both sides are
+    // linear in (C, v, pi), so the openings at each point are combined with
+    // random coefficients rho_k into one of sum_k rho_k * C_k to
+    // sum_k rho_k * v_k by the proof of elements sum_k rho_k * pi_{k, i}, and
+    // the checks at all points add up to a single equation. The coefficients
+    // are squeezed from the transcript of the first claim after absorbing
+    // all claims.
+    fn batch_check(vk: &Self::VerifierKey, claims: &[OpeningClaim<E::ScalarField, S, Self>]) -> bool
+    where
+        E::ScalarField: Absorb,
+    {
+        let vk = &vk.0;
+
+        // The openings (commitment, value and proof) grouped by their point,
+        // padded to the variables of the key
+        let mut groups: Vec<(Vec<E::ScalarField>, Vec<_>)> = Vec::new();
+
+        for claim in claims {
+            // Malformed claims are rejected as in check
+            if claim.commitments.len() != claim.values.len()
+                || claim.commitments.len() != claim.proof.len()
+                || claim.point.len() > vk.nv
+                || claim
+                    .commitments
+                    .iter()
+                    .any(|com| com.commitment().num_vars != claim.point.len())
+                || claim.proof.iter().any(|proof| proof.proofs.len() != vk.nv)
+            {
+                return false;
+            }
+
+            let point = pad_point(&claim.point, vk.nv);
+
+            let openings = claim
+                .commitments
+                .iter()
+                .zip(claim.values.iter())
+                .zip(claim.proof.iter())
+                .map(|((com, value), proof)| (com.commitment().g_product, *value, proof));
+
+            match groups.iter_mut().find(|(p, _)| *p == point) {
+                Some((_, group)) => group.extend(openings),
+                None => groups.push((point, openings.collect())),
+            }
+        }
+
+        let Some(first_claim) = claims.first() else {
+            return true;
+        };
+
+        let mut sponge = first_claim.sponge.clone();
+
+        for claim in claims {
+            let mut bytes = Vec::new();
+
+            for com in claim.commitments.iter() {
+                if com.commitment().serialize_compressed(&mut bytes).is_err() {
+                    return false;
+                }
+            }
+
+            if claim.proof.serialize_compressed(&mut bytes).is_err() {
+                return false;
+            }
+
+            sponge.absorb(&claim.point);
+            sponge.absorb(&claim.values);
+            sponge.absorb(&bytes);
+        }
+
+        let num_openings = groups.iter().map(|(_, group)| group.len()).sum();
+        let mut rhos = sponge
+            .squeeze_field_elements::<E::ScalarField>(num_openings)
+            .into_iter();
+
+        let mut combined_com = E::G1::zero();
+        let mut pairing_lefts = Vec::new();
+        let mut pairing_rights = Vec::new();
+
+        for (point, group) in groups {
+            let mut combined_proof = vec![E::G2::zero(); vk.nv];
+
+            for (g_product, value, proof) in group {
+                let rho = rhos.next().unwrap();
+
+                combined_com += (g_product.into_group() - vk.g * value) * rho;
+
+                for (combined, pi) in combined_proof.iter_mut().zip(proof.proofs.iter()) {
+                    *combined += *pi * rho;
+                }
+            }
+
+            pairing_lefts.extend(
+                vk.g_mask_random
+                    .iter()
+                    .zip(point)
+                    .map(|(g_i, x_i)| g_i.into_group() - vk.g * x_i),
+            );
+            pairing_rights.extend(combined_proof);
+        }
+
+        E::pairing(combined_com, vk.h) == E::multi_pairing(pairing_lefts, pairing_rights)
+    }
+}
+
+// Pads an MLE with zeros to the given number of variables: the additional
+// ones are the last (i.e. most significant) ones
+fn pad_polynomial<F: PrimeField>(polynomial: &Poly<F>, num_vars: usize) -> Poly<F> {
+    assert!(
+        polynomial.num_vars <= num_vars,
+        "MultilinearKZG: the polynomial has more variables than supported by the key"
+    );
+
+    let mut evaluations = polynomial.evaluations.clone();
+    evaluations.resize(1 << num_vars, F::zero());
+
+    Poly::from_evaluations_vec(num_vars, evaluations)
+}
+
+// Extends a point with zeros to the given number of variables, so that the
+// padded MLEs (cf. pad_polynomial) evaluate to the original ones
+fn pad_point<F: PrimeField>(point: &[F], num_vars: usize) -> Vec<F> {
+    let mut point = point.to_vec();
+    point.resize(num_vars, F::zero());
+
+    point
+}
@@ -0,0 +1,134 @@
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_ff::One;
+use ark_poly::{MultilinearExtension, Polynomial};
+use ark_poly_commit::LabeledPolynomial;
+use ark_std::{test_rng, UniformRand};
+
+use crate::{poseidon_sponge, OpeningClaim};
+
+use super::*;
+
+type S = PoseidonSponge<Fr>;
+
+// Number of variables the keys support
+const KEY_NUM_VARS: usize = 6;
+
+// Numbers of variables of the committed MLEs: that of the keys, an odd one
+// (which Hyrax lifts) and an even one, both smaller than that of the keys
+// (which MultilinearKZG pads)
+const MLE_NUM_VARS: [usize; 3] = [KEY_NUM_VARS, 5, 4];
+
+fn keys<PCS: SecurePCS<Fr, S>>() -> (PCS::CommitterKey, PCS::VerifierKey) {
+    // The security level of the weakest backend (MultilinearKZG over BN254)
+    let config = ProofConfig::new(100);
+
+    let pp = PCS::setup_for_config(&config, KEY_NUM_VARS, &mut test_rng()).unwrap();
+    PCS::trim_to_num_vars(&pp, KEY_NUM_VARS).unwrap()
+}
+
+// Commits to a random MLE in num_vars variables and opens it at a random
+// point, returning the (valid) claim of its evaluation there
+fn opening_claim<PCS: SecurePCS<Fr, S>>(
+    ck: &PCS::CommitterKey,
+    num_vars: usize,
+) -> OpeningClaim<Fr, S, PCS> {
+    let mut rng = test_rng();
+
+    let mle = LabeledPolynomial::new(
+        "mle".to_string(),
+        Poly::rand(num_vars, &mut rng),
+        None,
+        None,
+    );
+    let point: Vec<Fr> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+    let value = mle.evaluate(&point);
+
+    let (coms, states) = PCS::commit(ck, [&mle], None).unwrap();
+
+    let sponge = poseidon_sponge::<Fr>();
+    let proof = PCS::open(
+        ck,
+        [&mle],
+        &coms,
+        &point,
+        &mut sponge.clone(),
+        &states,
+        None,
+    )
+    .unwrap();
+
+    OpeningClaim::new(coms, point, vec![value], proof, &sponge)
+}
+
+// Openings of MLEs in as many and fewer variables than the keys are accepted,
+// both on their own and batched, and rejected if any of their values is off
+fn check_round_trip<PCS: SecurePCS<Fr, S>>() {
+    let (ck, vk) = keys::<PCS>();
+
+    let mut claims: Vec<_> = MLE_NUM_VARS
+        .into_iter()
+        .map(|num_vars| opening_claim::<PCS>(&ck, num_vars))
+        .collect();
+
+    assert!(claims.iter().all(|claim| claim.check(&vk)));
+    assert!(PCS::batch_check(&vk, &claims));
+
+    for i in 0..claims.len() {
+        claims[i].values[0] += Fr::one();
+
+        assert!(!claims[i].check(&vk));
+        assert!(!PCS::batch_check(&vk, &claims));
+
+        claims[i].values[0] -= Fr::one();
+    }
+}
+
+#[test]
+fn test_ligero_round_trip() {
+    check_round_trip::<Ligero<Fr>>();
+}
+
+#[test]
+fn test_brakedown_round_trip() {
+    check_round_trip::<Brakedown<Fr>>();
+}
+
+#[test]
+fn test_hyrax_round_trip() {
+    check_round_trip::<Hyrax<G1Affine>>();
+}
+
+#[test]
+fn test_multilinear_kzg_round_trip() {
+    check_round_trip::<MultilinearKZG<Bn254>>();
+}
+
+#[test]
+fn test_multilinear_kzg_wrong_num_vars() {
+    type PCS = MultilinearKZG<Bn254>;
+
+    let (ck, vk) = keys::<PCS>();
+    let claim = opening_claim::<PCS>(&ck, 4);
+
+    // The point must have exactly as many variables as the committed MLE:
+    // extending it with a zero is rejected even though the padded MLE takes
+    // the same value there
+    let mut longer_claim = opening_claim::<PCS>(&ck, 4);
+    longer_claim.point.push(Fr::from(0u64));
+
+    assert!(claim.check(&vk));
+    assert!(!longer_claim.check(&vk));
+
+    let mut shorter_claim = opening_claim::<PCS>(&ck, 4);
+    shorter_claim.point.pop();
+
+    assert!(!shorter_claim.check(&vk));
+
+    // As are points with more variables than the key
+    let mut oversized_claim = opening_claim::<PCS>(&ck, 4);
+    oversized_claim.point = vec![Fr::from(0u64); KEY_NUM_VARS + 1];
+
+    assert!(!oversized_claim.check(&vk));
+    assert!(!PCS::batch_check(&vk, &[claim, oversized_claim]));
+}
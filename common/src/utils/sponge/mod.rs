@@ -0,0 +1,148 @@
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+    CryptographicSponge,
+};
+use ark_ff::{BigInteger, PrimeField};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Security level, in bits, of the Poseidon instances returned by
+/// `poseidon_config`
+pub const POSEIDON_SECURITY_LEVEL: usize = 128;
+
+// Poseidon instance (S-box x^alpha) over a family of fields. The number of
+// rounds is that given for the security level above by the Poseidon paper
+// (https://eprint.iacr.org/2019/458, including the security margin of its
+// round-number script). Generic attacks on a sponge succeed after about
+// 2^(c / 2) permutation calls, where c is the number of bits of its capacity:
+// the bound each instance reaches is stated below.
+struct PoseidonInstance {
+    alpha: u64,
+    rate: usize,
+    capacity: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+}
+
+// Instance for fields of 250 to 256 bits, such as the scalar fields of BN254
+// and BLS12-381: width 3 and x^5, as in circomlib and the Poseidon paper.
+// The capacity of a single element only keeps log2(p) bits (254 for BN254),
+// so that generic attacks take about 2^127 calls (2^125 for a 250-bit field):
+// this instance follows the paper in treating that as
+// POSEIDON_SECURITY_LEVEL.
+const POSEIDON_255: PoseidonInstance = PoseidonInstance {
+    alpha: 5,
+    rate: 2,
+    capacity: 1,
+    full_rounds: 8,
+    partial_rounds: 57,
+};
+
+fn poseidon_instance(modulus_bits: u32) -> Option<PoseidonInstance> {
+    match modulus_bits {
+        250..=256 => Some(POSEIDON_255),
+        _ => None,
+    }
+}
+
+// Remainder of p - 1 modulo m, where p is the modulus of F. x^alpha is a
+// permutation of F if and only if alpha is coprime to p - 1.
+fn modulus_minus_one_rem<F: PrimeField>(m: u64) -> u64 {
+    let mut modulus_minus_one = F::MODULUS;
+    modulus_minus_one.sub_with_borrow(&F::BigInt::from(1u64));
+
+    modulus_minus_one
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |rem, limb| ((rem << 64) + *limb as u128) % m as u128) as u64
+}
+
+/// Poseidon parameters for F at `POSEIDON_SECURITY_LEVEL` bits of security.
+/// Unlike those of `test_sponge`, the round constants and the MDS matrix are
+/// derived from the Grain LFSR as specified by the Poseidon paper, hence are
+/// the same as those of any other implementation of the same instance.
+///
+/// # Panics
+///
+/// If there is no supported instance for F. Currently, these are the fields
+/// of 250 to 256 bits in which x^5 is a permutation, such as the scalar
+/// fields of BN254 and BLS12-381.
+pub fn poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
+    try_poseidon_config().unwrap_or_else(|| {
+        panic!(
+            "No Poseidon instance is supported for the field of {} bits",
+            F::MODULUS_BIT_SIZE
+        )
+    })
+}
+
+// Parameters returned by try_poseidon_config, by field. Generating the round
+// constants and the MDS matrix runs the Grain LFSR for thousands of steps,
+// whereas the verifier checks its sponge against them for every proof (cf.
+// ProofConfig::check_sponge).
+static POSEIDON_CONFIGS: OnceLock<Mutex<PoseidonConfigs>> = OnceLock::new();
+
+// Values are of type Option<PoseidonConfig<F>>, where F is the field whose
+// TypeId is the key
+type PoseidonConfigs = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+// Same as poseidon_config, returning None if there is no supported instance.
+// The parameters are only generated the first time they are requested for F.
+pub(crate) fn try_poseidon_config<F: PrimeField>() -> Option<PoseidonConfig<F>> {
+    let mut configs = POSEIDON_CONFIGS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    configs
+        .entry(TypeId::of::<F>())
+        .or_insert_with(|| Box::new(generate_poseidon_config::<F>()))
+        .downcast_ref::<Option<PoseidonConfig<F>>>()
+        .expect("Poseidon parameters are cached by the TypeId of their field")
+        .clone()
+}
+
+fn generate_poseidon_config<F: PrimeField>() -> Option<PoseidonConfig<F>> {
+    let instance = poseidon_instance(F::MODULUS_BIT_SIZE)?;
+
+    if modulus_minus_one_rem::<F>(instance.alpha) == 0 {
+        return None;
+    }
+
+    // The Grain LFSR only depends on the width (rate + capacity), which
+    // find_poseidon_ark_and_mds receives as its rate plus one.
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        instance.rate + instance.capacity - 1,
+        instance.full_rounds as u64,
+        instance.partial_rounds as u64,
+        0,
+    );
+
+    Some(PoseidonConfig::new(
+        instance.full_rounds,
+        instance.partial_rounds,
+        instance.alpha,
+        mds,
+        ark,
+        instance.rate,
+        instance.capacity,
+    ))
+}
+
+/// Poseidon sponge over F with the parameters of `poseidon_config`, to be
+/// used as the Fiat-Shamir sponge of the prover and the verifier
+///
+/// # Panics
+///
+/// If there is no supported Poseidon instance for F (cf. `poseidon_config`).
+pub fn poseidon_sponge<F: PrimeField>() -> PoseidonSponge<F> {
+    PoseidonSponge::new(&poseidon_config())
+}
@@ -0,0 +1,72 @@
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::CryptographicSponge;
+use ark_ff::MontFp;
+
+use super::*;
+
+#[test]
+fn test_poseidon_config_bn254() {
+    let config = poseidon_config::<Fr>();
+
+    assert_eq!(config.full_rounds, 8);
+    assert_eq!(config.partial_rounds, 57);
+    assert_eq!(config.alpha, 5);
+    assert_eq!(config.rate, 2);
+    assert_eq!(config.capacity, 1);
+    assert_eq!(config.ark.len(), 8 + 57);
+    assert!(config.ark.iter().all(|round| round.len() == 3));
+    assert_eq!(config.mds.len(), 3);
+    assert!(config.mds.iter().all(|row| row.len() == 3));
+}
+
+#[test]
+fn test_poseidon_config_bn254_round_constants() {
+    // First and last round constants of the BN254 width-3 instance, as
+    // generated by the reference script of the Poseidon paper (and found in
+    // circomlib)
+    let config = poseidon_config::<Fr>();
+
+    assert_eq!(
+        config.ark[0][0],
+        MontFp!("6745197990210204598374042828761989596302876299545964402857411729872131034734")
+    );
+}
+
+#[test]
+fn test_x5_permutation_bn254() {
+    // p - 1 is divisible by 3 but not by 5 for the BN254 scalar field
+    assert_eq!(modulus_minus_one_rem::<Fr>(3), 0);
+    assert_ne!(modulus_minus_one_rem::<Fr>(5), 0);
+    assert_eq!(modulus_minus_one_rem::<Fr>(2), 0);
+}
+
+#[test]
+fn test_poseidon_sponge_deterministic() {
+    let mut sponge_1 = poseidon_sponge::<Fr>();
+    let mut sponge_2 = poseidon_sponge::<Fr>();
+
+    sponge_1.absorb(&Fr::from(42u64));
+    sponge_2.absorb(&Fr::from(42u64));
+
+    assert_eq!(
+        sponge_1.squeeze_field_elements::<Fr>(2),
+        sponge_2.squeeze_field_elements::<Fr>(2)
+    );
+}
+
+#[test]
+fn test_poseidon_instance_unsupported_field() {
+    assert!(poseidon_instance(64).is_none());
+    assert!(poseidon_instance(381).is_none());
+}
+
+#[test]
+fn test_poseidon_config_cached() {
+    // Requesting the parameters again returns the ones generated first
+    let cached = poseidon_config::<Fr>();
+    let generated = generate_poseidon_config::<Fr>().unwrap();
+
+    assert_eq!(cached.ark, generated.ark);
+    assert_eq!(cached.mds, generated.mds);
+    assert_eq!(poseidon_config::<Fr>().ark, cached.ark);
+}
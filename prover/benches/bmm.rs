@@ -7,11 +7,11 @@ use ark_poly_commit::PolynomialCommitment;
 use ark_std::test_rng;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use hcs_common::{
-    python::*, quantise_f32_u8_nne, test_sponge, BMMNode, Ligero, Model, Node, NodeCommitment,
-    NodeCommitmentState, Poly, QArray, RequantiseBMMNode,
+    python::*, quantise_f32_u8_nne, BMMNode, Ligero, Model, Node, Poly, ProofConfig, QArray,
+    RequantiseBMMNode, SecurePCS,
 };
-use hcs_prover::ProveModel;
-use hcs_verifier::VerifyModel;
+use hcs_prover::{ProveModel, ProvingKey};
+use hcs_verifier::{VerifyModel, VerifyingKey};
 use pyo3::Python;
 
 const SAMPLE_SIZE: usize = 10;
@@ -41,8 +41,8 @@ where
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    let w_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights.json"));
-    let b_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias.json"));
+    let w_array: QArray<i8> = QArray::read(&format!(PATH!(), "weights.json")).unwrap();
+    let b_array: QArray<i32> = QArray::read(&format!(PATH!(), "bias.json")).unwrap();
 
     let bmm: BMMNode<i8, i32> = BMMNode::new(w_array, b_array, Z_I);
 
@@ -85,22 +85,26 @@ fn bench_fully_connected_layer(c: &mut Criterion) {
 
         bench_verifiaml_inference(c, &fc_model, &raw_input, resize_factor);
 
-        let mut sponge: PoseidonSponge<Fr> = test_sponge();
+        let config = ProofConfig::default();
+        let mut sponge: PoseidonSponge<Fr> = config.poseidon_sponge().unwrap();
         let mut rng = test_rng();
         let (ck, vk) = fc_model
-            .setup_keys::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, _>(&mut rng)
+            .setup_keys::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, _>(&config, &mut rng)
             .unwrap();
+        let (wck, wvk) = fc_model
+            .setup_weight_keys::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, _>(&config, &mut rng)
+            .unwrap();
+
+        let pk = fc_model.proving_key(config, ck, wck, None);
+        let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
 
-        let (node_coms, node_coms_states) =
-            bench_verifiaml_proof(c, &fc_model, &raw_input, &ck, &mut sponge, resize_factor);
+        bench_verifiaml_proof(c, &fc_model, &pk, &raw_input, &mut sponge, resize_factor);
 
         bench_verifiaml_verification::<Ligero<Fr>, PoseidonSponge<Fr>>(
             c,
             &fc_model,
-            &ck,
+            &pk,
             &vk,
-            &node_coms,
-            &node_coms_states,
             &raw_input,
             &mut sponge,
             resize_factor,
@@ -148,26 +152,17 @@ fn bench_verifiaml_inference(
 fn bench_verifiaml_proof<PCS, S>(
     c: &mut Criterion,
     model: &Model<i8, i32>,
+    pk: &ProvingKey<Fr, S, PCS, PCS>,
     raw_input: &QArray<f32>,
-    ck: &PCS::CommitterKey,
     sponge: &mut S,
     resize_factor: usize,
-) -> (
-    Vec<NodeCommitment<Fr, S, PCS>>,
-    Vec<NodeCommitmentState<Fr, S, PCS>>,
-)
-where
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<Fr, Poly<Fr>, S>,
+) where
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<Fr, Poly<Fr>, S> + 'static,
 {
     let mut group = c.benchmark_group("verifiaml");
     group.sample_size(SAMPLE_SIZE);
 
-    let (node_coms, node_com_states): (
-        Vec<NodeCommitment<Fr, S, PCS>>,
-        Vec<NodeCommitmentState<Fr, S, PCS>>,
-    ) = model.commit(ck, None).into_iter().unzip();
-
     let mut rng = test_rng();
 
     group.bench_function(
@@ -179,34 +174,23 @@ where
             b.iter(|| {
                 // Quantisation happens in the tf inference benchmark, so we benchmark it here
                 // too in order to make the comparison as fair as possible
-                model.prove_inference(
-                    ck,
-                    Some(&mut rng),
-                    sponge,
-                    &node_coms,
-                    &node_com_states,
-                    quantise_input(&raw_input),
-                );
+                model.prove_inference(pk, Some(&mut rng), sponge, quantise_input(&raw_input));
             })
         },
     );
-
-    (node_coms, node_com_states)
 }
 
 fn bench_verifiaml_verification<PCS, S>(
     c: &mut Criterion,
     model: &Model<i8, i32>,
-    ck: &PCS::CommitterKey,
-    vk: &PCS::VerifierKey,
-    node_coms: &Vec<NodeCommitment<Fr, S, PCS>>,
-    node_com_states: &Vec<NodeCommitmentState<Fr, S, PCS>>,
+    pk: &ProvingKey<Fr, S, PCS, PCS>,
+    vk: &VerifyingKey<Fr, S, PCS, PCS>,
     raw_input: &QArray<f32>,
     sponge: &mut S,
     resize_factor: usize,
 ) where
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<Fr, Poly<Fr>, S>,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<Fr, S> + 'static,
 {
     let mut group = c.benchmark_group("verifiaml");
     group.sample_size(SAMPLE_SIZE);
@@ -222,16 +206,14 @@ fn bench_verifiaml_verification<PCS, S>(
             b.iter_batched(
                 || {
                     model.prove_inference(
-                        ck,
+                        pk,
                         Some(&mut rng),
                         &mut sponge.clone(),
-                        node_coms,
-                        node_com_states,
                         quantise_input(&raw_input),
                     )
                 },
                 |proof| {
-                    model.verify_inference(vk, &mut sponge.clone(), node_coms, proof);
+                    model.verify_inference(vk, &mut sponge.clone(), proof);
                 },
                 criterion::BatchSize::SmallInput,
             )
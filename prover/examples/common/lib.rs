@@ -1,14 +1,28 @@
-use hcs_common::{quantise_f32_u8_nne, InferenceProof, Model, Poly, QArray};
+use hcs_common::{
+    quantise_f32_u8_nne, InferenceProof, LayeredInferenceProof, Model, Node, ProofConfig, QArray,
+    SecurePCS, UniversalSetup,
+};
 use hcs_prover::ProveModel;
 
-use hcs_verifier::VerifyModel;
+use hcs_verifier::{VerifyModel, VerifyingKey};
 
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::PolynomialCommitment;
 use ark_std::test_rng;
 
-pub fn prove_inference<F, S, PCS>(
+// Configuration for the highest security level met by both commitment
+// schemes, for which all keys are set up
+fn proof_config<F, S, PCS, WPCS>() -> ProofConfig
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+    WPCS: SecurePCS<F, S>,
+{
+    ProofConfig::new(PCS::security_bits().min(WPCS::security_bits()))
+}
+
+pub fn prove_inference<F, S, PCS, WPCS>(
     input_path: &str,
     expected_output_path: &str,
     model: &Model<i8, i32>,
@@ -17,11 +31,12 @@ pub fn prove_inference<F, S, PCS>(
     output_shape: Vec<usize>,
 ) where
     F: PrimeField + Absorb,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
 {
-    let input: QArray<f32> = QArray::read(input_path);
-    let expected_output: QArray<u8> = QArray::read(expected_output_path);
+    let input: QArray<f32> = QArray::read(input_path).unwrap();
+    let expected_output: QArray<u8> = QArray::read(expected_output_path).unwrap();
 
     let quantised_input: QArray<u8> = QArray::new(
         quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
@@ -32,20 +47,18 @@ pub fn prove_inference<F, S, PCS>(
 
     let mut sponge = sponge;
 
+    let config = proof_config::<F, S, PCS, WPCS>();
+
     let mut rng = test_rng();
-    let (ck, _) = model.setup_keys::<F, S, PCS, _>(&mut rng).unwrap();
-
-    let (node_coms, node_com_states): (Vec<_>, Vec<_>) =
-        model.commit(&ck, None).into_iter().unzip();
-
-    let inference_proof: InferenceProof<F, S, PCS, i8, i32> = model.prove_inference(
-        &ck,
-        Some(&mut rng),
-        &mut sponge,
-        &node_coms,
-        &node_com_states,
-        input_i8,
-    );
+    let (ck, _) = model.setup_keys::<F, S, PCS, _>(&config, &mut rng).unwrap();
+    let (wck, _) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference(&pk, Some(&mut rng), &mut sponge, input_i8);
 
     let output_qtypearray = inference_proof.outputs[0].clone();
 
@@ -58,7 +71,7 @@ pub fn prove_inference<F, S, PCS>(
     println!("Inference proof test successful");
 }
 
-pub fn verify_inference<F, S, PCS>(
+pub fn verify_inference<F, S, PCS, WPCS>(
     input_path: &str,
     expected_output_path: &str,
     model: &Model<i8, i32>,
@@ -67,11 +80,12 @@ pub fn verify_inference<F, S, PCS>(
     output_shape: Vec<usize>,
 ) where
     F: PrimeField + Absorb,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
 {
-    let input: QArray<f32> = QArray::read(input_path);
-    let expected_output: QArray<u8> = QArray::read(expected_output_path);
+    let input: QArray<f32> = QArray::read(input_path).unwrap();
+    let expected_output: QArray<u8> = QArray::read(expected_output_path).unwrap();
 
     let quantised_input: QArray<u8> = QArray::new(
         quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
@@ -80,29 +94,439 @@ pub fn verify_inference<F, S, PCS>(
 
     let input_i8 = (quantised_input.cast::<i32>() - 128).cast::<i8>();
 
+    // Cloning the initial state of the sponge to start proof and verification
+    // with the same fresh sponge
+    let mut proving_sponge = sponge.clone();
+    let mut verification_sponge = sponge.clone();
+
+    let config = proof_config::<F, S, PCS, WPCS>();
+
+    let mut rng = test_rng();
+    let (ck, vk) = model.setup_keys::<F, S, PCS, _>(&config, &mut rng).unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference(&pk, Some(&mut rng), &mut proving_sponge, input_i8.clone());
+
+    let output_qtypearray = inference_proof.outputs[0].clone();
+
+    assert!(model.verify_inference(&vk, &mut verification_sponge, inference_proof));
+
+    // Proofs are bound to the configuration they were produced under, and
+    // therefore rejected by a verifier expecting a different one
+    let weaker_vk = VerifyingKey::new(
+        ProofConfig::new(config.security_bits() - 1),
+        vk.vk().clone(),
+        vk.wvk().clone(),
+        pk.node_coms.clone(),
+    )
+    .unwrap();
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference(&pk, Some(&mut rng), &mut sponge.clone(), input_i8);
+
+    assert!(!model.verify_inference(&weaker_vk, &mut sponge.clone(), inference_proof));
+
+    let output_i8 = output_qtypearray.unwrap_small();
+
+    let output_u8 = (output_i8.cast::<i32>() + 128).cast::<u8>();
+
+    assert_eq!(output_u8.compact_resize(output_shape, 0), expected_output);
+
+    println!("Inference verification test successful");
+}
+
+pub fn verify_inference_batch<F, S, PCS, WPCS>(
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    // No value computed by the model can overflow i32 or wrap around in F
+    model.check_bounds::<F>().unwrap();
+
+    prove_and_verify_inference_batch::<F, S, PCS, WPCS>(
+        inputs_path,
+        expected_outputs_path,
+        model,
+        qinfo,
+        sponge,
+        output_shape,
+    );
+
+    println!("Batch inference verification test successful");
+}
+
+// Same as verify_inference_batch, but meant to be run with each of the
+// available commitment schemes (or combinations thereof for node values and
+// node parameters), which are named in the output
+pub fn verify_inference_batch_with_pcs<F, S, PCS, WPCS>(
+    pcs_name: &str,
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    // No value computed by the model can overflow i32 or wrap around in F
+    model.check_bounds::<F>().unwrap();
+
+    prove_and_verify_inference_batch::<F, S, PCS, WPCS>(
+        inputs_path,
+        expected_outputs_path,
+        model,
+        qinfo,
+        sponge,
+        output_shape,
+    );
+
+    println!("Batch inference verification test successful ({pcs_name})");
+}
+
+fn prove_and_verify_inference_batch<F, S, PCS, WPCS>(
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
+
+    let inputs_i8: Vec<QArray<i8>> = raw_inputs
+        .iter()
+        .map(|input| {
+            let quantised_input: QArray<u8> = QArray::new(
+                quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
+                input.shape().clone(),
+            );
+
+            (quantised_input.cast::<i32>() - 128).cast::<i8>()
+        })
+        .collect();
+
+    // Cloning the initial state of the sponge to start proof and verification
+    // with the same fresh sponge
+    let mut proving_sponge = sponge.clone();
+    let mut verification_sponge = sponge;
+
+    let config = proof_config::<F, S, PCS, WPCS>();
+
+    let mut rng = test_rng();
+    let (ck, vk) = model
+        .setup_keys_for_batch::<F, S, PCS, _>(&config, inputs_i8.len(), &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference_batch(&pk, Some(&mut rng), &mut proving_sponge, inputs_i8);
+
+    let outputs: Vec<QArray<u8>> = inference_proof
+        .outputs
+        .iter()
+        .map(|output| {
+            (output.clone().unwrap_small().cast::<i32>() + 128)
+                .cast::<u8>()
+                .compact_resize(output_shape.clone(), 0)
+        })
+        .collect();
+
+    assert!(model.verify_inference(&vk, &mut verification_sponge, inference_proof));
+
+    assert_eq!(outputs, expected_outputs);
+}
+
+pub fn verify_packed_inference_batch<F, S, PCS, WPCS>(
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
+
+    let inputs_i8: Vec<QArray<i8>> = raw_inputs
+        .iter()
+        .map(|input| {
+            let quantised_input: QArray<u8> = QArray::new(
+                quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
+                input.shape().clone(),
+            );
+
+            (quantised_input.cast::<i32>() - 128).cast::<i8>()
+        })
+        .collect();
+
+    // Cloning the initial state of the sponge to start proof and verification
+    // with the same fresh sponge
+    let mut proving_sponge = sponge.clone();
+    let mut verification_sponge = sponge;
+
+    let config = proof_config::<F, S, PCS, WPCS>();
+
+    let mut rng = test_rng();
+    let (ck, vk) = model
+        .setup_keys_for_batch::<F, S, PCS, _>(&config, inputs_i8.len(), &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    // All node values are committed to at once
+    let mut pk = model.proving_key(config, ck, wck, None);
+    pk.pack_node_values = true;
+
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference_batch(&pk, Some(&mut rng), &mut proving_sponge, inputs_i8);
+
+    assert_eq!(inference_proof.node_value_commitments.len(), 1);
+
+    let outputs: Vec<QArray<u8>> = inference_proof
+        .outputs
+        .iter()
+        .map(|output| {
+            (output.clone().unwrap_small().cast::<i32>() + 128)
+                .cast::<u8>()
+                .compact_resize(output_shape.clone(), 0)
+        })
+        .collect();
+
+    assert!(model.verify_inference(&vk, &mut verification_sponge, inference_proof));
+
+    assert_eq!(outputs, expected_outputs);
+
+    println!("Packed inference verification test successful");
+}
+
+pub fn verify_many_inferences<F, S, PCS, WPCS>(
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
+
+    let config = proof_config::<F, S, PCS, WPCS>();
+
+    // Keys are trimmed from universal setups with room to spare, as if these
+    // were shared with larger models
+    let mut rng = test_rng();
+    let setup_num_vars = model.value_key_num_vars::<F, S, PCS>(1) + 2;
+    let setup = UniversalSetup::<F, S, PCS>::new(&config, setup_num_vars, &mut rng).unwrap();
+    let weight_setup =
+        UniversalSetup::<F, S, WPCS>::new(&config, setup_num_vars, &mut rng).unwrap();
+
+    assert_eq!(model.check_setup(&setup, 1), Ok(()));
+
+    let keys = model.keys_from_setup(&setup, 1).unwrap();
+    let weight_keys = model.weight_keys_from_setup(&weight_setup).unwrap();
+    assert_eq!(keys.setup_id, setup.id());
+
+    let pk = model.proving_key(config, keys.ck, weight_keys.ck, None);
+    let vk = VerifyingKey::new(config, keys.vk, weight_keys.vk, pk.node_coms.clone()).unwrap();
+
+    // Each proof is produced independently, starting from a fresh sponge
+    let inference_proofs: Vec<InferenceProof<F, S, PCS, WPCS, i8, i32>> = raw_inputs
+        .iter()
+        .map(|input| {
+            let quantised_input: QArray<u8> = QArray::new(
+                quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
+                input.shape().clone(),
+            );
+
+            let input_i8 = (quantised_input.cast::<i32>() - 128).cast::<i8>();
+
+            model.prove_inference(&pk, Some(&mut rng), &mut sponge.clone(), input_i8)
+        })
+        .collect();
+
+    let outputs: Vec<QArray<u8>> = inference_proofs
+        .iter()
+        .map(|proof| {
+            (proof.outputs[0].clone().unwrap_small().cast::<i32>() + 128)
+                .cast::<u8>()
+                .compact_resize(output_shape.clone(), 0)
+        })
+        .collect();
+
+    assert_eq!(model.verify_many(&vk, &sponge, inference_proofs), Ok(()));
+
+    assert_eq!(outputs, expected_outputs);
+
+    println!("Aggregated inference verification test successful");
+}
+
+pub fn verify_layered_inference_batch<F, S, PCS, WPCS>(
+    inputs_path: &str,
+    expected_outputs_path: &str,
+    model: &Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    let raw_inputs: Vec<QArray<f32>> = QArray::read_list(inputs_path).unwrap();
+    let expected_outputs: Vec<QArray<u8>> = QArray::read_list(expected_outputs_path).unwrap();
+
+    let inputs_i8: Vec<QArray<i8>> = raw_inputs
+        .iter()
+        .map(|input| {
+            let quantised_input: QArray<u8> = QArray::new(
+                quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
+                input.shape().clone(),
+            );
+
+            (quantised_input.cast::<i32>() - 128).cast::<i8>()
+        })
+        .collect();
+
     // Cloning the initial state of the sponge to start proof and verification
     // with the same fresh sponge
     let mut proving_sponge = sponge.clone();
     let mut verification_sponge = sponge;
 
+    let config = proof_config::<F, S, PCS, WPCS>();
+
     let mut rng = test_rng();
-    let (ck, vk) = model.setup_keys::<F, S, PCS, _>(&mut rng).unwrap();
-
-    let (node_coms, node_com_states): (Vec<_>, Vec<_>) =
-        model.commit(&ck, None).into_iter().unzip();
-
-    let inference_proof: InferenceProof<F, S, PCS, i8, i32> = model.prove_inference(
-        &ck,
-        Some(&mut rng),
-        &mut proving_sponge,
-        &node_coms,
-        &node_com_states,
-        input_i8,
+    let (ck, vk) = model
+        .setup_keys_for_batch::<F, S, PCS, _>(&config, inputs_i8.len(), &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    // The node values at the layer boundaries are committed to
+    let inference_proof: LayeredInferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference_layered(&pk, Some(&mut rng), &mut proving_sponge, inputs_i8);
+
+    assert_eq!(
+        inference_proof.boundary_commitments.len(),
+        model.layer_boundaries().len()
     );
 
+    let outputs: Vec<QArray<u8>> = inference_proof
+        .outputs
+        .iter()
+        .map(|output| {
+            (output.clone().unwrap_small().cast::<i32>() + 128)
+                .cast::<u8>()
+                .compact_resize(output_shape.clone(), 0)
+        })
+        .collect();
+
+    assert!(model.verify_inference_layered(&vk, &mut verification_sponge, inference_proof));
+
+    assert_eq!(outputs, expected_outputs);
+
+    println!("Layered inference verification test successful");
+}
+
+pub fn verify_sparse_inference<F, S, PCS, WPCS>(
+    input_path: &str,
+    expected_output_path: &str,
+    mut model: Model<i8, i32>,
+    qinfo: (f32, u8),
+    sponge: S,
+    output_shape: Vec<usize>,
+) where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
+{
+    // Replacing the dense BMM nodes by sparse ones without pruning any
+    // weights, so that the model computes the same function
+    for node in model.nodes.iter_mut() {
+        if let Node::BMM(bmm) = node {
+            *node = Node::SparseBMM(bmm.to_sparse(0));
+        }
+    }
+
+    let input: QArray<f32> = QArray::read(input_path).unwrap();
+    let expected_output: QArray<u8> = QArray::read(expected_output_path).unwrap();
+
+    let quantised_input: QArray<u8> = QArray::new(
+        quantise_f32_u8_nne(input.values(), qinfo.0, qinfo.1),
+        input.shape().clone(),
+    );
+
+    let input_i8 = (quantised_input.cast::<i32>() - 128).cast::<i8>();
+
+    // Cloning the initial state of the sponge to start proof and verification
+    // with the same fresh sponge
+    let mut proving_sponge = sponge.clone();
+    let mut verification_sponge = sponge.clone();
+
+    let config = proof_config::<F, S, PCS, WPCS>();
+
+    let mut rng = test_rng();
+    let (ck, vk) = model.setup_keys::<F, S, PCS, _>(&config, &mut rng).unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let inference_proof: InferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference(&pk, Some(&mut rng), &mut proving_sponge, input_i8.clone());
+
     let output_qtypearray = inference_proof.outputs[0].clone();
 
-    assert!(model.verify_inference(&vk, &mut verification_sponge, &node_coms, inference_proof));
+    assert!(model.verify_inference(&vk, &mut verification_sponge, inference_proof));
 
     let output_i8 = output_qtypearray.unwrap_small();
 
@@ -110,5 +534,11 @@ pub fn verify_inference<F, S, PCS>(
 
     assert_eq!(output_u8.compact_resize(output_shape, 0), expected_output);
 
-    println!("Inference verification test successful");
+    // Sparse nodes also reduce claims in layered proofs
+    let layered_proof: LayeredInferenceProof<F, S, PCS, WPCS, i8, i32> =
+        model.prove_inference_layered(&pk, Some(&mut rng), &mut sponge.clone(), vec![input_i8]);
+
+    assert!(model.verify_inference_layered(&vk, &mut sponge.clone(), layered_proof));
+
+    println!("Sparse inference verification test successful");
 }
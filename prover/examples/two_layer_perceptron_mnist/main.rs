@@ -1,10 +1,10 @@
 use hcs_common::{
-    test_sponge,
+    poseidon_sponge,
     two_layer_perceptron_mnist::{build_two_layer_perceptron_mnist, parameters::*, OUTPUT_DIM},
-    Ligero,
+    Brakedown, Hyrax, Ligero, MultilinearKZG,
 };
 
-use ark_bn254::Fr;
+use ark_bn254::{Bn254, Fr, G1Affine};
 use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
 
 #[path = "../common/lib.rs"]
@@ -33,11 +33,11 @@ fn main() {
 
     // We need to construct the sponge outside the common/lib functions to keep
     // the latter generic on the sponge type
-    let sponge: PoseidonSponge<Fr> = test_sponge();
+    let sponge: PoseidonSponge<Fr> = poseidon_sponge();
 
     let output_shape = vec![OUTPUT_DIM];
 
-    prove_inference::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>(
+    prove_inference::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
         &format!(PATH!(), "data/input_test_150.json"),
         &format!(PATH!(), "data/output_test_150.json"),
         &two_layer_perceptron,
@@ -46,11 +46,101 @@ fn main() {
         output_shape.clone(),
     );
 
-    verify_inference::<Fr, PoseidonSponge<Fr>, Ligero<Fr>>(
+    verify_inference::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
         &format!(PATH!(), "data/input_test_150.json"),
         &format!(PATH!(), "data/output_test_150.json"),
         &two_layer_perceptron,
         qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_inference_batch::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_many_inferences::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_packed_inference_batch::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_layered_inference_batch::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, Ligero<Fr>>(
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_inference_batch_with_pcs::<Fr, PoseidonSponge<Fr>, Brakedown<Fr>, Brakedown<Fr>>(
+        "Brakedown",
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_inference_batch_with_pcs::<Fr, PoseidonSponge<Fr>, Hyrax<G1Affine>, Hyrax<G1Affine>>(
+        "Hyrax",
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_inference_batch_with_pcs::<
+        Fr,
+        PoseidonSponge<Fr>,
+        MultilinearKZG<Bn254>,
+        MultilinearKZG<Bn254>,
+    >(
+        "multilinear KZG",
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_inference_batch_with_pcs::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, MultilinearKZG<Bn254>>(
+        "Ligero for node values, multilinear KZG for node parameters",
+        &format!(PATH!(), "data/10_test_inputs.json"),
+        &format!(PATH!(), "data/10_test_outputs.json"),
+        &two_layer_perceptron,
+        qinfo,
+        sponge.clone(),
+        output_shape.clone(),
+    );
+
+    verify_sparse_inference::<Fr, PoseidonSponge<Fr>, Ligero<Fr>, MultilinearKZG<Bn254>>(
+        &format!(PATH!(), "data/input_test_150.json"),
+        &format!(PATH!(), "data/output_test_150.json"),
+        two_layer_perceptron,
+        qinfo,
         sponge,
         output_shape,
     );
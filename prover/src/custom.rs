@@ -0,0 +1,105 @@
+// Registry of the proving operations of custom nodes (cf. `CustomNodeOps`).
+// A custom node is only known to the library as a trait object, so its type
+// is recovered at proving time by looking up a cast registered for it (and
+// for the field, sponge, PCSs and value types used) by the crate defining it.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{CustomNode, CustomNodeOps, InnerType, Poly};
+
+use crate::NodeOpsProve;
+
+// Recovers the proving operations of a custom node of the type the cast was
+// registered for, returning None on nodes of any other type
+type ProverCast<F, S, PCS, WPCS, ST, LT> =
+    fn(&dyn Any) -> Option<&dyn NodeOpsProve<F, S, PCS, WPCS, ST, LT>>;
+
+// Registered casts, indexed by the node type and the type of the cast itself
+// (which determines the remaining type parameters)
+type Registry = HashMap<(TypeId, TypeId), Box<dyn Any + Send + Sync>>;
+
+static CUSTOM_PROVERS: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+/// Registers the proving operations of custom nodes of type `N` for the
+/// field `F`, sponge `S` and PCSs `PCS` and `WPCS` of node values and
+/// parameters respectively. This must be called before computing a proving
+/// key of, or proving inference of, any model containing such nodes.
+pub fn register_custom_prover<F, S, PCS, WPCS, ST, LT, N>()
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType,
+    LT: InnerType,
+    N: CustomNodeOps<ST, LT> + NodeOpsProve<F, S, PCS, WPCS, ST, LT>,
+{
+    let key = (
+        TypeId::of::<N>(),
+        TypeId::of::<ProverCast<F, S, PCS, WPCS, ST, LT>>(),
+    );
+    let cast: ProverCast<F, S, PCS, WPCS, ST, LT> = cast_node::<F, S, PCS, WPCS, ST, LT, N>;
+
+    CUSTOM_PROVERS
+        .get_or_init(Default::default)
+        .write()
+        .unwrap()
+        .insert(key, Box::new(cast));
+}
+
+/// Returns the proving operations registered for the type of `node`
+pub(crate) fn custom_prover<F, S, PCS, WPCS, ST, LT>(
+    node: &CustomNode<ST, LT>,
+) -> &dyn NodeOpsProve<F, S, PCS, WPCS, ST, LT>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType,
+    LT: InnerType,
+{
+    let key = (
+        node.as_any().type_id(),
+        TypeId::of::<ProverCast<F, S, PCS, WPCS, ST, LT>>(),
+    );
+
+    let cast = CUSTOM_PROVERS.get().and_then(|registry| {
+        registry
+            .read()
+            .unwrap()
+            .get(&key)
+            .and_then(|cast| cast.downcast_ref::<ProverCast<F, S, PCS, WPCS, ST, LT>>())
+            .copied()
+    });
+
+    match cast.and_then(|cast| cast(node.as_any())) {
+        Some(prover) => prover,
+        None => panic!(
+            "No prover registered for custom node of type {}",
+            node.type_name()
+        ),
+    }
+}
+
+fn cast_node<F, S, PCS, WPCS, ST, LT, N>(
+    node: &dyn Any,
+) -> Option<&dyn NodeOpsProve<F, S, PCS, WPCS, ST, LT>>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType,
+    LT: InnerType,
+    N: NodeOpsProve<F, S, PCS, WPCS, ST, LT> + 'static,
+{
+    node.downcast_ref::<N>()
+        .map(|node| node as &dyn NodeOpsProve<F, S, PCS, WPCS, ST, LT>)
+}
@@ -0,0 +1,44 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{NodeCommitment, NodeCommitmentState, NodeMLEs, Poly, ProofConfig};
+
+/// Everything the prover needs about a model beyond its plain description,
+/// computed once (cf. `ProveModel::proving_key`) and reused across inference
+/// proofs: the proof configuration, the committer keys of the PCSs of node
+/// values and node parameters and, for each node, the MLEs of its parameters together with the
+/// commitments to them and their states.
+pub struct ProvingKey<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// Configuration the keys were set up for, which is bound to every proof
+    pub config: ProofConfig,
+
+    /// Committer key of the PCS used for node values and any other
+    /// polynomials committed to in each proof
+    pub ck: PCS::CommitterKey,
+
+    /// Committer key of the PCS used for node parameters
+    pub wck: WPCS::CommitterKey,
+
+    /// MLEs of the parameters of each node
+    pub node_mles: Vec<NodeMLEs<F>>,
+
+    /// Commitments to the parameters of each node, which the verifier also
+    /// needs
+    pub node_coms: Vec<NodeCommitment<F, S, WPCS>>,
+
+    /// Commitment states associated to `node_coms`
+    pub node_com_states: Vec<NodeCommitmentState<F, S, WPCS>>,
+
+    /// Whether to pack the values of all nodes into a single MLE when proving
+    /// inference, thereby committing to them at once (cf.
+    /// `NodeValueCommitment`). The committer key must then support the
+    /// selector variables, as those of `Model::setup_keys_for_batch` do.
+    pub pack_node_values: bool,
+}
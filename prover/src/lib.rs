@@ -1,91 +1,159 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_poly_commit::PolynomialCommitment;
 use ark_std::rand::RngCore;
 
 use hcs_common::{
-    InnerType, LabeledPoly, Node, NodeCommitment, NodeCommitmentState, NodeProof, Poly,
+    FieldEncoding, InnerType, Node, NodeCommitment, NodeCommitmentState, NodeMLEs, NodeProof,
+    NodeReductionProof, Poly,
 };
 
+use custom::custom_prover;
+
+mod custom;
+mod key;
 mod model;
 mod nodes;
 #[macro_use]
 mod util;
+mod value;
 
+pub use custom::register_custom_prover;
+pub use key::ProvingKey;
 pub use model::ProveModel;
+pub use value::NodeValue;
 
-/// SNARK-specific operations that each node must implement.
-pub trait NodeOpsProve<F, S, PCS, I, O>
+/// SNARK-specific operations that each node must implement. Node values are
+/// committed to with `PCS` and node parameters with `WPCS`, whose committer
+/// keys are `ck` and `wck` respectively.
+pub trait NodeOpsProve<F, S, PCS, WPCS, I, O>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
-    /// Produce a node output proof
+    /// Produce a node output proof. The input and output values are opened
+    /// through `NodeValue::open`, regardless of how they were committed to.
     fn prove(
         &self,
         ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
         s: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        node_com_state: &NodeCommitmentState<F, S, PCS>,
-        input: &LabeledPoly<F>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        input_com_state: &PCS::CommitmentState,
-        output: &LabeledPoly<F>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        output_com_state: &PCS::CommitmentState,
-    ) -> NodeProof<F, S, PCS>;
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &NodeValue<F, S, PCS>,
+        output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS>;
 
-    /// Commit to the node parameters
-    fn commit(
+    /// Reduce the claim that the output MLE takes the value `output_value` at
+    /// `output_point` (whose last variables index the inference in the batch,
+    /// as in `prove`) to a claim about the input MLE, returning the proof and
+    /// the point of the new claim, whose value is part of the proof. This
+    /// must only be called on nodes with a claim reduction (cf.
+    /// `Node::has_claim_reduction`). `ReLUNode` and `RequantiseBMMNode` have
+    /// none, as they are not linear in their input: their output values are
+    /// committed to instead (cf. `Model::layer_boundaries`).
+    fn prove_reduction(
         &self,
         ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
+        s: &mut S,
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &Poly<F>,
+        output_point: &[F],
+        output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>);
+
+    /// Compute the MLEs of the node parameters
+    fn mles(&self) -> NodeMLEs<F>;
+
+    /// Commit to the node parameters, given their MLEs as computed by `mles`
+    fn commit(
+        &self,
+        wck: &WPCS::CommitterKey,
+        node_mles: &NodeMLEs<F>,
         rng: Option<&mut dyn RngCore>,
-    ) -> (NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>);
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>);
 }
 
-impl<F, S, PCS, I, O> NodeOpsProve<F, S, PCS, I, O> for Node<I, O>
+// Custom nodes are dispatched to the operations registered for their type
+// (cf. `register_custom_prover`), which requires the type parameters to be
+// 'static
+impl<F, S, PCS, WPCS, I, O> NodeOpsProve<F, S, PCS, WPCS, I, O> for Node<I, O>
 where
-    F: PrimeField + Absorb + From<I> + From<O> + From<O>,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    F: PrimeField + Absorb + FieldEncoding<I> + FieldEncoding<O>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
     I: InnerType + TryFrom<O>,
     O: InnerType + From<I>,
 {
     fn prove(
         &self,
         ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
         s: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        node_com_state: &NodeCommitmentState<F, S, PCS>,
-        input: &LabeledPoly<F>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        input_com_state: &PCS::CommitmentState,
-        output: &LabeledPoly<F>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        output_com_state: &PCS::CommitmentState,
-    ) -> NodeProof<F, S, PCS> {
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &NodeValue<F, S, PCS>,
+        output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS> {
         node_operation!(
             self,
             prove,
             ck,
+            wck,
             s,
+            node_mles,
             node_com,
             node_com_state,
             input,
-            input_com,
-            input_com_state,
-            output,
-            output_com,
-            output_com_state
+            output
         )
     }
 
-    fn commit(
+    fn prove_reduction(
         &self,
         ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
+        s: &mut S,
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &Poly<F>,
+        output_point: &[F],
+        output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>) {
+        node_operation!(
+            self,
+            prove_reduction,
+            ck,
+            wck,
+            s,
+            node_mles,
+            node_com,
+            node_com_state,
+            input,
+            output_point,
+            output_value
+        )
+    }
+
+    fn mles(&self) -> NodeMLEs<F> {
+        node_operation!(self, mles)
+    }
+
+    fn commit(
+        &self,
+        wck: &WPCS::CommitterKey,
+        node_mles: &NodeMLEs<F>,
         rng: Option<&mut dyn RngCore>,
-    ) -> (NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>) {
-        node_operation!(self, commit, ck, rng)
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>) {
+        node_operation!(self, commit, wck, node_mles, rng)
     }
 }
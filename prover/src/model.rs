@@ -1,43 +1,91 @@
-use ark_std::{log2, rand::RngCore};
+use ark_std::{cfg_into_iter, log2, rand::RngCore};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly::MultilinearExtension;
+use ark_poly::{MultilinearExtension, Polynomial};
 use ark_poly_commit::{LabeledPolynomial, PolynomialCommitment};
-use hcs_common::{InferenceProof, InnerType, Model};
-use hcs_common::{NodeCommitment, NodeCommitmentState, Poly, QArray, QTypeArray};
+use hcs_common::{
+    FieldEncoding, InferenceProof, InnerType, LayeredInferenceProof, Model, NodeValueCommitment,
+};
+use hcs_common::{LabeledPoly, NodeMLEs, Poly, ProofConfig, QArray, QTypeArray};
+
+use crate::{NodeOpsProve, NodeValue, ProvingKey};
+
+#[cfg(test)]
+mod tests;
 
-use crate::NodeOpsProve;
-pub trait ProveModel<F, S, PCS, ST, LT>
+/// Inference proving. Node values are committed to with `PCS` and node
+/// parameters with `WPCS` (cf. `ProvingKey`), which may coincide.
+pub trait ProveModel<F, S, PCS, WPCS, ST, LT>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
     fn padded_evaluate(&self, input: QArray<ST>) -> QArray<ST>;
 
     fn prove_inference(
         &self,
-        ck: &PCS::CommitterKey,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
         rng: Option<&mut dyn RngCore>,
         sponge: &mut S,
-        node_coms: &Vec<NodeCommitment<F, S, PCS>>,
-        node_com_states: &Vec<NodeCommitmentState<F, S, PCS>>,
         input: QArray<ST>,
-    ) -> InferenceProof<F, S, PCS, ST, LT>;
-
-    fn commit(
+    ) -> InferenceProof<F, S, PCS, WPCS, ST, LT>;
+
+    /// Proves several inferences of the model with a single proof. The values
+    /// of each node across the batch are committed to as one MLE, whose last
+    /// log2(inputs.len()) variables (rounded up) index the inference, and
+    /// each node is proved once for all of them. The committer key must
+    /// support the additional variables (cf. `Model::setup_keys_for_batch`).
+    /// If `pk.pack_node_values` is set, the values of all nodes are packed
+    /// into a single MLE and only one commitment to them is sent.
+    fn prove_inference_batch(
         &self,
-        ck: &PCS::CommitterKey,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
+        rng: Option<&mut dyn RngCore>,
+        sponge: &mut S,
+        inputs: Vec<QArray<ST>>,
+    ) -> InferenceProof<F, S, PCS, WPCS, ST, LT>;
+
+    /// Proves a batch of inferences (possibly of size one) in layered
+    /// (GKR-style) fashion: a claim about the model output is reduced node by
+    /// node to one about the model input. The node values at the boundaries
+    /// of nodes without a claim reduction are still committed to (cf.
+    /// `LayeredInferenceProof`). As in `prove_inference_batch`, the
+    /// values of each node across the batch are treated as a single MLE;
+    /// `pk.pack_node_values` is ignored.
+    fn prove_inference_layered(
+        &self,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
+        rng: Option<&mut dyn RngCore>,
+        sponge: &mut S,
+        inputs: Vec<QArray<ST>>,
+    ) -> LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>;
+
+    /// Computes the MLEs of the parameters of all nodes and commits to them
+    /// with `wck` (cf. `Model::setup_weight_keys`), whereas `ck` is kept for
+    /// the commitments made in each proof. Both keys must have been set up
+    /// for `config`, which is bound to every proof. This only needs to be
+    /// done once per model and committer keys.
+    fn proving_key(
+        &self,
+        config: ProofConfig,
+        ck: PCS::CommitterKey,
+        wck: WPCS::CommitterKey,
         _rng: Option<&mut dyn RngCore>,
-    ) -> Vec<(NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>)>;
+    ) -> ProvingKey<F, S, PCS, WPCS>;
 }
 
-impl<F, S, PCS, ST, LT> ProveModel<F, S, PCS, ST, LT> for Model<ST, LT>
+impl<F, S, PCS, WPCS, ST, LT> ProveModel<F, S, PCS, WPCS, ST, LT> for Model<ST, LT>
 where
-    F: PrimeField + Absorb + From<ST> + From<LT>,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
@@ -69,173 +117,445 @@ where
 
     fn prove_inference(
         &self,
-        ck: &PCS::CommitterKey,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
         rng: Option<&mut dyn RngCore>,
         sponge: &mut S,
-        node_coms: &Vec<NodeCommitment<F, S, PCS>>,
-        node_com_states: &Vec<NodeCommitmentState<F, S, PCS>>,
         input: QArray<ST>,
-    ) -> InferenceProof<F, S, PCS, ST, LT> {
-        // TODO Absorb public parameters into s (to be determined what exactly)
+    ) -> InferenceProof<F, S, PCS, WPCS, ST, LT> {
+        self.prove_inference_batch(pk, rng, sponge, vec![input])
+    }
 
-        let output = input.compact_resize(
-            self.input_shape
-                .iter()
-                .map(|x| x.next_power_of_two())
-                .collect(),
-            ST::ZERO,
+    fn prove_inference_batch(
+        &self,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
+        rng: Option<&mut dyn RngCore>,
+        sponge: &mut S,
+        inputs: Vec<QArray<ST>>,
+    ) -> InferenceProof<F, S, PCS, WPCS, ST, LT> {
+        assert!(
+            !inputs.is_empty(),
+            "Cannot prove an empty batch of inferences"
         );
 
-        let output_f: Vec<F> = output.values().iter().map(|x| F::from(*x)).collect();
+        let (node_output_mles, inputs, outputs) = batch_node_values(self, inputs);
 
-        let mut output = QTypeArray::S(output);
+        prove_node_values(self, pk, rng, sponge, node_output_mles, inputs, outputs)
+    }
 
-        // First pass: computing node values
-        // TODO handling F and QSmallType is inelegant; we might want to switch
-        // to F for IO in NodeOps::prove
-        let mut node_outputs = vec![output.clone()];
-        let mut node_output_mles = vec![Poly::from_evaluations_vec(
-            log2(output_f.len()) as usize,
-            output_f,
-        )];
+    fn prove_inference_layered(
+        &self,
+        pk: &ProvingKey<F, S, PCS, WPCS>,
+        rng: Option<&mut dyn RngCore>,
+        sponge: &mut S,
+        inputs: Vec<QArray<ST>>,
+    ) -> LayeredInferenceProof<F, S, PCS, WPCS, ST, LT> {
+        assert!(
+            !inputs.is_empty(),
+            "Cannot prove an empty batch of inferences"
+        );
 
-        for node in &self.nodes {
-            output = node.padded_evaluate(&output);
+        let ck = &pk.ck;
 
-            let output_f: Vec<F> = match &output {
-                QTypeArray::S(o) => o.values().iter().map(|x| F::from(*x)).collect(),
-                QTypeArray::L(o) => o.values().iter().map(|x| F::from(*x)).collect(),
-            };
+        // Cf. prove_inference_batch
+        sponge.absorb(&pk.config);
 
-            node_outputs.push(output.clone());
-            node_output_mles.push(Poly::from_evaluations_vec(
-                log2(output_f.len()) as usize,
-                output_f,
-            ));
-        }
+        let (node_output_mles, inputs, outputs) = batch_node_values(self, inputs);
 
-        let input_node_f = node_output_mles.first().unwrap().to_evaluations();
-        let output_node_f = node_output_mles.last().unwrap().to_evaluations();
-
-        // Committing to node outputs as MLEs (individual per node for now)
-        let labeled_output_mles: Vec<LabeledPolynomial<F, Poly<F>>> = node_output_mles
-            .into_iter()
-            .map(|mle|
-            // TODO change dummy label once we e.g. have given numbers to the
-            // nodes in the model: fc_1, fc_2, relu_1, etc.
-            // TODO maybe we don't need to clone, if `prove` can take a reference
-            LabeledPolynomial::new(
-                "dummy".to_string(),
-                mle,
-                None,
-                None,
-            ))
+        // Committing to the node values at the layer boundaries only
+        let boundaries = self.layer_boundaries();
+
+        let labeled_boundary_mles: Vec<LabeledPoly<F>> = boundaries
+            .iter()
+            .map(|i| {
+                LabeledPolynomial::new(
+                    format!("node_value_{i}"),
+                    node_output_mles[*i].clone(),
+                    None,
+                    None,
+                )
+            })
             .collect();
 
-        let (output_coms, output_com_states) = PCS::commit(ck, &labeled_output_mles, rng).unwrap();
+        let (boundary_coms, boundary_com_states) =
+            PCS::commit(ck, &labeled_boundary_mles, rng).unwrap();
+
+        // Absorb the plain model IO and the commitments into the sponge
+        sponge.absorb(&node_output_mles.first().unwrap().to_evaluations());
+        sponge.absorb(&node_output_mles.last().unwrap().to_evaluations());
+        sponge.absorb(&boundary_coms);
 
-        // Absorb all commitments into the sponge
-        sponge.absorb(&output_coms);
+        // Opens the committed values of the given node at the point of its
+        // only claim. As all other openings, this is proved on a fork of the
+        // transcript.
+        let open_boundary = |i: usize, point: &Vec<F>, sponge: &S| {
+            let j = boundaries.binary_search(&i).unwrap();
 
-        // TODO Prove that all commited NIOs live in the right range (to be
-        // discussed)
+            PCS::open(
+                ck,
+                [&labeled_boundary_mles[j]],
+                [&boundary_coms[j]],
+                point,
+                &mut sponge.clone(),
+                [&boundary_com_states[j]],
+                None,
+            )
+            .unwrap()
+        };
 
         let mut node_proofs = Vec::new();
+        let mut boundary_opening_values = Vec::new();
+        let mut boundary_opening_proofs = Vec::new();
 
-        // Second pass: proving
-        for (((((node, node_com), node_com_state), values), l_v_coms), v_coms_states) in self
+        // Claim (point and value) about the output of the current node, if
+        // any, as passed on by the claim reduction of the following one
+        let mut claim: Option<(Vec<F>, F)> = None;
+
+        for (k, ((node, node_mles), (node_com, node_com_state))) in self
             .nodes
             .iter()
-            .zip(node_coms.iter())
-            .zip(node_com_states.iter())
-            .zip(labeled_output_mles.windows(2))
-            .zip(output_coms.windows(2))
-            .zip(output_com_states.windows(2))
+            .zip(pk.node_mles.iter())
+            .zip(pk.node_coms.iter().zip(pk.node_com_states.iter()))
+            .enumerate()
+            .rev()
         {
-            node_proofs.push(node.prove(
+            if !node.has_claim_reduction() {
+                // A claim about the input of a reducible node which is the
+                // output of an irreducible one is checked by opening the
+                // committed values
+                if let Some((point, value)) = claim.take() {
+                    boundary_opening_values.push(value);
+                    boundary_opening_proofs.push(open_boundary(k + 1, &point, sponge));
+                }
+
+                node_proofs.push(None);
+                continue;
+            }
+
+            // Otherwise, a new claim is started at a random point if there is
+            // none: the model output is evaluated by the verifier itself,
+            // whereas committed values are opened
+            let (output_point, output_value) = claim.take().unwrap_or_else(|| {
+                let output_mle = &node_output_mles[k + 1];
+                let point: Vec<F> = sponge.squeeze_field_elements(output_mle.num_vars);
+                let value = output_mle.evaluate(&point);
+
+                if k + 1 < self.nodes.len() {
+                    sponge.absorb(&value);
+
+                    boundary_opening_values.push(value);
+                    boundary_opening_proofs.push(open_boundary(k + 1, &point, sponge));
+                }
+
+                (point, value)
+            });
+
+            let (node_proof, input_point) = node.prove_reduction(
                 ck,
+                &pk.wck,
                 sponge,
+                node_mles,
                 node_com,
                 node_com_state,
-                &values[0],
-                &l_v_coms[0],
-                &v_coms_states[0],
-                &values[1],
-                &l_v_coms[1],
-                &v_coms_states[1],
-            ));
+                &node_output_mles[k],
+                &output_point,
+                output_value,
+            );
+
+            let input_value = node_output_mles[k].evaluate(&input_point);
+
+            node_proofs.push(Some(node_proof));
+            claim = Some((input_point, input_value));
         }
 
-        // Opening model IO
-        // TODO maybe this can be made more efficient by not committing to the
-        // output nodes and instead working witht their plain values all along,
-        // but that would require messy node-by-node handling
-        let mut node_outputs = node_outputs.into_iter();
-        let input_node = node_outputs.next().unwrap();
-        let output_node = node_outputs.last().unwrap();
-
-        let input_labeled_value = labeled_output_mles.first().unwrap();
-        let input_node_com = output_coms.first().unwrap();
-        let input_node_com_state = output_com_states.first().unwrap();
-
-        let output_labeled_value = labeled_output_mles.last().unwrap();
-        let output_node_com = output_coms.last().unwrap();
-        let output_node_com_state = output_com_states.last().unwrap();
-
-        // Absorb the model IO output and squeeze the challenge point
-        // Absorb the plain output and squeeze the challenge point
-        sponge.absorb(&input_node_f);
-        sponge.absorb(&output_node_f);
-        let input_challenge_point =
-            sponge.squeeze_field_elements(log2(input_node_f.len()) as usize);
-        let output_challenge_point =
-            sponge.squeeze_field_elements(log2(output_node_f.len()) as usize);
-
-        // TODO we have to pass rng, not None, but it has been moved before
-        // fix this once we have decided how to handle the cumbersome
-        // Option<&mut rng...>
-        let input_opening_proof = PCS::open(
-            ck,
-            [input_labeled_value],
-            [input_node_com],
-            &input_challenge_point,
-            sponge,
-            [input_node_com_state],
-            None,
-        )
-        .unwrap();
-
-        // TODO we have to pass rng, not None, but it has been moved before
-        // fix this once we have decided how to handle the cumbersome
-        // Option<&mut rng...>
-        let output_opening_proof = PCS::open(
-            ck,
-            [output_labeled_value],
-            [output_node_com],
-            &output_challenge_point,
-            sponge,
-            [output_node_com_state],
-            None,
-        )
-        .unwrap();
-
-        // TODO prove that inputs match input commitments?
-        InferenceProof {
-            inputs: vec![input_node],
-            outputs: vec![output_node],
-            node_value_commitments: output_coms,
+        // Any remaining claim is about the model input, which the verifier
+        // checks in plain. The proofs were produced from the last node to the
+        // first one, and so were the boundary openings.
+        node_proofs.reverse();
+        boundary_opening_values.reverse();
+        boundary_opening_proofs.reverse();
+
+        LayeredInferenceProof {
+            inputs,
+            outputs,
+            boundary_commitments: boundary_coms,
             node_proofs,
-            input_opening_proofs: vec![input_opening_proof],
-            output_opening_proofs: vec![output_opening_proof],
+            boundary_opening_values,
+            boundary_opening_proofs,
         }
     }
 
-    fn commit(
+    fn proving_key(
         &self,
-        ck: &PCS::CommitterKey,
+        config: ProofConfig,
+        ck: PCS::CommitterKey,
+        wck: WPCS::CommitterKey,
         _rng: Option<&mut dyn RngCore>,
-    ) -> Vec<(NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>)> {
+    ) -> ProvingKey<F, S, PCS, WPCS> {
+        let node_mles: Vec<NodeMLEs<F>> = self
+            .nodes
+            .iter()
+            .map(NodeOpsProve::<F, S, PCS, WPCS, _, _>::mles)
+            .collect();
+
         // TODO blindly passing None, likely need to change to get hiding
-        self.nodes.iter().map(|n| n.commit(ck, None)).collect()
+        let (node_coms, node_com_states) = self
+            .nodes
+            .iter()
+            .zip(node_mles.iter())
+            .map(|(n, mles)| NodeOpsProve::<F, S, PCS, WPCS, _, _>::commit(n, &wck, mles, None))
+            .unzip();
+
+        ProvingKey {
+            config,
+            ck,
+            wck,
+            node_mles,
+            node_coms,
+            node_com_states,
+            pack_node_values: false,
+        }
     }
 }
+
+// Proves a batch of inferences of `model` given the MLEs of the values of all
+// nodes across the batch, as well as the model inputs and outputs of each
+// inference (cf. `batch_node_values`)
+fn prove_node_values<F, S, PCS, WPCS, ST, LT>(
+    model: &Model<ST, LT>,
+    pk: &ProvingKey<F, S, PCS, WPCS>,
+    rng: Option<&mut dyn RngCore>,
+    sponge: &mut S,
+    node_output_mles: Vec<Poly<F>>,
+    inputs: Vec<QTypeArray<ST, LT>>,
+    outputs: Vec<QTypeArray<ST, LT>>,
+) -> InferenceProof<F, S, PCS, WPCS, ST, LT>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    let ck = &pk.ck;
+
+    // Binding the proof to its configuration (cf. ProofConfig)
+    // TODO Absorb the remaining public parameters (to be determined what
+    // exactly)
+    sponge.absorb(&pk.config);
+
+    let input_node_f = node_output_mles.first().unwrap().to_evaluations();
+    let output_node_f = node_output_mles.last().unwrap().to_evaluations();
+
+    // Committing to node values, either as one MLE per node or packed
+    // into a single MLE (cf. NodeValueCommitment)
+    let value_num_vars: Vec<usize> = node_output_mles.iter().map(|mle| mle.num_vars).collect();
+
+    let (node_output_mles, labeled_value_mles): (Vec<Poly<F>>, Vec<LabeledPoly<F>>) =
+        if pk.pack_node_values {
+            let packed_mle = LabeledPolynomial::new(
+                "node_values".to_string(),
+                pack_values(&node_output_mles),
+                None,
+                None,
+            );
+
+            (node_output_mles, vec![packed_mle])
+        } else {
+            let labeled_mles = node_output_mles
+                .into_iter()
+                .enumerate()
+                .map(|(i, mle)| LabeledPolynomial::new(format!("node_value_{i}"), mle, None, None))
+                .collect();
+
+            (Vec::new(), labeled_mles)
+        };
+
+    let (output_coms, output_com_states) = PCS::commit(ck, &labeled_value_mles, rng).unwrap();
+
+    let value_coms = if pk.pack_node_values {
+        NodeValueCommitment::packed(&output_coms[0], &value_num_vars)
+    } else {
+        NodeValueCommitment::separate(&output_coms)
+    };
+
+    // Each node value is opened through the polynomial committed to it
+    let node_values: Vec<NodeValue<F, S, PCS>> = value_coms
+        .iter()
+        .enumerate()
+        .map(|(i, com)| {
+            let (mle, j) = if pk.pack_node_values {
+                (&node_output_mles[i], 0)
+            } else {
+                (labeled_value_mles[i].polynomial(), i)
+            };
+
+            NodeValue {
+                mle,
+                committed: &labeled_value_mles[j],
+                com,
+                com_state: &output_com_states[j],
+            }
+        })
+        .collect();
+
+    // Absorb all commitments into the sponge
+    sponge.absorb(&output_coms);
+
+    // TODO Prove that all commited NIOs live in the right range (to be
+    // discussed)
+
+    let mut node_proofs = Vec::new();
+
+    // Proving each node on the committed values
+    for ((((node, node_mles), node_com), node_com_state), values) in model
+        .nodes
+        .iter()
+        .zip(pk.node_mles.iter())
+        .zip(pk.node_coms.iter())
+        .zip(pk.node_com_states.iter())
+        .zip(node_values.windows(2))
+    {
+        node_proofs.push(node.prove(
+            ck,
+            &pk.wck,
+            sponge,
+            node_mles,
+            node_com,
+            node_com_state,
+            &values[0],
+            &values[1],
+        ));
+    }
+
+    // Opening model IO
+    // TODO maybe this can be made more efficient by not committing to the
+    // output nodes and instead working witht their plain values all along,
+    // but that would require messy node-by-node handling
+    // Absorb the model IO output and squeeze the challenge point
+    // Absorb the plain output and squeeze the challenge point
+    sponge.absorb(&input_node_f);
+    sponge.absorb(&output_node_f);
+    let input_challenge_point = sponge.squeeze_field_elements(log2(input_node_f.len()) as usize);
+    let output_challenge_point = sponge.squeeze_field_elements(log2(output_node_f.len()) as usize);
+
+    // As for the node openings, the IO openings are proved on forks of the
+    // transcript
+    let input_opening_proof = node_values
+        .first()
+        .unwrap()
+        .open(ck, &input_challenge_point, sponge);
+
+    let output_opening_proof =
+        node_values
+            .last()
+            .unwrap()
+            .open(ck, &output_challenge_point, sponge);
+
+    // TODO prove that inputs match input commitments?
+    InferenceProof {
+        inputs,
+        outputs,
+        node_value_commitments: output_coms,
+        node_proofs,
+        input_opening_proofs: vec![input_opening_proof],
+        output_opening_proofs: vec![output_opening_proof],
+    }
+}
+
+// Computes the values of all nodes (the model input first) for each inference
+// in the batch and returns their MLEs across the batch, together with the
+// model inputs and outputs of each inference
+#[allow(clippy::type_complexity)]
+fn batch_node_values<F, ST, LT>(
+    model: &Model<ST, LT>,
+    inputs: Vec<QArray<ST>>,
+) -> (
+    Vec<Poly<F>>,
+    Vec<QTypeArray<ST, LT>>,
+    Vec<QTypeArray<ST, LT>>,
+)
+where
+    F: PrimeField + FieldEncoding<ST> + FieldEncoding<LT>,
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    // Computing node values for each inference in the batch (in parallel
+    // under the `parallel` feature)
+    // TODO handling F and QSmallType is inelegant; we might want to switch
+    // to F for IO in NodeOps::prove
+    let batch_node_outputs: Vec<Vec<QTypeArray<ST, LT>>> = cfg_into_iter!(inputs)
+        .map(|input| {
+            let mut output = QTypeArray::S(
+                input.compact_resize(
+                    model
+                        .input_shape
+                        .iter()
+                        .map(|x| x.next_power_of_two())
+                        .collect(),
+                    ST::ZERO,
+                ),
+            );
+
+            let mut node_outputs = vec![output.clone()];
+
+            for node in &model.nodes {
+                output = node.padded_evaluate(&output);
+                node_outputs.push(output.clone());
+            }
+
+            node_outputs
+        })
+        .collect();
+
+    // The values of each node across the batch are stacked into a single
+    // vector, the number of slots being rounded up to a power of two by
+    // repeating the first inference
+    let batch_size = batch_node_outputs.len().next_power_of_two();
+
+    let node_output_mles: Vec<Poly<F>> = cfg_into_iter!(0..=model.nodes.len())
+        .map(|i| {
+            let output_f: Vec<F> = (0..batch_size)
+                .flat_map(|k| {
+                    let output = &batch_node_outputs.get(k).unwrap_or(&batch_node_outputs[0])[i];
+
+                    match output {
+                        QTypeArray::S(o) => {
+                            o.values().iter().map(|x| F::encode(*x)).collect::<Vec<F>>()
+                        }
+                        QTypeArray::L(o) => o.values().iter().map(|x| F::encode(*x)).collect(),
+                    }
+                })
+                .collect();
+
+            Poly::from_evaluations_vec(log2(output_f.len()) as usize, output_f)
+        })
+        .collect();
+
+    let (inputs, outputs) = batch_node_outputs
+        .into_iter()
+        .map(|mut node_outputs| {
+            let output = node_outputs.pop().unwrap();
+            (node_outputs.swap_remove(0), output)
+        })
+        .unzip();
+
+    (node_output_mles, inputs, outputs)
+}
+
+// Packs the given MLEs into one whose last variables select the MLE (cf.
+// NodeValueCommitment): each one is padded with zeros to the largest number
+// of variables, as is the number of MLEs to a power of two
+fn pack_values<F: PrimeField>(mles: &[Poly<F>]) -> Poly<F> {
+    let slot_num_vars = mles.iter().map(|mle| mle.num_vars).max().unwrap();
+    let num_vars = slot_num_vars + log2(mles.len()) as usize;
+
+    let mut evaluations = vec![F::zero(); 1 << num_vars];
+
+    for (i, mle) in mles.iter().enumerate() {
+        let offset = i << slot_num_vars;
+        evaluations[offset..offset + mle.evaluations.len()].copy_from_slice(&mle.evaluations);
+    }
+
+    Poly::from_evaluations_vec(num_vars, evaluations)
+}
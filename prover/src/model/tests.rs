@@ -0,0 +1,148 @@
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_std::{test_rng, UniformRand};
+
+use hcs_common::{poseidon_sponge, Ligero, OpeningClaim, SecurePCS};
+
+use super::*;
+
+type S = PoseidonSponge<Fr>;
+type PCS = Ligero<Fr>;
+
+// MLEs of node values of different sizes, in 2, 4 and 3 variables, whose
+// number (padded to 4) takes two selector variables
+fn node_value_mles() -> Vec<Poly<Fr>> {
+    let mut rng = test_rng();
+
+    [2, 4, 3]
+        .into_iter()
+        .map(|num_vars| Poly::rand(num_vars, &mut rng))
+        .collect()
+}
+
+#[test]
+fn test_packed_values() {
+    let mles = node_value_mles();
+    let value_num_vars: Vec<usize> = mles.iter().map(|mle| mle.num_vars).collect();
+
+    let packed = pack_values(&mles);
+
+    assert_eq!(
+        packed.num_vars,
+        NodeValueCommitment::<Fr, S, PCS>::packed_num_vars(&value_num_vars)
+    );
+    assert_eq!(packed.num_vars, 6);
+
+    let mut rng = test_rng();
+    let config = ProofConfig::default();
+    let pp =
+        <PCS as SecurePCS<Fr, S>>::setup_for_config(&config, packed.num_vars, &mut rng).unwrap();
+    let (ck, vk) = <PCS as SecurePCS<Fr, S>>::trim_to_num_vars(&pp, packed.num_vars).unwrap();
+
+    let labeled_packed = LabeledPolynomial::new("node_values".to_string(), packed, None, None);
+    let (coms, states) = PCS::commit(&ck, [&labeled_packed], None).unwrap();
+
+    let sponge: S = poseidon_sponge();
+
+    // Opening the packed MLE at the point of each slot yields the evaluation
+    // of the values of that node alone
+    for (com, mle) in NodeValueCommitment::<Fr, S, PCS>::packed(&coms[0], &value_num_vars)
+        .iter()
+        .zip(mles.iter())
+    {
+        let point: Vec<Fr> = (0..mle.num_vars).map(|_| Fr::rand(&mut rng)).collect();
+        let value = mle.evaluate(&point);
+
+        assert_eq!(labeled_packed.evaluate(&com.point(&point)), value);
+
+        let proof = PCS::open(
+            &ck,
+            [&labeled_packed],
+            [&com.com],
+            &com.point(&point),
+            &mut sponge.clone(),
+            [&states[0]],
+            None,
+        )
+        .unwrap();
+
+        assert!(OpeningClaim::node_value(com, &point, value, proof.clone(), &sponge).check(&vk));
+        assert!(
+            !OpeningClaim::node_value(com, &point, value + Fr::from(1u64), proof, &sponge)
+                .check(&vk)
+        );
+    }
+}
+
+// Perceptron on inputs of size 6 whose first BMM node has 3 output units, so
+// that its padded output has one padding entry
+#[cfg(feature = "test-types")]
+fn padded_perceptron() -> Model<i8, i32> {
+    use hcs_common::{ModelBuilder, QInfo};
+
+    ModelBuilder::new(vec![6], QInfo::new(0.004, -128))
+        .bmm(
+            QArray::new((0..18).map(|x| 7 * x - 60).collect(), vec![6, 3]),
+            QArray::new(vec![100, -20, 7], vec![3]),
+            QInfo::new(0.007, 0),
+            QInfo::new(0.06, -100),
+        )
+        .relu()
+        .bmm(
+            QArray::new(vec![5, -1, 9, 0, -7, 3], vec![3, 2]),
+            QArray::new(vec![-3, 12], vec![2]),
+            QInfo::new(0.01, 0),
+            QInfo::new(0.2, 31),
+        )
+        .build()
+        .unwrap()
+}
+
+#[cfg(feature = "test-types")]
+#[test]
+fn test_bmm_output_padding_is_bound() {
+    use ark_ff::One;
+    use hcs_verifier::{VerifyModel, VerifyingKey};
+
+    let model = padded_perceptron();
+    let config = ProofConfig::new(<PCS as SecurePCS<Fr, S>>::security_bits());
+
+    let mut rng = test_rng();
+    let (ck, vk) = model
+        .setup_keys::<Fr, S, PCS, _>(&config, &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<Fr, S, PCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk: ProvingKey<Fr, S, PCS, PCS> = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let input = QArray::new(vec![-128, 127, 0, -5, 9, 64], vec![6]);
+
+    for tamper in [false, true] {
+        let (mut node_output_mles, inputs, outputs) =
+            batch_node_values::<Fr, i8, i32>(&model, vec![input.clone()]);
+
+        // The output of the first BMM node has the padding entry 3, which the
+        // next node receives as it is
+        if tamper {
+            node_output_mles[1].evaluations[3] += Fr::one();
+        }
+
+        let proof = prove_node_values(
+            &model,
+            &pk,
+            None,
+            &mut poseidon_sponge(),
+            node_output_mles,
+            inputs,
+            outputs,
+        );
+
+        assert_eq!(
+            model.verify_inference(&vk, &mut poseidon_sponge(), proof),
+            !tamper
+        );
+    }
+}
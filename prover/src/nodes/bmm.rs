@@ -3,235 +3,513 @@ use std::rc::Rc;
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
 use ark_poly::{MultilinearExtension, Polynomial};
-use ark_poly_commit::{LabeledCommitment, LabeledPolynomial, PolynomialCommitment};
+use ark_poly_commit::{LabeledPolynomial, PolynomialCommitment};
 use ark_std::rand::RngCore;
 use ark_sumcheck::ml_sumcheck::{protocol::ListOfProductsOfPolynomials, MLSumcheck};
 
 use hcs_common::{
-    BMMNode, BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeProof, InnerType, LabeledPoly,
-    NodeCommitment, NodeCommitmentState, NodeOpsPadded, NodeProof, Poly,
+    BMMNode, BMMNodeCommitment, BMMNodeCommitmentState, BMMNodeMLEs, BMMNodeProof,
+    BMMNodeReductionProof, FieldEncoding, InnerType, NodeCommitment, NodeCommitmentState, NodeMLEs,
+    NodeProof, NodeReductionProof, Poly,
 };
 
-use crate::NodeOpsProve;
+use crate::{
+    util::{fix_last_variables, prove_sum},
+    NodeOpsProve, NodeValue,
+};
 
-impl<F, S, PCS, ST, LT> NodeOpsProve<F, S, PCS, ST, LT> for BMMNode<ST, LT>
+impl<F, S, PCS, WPCS, ST, LT> NodeOpsProve<F, S, PCS, WPCS, ST, LT> for BMMNode<ST, LT>
 where
-    F: PrimeField + Absorb + From<ST> + From<LT>,
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
     fn prove(
         &self,
         ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
         sponge: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        node_com_state: &NodeCommitmentState<F, S, PCS>,
-        input: &LabeledPoly<F>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        input_com_state: &PCS::CommitmentState,
-        output: &LabeledPoly<F>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        output_com_state: &PCS::CommitmentState,
-    ) -> NodeProof<F, S, PCS> {
-        let (weight_com, bias_com) = match node_com {
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &NodeValue<F, S, PCS>,
+        output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS> {
+        let (weight_mles, bias_mle) = match node_mles {
+            NodeMLEs::BMM(BMMNodeMLEs {
+                weight_mles,
+                bias_mle,
+            }) => (weight_mles, bias_mle),
+            _ => panic!("BMMNode::prove expected node MLEs of type BMMNodeMLEs"),
+        };
+
+        let (weight_coms, bias_com) = match node_com {
             NodeCommitment::BMM(BMMNodeCommitment {
-                weight_com,
+                weight_coms,
                 bias_com,
-            }) => (weight_com, bias_com),
+            }) => (weight_coms, bias_com),
             _ => panic!("BMMNode::prove expected node commitment of type BMMNodeCommitment"),
         };
 
-        let (weight_com_state, bias_com_state) = match node_com_state {
+        let (weight_com_states, bias_com_state) = match node_com_state {
             NodeCommitmentState::BMM(BMMNodeCommitmentState {
-                weight_com_state,
+                weight_com_states,
                 bias_com_state,
-            }) => (weight_com_state, bias_com_state),
+            }) => (weight_com_states, bias_com_state),
             _ => panic!(
                 "BMMNode::prove expected node commitment state of type BMMNodeCommitmentState"
             ),
         };
 
+        let row_blocks = self.row_blocks();
+        let num_col_blocks = self.col_blocks().len();
+
+        // Any variables of the input and output MLEs beyond those of a single
+        // inference index the inference in the batch
+        let num_batch_vars = input.mle.num_vars - self.padded_dims_log.0;
+
         // We can squeeze directly, since the sponge has already absorbed all the
-        // commitments in Model::prove_inference
+        // commitments in Model::prove_inference. The challenge r binds the
+        // variables of the padded output, and for each column block, r_l are
+        // its low-order variables and gamma_l the coefficient of its claim in
+        // the combined one (cf. BMMNode::col_block_challenges)
         let r: Vec<F> = sponge.squeeze_field_elements(self.padded_dims_log.1);
+        let (rs, gammas) = self.col_block_challenges(&r);
 
-        let i_z_p_f = F::from(self.input_zero_point);
+        // Challenge combining the inferences in the batch (empty if there is
+        // only one)
+        let rho: Vec<F> = sponge.squeeze_field_elements(num_batch_vars);
 
-        // (f - zero-point)^
-        let shifted_input_mle = Poly::from_evaluations_vec(
-            input.num_vars(),
-            input.polynomial().iter().map(|x| *x - i_z_p_f).collect(),
-        );
+        let i_z_p_f = F::encode(self.input_zero_point);
 
-        // TODO consider whether this can be done once and stored
-        let weights_f = self
-            .padded_weights
-            .values()
+        // input^(x, rho) over the padded input hypercube
+        let bound_input = fix_last_variables(input.mle, &rho);
+
+        // The output is opened at r || rho and the bias at r, which binds the
+        // padding of the output as well as its blocks
+        let output_point: Vec<F> = r.iter().chain(rho.iter()).cloned().collect();
+
+        let bias_opening_value = bias_mle.evaluate(&r);
+        let output_opening_value = output.mle.evaluate(&output_point);
+
+        // For each row block j, the factors of the sumcheck polynomial
+        // g_j(x) = (input - zero_point)^(x, j, rho) * sum_l gamma_l * W_{j, l}^(r_l, x)
+        let block_factors: Vec<(Poly<F>, Poly<F>)> = row_blocks
             .iter()
-            .map(|w| F::from(*w))
-            .collect();
+            .enumerate()
+            .map(|(j, (offset, log_size))| {
+                // Since the block is aligned, its entries are contiguous
+                let shifted_input_block = Poly::from_evaluations_vec(
+                    *log_size,
+                    bound_input.evaluations[*offset..*offset + (1 << log_size)]
+                        .iter()
+                        .map(|x| *x - i_z_p_f)
+                        .collect(),
+                );
 
-        // Dual of the MLE of the row-major flattening of the weight matrix
-        let weight_mle = Poly::from_evaluations_vec(self.com_num_vars(), weights_f);
+                let mut combined_weights = vec![F::zero(); 1 << log_size];
 
-        // TODO consider whether this can be done once and stored
-        let bias_f = self
-            .padded_bias
-            .values()
+                for (l, (r_l, gamma)) in rs.iter().zip(gammas.iter()).enumerate() {
+                    let bound_weights = weight_mles[j * num_col_blocks + l].fix_variables(r_l);
+
+                    for (acc, w) in combined_weights.iter_mut().zip(bound_weights.evaluations) {
+                        *acc += *gamma * w;
+                    }
+                }
+
+                (
+                    shifted_input_block,
+                    Poly::from_evaluations_vec(*log_size, combined_weights),
+                )
+            })
+            .collect();
+
+        // The sum of these is output^(r, rho) - bias^(r)
+        let block_sums: Vec<F> = block_factors
             .iter()
-            .map(|w| F::from(*w))
+            .map(|(i, w)| {
+                i.evaluations
+                    .iter()
+                    .zip(w.evaluations.iter())
+                    .map(|(x, y)| *x * y)
+                    .sum()
+            })
             .collect();
-        // Dual of the MLE of the bias vector
-        let bias_mle = Poly::from_evaluations_vec(self.padded_dims_log.1, bias_f);
 
-        let bias_opening_value = bias_mle.evaluate(&r);
-        let output_opening_value = output.evaluate(&r);
+        sponge.absorb(&block_sums);
 
-        // Constructing the sumcheck polynomial
-        // g(x) = (input - zero_point)^(x) * W^(r, x),
-        let bound_weight_mle = weight_mle.fix_variables(&r);
-        let mut g = ListOfProductsOfPolynomials::new(self.padded_dims_log.0);
+        let mut sumcheck_proofs = Vec::new();
+        let mut sumcheck_points = Vec::new();
 
-        // TODO we are cloning the input here, can we do better?
-        g.add_product(
-            vec![shifted_input_mle, bound_weight_mle]
-                .into_iter()
-                .map(Rc::new)
-                .collect::<Vec<_>>(),
-            F::one(),
-        );
+        for ((shifted_input_block, combined_weights), (_, log_size)) in
+            block_factors.iter().zip(row_blocks.iter())
+        {
+            // A block consisting of a single row results in a constant
+            // polynomial, which requires no sumcheck
+            if *log_size == 0 {
+                sumcheck_proofs.push(Vec::new());
+                sumcheck_points.push(Vec::new());
+                continue;
+            }
 
-        let (sumcheck_proof, prover_state) =
-            MLSumcheck::<F, S>::prove_as_subprotocol(&g, sponge).unwrap();
+            let mut g = ListOfProductsOfPolynomials::new(*log_size);
 
-        // The prover computes the claimed evaluations of weight_mle and
-        // input_mle at the random challenge point
-        // s := prover_state.randomness, the list of random values sampled by
-        // the verifier during sumcheck. Note that this is different from r
-        // above.
+            // TODO we are cloning the input here, can we do better?
+            g.add_product(
+                vec![shifted_input_block.clone(), combined_weights.clone()]
+                    .into_iter()
+                    .map(Rc::new)
+                    .collect::<Vec<_>>(),
+                F::one(),
+            );
+
+            let (sumcheck_proof, prover_state) =
+                MLSumcheck::<F, S>::prove_as_subprotocol(&g, sponge).unwrap();
+
+            sumcheck_proofs.push(sumcheck_proof);
+            sumcheck_points.push(prover_state.randomness);
+        }
+
+        // The prover computes the claimed evaluations of the input and weight
+        // MLEs at the random challenge points s_j := sumcheck_points[j], the
+        // lists of random values sampled by the verifier during each sumcheck.
+        //
+        // We need to reveal each g_j(s_j) by opening input^ at s_j || j || rho
+        // and each W_{j, l}^ at r_l || s_j; and also open output^ at r || rho
+        // and bias^ at r
         //
-        // We need to reveal g(s) by opening input^ at s and weight^ at s || r;
-        // and also open output^ and bias^ at r
-        let claimed_evaluations: Vec<F> = g
-            .flattened_ml_extensions
+        // Recall that the first factor of g_j was the *shifted* dual input
+        // (input - zero_point)^
+        let input_opening_values: Vec<F> = block_factors
             .iter()
-            .map(|x| x.evaluate(&prover_state.randomness))
+            .zip(sumcheck_points.iter())
+            .map(|((shifted_input_block, _), s)| shifted_input_block.evaluate(s) + i_z_p_f)
             .collect();
 
-        // Recall that the first factor of g was the *shifted* dual input
-        // (input - zero_point)^
-        let input_opening_value = claimed_evaluations[0] + i_z_p_f;
-        let weight_opening_value = claimed_evaluations[1];
-
-        let input_opening_proof = PCS::open(
-            ck,
-            [input],
-            [input_com],
-            &prover_state.randomness,
-            sponge,
-            [input_com_state],
-            None,
-        )
-        .unwrap();
+        let weight_points: Vec<Vec<F>> = self
+            .weight_blocks()
+            .into_iter()
+            .map(|(j, l)| {
+                rs[l]
+                    .iter()
+                    .chain(sumcheck_points[j].iter())
+                    .cloned()
+                    .collect()
+            })
+            .collect();
 
-        let weight_opening_proof = PCS::open(
-            ck,
-            [&LabeledPolynomial::new(
-                "weight_mle".to_string(),
-                weight_mle,
-                Some(1),
-                None,
-            )],
-            [weight_com],
-            &r.clone()
-                .into_iter()
-                .chain(prover_state.randomness)
-                .collect(),
-            sponge,
-            [weight_com_state],
-            None,
-        )
-        .unwrap();
+        let weight_opening_values: Vec<F> = weight_mles
+            .iter()
+            .zip(weight_points.iter())
+            .map(|(w, p)| w.evaluate(p))
+            .collect();
 
-        // TODO: b and o are opened at the same point, so they could be opened
-        // with a single call to PCS::open
-        let output_bias_opening_proof = PCS::open(
-            ck,
-            [
-                output,
-                &LabeledPolynomial::new("bias_mle".to_string(), bias_mle, Some(1), None),
-            ],
-            [output_com, bias_com],
+        // All openings are proved on forks of the transcript, so that they do
+        // not affect later challenges and the verifier can defer their checks
+        // (cf. VerifyModel::verify_many)
+        // TODO points shared by several polynomials could be opened with a
+        // single call to PCS::open
+        let input_opening_proofs = row_blocks
+            .iter()
+            .zip(sumcheck_points.iter())
+            .map(|(block, s)| {
+                let point: Vec<F> = Self::block_point(s, *block, self.padded_dims_log.0)
+                    .into_iter()
+                    .chain(rho.iter().cloned())
+                    .collect();
+
+                input.open(ck, &point, sponge)
+            })
+            .collect();
+
+        let weight_opening_proofs = weight_mles
+            .iter()
+            .zip(weight_coms.iter())
+            .zip(weight_com_states.iter())
+            .zip(weight_points.iter())
+            .map(|(((mle, com), com_state), point)| {
+                WPCS::open(
+                    wck,
+                    [mle],
+                    [com],
+                    point,
+                    &mut sponge.clone(),
+                    [com_state],
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let output_opening_proof = output.open(ck, &output_point, sponge);
+
+        let bias_opening_proof = WPCS::open(
+            wck,
+            [bias_mle],
+            [bias_com],
             &r,
-            sponge,
-            [output_com_state, bias_com_state],
+            &mut sponge.clone(),
+            [bias_com_state],
             None,
         )
         .unwrap();
 
         NodeProof::BMM(BMMNodeProof {
-            sumcheck_proof,
-            input_opening_proof,
-            input_opening_value,
-            weight_opening_proof,
-            weight_opening_value,
-            output_bias_opening_proof,
+            block_sums,
+            sumcheck_proofs,
+            input_opening_proofs,
+            input_opening_values,
+            weight_opening_proofs,
+            weight_opening_values,
+            output_opening_proof,
             output_opening_value,
+            bias_opening_proof,
             bias_opening_value,
         })
     }
 
-    fn commit(
+    fn prove_reduction(
         &self,
-        ck: &PCS::CommitterKey,
-        rng: Option<&mut dyn RngCore>,
-    ) -> (NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>) {
-        // TODO should we separate the associated commitment type into one with state and one without?
-        let padded_weights_f: Vec<F> = self
-            .padded_weights
-            .values()
+        _ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
+        sponge: &mut S,
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &Poly<F>,
+        output_point: &[F],
+        _output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>) {
+        let NodeMLEs::BMM(BMMNodeMLEs {
+            weight_mles,
+            bias_mle,
+        }) = node_mles
+        else {
+            panic!("BMMNode::prove_reduction expected node MLEs of type BMMNodeMLEs")
+        };
+
+        let NodeCommitment::BMM(BMMNodeCommitment {
+            weight_coms,
+            bias_com,
+        }) = node_com
+        else {
+            panic!("BMMNode::prove_reduction expected node commitment of type BMMNodeCommitment")
+        };
+
+        let NodeCommitmentState::BMM(BMMNodeCommitmentState {
+            weight_com_states,
+            bias_com_state,
+        }) = node_com_state
+        else {
+            panic!(
+                "BMMNode::prove_reduction expected node commitment state of type BMMNodeCommitmentState"
+            )
+        };
+
+        let (num_row_vars, num_col_vars) = self.padded_dims_log;
+        let row_blocks = self.row_blocks();
+        let num_col_blocks = self.col_blocks().len();
+
+        // The output point consists of r, over the padded output, and the
+        // batch challenge point rho (empty if there is only one inference)
+        let (r, rho) = output_point.split_at(num_col_vars);
+        let r = r.to_vec();
+
+        // The low-order variables r_l of r corresponding to each column block
+        // and the coefficients gamma_l of its claim, which come from the
+        // high-order ones
+        let (rs, gammas) = self.col_block_challenges(&r);
+
+        let bias_opening_value = bias_mle.evaluate(&r);
+
+        sponge.absorb(&bias_opening_value);
+
+        // Sumcheck of (input - zero_point)^(x, rho) * sum_l gamma_l * W^(r_l, x)
+        // over the padded input, where W^(r_l, .) is given by the weight
+        // blocks on the rows they cover and is zero elsewhere
+        let i_z_p_f = F::encode(self.input_zero_point);
+
+        let shifted_input = Poly::from_evaluations_vec(
+            num_row_vars,
+            fix_last_variables(input, rho)
+                .evaluations
+                .into_iter()
+                .map(|x| x - i_z_p_f)
+                .collect(),
+        );
+
+        let mut combined_weights = vec![F::zero(); 1 << num_row_vars];
+
+        for (j, (offset, log_size)) in row_blocks.iter().enumerate() {
+            for (l, (r_l, gamma)) in rs.iter().zip(gammas.iter()).enumerate() {
+                let bound_weights = weight_mles[j * num_col_blocks + l].fix_variables(r_l);
+
+                for (acc, w) in combined_weights[*offset..*offset + (1 << log_size)]
+                    .iter_mut()
+                    .zip(bound_weights.evaluations)
+                {
+                    *acc += *gamma * w;
+                }
+            }
+        }
+
+        let mut g = ListOfProductsOfPolynomials::new(num_row_vars);
+
+        g.add_product(
+            [
+                Rc::new(shifted_input.clone()),
+                Rc::new(Poly::from_evaluations_vec(num_row_vars, combined_weights)),
+            ],
+            F::one(),
+        );
+
+        let (sumcheck_proof, s) = prove_sum(&g, sponge);
+
+        // The final claim of the sumcheck is reduced to the input claim at
+        // s || rho and openings of each W_{j, l}^ at r_l || s_j, where s_j are
+        // the low-order variables of s corresponding to the row block
+        let input_value = shifted_input.evaluate(&s) + i_z_p_f;
+
+        let weight_points: Vec<Vec<F>> = self
+            .weight_blocks()
+            .into_iter()
+            .map(|(j, l)| {
+                rs[l]
+                    .iter()
+                    .chain(s[..row_blocks[j].1].iter())
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+
+        let weight_opening_values: Vec<F> = weight_mles
             .iter()
-            .map(|w| F::from(*w))
+            .zip(weight_points.iter())
+            .map(|(w, p)| w.evaluate(p))
             .collect();
 
-        // TODO part of this code is duplicated in prove, another hint that this should probs
-        // be stored
-        let weight_poly = LabeledPolynomial::new(
-            "weight_poly".to_string(),
-            Poly::from_evaluations_vec(self.com_num_vars(), padded_weights_f),
-            Some(1),
+        sponge.absorb(&weight_opening_values);
+
+        // The openings are proved on forks of the transcript
+        let weight_opening_proofs = weight_mles
+            .iter()
+            .zip(weight_coms.iter())
+            .zip(weight_com_states.iter())
+            .zip(weight_points.iter())
+            .map(|(((mle, com), com_state), point)| {
+                WPCS::open(
+                    wck,
+                    [mle],
+                    [com],
+                    point,
+                    &mut sponge.clone(),
+                    [com_state],
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let bias_opening_proof = WPCS::open(
+            wck,
+            [bias_mle],
+            [bias_com],
+            &r,
+            &mut sponge.clone(),
+            [bias_com_state],
             None,
-        );
+        )
+        .unwrap();
+
+        // The claimed input value is passed on to the preceding node
+        sponge.absorb(&input_value);
+
+        (
+            NodeReductionProof::BMM(BMMNodeReductionProof {
+                sumcheck_proof,
+                input_value,
+                weight_opening_proofs,
+                weight_opening_values,
+                bias_opening_proof,
+                bias_opening_value,
+            }),
+            s.into_iter().chain(rho.iter().cloned()).collect(),
+        )
+    }
+
+    fn mles(&self) -> NodeMLEs<F> {
+        let row_blocks = self.row_blocks();
+        let col_blocks = self.col_blocks();
 
-        let padded_bias_f: Vec<F> = self
+        let weight_mles = self
+            .weight_blocks()
+            .into_iter()
+            .map(|(j, l)| {
+                let block = self.weight_block(row_blocks[j], col_blocks[l]);
+                let weights_f: Vec<F> = block.values().iter().map(|w| F::encode(*w)).collect();
+
+                LabeledPolynomial::new(
+                    format!("weight_mle_{j}_{l}"),
+                    Poly::from_evaluations_vec(row_blocks[j].1 + col_blocks[l].1, weights_f),
+                    Some(1),
+                    None,
+                )
+            })
+            .collect();
+
+        let bias_f: Vec<F> = self
             .padded_bias
             .values()
             .iter()
-            .map(|b| F::from(*b))
+            .map(|b| F::encode(*b))
             .collect();
 
-        let bias_poly = LabeledPolynomial::new(
-            "bias_poly".to_string(),
-            Poly::from_evaluations_vec(self.padded_dims_log.1, padded_bias_f),
+        let bias_mle = LabeledPolynomial::new(
+            "bias_mle".to_string(),
+            Poly::from_evaluations_vec(self.padded_dims_log.1, bias_f),
             Some(1),
             None,
         );
 
-        let (coms, com_states) = PCS::commit(ck, vec![&weight_poly, &bias_poly], rng).unwrap();
+        NodeMLEs::BMM(BMMNodeMLEs {
+            weight_mles,
+            bias_mle,
+        })
+    }
+
+    fn commit(
+        &self,
+        wck: &WPCS::CommitterKey,
+        node_mles: &NodeMLEs<F>,
+        rng: Option<&mut dyn RngCore>,
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>) {
+        let (weight_mles, bias_mle) = match node_mles {
+            NodeMLEs::BMM(BMMNodeMLEs {
+                weight_mles,
+                bias_mle,
+            }) => (weight_mles, bias_mle),
+            _ => panic!("BMMNode::commit expected node MLEs of type BMMNodeMLEs"),
+        };
+
+        // TODO should we separate the associated commitment type into one with state and one without?
+        let (mut weight_coms, mut weight_com_states) =
+            WPCS::commit(wck, weight_mles.iter().chain([bias_mle]), rng).unwrap();
 
-        let mut coms = coms.into_iter();
-        let mut com_states = com_states.into_iter();
+        // The bias commitment comes last
+        let bias_com = weight_coms.pop().unwrap();
+        let bias_com_state = weight_com_states.pop().unwrap();
 
         (
             NodeCommitment::BMM(BMMNodeCommitment {
-                weight_com: coms.next().unwrap(),
-                bias_com: coms.next().unwrap(),
+                weight_coms,
+                bias_com,
             }),
             NodeCommitmentState::BMM(BMMNodeCommitmentState {
-                weight_com_state: com_states.next().unwrap(),
-                bias_com_state: com_states.next().unwrap(),
+                weight_com_states,
+                bias_com_state,
             }),
         )
     }
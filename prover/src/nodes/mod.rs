@@ -2,3 +2,4 @@ mod bmm;
 mod relu;
 mod requantise_bmm;
 mod reshape;
+mod sparse_bmm;
@@ -1,42 +1,62 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_poly_commit::PolynomialCommitment;
 use ark_std::rand::RngCore;
 
 use hcs_common::{
-    InnerType, LabeledPoly, NodeCommitment, NodeCommitmentState, NodeProof, Poly, ReLUNode,
+    InnerType, NodeCommitment, NodeCommitmentState, NodeMLEs, NodeProof, NodeReductionProof, Poly,
+    ReLUNode,
 };
 
-use crate::NodeOpsProve;
+use crate::{NodeOpsProve, NodeValue};
 
-impl<F, S, PCS, ST> NodeOpsProve<F, S, PCS, ST, ST> for ReLUNode<ST>
+impl<F, S, PCS, WPCS, ST> NodeOpsProve<F, S, PCS, WPCS, ST, ST> for ReLUNode<ST>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
     ST: InnerType,
 {
     fn prove(
         &self,
         _ck: &PCS::CommitterKey,
+        _wck: &WPCS::CommitterKey,
         _s: &mut S,
-        _node_com: &NodeCommitment<F, S, PCS>,
-        _node_com_state: &NodeCommitmentState<F, S, PCS>,
-        _input: &LabeledPoly<F>,
-        _input_com: &LabeledCommitment<PCS::Commitment>,
-        _input_com_state: &PCS::CommitmentState,
-        _output: &LabeledPoly<F>,
-        _output_com: &LabeledCommitment<PCS::Commitment>,
-        _output_com_state: &PCS::CommitmentState,
-    ) -> NodeProof<F, S, PCS> {
+        _node_mles: &NodeMLEs<F>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        _input: &NodeValue<F, S, PCS>,
+        _output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS> {
         NodeProof::ReLU(())
     }
 
-    fn commit(
+    fn prove_reduction(
         &self,
         _ck: &PCS::CommitterKey,
+        _wck: &WPCS::CommitterKey,
+        _s: &mut S,
+        _node_mles: &NodeMLEs<F>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        _input: &Poly<F>,
+        _output_point: &[F],
+        _output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>) {
+        panic!("ReLUNode has no claim reduction")
+    }
+
+    fn mles(&self) -> NodeMLEs<F> {
+        NodeMLEs::ReLU(())
+    }
+
+    fn commit(
+        &self,
+        _wck: &WPCS::CommitterKey,
+        _node_mles: &NodeMLEs<F>,
         _rng: Option<&mut dyn RngCore>,
-    ) -> (NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>) {
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>) {
         (NodeCommitment::ReLU(()), NodeCommitmentState::ReLU(()))
     }
 }
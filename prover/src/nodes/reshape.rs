@@ -1,42 +1,112 @@
+use std::rc::Rc;
+
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_poly::Polynomial;
+use ark_poly_commit::PolynomialCommitment;
 use ark_std::rand::RngCore;
+use ark_sumcheck::ml_sumcheck::protocol::ListOfProductsOfPolynomials;
 
 use hcs_common::{
-    InnerType, LabeledPoly, NodeCommitment, NodeCommitmentState, NodeProof, Poly, ReshapeNode,
+    eq_evaluations, InnerType, NodeCommitment, NodeCommitmentState, NodeMLEs, NodeOpsPadded,
+    NodeProof, NodeReductionProof, Poly, ReshapeNode, ReshapeNodeReductionProof,
 };
 
-use crate::NodeOpsProve;
+use crate::{
+    util::{fix_last_variables, prove_sum},
+    NodeOpsProve, NodeValue,
+};
 
-impl<F, S, PCS, ST> NodeOpsProve<F, S, PCS, ST, ST> for ReshapeNode
+impl<F, S, PCS, WPCS, ST> NodeOpsProve<F, S, PCS, WPCS, ST, ST> for ReshapeNode
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
     ST: InnerType,
 {
     fn prove(
         &self,
         _ck: &PCS::CommitterKey,
+        _wck: &WPCS::CommitterKey,
         _s: &mut S,
-        _node_com: &NodeCommitment<F, S, PCS>,
-        _node_com_state: &NodeCommitmentState<F, S, PCS>,
-        _input: &LabeledPoly<F>,
-        _input_com: &LabeledCommitment<PCS::Commitment>,
-        _input_com_state: &PCS::CommitmentState,
-        _output: &LabeledPoly<F>,
-        _output_com: &LabeledCommitment<PCS::Commitment>,
-        _output_com_state: &PCS::CommitmentState,
-    ) -> NodeProof<F, S, PCS> {
+        _node_mles: &NodeMLEs<F>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        _input: &NodeValue<F, S, PCS>,
+        _output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS> {
         NodeProof::Reshape(())
     }
 
-    fn commit(
+    fn prove_reduction(
         &self,
         _ck: &PCS::CommitterKey,
+        _wck: &WPCS::CommitterKey,
+        sponge: &mut S,
+        _node_mles: &NodeMLEs<F>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &Poly<F>,
+        output_point: &[F],
+        _output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>) {
+        let num_input_vars: usize = self.padded_input_shape_log.iter().sum();
+        let num_output_vars = NodeOpsPadded::<ST, ST>::padded_num_units_log(self);
+
+        // The output point consists of r, over the padded output, and the
+        // batch challenge point rho (empty if there is only one inference)
+        let (r, rho) = output_point.split_at(num_output_vars);
+
+        // M^(r, y) as a function of y, which is eq(r, .) at the image of y
+        // under the reshape and zero on the padding
+        let eq_r = eq_evaluations(r);
+        let mut bound_map = vec![F::zero(); 1 << num_input_vars];
+
+        for (y, x) in self.padded_index_map() {
+            bound_map[y] = eq_r[x];
+        }
+
+        // Sumcheck of input^(y, rho) * M^(r, y), whose sum is output^(r, rho)
+        let mut g = ListOfProductsOfPolynomials::new(num_input_vars);
+
+        g.add_product(
+            [
+                Rc::new(fix_last_variables(input, rho)),
+                Rc::new(Poly::from_evaluations_vec(num_input_vars, bound_map)),
+            ],
+            F::one(),
+        );
+
+        let (sumcheck_proof, sumcheck_point) = prove_sum(&g, sponge);
+
+        let input_point: Vec<F> = sumcheck_point
+            .into_iter()
+            .chain(rho.iter().cloned())
+            .collect();
+        let input_value = input.evaluate(&input_point);
+
+        sponge.absorb(&input_value);
+
+        (
+            NodeReductionProof::Reshape(ReshapeNodeReductionProof {
+                sumcheck_proof,
+                input_value,
+            }),
+            input_point,
+        )
+    }
+
+    fn mles(&self) -> NodeMLEs<F> {
+        NodeMLEs::Reshape(())
+    }
+
+    fn commit(
+        &self,
+        _wck: &WPCS::CommitterKey,
+        _node_mles: &NodeMLEs<F>,
         _rng: Option<&mut dyn RngCore>,
-    ) -> (NodeCommitment<F, S, PCS>, NodeCommitmentState<F, S, PCS>) {
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>) {
         (
             NodeCommitment::Reshape(()),
             NodeCommitmentState::Reshape(()),
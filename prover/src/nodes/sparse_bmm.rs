@@ -0,0 +1,633 @@
+use std::rc::Rc;
+
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::{batch_inversion, PrimeField};
+use ark_poly::{MultilinearExtension, Polynomial};
+use ark_poly_commit::{LabeledPolynomial, PolynomialCommitment};
+use ark_std::rand::RngCore;
+use ark_sumcheck::ml_sumcheck::protocol::ListOfProductsOfPolynomials;
+
+use hcs_common::{
+    eq_evaluations, FieldEncoding, InnerType, LabeledPoly, NodeCommitment, NodeCommitmentState,
+    NodeMLEs, NodeProof, NodeReductionProof, Poly, SparseBMMNode, SparseBMMNodeCommitment,
+    SparseBMMNodeCommitmentState, SparseBMMNodeMLEs, SparseBMMNodeProof,
+    SparseBMMNodeReductionProof,
+};
+
+use crate::{
+    util::{fix_last_variables, prove_sum},
+    NodeOpsProve, NodeValue,
+};
+
+impl<F, S, PCS, WPCS, ST, LT> NodeOpsProve<F, S, PCS, WPCS, ST, LT> for SparseBMMNode<ST, LT>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    fn prove(
+        &self,
+        ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
+        sponge: &mut S,
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &NodeValue<F, S, PCS>,
+        output: &NodeValue<F, S, PCS>,
+    ) -> NodeProof<F, S, PCS, WPCS> {
+        let (num_row_vars, num_col_vars) = self.padded_dims_log;
+
+        // Any variables of the input and output MLEs beyond those of a single
+        // inference index the inference in the batch
+        let num_batch_vars = input.mle.num_vars - num_row_vars;
+
+        // We can squeeze directly, since the sponge has already absorbed all
+        // the commitments in Model::prove_inference
+        let r: Vec<F> = sponge.squeeze_field_elements(num_col_vars);
+
+        // Challenge combining the inferences in the batch (empty if there is
+        // only one)
+        let rho: Vec<F> = sponge.squeeze_field_elements(num_batch_vars);
+
+        let output_point: Vec<F> = r.iter().chain(rho.iter()).cloned().collect();
+        let output_opening_value = output.mle.evaluate(&output_point);
+
+        let (reduction_proof, input_point) = reduce_output_claim(
+            self,
+            ck,
+            wck,
+            sponge,
+            node_mles,
+            node_com,
+            node_com_state,
+            input.mle,
+            &output_point,
+            output_opening_value,
+        );
+
+        // As all other openings, these are proved on forks of the transcript
+        let input_opening_proof = input.open(ck, &input_point, sponge);
+        let output_opening_proof = output.open(ck, &output_point, sponge);
+
+        NodeProof::SparseBMM(SparseBMMNodeProof {
+            reduction_proof,
+            input_opening_proof,
+            output_opening_proof,
+            output_opening_value,
+        })
+    }
+
+    fn prove_reduction(
+        &self,
+        ck: &PCS::CommitterKey,
+        wck: &WPCS::CommitterKey,
+        sponge: &mut S,
+        node_mles: &NodeMLEs<F>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        node_com_state: &NodeCommitmentState<F, S, WPCS>,
+        input: &Poly<F>,
+        output_point: &[F],
+        output_value: F,
+    ) -> (NodeReductionProof<F, S, PCS, WPCS>, Vec<F>) {
+        let (proof, input_point) = reduce_output_claim(
+            self,
+            ck,
+            wck,
+            sponge,
+            node_mles,
+            node_com,
+            node_com_state,
+            input,
+            output_point,
+            output_value,
+        );
+
+        // Unlike in the above, where it is opened, the claimed input value
+        // is passed on to the preceding node
+        sponge.absorb(&proof.input_value);
+
+        (NodeReductionProof::SparseBMM(Box::new(proof)), input_point)
+    }
+
+    fn mles(&self) -> NodeMLEs<F> {
+        let (num_row_vars, num_col_vars) = self.padded_dims_log;
+        let entries = self.padded_entries();
+
+        let mut row_counts = vec![F::zero(); 1 << num_row_vars];
+        let mut col_counts = vec![F::zero(); 1 << num_col_vars];
+
+        for (i, j, _) in &entries {
+            row_counts[*i] += F::one();
+            col_counts[*j] += F::one();
+        }
+
+        let bias_f: Vec<F> = self
+            .padded_bias
+            .values()
+            .iter()
+            .map(|b| F::encode(*b))
+            .collect();
+
+        NodeMLEs::SparseBMM(Box::new(SparseBMMNodeMLEs {
+            value_mle: labeled(
+                "sparse_value_mle",
+                self.padded_num_entries_log,
+                entries.iter().map(|(_, _, w)| F::encode(*w)).collect(),
+            ),
+            row_mle: labeled(
+                "sparse_row_mle",
+                self.padded_num_entries_log,
+                entries.iter().map(|(i, _, _)| F::from(*i as u64)).collect(),
+            ),
+            col_mle: labeled(
+                "sparse_col_mle",
+                self.padded_num_entries_log,
+                entries.iter().map(|(_, j, _)| F::from(*j as u64)).collect(),
+            ),
+            row_count_mle: labeled("sparse_row_count_mle", num_row_vars, row_counts),
+            col_count_mle: labeled("sparse_col_count_mle", num_col_vars, col_counts),
+            bias_mle: labeled("bias_mle", num_col_vars, bias_f),
+        }))
+    }
+
+    fn commit(
+        &self,
+        wck: &WPCS::CommitterKey,
+        node_mles: &NodeMLEs<F>,
+        rng: Option<&mut dyn RngCore>,
+    ) -> (NodeCommitment<F, S, WPCS>, NodeCommitmentState<F, S, WPCS>) {
+        let NodeMLEs::SparseBMM(mles) = node_mles else {
+            panic!("SparseBMMNode::commit expected node MLEs of type SparseBMMNodeMLEs")
+        };
+
+        let (coms, com_states) = WPCS::commit(
+            wck,
+            [
+                &mles.value_mle,
+                &mles.row_mle,
+                &mles.col_mle,
+                &mles.row_count_mle,
+                &mles.col_count_mle,
+                &mles.bias_mle,
+            ],
+            rng,
+        )
+        .unwrap();
+
+        let [value_com, row_com, col_com, row_count_com, col_count_com, bias_com]: [_; 6] =
+            coms.try_into().ok().unwrap();
+
+        let [value_com_state, row_com_state, col_com_state, row_count_com_state, col_count_com_state, bias_com_state]: [_; 6] =
+            com_states.try_into().ok().unwrap();
+
+        (
+            NodeCommitment::SparseBMM(SparseBMMNodeCommitment {
+                value_com,
+                row_com,
+                col_com,
+                row_count_com,
+                col_count_com,
+                bias_com,
+            }),
+            NodeCommitmentState::SparseBMM(SparseBMMNodeCommitmentState {
+                value_com_state,
+                row_com_state,
+                col_com_state,
+                row_count_com_state,
+                col_count_com_state,
+                bias_com_state,
+            }),
+        )
+    }
+}
+
+// Reduces the claim that the output MLE takes the value output_value at
+// output_point to one about the input MLE, returning the proof and the point
+// of the new claim. This is shared by the proof of the node, where the output
+// claim comes from a challenge point and both claims are proved by opening
+// the committed values, and by its claim reduction.
+fn reduce_output_claim<F, S, PCS, WPCS, ST, LT>(
+    node: &SparseBMMNode<ST, LT>,
+    ck: &PCS::CommitterKey,
+    wck: &WPCS::CommitterKey,
+    sponge: &mut S,
+    node_mles: &NodeMLEs<F>,
+    node_com: &NodeCommitment<F, S, WPCS>,
+    node_com_state: &NodeCommitmentState<F, S, WPCS>,
+    input: &Poly<F>,
+    output_point: &[F],
+    output_value: F,
+) -> (SparseBMMNodeReductionProof<F, S, PCS, WPCS>, Vec<F>)
+where
+    F: PrimeField + Absorb + FieldEncoding<ST>,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+    ST: InnerType,
+    LT: InnerType,
+{
+    let NodeMLEs::SparseBMM(mles) = node_mles else {
+        panic!("SparseBMMNode expected node MLEs of type SparseBMMNodeMLEs")
+    };
+
+    let NodeCommitment::SparseBMM(coms) = node_com else {
+        panic!("SparseBMMNode expected node commitment of type SparseBMMNodeCommitment")
+    };
+
+    let NodeCommitmentState::SparseBMM(com_states) = node_com_state else {
+        panic!("SparseBMMNode expected node commitment state of type SparseBMMNodeCommitmentState")
+    };
+
+    let num_entry_vars = node.padded_num_entries_log;
+    let (num_row_vars, num_col_vars) = node.padded_dims_log;
+
+    // The output point consists of r, over the padded output, and the
+    // batch challenge point rho (empty if there is only one inference)
+    let (r, rho) = output_point.split_at(num_col_vars);
+    let r = r.to_vec();
+
+    let bias_opening_value = mles.bias_mle.evaluate(&r);
+
+    // The tables looked up into: input^(y, rho) and eq(r, y)
+    let bound_input = fix_last_variables(input, rho);
+    let eq_r = Poly::from_evaluations_vec(num_col_vars, eq_evaluations(&r));
+
+    let entries = node.padded_entries();
+
+    let input_lookup: Vec<F> = entries
+        .iter()
+        .map(|(i, _, _)| bound_input.evaluations[*i])
+        .collect();
+    let eq_lookup: Vec<F> = entries
+        .iter()
+        .map(|(_, j, _)| eq_r.evaluations[*j])
+        .collect();
+
+    let lookup_mles = [
+        labeled("input_lookup", num_entry_vars, input_lookup),
+        labeled("eq_lookup", num_entry_vars, eq_lookup),
+    ];
+
+    let (lookup_coms, lookup_com_states) = PCS::commit(ck, &lookup_mles, None).unwrap();
+
+    sponge.absorb(&lookup_coms);
+
+    // Lookup challenges, which compress each (index, value) pair into
+    // index + alpha * value and evaluate the log derivatives at beta
+    let lookup_challenges: Vec<F> = sponge.squeeze_field_elements(2);
+    let (alpha, beta) = (lookup_challenges[0], lookup_challenges[1]);
+
+    let row_index = index_mle(num_row_vars);
+    let col_index = index_mle(num_col_vars);
+
+    let row_inverses = log_derivative_terms(
+        &mles.row_mle.evaluations,
+        &lookup_mles[0].evaluations,
+        None,
+        alpha,
+        beta,
+    );
+    let col_inverses = log_derivative_terms(
+        &mles.col_mle.evaluations,
+        &lookup_mles[1].evaluations,
+        None,
+        alpha,
+        beta,
+    );
+    let input_table_inverses = log_derivative_terms(
+        &row_index.evaluations,
+        &bound_input.evaluations,
+        Some(&mles.row_count_mle.evaluations),
+        alpha,
+        beta,
+    );
+    let eq_table_inverses = log_derivative_terms(
+        &col_index.evaluations,
+        &eq_r.evaluations,
+        Some(&mles.col_count_mle.evaluations),
+        alpha,
+        beta,
+    );
+
+    let inverse_sums = vec![
+        row_inverses.iter().sum::<F>(),
+        col_inverses.iter().sum::<F>(),
+    ];
+
+    let inverse_mles = [
+        labeled("row_inverses", num_entry_vars, row_inverses),
+        labeled("col_inverses", num_entry_vars, col_inverses),
+        labeled("input_table_inverses", num_row_vars, input_table_inverses),
+        labeled("eq_table_inverses", num_col_vars, eq_table_inverses),
+    ];
+
+    let (inverse_coms, inverse_com_states) = PCS::commit(ck, &inverse_mles, None).unwrap();
+
+    sponge.absorb(&inverse_coms);
+    sponge.absorb(&inverse_sums);
+    sponge.absorb(&vec![output_value, bias_opening_value]);
+
+    // Points at which the zero checks of the inverses take place, and
+    // coefficients combining the claims about the entries
+    let t_entries: Vec<F> = sponge.squeeze_field_elements(num_entry_vars);
+    let t_input_table: Vec<F> = sponge.squeeze_field_elements(num_row_vars);
+    let t_eq_table: Vec<F> = sponge.squeeze_field_elements(num_col_vars);
+    let lambdas: Vec<F> = sponge.squeeze_field_elements(4);
+
+    let i_z_p_f = F::encode(node.input_zero_point);
+
+    // Sumcheck over the entries of
+    //   value * (a - zero_point) * b
+    //   + lambda_0 * eq(t, .) * (h_row * (beta - row - alpha * a) - 1)
+    //   + lambda_1 * eq(t, .) * (h_col * (beta - col - alpha * b) - 1)
+    //   + lambda_2 * h_row + lambda_3 * h_col,
+    // whose sum is output^(r) - bias^(r) + lambda_2 * sum(h_row) + lambda_3 * sum(h_col)
+    let value = Rc::new(mles.value_mle.polynomial().clone());
+    let row = Rc::new(mles.row_mle.polynomial().clone());
+    let col = Rc::new(mles.col_mle.polynomial().clone());
+    let a = Rc::new(lookup_mles[0].polynomial().clone());
+    let b = Rc::new(lookup_mles[1].polynomial().clone());
+    let h_row = Rc::new(inverse_mles[0].polynomial().clone());
+    let h_col = Rc::new(inverse_mles[1].polynomial().clone());
+    let eq_t = Rc::new(Poly::from_evaluations_vec(
+        num_entry_vars,
+        eq_evaluations(&t_entries),
+    ));
+
+    let mut g = ListOfProductsOfPolynomials::new(num_entry_vars);
+
+    g.add_product([value.clone(), a.clone(), b.clone()], F::one());
+    g.add_product([value, b.clone()], -i_z_p_f);
+
+    for ((h, index, lookup), lambda) in [(h_row.clone(), row, a), (h_col.clone(), col, b)]
+        .into_iter()
+        .zip(lambdas.iter())
+    {
+        g.add_product([eq_t.clone(), h.clone()], *lambda * beta);
+        g.add_product([eq_t.clone(), h.clone(), index], -*lambda);
+        g.add_product([eq_t.clone(), h, lookup], -*lambda * alpha);
+        g.add_product([eq_t.clone()], -*lambda);
+    }
+
+    g.add_product([h_row], lambdas[2]);
+    g.add_product([h_col], lambdas[3]);
+
+    let (entry_sumcheck_proof, entry_point) = prove_sum(&g, sponge);
+
+    // Sumchecks over each table of
+    //   eq(t, .) * (q * (beta - y - alpha * table) - count) + q,
+    // whose sum is sum(q)
+    let (input_table_sumcheck_proof, input_table_point) = prove_sum(
+        &table_polynomial(
+            eq_evaluations(&t_input_table),
+            &inverse_mles[2],
+            row_index,
+            bound_input,
+            &mles.row_count_mle,
+            alpha,
+            beta,
+        ),
+        sponge,
+    );
+
+    let (eq_table_sumcheck_proof, eq_table_point) = prove_sum(
+        &table_polynomial(
+            eq_evaluations(&t_eq_table),
+            &inverse_mles[3],
+            col_index,
+            eq_r,
+            &mles.col_count_mle,
+            alpha,
+            beta,
+        ),
+        sponge,
+    );
+
+    // The final claims of the sumchecks are reduced to openings of the
+    // committed polynomials, which are proved on forks of the transcript
+    // (cf. VerifyModel::verify_many)
+    let entry_polys = [
+        &mles.value_mle,
+        &mles.row_mle,
+        &mles.col_mle,
+        &lookup_mles[0],
+        &lookup_mles[1],
+        &inverse_mles[0],
+        &inverse_mles[1],
+    ];
+
+    let entry_opening_values = entry_polys
+        .iter()
+        .map(|p| p.evaluate(&entry_point))
+        .collect();
+
+    // The node parameters and the polynomials committed to above are
+    // opened separately, as they may be committed to with different PCSs
+    let entry_weight_opening_proof = WPCS::open(
+        wck,
+        entry_polys[..3].iter().cloned(),
+        [&coms.value_com, &coms.row_com, &coms.col_com],
+        &entry_point,
+        &mut sponge.clone(),
+        [
+            &com_states.value_com_state,
+            &com_states.row_com_state,
+            &com_states.col_com_state,
+        ],
+        None,
+    )
+    .unwrap();
+
+    let entry_opening_proof = PCS::open(
+        ck,
+        entry_polys[3..].iter().cloned(),
+        [
+            &lookup_coms[0],
+            &lookup_coms[1],
+            &inverse_coms[0],
+            &inverse_coms[1],
+        ],
+        &entry_point,
+        &mut sponge.clone(),
+        [
+            &lookup_com_states[0],
+            &lookup_com_states[1],
+            &inverse_com_states[0],
+            &inverse_com_states[1],
+        ],
+        None,
+    )
+    .unwrap();
+
+    let input_table_opening_values = vec![
+        inverse_mles[2].evaluate(&input_table_point),
+        mles.row_count_mle.evaluate(&input_table_point),
+    ];
+
+    let input_table_opening_proof = PCS::open(
+        ck,
+        [&inverse_mles[2]],
+        [&inverse_coms[2]],
+        &input_table_point,
+        &mut sponge.clone(),
+        [&inverse_com_states[2]],
+        None,
+    )
+    .unwrap();
+
+    let row_count_opening_proof = WPCS::open(
+        wck,
+        [&mles.row_count_mle],
+        [&coms.row_count_com],
+        &input_table_point,
+        &mut sponge.clone(),
+        [&com_states.row_count_com_state],
+        None,
+    )
+    .unwrap();
+
+    let input_point: Vec<F> = input_table_point
+        .iter()
+        .chain(rho.iter())
+        .cloned()
+        .collect();
+
+    let input_value = input.evaluate(&input_point);
+
+    let eq_table_opening_values = vec![
+        inverse_mles[3].evaluate(&eq_table_point),
+        mles.col_count_mle.evaluate(&eq_table_point),
+    ];
+
+    let eq_table_opening_proof = PCS::open(
+        ck,
+        [&inverse_mles[3]],
+        [&inverse_coms[3]],
+        &eq_table_point,
+        &mut sponge.clone(),
+        [&inverse_com_states[3]],
+        None,
+    )
+    .unwrap();
+
+    let col_count_opening_proof = WPCS::open(
+        wck,
+        [&mles.col_count_mle],
+        [&coms.col_count_com],
+        &eq_table_point,
+        &mut sponge.clone(),
+        [&com_states.col_count_com_state],
+        None,
+    )
+    .unwrap();
+
+    let bias_opening_proof = WPCS::open(
+        wck,
+        [&mles.bias_mle],
+        [&coms.bias_com],
+        &r,
+        &mut sponge.clone(),
+        [&com_states.bias_com_state],
+        None,
+    )
+    .unwrap();
+
+    (
+        SparseBMMNodeReductionProof {
+            lookup_coms,
+            inverse_coms,
+            inverse_sums,
+            entry_sumcheck_proof,
+            input_table_sumcheck_proof,
+            eq_table_sumcheck_proof,
+            entry_weight_opening_proof,
+            entry_opening_proof,
+            entry_opening_values,
+            input_table_opening_proof,
+            row_count_opening_proof,
+            input_table_opening_values,
+            input_value,
+            eq_table_opening_proof,
+            col_count_opening_proof,
+            eq_table_opening_values,
+            bias_opening_proof,
+            bias_opening_value,
+        },
+        input_point,
+    )
+}
+
+fn labeled<F: PrimeField>(label: &str, num_vars: usize, evaluations: Vec<F>) -> LabeledPoly<F> {
+    LabeledPolynomial::new(
+        label.to_string(),
+        Poly::from_evaluations_vec(num_vars, evaluations),
+        Some(1),
+        None,
+    )
+}
+
+// MLE of the map sending each point of the hypercube to the integer it
+// represents
+fn index_mle<F: PrimeField>(num_vars: usize) -> Poly<F> {
+    Poly::from_evaluations_vec(
+        num_vars,
+        (0..1u64 << num_vars).map(|y| F::from(y)).collect(),
+    )
+}
+
+// Terms numerator_i / (beta - index_i - alpha * value_i) of a log-derivative
+// lookup argument, with numerators equal to one if none are given
+fn log_derivative_terms<F: PrimeField>(
+    indices: &[F],
+    values: &[F],
+    numerators: Option<&[F]>,
+    alpha: F,
+    beta: F,
+) -> Vec<F> {
+    let mut terms: Vec<F> = indices
+        .iter()
+        .zip(values.iter())
+        .map(|(index, value)| beta - index - alpha * value)
+        .collect();
+
+    // The denominators are non-zero except with negligible probability
+    batch_inversion(&mut terms);
+
+    if let Some(numerators) = numerators {
+        for (term, numerator) in terms.iter_mut().zip(numerators.iter()) {
+            *term *= numerator;
+        }
+    }
+
+    terms
+}
+
+// Polynomial eq(t, .) * (q * (beta - index - alpha * table) - count) + q
+// over a lookup table, given the evaluations of eq(t, .)
+fn table_polynomial<F: PrimeField>(
+    eq_t: Vec<F>,
+    q: &LabeledPoly<F>,
+    index: Poly<F>,
+    table: Poly<F>,
+    count: &LabeledPoly<F>,
+    alpha: F,
+    beta: F,
+) -> ListOfProductsOfPolynomials<F> {
+    let num_vars = q.num_vars();
+    let eq_t = Rc::new(Poly::from_evaluations_vec(num_vars, eq_t));
+    let q = Rc::new(q.polynomial().clone());
+
+    let mut g = ListOfProductsOfPolynomials::new(num_vars);
+
+    g.add_product([eq_t.clone(), q.clone()], beta);
+    g.add_product([eq_t.clone(), q.clone(), Rc::new(index)], -F::one());
+    g.add_product([eq_t.clone(), q.clone(), Rc::new(table)], -alpha);
+    g.add_product([eq_t, Rc::new(count.polynomial().clone())], -F::one());
+    g.add_product([q], F::one());
+
+    g
+}
@@ -1,10 +1,65 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly::MultilinearExtension;
+use ark_sumcheck::ml_sumcheck::{protocol::ListOfProductsOfPolynomials, MLSumcheck, Proof};
+
+use hcs_common::Poly;
+
+// The trait is spelled out (rather than calling e.g. node.prove(...)) since
+// some methods, such as mles, do not determine all of its type parameters
 macro_rules! node_operation {
-    ($self:expr, $method:ident, $($arg:expr),*) => {
+    ($self:expr, $method:ident $(, $arg:expr)*) => {
         match $self {
-            Node::BMM(node) => node.$method($($arg),*),
-            Node::RequantiseBMM(node) => node.$method($($arg),*),
-            Node::ReLU(node) => node.$method($($arg),*),
-            Node::Reshape(node) => NodeOpsProve::<_, _, _, I, _>::$method(node, $($arg),*),
+            Node::BMM(node) => NodeOpsProve::<F, S, PCS, WPCS, _, _>::$method(node $(, $arg)*),
+            Node::SparseBMM(node) => NodeOpsProve::<F, S, PCS, WPCS, _, _>::$method(node $(, $arg)*),
+            Node::RequantiseBMM(node) => NodeOpsProve::<F, S, PCS, WPCS, _, _>::$method(node $(, $arg)*),
+            Node::ReLU(node) => NodeOpsProve::<F, S, PCS, WPCS, _, _>::$method(node $(, $arg)*),
+            Node::Reshape(node) => NodeOpsProve::<F, S, PCS, WPCS, I, _>::$method(node $(, $arg)*),
+            Node::Custom(node) => NodeOpsProve::<F, S, PCS, WPCS, I, O>::$method(
+                custom_prover::<F, S, PCS, WPCS, I, O>(node) $(, $arg)*
+            ),
         }
     };
 }
+
+/// Binds the *last* variables of `mle` to the values in `point`, returning
+/// an MLE in the remaining (first) variables. This is used to collapse the
+/// batch variables of stacked node values (cf.
+/// `ProveModel::prove_inference_batch`); `fix_variables` binds the first ones
+/// instead.
+pub(crate) fn fix_last_variables<F: PrimeField>(mle: &Poly<F>, point: &[F]) -> Poly<F> {
+    let num_vars = mle.num_vars() - point.len();
+    let mut evaluations = mle.to_evaluations();
+
+    // The last variable splits the evaluations into two contiguous halves
+    for p in point.iter().rev() {
+        let half = evaluations.len() / 2;
+
+        for i in 0..half {
+            evaluations[i] = evaluations[i] + *p * (evaluations[i + half] - evaluations[i]);
+        }
+
+        evaluations.truncate(half);
+    }
+
+    Poly::from_evaluations_vec(num_vars, evaluations)
+}
+
+/// Runs the sumcheck protocol on `g`, returning the proof and the challenge
+/// point. A polynomial in no variables is a constant and needs no proof.
+pub(crate) fn prove_sum<F, S>(
+    g: &ListOfProductsOfPolynomials<F>,
+    sponge: &mut S,
+) -> (Proof<F>, Vec<F>)
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    if g.num_variables == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let (proof, prover_state) = MLSumcheck::<F, S>::prove_as_subprotocol(g, sponge).unwrap();
+
+    (proof, prover_state.randomness)
+}
@@ -0,0 +1,47 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{LabeledPoly, NodeValueCommitment, Poly};
+
+/// The values of a node (across the batch) as seen by the node prover: their
+/// MLE, together with the committed polynomial through which they are opened.
+/// The latter is either the MLE itself or the packed MLE of all node values
+/// (cf. `NodeValueCommitment`), which nodes need not be aware of.
+pub struct NodeValue<'a, F, S, PCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// MLE of the values of the node alone
+    pub mle: &'a Poly<F>,
+    pub(crate) committed: &'a LabeledPoly<F>,
+    pub(crate) com: &'a NodeValueCommitment<F, S, PCS>,
+    pub(crate) com_state: &'a PCS::CommitmentState,
+}
+
+impl<'a, F, S, PCS> NodeValue<'a, F, S, PCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// Opens the node values at `point`. As all other openings, this is proved
+    /// on a fork of the transcript.
+    pub fn open(&self, ck: &PCS::CommitterKey, point: &[F], sponge: &S) -> PCS::Proof {
+        // TODO we have to pass rng, not None, but it has been moved before
+        // fix this once we have decided how to handle the cumbersome
+        // Option<&mut rng...>
+        PCS::open(
+            ck,
+            [self.committed],
+            [&self.com.com],
+            &self.com.point(point),
+            &mut sponge.clone(),
+            [self.com_state],
+            None,
+        )
+        .unwrap()
+    }
+}
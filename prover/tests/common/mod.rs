@@ -0,0 +1,177 @@
+// Small model, inputs and keys shared by the proof tests, which tamper with
+// the (public) fields of honest proofs and check that these are rejected
+
+#![allow(dead_code)]
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_std::test_rng;
+
+use hcs_common::{
+    poseidon_sponge, InferenceProof, Ligero, Model, ModelBuilder, ProofConfig, QArray, QInfo,
+    SecurePCS,
+};
+use hcs_prover::{ProveModel, ProvingKey};
+use hcs_verifier::VerifyingKey;
+
+pub type F = Fr;
+pub type S = PoseidonSponge<Fr>;
+pub type PCS = Ligero<Fr>;
+
+pub type TestProof = InferenceProof<F, S, PCS, PCS, i8, i32>;
+
+const S_I: f32 = 0.004;
+const Z_I: i8 = -128;
+const S_1_W: f32 = 0.007;
+const S_1_O: f32 = 0.06;
+const Z_1_O: i8 = -100;
+const S_2_W: f32 = 0.01;
+const S_2_O: f32 = 0.2;
+const Z_2_O: i8 = 31;
+
+// Weights of shape (6, 3) and (3, 2), whose rows and columns are split into
+// several power-of-two blocks
+fn w1() -> QArray<i8> {
+    QArray::new((0..18).map(|x| 7 * x - 60).collect(), vec![6, 3])
+}
+
+fn b1() -> QArray<i32> {
+    QArray::new(vec![100, -20, 7], vec![3])
+}
+
+fn w2() -> QArray<i8> {
+    QArray::new(vec![5, -1, 9, 0, -7, 3], vec![3, 2])
+}
+
+fn b2() -> QArray<i32> {
+    QArray::new(vec![-3, 12], vec![2])
+}
+
+/// Two-layer perceptron on inputs of shape (2, 3)
+pub fn model() -> Model<i8, i32> {
+    ModelBuilder::new(vec![2, 3], QInfo::new(S_I, Z_I))
+        .flatten()
+        .bmm(w1(), b1(), QInfo::new(S_1_W, 0), QInfo::new(S_1_O, Z_1_O))
+        .relu()
+        .bmm(w2(), b2(), QInfo::new(S_2_W, 0), QInfo::new(S_2_O, Z_2_O))
+        .build()
+        .unwrap()
+}
+
+// Bias of the layer with weights of shape (6, 4), and weights and bias of
+// the layer of shape (4, 2) following it
+fn b3() -> QArray<i32> {
+    QArray::new(vec![100, -20, 7, 0], vec![4])
+}
+
+fn w4() -> QArray<i8> {
+    QArray::new(vec![12, -3, 0, 40, -25, 6, 9, -17], vec![4, 2])
+}
+
+fn b4() -> QArray<i32> {
+    QArray::new(vec![5, -8], vec![2])
+}
+
+// Two-layer perceptron on inputs of shape (2, 3) with the given first layer
+// (of output size 4)
+fn perceptron_with(
+    first_layer: impl FnOnce(ModelBuilder<i8, i32>) -> ModelBuilder<i8, i32>,
+) -> Model<i8, i32> {
+    first_layer(ModelBuilder::new(vec![2, 3], QInfo::new(S_I, Z_I)).flatten())
+        .relu()
+        .bmm(w4(), b4(), QInfo::new(S_2_W, 0), QInfo::new(S_2_O, Z_2_O))
+        .build()
+        .unwrap()
+}
+
+/// Two-layer perceptron on inputs of shape (2, 3) whose first layer keeps
+/// the weights (of shape (6, 4)) of absolute value above `threshold` (cf.
+/// `SparseBMMNode::from_dense`)
+pub fn sparse_model(weights: &QArray<i8>, threshold: i8) -> Model<i8, i32> {
+    perceptron_with(|builder| {
+        builder.sparse_bmm(
+            weights,
+            b3(),
+            threshold,
+            QInfo::new(S_1_W, 0),
+            QInfo::new(S_1_O, Z_1_O),
+        )
+    })
+}
+
+/// Same as `sparse_model`, with a dense first layer keeping all weights
+pub fn dense_model(weights: QArray<i8>) -> Model<i8, i32> {
+    perceptron_with(|builder| {
+        builder.bmm(
+            weights,
+            b3(),
+            QInfo::new(S_1_W, 0),
+            QInfo::new(S_1_O, Z_1_O),
+        )
+    })
+}
+
+/// `n` distinct inputs of `model`
+pub fn inputs(n: usize) -> Vec<QArray<i8>> {
+    (0..n)
+        .map(|i| {
+            QArray::new(
+                (0..6)
+                    .map(|j| (37 * (i + j) as i32 % 256 - 128) as i8)
+                    .collect(),
+                vec![2, 3],
+            )
+        })
+        .collect()
+}
+
+pub fn sponge() -> S {
+    poseidon_sponge()
+}
+
+/// Proving and verifying keys of `model` for batches of up to `batch_size`
+/// inferences
+pub fn keys(
+    model: &Model<i8, i32>,
+    batch_size: usize,
+) -> (ProvingKey<F, S, PCS, PCS>, VerifyingKey<F, S, PCS, PCS>) {
+    keys_with::<PCS>(model, batch_size)
+}
+
+/// Same as `keys`, with `P` as the PCS of both node values and node
+/// parameters
+pub fn keys_with<P>(
+    model: &Model<i8, i32>,
+    batch_size: usize,
+) -> (ProvingKey<F, S, P, P>, VerifyingKey<F, S, P, P>)
+where
+    P: SecurePCS<F, S> + 'static,
+{
+    let config = ProofConfig::new(P::security_bits());
+
+    let mut rng = test_rng();
+    let (ck, vk) = model
+        .setup_keys_for_batch::<F, S, P, _>(&config, batch_size, &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, P, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    (pk, vk)
+}
+
+/// Proof of the batch of inferences of `model` on `inputs`, starting from a
+/// fresh sponge (as the verifier does)
+pub fn prove<P>(
+    model: &Model<i8, i32>,
+    pk: &ProvingKey<F, S, P, P>,
+    inputs: Vec<QArray<i8>>,
+) -> InferenceProof<F, S, P, P, i8, i32>
+where
+    P: SecurePCS<F, S> + 'static,
+{
+    model.prove_inference_batch(pk, Some(&mut test_rng()), &mut sponge(), inputs)
+}
@@ -0,0 +1,232 @@
+use ark_bn254::Bn254;
+use ark_crypto_primitives::sponge::CryptographicSponge;
+use ark_std::test_rng;
+
+use hcs_common::{
+    poseidon_config, MultilinearKZG, ProofConfig, ProofConfigError, QArray, QTypeArray, SecurePCS,
+};
+use hcs_prover::ProveModel;
+use hcs_verifier::{VerifyModel, VerifyingKey};
+
+mod common;
+use common::*;
+
+// Batches of a single inference, of a size other than a power of two (which
+// the prover pads by repeating the first input) and of a power of two
+const BATCH_SIZES: [usize; 3] = [1, 3, 4];
+
+#[test]
+fn test_batch_proofs() {
+    let model = model();
+
+    for batch_size in BATCH_SIZES {
+        let (pk, vk) = keys(&model, batch_size);
+
+        let proof = prove(&model, &pk, inputs(batch_size));
+
+        assert_eq!(proof.inputs.len(), batch_size);
+        assert_eq!(proof.outputs.len(), batch_size);
+
+        for (output, input) in proof.outputs.iter().zip(inputs(batch_size)) {
+            assert_eq!(
+                output
+                    .clone()
+                    .unwrap_small()
+                    .compact_resize(model.output_shape.clone(), 0),
+                model.evaluate(input)
+            );
+        }
+
+        assert!(model.verify_inference(&vk, &mut sponge(), proof));
+    }
+}
+
+#[test]
+fn test_batch_altered_output() {
+    let model = model();
+
+    for batch_size in BATCH_SIZES {
+        let (pk, vk) = keys(&model, batch_size);
+
+        let mut proof = prove(&model, &pk, inputs(batch_size));
+
+        // Changing the first entry of the output of the last inference
+        let output = proof.outputs[batch_size - 1].clone().unwrap_small();
+        let shape = output.shape().clone();
+        let mut values = output.move_values();
+        values[0] = values[0].wrapping_add(1);
+
+        proof.outputs[batch_size - 1] = QTypeArray::S(QArray::new(values, shape));
+
+        assert!(!model.verify_inference(&vk, &mut sponge(), proof));
+    }
+}
+
+// Several proofs, one of which (at each position in turn) carries the input
+// opening proof of another one: its transcript is valid, but its openings
+// are not, and verify_many must single it out
+fn check_verify_many_tampered_proof<P>()
+where
+    P: SecurePCS<F, S> + 'static,
+{
+    const NUM_PROOFS: usize = 4;
+
+    let model = model();
+    let (pk, vk) = keys_with::<P>(&model, 1);
+
+    let proofs = || -> Vec<_> {
+        inputs(NUM_PROOFS)
+            .into_iter()
+            .map(|input| prove(&model, &pk, vec![input]))
+            .collect()
+    };
+
+    assert_eq!(model.verify_many(&vk, &sponge(), proofs()), Ok(()));
+
+    for i in 0..NUM_PROOFS {
+        let mut tampered = proofs();
+        tampered[i].input_opening_proofs =
+            tampered[(i + 1) % NUM_PROOFS].input_opening_proofs.clone();
+
+        assert_eq!(model.verify_many(&vk, &sponge(), tampered), Err(vec![i]));
+    }
+}
+
+// Openings are checked one by one with Ligero, and combined into a single
+// check with multilinear KZG (cf. SecurePCS::batch_check)
+#[test]
+fn test_verify_many_tampered_proof() {
+    check_verify_many_tampered_proof::<PCS>();
+    check_verify_many_tampered_proof::<MultilinearKZG<Bn254>>();
+}
+
+// Proofs with all node values packed into one commitment (cf.
+// NodeValueCommitment), in which the model input and output are opened
+// through the first and last slots
+#[test]
+fn test_packed_node_values() {
+    let model = model();
+
+    for batch_size in BATCH_SIZES {
+        let (mut pk, vk) = keys(&model, batch_size);
+        pk.pack_node_values = true;
+
+        let proof = prove(&model, &pk, inputs(batch_size));
+        assert_eq!(proof.node_value_commitments.len(), 1);
+        assert!(model.verify_inference(&vk, &mut sponge(), proof));
+
+        // Changing the first entry of the input or the output of the last
+        // inference
+        for tamper_input in [true, false] {
+            let mut proof = prove(&model, &pk, inputs(batch_size));
+
+            let values = if tamper_input {
+                &mut proof.inputs
+            } else {
+                &mut proof.outputs
+            };
+
+            let value = values[batch_size - 1].clone().unwrap_small();
+            let shape = value.shape().clone();
+            let mut entries = value.move_values();
+            entries[0] = entries[0].wrapping_add(1);
+
+            values[batch_size - 1] = QTypeArray::S(QArray::new(entries, shape));
+
+            assert!(!model.verify_inference(&vk, &mut sponge(), proof));
+        }
+    }
+}
+
+// The verifier only accepts the sponge and verifier keys of the
+// configuration of the verifying key
+#[test]
+fn test_config_enforced() {
+    let model = model();
+    let (pk, vk) = keys(&model, 1);
+
+    // A prover and verifier sharing a sponge with fewer rounds than the
+    // configured one
+    let mut weak_config = poseidon_config::<F>();
+    weak_config.partial_rounds -= 1;
+    let weak_sponge = S::new(&weak_config);
+
+    let proof = model.prove_inference_batch(
+        &pk,
+        Some(&mut test_rng()),
+        &mut weak_sponge.clone(),
+        inputs(1),
+    );
+    assert!(!model.verify_inference(&vk, &mut weak_sponge.clone(), proof));
+
+    // Ligero keys set up for another code rate than the configured one
+    let config = ProofConfig::new(PCS::security_bits()).with_ligero_rho_inv(8);
+
+    assert!(matches!(
+        VerifyingKey::<F, S, PCS, PCS>::new(
+            config,
+            vk.vk().clone(),
+            vk.wvk().clone(),
+            pk.node_coms.clone()
+        ),
+        Err(ProofConfigError::PCSKeyMismatch { .. })
+    ));
+}
+
+// Weights committed to with multilinear KZG over BN254, which reaches about
+// 100 bits, and node values with Ligero: the default configuration rejects
+// the former, so the configuration must be lowered to its level
+#[test]
+fn test_kzg_weights_ligero_values() {
+    type WPCS = MultilinearKZG<Bn254>;
+
+    let model = model();
+    let mut rng = test_rng();
+
+    assert!(matches!(
+        model.setup_weight_keys::<F, S, WPCS, _>(&ProofConfig::default(), &mut rng),
+        Err(ProofConfigError::PCSTooWeak { .. })
+    ));
+
+    let config = ProofConfig::new(<WPCS as SecurePCS<F, S>>::security_bits());
+    assert_eq!(config.security_bits(), 100);
+
+    let (ck, vk) = model
+        .setup_keys_for_batch::<F, S, PCS, _>(&config, 1, &mut rng)
+        .unwrap();
+    let (wck, wvk) = model
+        .setup_weight_keys::<F, S, WPCS, _>(&config, &mut rng)
+        .unwrap();
+
+    let pk = model.proving_key(config, ck, wck, None);
+    let vk = VerifyingKey::new(config, vk, wvk, pk.node_coms.clone()).unwrap();
+
+    let proof = model.prove_inference_batch(&pk, Some(&mut test_rng()), &mut sponge(), inputs(1));
+    assert!(model.verify_inference(&vk, &mut sponge(), proof));
+}
+
+// A verifying key lacking the commitment of a node, which the verifier would
+// otherwise skip
+#[test]
+fn test_missing_node_commitment() {
+    let model = model();
+    let (pk, vk) = keys(&model, 1);
+
+    let mut node_coms = pk.node_coms.clone();
+    node_coms.pop();
+
+    let short_vk = VerifyingKey::<F, S, PCS, PCS>::new(
+        *vk.config(),
+        vk.vk().clone(),
+        vk.wvk().clone(),
+        node_coms,
+    )
+    .unwrap();
+
+    assert!(!model.verify_inference(&short_vk, &mut sponge(), prove(&model, &pk, inputs(1))));
+    assert!(!model.verify_inference_layered(
+        &short_vk,
+        &mut sponge(),
+        model.prove_inference_layered(&pk, Some(&mut test_rng()), &mut sponge(), inputs(1))
+    ));
+}
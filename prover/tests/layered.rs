@@ -0,0 +1,83 @@
+use ark_ff::One;
+use ark_std::test_rng;
+
+use hcs_common::{LayeredInferenceProof, Model};
+use hcs_prover::{ProveModel, ProvingKey};
+use hcs_verifier::VerifyModel;
+
+mod common;
+use common::*;
+
+type TestLayeredProof = LayeredInferenceProof<F, S, PCS, PCS, i8, i32>;
+
+fn prove_layered(
+    model: &Model<i8, i32>,
+    pk: &ProvingKey<F, S, PCS, PCS>,
+    batch_size: usize,
+) -> TestLayeredProof {
+    model.prove_inference_layered(pk, Some(&mut test_rng()), &mut sponge(), inputs(batch_size))
+}
+
+#[test]
+fn test_layered_proofs() {
+    let model = model();
+
+    // The BMM nodes and the initial reshape are reduced, whereas the values
+    // around the requantisation and ReLU nodes are committed to
+    assert_eq!(model.layer_boundaries(), vec![2, 4, 5]);
+
+    for batch_size in [1, 3] {
+        let (pk, vk) = keys(&model, batch_size);
+
+        let proof = prove_layered(&model, &pk, batch_size);
+
+        assert_eq!(proof.boundary_commitments.len(), 3);
+        assert_eq!(
+            proof
+                .node_proofs
+                .iter()
+                .map(Option::is_some)
+                .collect::<Vec<_>>(),
+            vec![true, true, false, false, true, false]
+        );
+
+        assert!(model.verify_inference_layered(&vk, &mut sponge(), proof));
+    }
+}
+
+#[test]
+fn test_layered_tampered_boundary_value() {
+    let model = model();
+    let (pk, vk) = keys(&model, 1);
+
+    for k in 0..model.layer_boundaries().len() {
+        let mut proof = prove_layered(&model, &pk, 1);
+        proof.boundary_opening_values[k] += F::one();
+
+        assert!(!model.verify_inference_layered(&vk, &mut sponge(), proof));
+    }
+}
+
+#[test]
+fn test_layered_dropped_node_proof() {
+    let model = model();
+    let (pk, vk) = keys(&model, 1);
+
+    // Replacing the proof of a node with a claim reduction by None
+    for i in 0..model.nodes.len() {
+        if !model.nodes[i].has_claim_reduction() {
+            continue;
+        }
+
+        let mut proof = prove_layered(&model, &pk, 1);
+        proof.node_proofs[i] = None;
+
+        assert!(!model.verify_inference_layered(&vk, &mut sponge(), proof));
+    }
+
+    // Removing it altogether, which shifts the remaining ones
+    let mut proof = prove_layered(&model, &pk, 1);
+    proof.node_proofs.remove(1);
+
+    assert!(!model.verify_inference_layered(&vk, &mut sponge(), proof));
+}
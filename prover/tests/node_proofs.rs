@@ -0,0 +1,48 @@
+use hcs_common::{BMMNodeProof, Node, NodeProof};
+use hcs_verifier::VerifyModel;
+
+mod common;
+use common::*;
+
+type TestBMMNodeProof = BMMNodeProof<F, S, PCS, PCS>;
+
+// Index of the first (dense) BMM node of the model
+fn bmm_index() -> usize {
+    model()
+        .nodes
+        .iter()
+        .position(|node| matches!(node, Node::BMM(_)))
+        .unwrap()
+}
+
+#[test]
+fn test_bmm_truncated_opening_proofs() {
+    let model = model();
+    let (pk, vk) = keys(&model, 1);
+
+    let proof = prove(&model, &pk, inputs(1));
+    assert!(model.verify_inference(&vk, &mut sponge(), proof));
+
+    // Dropping the last opening proof of any kind leaves the remaining ones
+    // matched to their blocks, but one claim unchecked
+    let truncations: [fn(&mut TestBMMNodeProof); 2] = [
+        |p| {
+            p.input_opening_proofs.pop();
+        },
+        |p| {
+            p.weight_opening_proofs.pop();
+        },
+    ];
+
+    for truncate in truncations {
+        let mut proof = prove(&model, &pk, inputs(1));
+
+        let NodeProof::BMM(bmm_proof) = &mut proof.node_proofs[bmm_index()] else {
+            panic!("Expected BMMNodeProof");
+        };
+
+        truncate(bmm_proof);
+
+        assert!(!model.verify_inference(&vk, &mut sponge(), proof));
+    }
+}
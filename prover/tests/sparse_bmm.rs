@@ -0,0 +1,124 @@
+use ark_ff::One;
+use ark_std::test_rng;
+
+use hcs_common::{LayeredInferenceProof, Model, Node, NodeProof, QArray, SparseBMMNode};
+use hcs_prover::ProveModel;
+use hcs_verifier::VerifyModel;
+
+mod common;
+use common::*;
+
+const THRESHOLD: i8 = 40;
+
+// Weights of which only those of absolute value above THRESHOLD (six out of
+// 24) are kept, none of them in row 2 or column 1
+fn weights() -> QArray<i8> {
+    QArray::new(
+        vec![
+            10, -5, 90, 3, //
+            -70, 8, -2, 100, //
+            12, -30, 25, -40, //
+            0, 15, -60, 7, //
+            50, 20, -9, -11, //
+            -4, 33, 1, 80,
+        ],
+        vec![6, 4],
+    )
+}
+
+fn pruned_weights() -> QArray<i8> {
+    QArray::new(
+        vec![
+            0, 0, 90, 0, //
+            -70, 0, 0, 100, //
+            0, 0, 0, 0, //
+            0, 0, -60, 0, //
+            50, 0, 0, 0, //
+            0, 0, 0, 80,
+        ],
+        vec![6, 4],
+    )
+}
+
+// Index of the sparse BMM node of the model
+fn sparse_index(model: &Model<i8, i32>) -> usize {
+    model
+        .nodes
+        .iter()
+        .position(|node| matches!(node, Node::SparseBMM(_)))
+        .unwrap()
+}
+
+fn sparse_node(model: &Model<i8, i32>) -> &SparseBMMNode<i8, i32> {
+    match &model.nodes[sparse_index(model)] {
+        Node::SparseBMM(node) => node,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_sparse_pruning() {
+    let model = sparse_model(&weights(), THRESHOLD);
+    let node = sparse_node(&model);
+
+    assert_eq!(node.num_entries(), 6);
+    assert_eq!(node.to_dense(), pruned_weights());
+
+    // The node computes the same function as a dense one with the pruned
+    // weights, including on the entry-free row and column
+    let dense = dense_model(pruned_weights());
+
+    for input in inputs(4) {
+        assert_eq!(model.evaluate(input.clone()), dense.evaluate(input));
+    }
+}
+
+#[test]
+fn test_sparse_proofs() {
+    let model = sparse_model(&weights(), THRESHOLD);
+
+    for batch_size in [1, 3] {
+        let (pk, vk) = keys(&model, batch_size);
+
+        let proof = prove(&model, &pk, inputs(batch_size));
+        assert!(model.verify_inference(&vk, &mut sponge(), proof));
+
+        let layered_proof: LayeredInferenceProof<F, S, PCS, PCS, i8, i32> = model
+            .prove_inference_layered(
+                &pk,
+                Some(&mut test_rng()),
+                &mut sponge(),
+                inputs(batch_size),
+            );
+        assert!(model.verify_inference_layered(&vk, &mut sponge(), layered_proof));
+    }
+}
+
+#[test]
+fn test_sparse_altered_entry_value() {
+    let model = sparse_model(&weights(), THRESHOLD);
+    let (_, vk) = keys(&model, 1);
+
+    // A prover using a model in which one of the committed entries has a
+    // different value (keeping the sparsity pattern) is caught by the
+    // openings of the committed weights
+    let mut altered_weights = weights().move_values();
+    altered_weights[2] = 91;
+    let altered_model = sparse_model(&QArray::new(altered_weights, vec![6, 4]), THRESHOLD);
+    let (altered_pk, _) = keys(&altered_model, 1);
+
+    let proof = prove(&altered_model, &altered_pk, inputs(1));
+    assert!(!model.verify_inference(&vk, &mut sponge(), proof));
+
+    // So is an altered opening value of the entry values
+    let (pk, _) = keys(&model, 1);
+    let mut proof = prove(&model, &pk, inputs(1));
+
+    let NodeProof::SparseBMM(sparse_proof) = &mut proof.node_proofs[sparse_index(&model)] else {
+        panic!("Expected SparseBMMNodeProof");
+    };
+
+    sparse_proof.reduction_proof.entry_opening_values[0] += F::one();
+
+    assert!(!model.verify_inference(&vk, &mut sponge(), proof));
+}
@@ -0,0 +1,98 @@
+// Registry of the verifying operations of custom nodes (cf. `CustomNodeOps`).
+// A custom node is only known to the library as a trait object, so its type
+// is recovered at verification time by looking up a cast registered for it
+// (and for the field, sponge and PCSs used) by the crate defining it.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{CustomNode, InnerType, Poly};
+
+use crate::NodeOpsVerify;
+
+// Recovers the verifying operations of a custom node of the type the cast was
+// registered for, returning None on nodes of any other type
+type VerifierCast<F, S, PCS, WPCS> = fn(&dyn Any) -> Option<&dyn NodeOpsVerify<F, S, PCS, WPCS>>;
+
+// Registered casts, indexed by the node type and the type of the cast itself
+// (which determines the remaining type parameters)
+type Registry = HashMap<(TypeId, TypeId), Box<dyn Any + Send + Sync>>;
+
+static CUSTOM_VERIFIERS: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+/// Registers the verifying operations of custom nodes of type `N` for the
+/// field `F`, sponge `S` and PCSs `PCS` and `WPCS` of node values and
+/// parameters respectively. This must be called before verifying inference
+/// of any model containing such nodes.
+pub fn register_custom_verifier<F, S, PCS, WPCS, N>()
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    N: NodeOpsVerify<F, S, PCS, WPCS> + 'static,
+{
+    let key = (
+        TypeId::of::<N>(),
+        TypeId::of::<VerifierCast<F, S, PCS, WPCS>>(),
+    );
+    let cast: VerifierCast<F, S, PCS, WPCS> = cast_node::<F, S, PCS, WPCS, N>;
+
+    CUSTOM_VERIFIERS
+        .get_or_init(Default::default)
+        .write()
+        .unwrap()
+        .insert(key, Box::new(cast));
+}
+
+/// Returns the verifying operations registered for the type of `node`
+pub(crate) fn custom_verifier<F, S, PCS, WPCS, ST, LT>(
+    node: &CustomNode<ST, LT>,
+) -> &dyn NodeOpsVerify<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType,
+    LT: InnerType,
+{
+    let key = (
+        node.as_any().type_id(),
+        TypeId::of::<VerifierCast<F, S, PCS, WPCS>>(),
+    );
+
+    let cast = CUSTOM_VERIFIERS.get().and_then(|registry| {
+        registry
+            .read()
+            .unwrap()
+            .get(&key)
+            .and_then(|cast| cast.downcast_ref::<VerifierCast<F, S, PCS, WPCS>>())
+            .copied()
+    });
+
+    match cast.and_then(|cast| cast(node.as_any())) {
+        Some(verifier) => verifier,
+        None => panic!(
+            "No verifier registered for custom node of type {}",
+            node.type_name()
+        ),
+    }
+}
+
+fn cast_node<F, S, PCS, WPCS, N>(node: &dyn Any) -> Option<&dyn NodeOpsVerify<F, S, PCS, WPCS>>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    N: NodeOpsVerify<F, S, PCS, WPCS> + 'static,
+{
+    node.downcast_ref::<N>()
+        .map(|node| node as &dyn NodeOpsVerify<F, S, PCS, WPCS>)
+}
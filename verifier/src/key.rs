@@ -0,0 +1,77 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{NodeCommitment, Poly, ProofConfig, ProofConfigError, SecurePCS};
+
+/// Everything the verifier needs about a model beyond its plain description
+/// (cf. `ProvingKey` on the prover side): the proof configuration, the
+/// verifier keys of the PCSs of node values and node parameters and the
+/// commitments to the parameters of each node. Proofs are only accepted if
+/// they were produced under the same configuration.
+pub struct VerifyingKey<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    config: ProofConfig,
+    vk: PCS::VerifierKey,
+    wvk: WPCS::VerifierKey,
+    node_coms: Vec<NodeCommitment<F, S, WPCS>>,
+}
+
+impl<F, S, PCS, WPCS> VerifyingKey<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    /// Builds the verifying key from the verifier keys `vk` and `wvk` (cf.
+    /// `Model::setup_keys` and `Model::setup_weight_keys`), which must have
+    /// been set up for `config`, and the node commitments of the proving key.
+    /// Fails if the field or either PCS do not meet the security level of
+    /// `config`, or if either verifier key was not set up for it.
+    pub fn new(
+        config: ProofConfig,
+        vk: PCS::VerifierKey,
+        wvk: WPCS::VerifierKey,
+        node_coms: Vec<NodeCommitment<F, S, WPCS>>,
+    ) -> Result<Self, ProofConfigError>
+    where
+        PCS: SecurePCS<F, S>,
+        WPCS: SecurePCS<F, S>,
+    {
+        config.check::<F, S, PCS, WPCS>()?;
+        config.check_verifier_key::<F, S, PCS>(&vk)?;
+        config.check_verifier_key::<F, S, WPCS>(&wvk)?;
+
+        Ok(Self {
+            config,
+            vk,
+            wvk,
+            node_coms,
+        })
+    }
+
+    pub fn config(&self) -> &ProofConfig {
+        &self.config
+    }
+
+    /// Verifier key of the PCS used for node values
+    pub fn vk(&self) -> &PCS::VerifierKey {
+        &self.vk
+    }
+
+    /// Verifier key of the PCS used for node parameters
+    pub fn wvk(&self) -> &WPCS::VerifierKey {
+        &self.wvk
+    }
+
+    /// Commitments to the parameters of each node
+    pub fn node_coms(&self) -> &[NodeCommitment<F, S, WPCS>] {
+        &self.node_coms
+    }
+}
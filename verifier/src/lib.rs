@@ -1,63 +1,144 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_poly_commit::PolynomialCommitment;
 
-use hcs_common::{InnerType, Node, NodeCommitment, NodeProof, Poly};
+use hcs_common::{
+    FieldEncoding, InnerType, Node, NodeCommitment, NodeProof, NodeReductionProof,
+    NodeValueCommitment, Poly,
+};
 
+use custom::custom_verifier;
+
+mod custom;
+mod key;
 mod model;
 mod nodes;
+mod opening;
+mod util;
 
+pub use custom::register_custom_verifier;
+pub use hcs_common::OpeningClaim;
+pub use key::VerifyingKey;
 pub use model::VerifyModel;
+pub use opening::{check_opening_claims, OpeningClaims};
 
-pub trait NodeOpsVerify<F, S, PCS>
+/// SNARK-specific verification that each node must implement. As for
+/// proving, node values are committed to with `PCS` and node parameters with
+/// `WPCS`.
+pub trait NodeOpsVerify<F, S, PCS, WPCS>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
+    /// Verify a node proof. The node values of all inferences in a batch are
+    /// committed to together, with the last `num_batch_vars` variables of the
+    /// input and output MLEs indexing the inference (zero for a single one).
+    /// Their openings must be claimed through `OpeningClaim::node_value`,
+    /// since they may be packed with those of other nodes.
+    /// PCS openings are not checked here but pushed to `claims` (according to
+    /// their PCS), and only the rest of the proof determines the returned
+    /// value.
     fn verify(
         &self,
-        vk: &PCS::VerifierKey,
         s: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        proof: NodeProof<F, S, PCS>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        input_com: &NodeValueCommitment<F, S, PCS>,
+        output_com: &NodeValueCommitment<F, S, PCS>,
+        proof: NodeProof<F, S, PCS, WPCS>,
+        num_batch_vars: usize,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
     ) -> bool;
+
+    /// Verify the reduction of the claim that the output MLE takes the value
+    /// `output_value` at `output_point` to a claim about the input MLE,
+    /// returning the point and value of the latter (or `None` if the proof is
+    /// invalid). As in `verify`, PCS openings are pushed to `claims`. This
+    /// must only be called on nodes with a claim reduction (cf.
+    /// `Node::has_claim_reduction`), which `ReLUNode` and `RequantiseBMMNode`
+    /// lack.
+    fn verify_reduction(
+        &self,
+        s: &mut S,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        output_point: &[F],
+        output_value: F,
+        proof: NodeReductionProof<F, S, PCS, WPCS>,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)>;
 }
 
-impl<F, S, PCS, ST, LT> NodeOpsVerify<F, S, PCS> for Node<ST, LT>
+// Custom nodes are dispatched to the operations registered for their type
+// (cf. `register_custom_verifier`), which requires the type parameters to be
+// 'static
+impl<F, S, PCS, WPCS, ST, LT> NodeOpsVerify<F, S, PCS, WPCS> for Node<ST, LT>
 where
-    F: PrimeField + Absorb + From<ST>,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    F: PrimeField + Absorb + FieldEncoding<ST>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
     fn verify(
         &self,
-        vk: &PCS::VerifierKey,
         s: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        proof: NodeProof<F, S, PCS>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        input_com: &NodeValueCommitment<F, S, PCS>,
+        output_com: &NodeValueCommitment<F, S, PCS>,
+        proof: NodeProof<F, S, PCS, WPCS>,
+        num_batch_vars: usize,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
     ) -> bool {
-        node_as_node_ops_snark(self).verify(vk, s, node_com, input_com, output_com, proof)
+        node_as_node_ops_snark(self).verify(
+            s,
+            node_com,
+            input_com,
+            output_com,
+            proof,
+            num_batch_vars,
+            claims,
+        )
+    }
+
+    fn verify_reduction(
+        &self,
+        s: &mut S,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        output_point: &[F],
+        output_value: F,
+        proof: NodeReductionProof<F, S, PCS, WPCS>,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)> {
+        node_as_node_ops_snark(self).verify_reduction(
+            s,
+            node_com,
+            output_point,
+            output_value,
+            proof,
+            claims,
+        )
     }
 }
 
-fn node_as_node_ops_snark<F, S, PCS, ST, LT>(node: &Node<ST, LT>) -> &dyn NodeOpsVerify<F, S, PCS>
+fn node_as_node_ops_snark<F, S, PCS, WPCS, ST, LT>(
+    node: &Node<ST, LT>,
+) -> &dyn NodeOpsVerify<F, S, PCS, WPCS>
 where
-    F: PrimeField + Absorb + From<ST>,
-    S: CryptographicSponge,
-    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    F: PrimeField + Absorb + FieldEncoding<ST>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
     ST: InnerType,
+    LT: InnerType,
 {
     match node {
         Node::BMM(fc) => fc,
+        Node::SparseBMM(fc) => fc,
         Node::RequantiseBMM(r) => r,
         Node::ReLU(r) => r,
         Node::Reshape(r) => r,
+        Node::Custom(c) => custom_verifier(c),
     }
 }
@@ -1,42 +1,182 @@
-use crate::NodeOpsVerify;
+use crate::{check_opening_claims, NodeOpsVerify, OpeningClaim, OpeningClaims, VerifyingKey};
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
 use ark_poly::Polynomial;
 use ark_poly_commit::PolynomialCommitment;
-use ark_std::log2;
+use ark_std::{log2, ops::Range};
 
-use hcs_common::{InferenceProof, InnerType, Model, NodeCommitment, Poly};
+use hcs_common::{
+    FieldEncoding, InferenceProof, InnerType, LayeredInferenceProof, Model, NodeValueCommitment,
+    Poly, QTypeArray, SecurePCS,
+};
 
-pub trait VerifyModel<F, S, PCS, ST, LT>
+/// Inference verification. The openings of node values are checked with the
+/// verifier key of `PCS`, and those of node parameters with that of `WPCS`
+/// (cf. `Model::setup_weight_keys`), both of which are part of the
+/// `VerifyingKey`.
+pub trait VerifyModel<F, S, PCS, WPCS, ST, LT>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
+    /// Verifies a proof of a (batch of) inference(s). `sponge` must be the
+    /// Poseidon sponge of the configuration of `vk` (cf.
+    /// `ProofConfig::check_sponge`), in the same state as the one the proof
+    /// was produced with; proofs are rejected otherwise.
     fn verify_inference(
         &self,
-        vk: &PCS::VerifierKey,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
         sponge: &mut S,
-        node_commitments: &Vec<NodeCommitment<F, S, PCS>>,
-        inference_proof: InferenceProof<F, S, PCS, ST, LT>,
+        inference_proof: InferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> bool;
+
+    /// Verifies several independent inference proofs of the model, each of
+    /// which must have been produced from a copy of `sponge` in its current
+    /// state (as for `verify_inference`). The transcripts of all proofs are
+    /// verified first, and the PCS openings of all of them are then checked
+    /// together. If verification fails, the indices of the offending proofs
+    /// are returned.
+    fn verify_many(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &S,
+        inference_proofs: Vec<InferenceProof<F, S, PCS, WPCS, ST, LT>>,
+    ) -> Result<(), Vec<usize>>;
+
+    /// Verifies a layered proof of a batch of inferences (cf.
+    /// `ProveModel::prove_inference_layered`)
+    fn verify_inference_layered(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>,
     ) -> bool;
 }
 
-impl<F, S, PCS, ST, LT> VerifyModel<F, S, PCS, ST, LT> for Model<ST, LT>
+// Functionality shared by verify_inference and verify_many, not exposed as
+// part of the trait
+trait VerifyModelClaims<F, S, PCS, WPCS, ST, LT>
 where
-    F: PrimeField + Absorb + From<ST> + From<LT>,
+    F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    // Verifies everything in the proof except for the PCS openings, which are
+    // returned as claims. Returns None if the transcript is invalid.
+    fn inference_claims(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: InferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> Option<OpeningClaims<F, S, PCS, WPCS>>;
+
+    // Same as inference_claims, for layered proofs
+    fn layered_inference_claims(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> Option<OpeningClaims<F, S, PCS, WPCS>>;
+}
+
+impl<F, S, PCS, WPCS, ST, LT> VerifyModel<F, S, PCS, WPCS, ST, LT> for Model<ST, LT>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+    S: CryptographicSponge + 'static,
+    PCS: SecurePCS<F, S> + 'static,
+    WPCS: SecurePCS<F, S> + 'static,
     ST: InnerType + TryFrom<LT>,
     LT: InnerType + From<ST>,
 {
     fn verify_inference(
         &self,
-        vk: &PCS::VerifierKey,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: InferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> bool {
+        match self.inference_claims(vk, sponge, inference_proof) {
+            Some(claims) => {
+                check_opening_claims(vk.vk(), vk.wvk(), &claims.values, &claims.weights)
+            }
+            None => false,
+        }
+    }
+
+    fn verify_many(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &S,
+        inference_proofs: Vec<InferenceProof<F, S, PCS, WPCS, ST, LT>>,
+    ) -> Result<(), Vec<usize>> {
+        let mut failed = Vec::new();
+
+        // Claims of all proofs, together with the ranges of those of each one
+        // (about node values and node parameters)
+        let mut claims = OpeningClaims::new();
+        let mut proof_ranges = Vec::new();
+
+        // Transcripts are verified first, so that proofs failing them do not
+        // incur any PCS work
+        for (i, inference_proof) in inference_proofs.into_iter().enumerate() {
+            match self.inference_claims(vk, &mut sponge.clone(), inference_proof) {
+                Some(proof_claims) => {
+                    let start = (claims.values.len(), claims.weights.len());
+                    claims.append(proof_claims);
+                    proof_ranges.push((
+                        i,
+                        start.0..claims.values.len(),
+                        start.1..claims.weights.len(),
+                    ));
+                }
+                None => failed.push(i),
+            }
+        }
+
+        // All openings are checked at once, and only if this fails are the
+        // proofs at fault identified
+        failed.extend(failing_proofs(vk, &claims, &proof_ranges));
+        failed.sort_unstable();
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+
+    fn verify_inference_layered(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
         sponge: &mut S,
-        node_commitments: &Vec<NodeCommitment<F, S, PCS>>,
-        inference_proof: InferenceProof<F, S, PCS, ST, LT>,
+        inference_proof: LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>,
     ) -> bool {
+        match self.layered_inference_claims(vk, sponge, inference_proof) {
+            Some(claims) => {
+                check_opening_claims(vk.vk(), vk.wvk(), &claims.values, &claims.weights)
+            }
+            None => false,
+        }
+    }
+}
+
+impl<F, S, PCS, WPCS, ST, LT> VerifyModelClaims<F, S, PCS, WPCS, ST, LT> for Model<ST, LT>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST> + FieldEncoding<LT>,
+    S: CryptographicSponge + 'static,
+    PCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    WPCS: PolynomialCommitment<F, Poly<F>, S> + 'static,
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    fn inference_claims(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: InferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> Option<OpeningClaims<F, S, PCS, WPCS>> {
         let InferenceProof {
             inputs,
             outputs,
@@ -46,6 +186,46 @@ where
             output_opening_proofs,
         } = inference_proof;
 
+        // The proof may cover a batch of inferences, whose node values are
+        // committed to together (cf. ProveModel::prove_inference_batch). The
+        // verifying key must hold the commitment of every node, as the nodes
+        // are zipped with them below.
+        if inputs.is_empty()
+            || inputs.len() != outputs.len()
+            || node_proofs.len() != self.nodes.len()
+            || vk.node_coms().len() != self.nodes.len()
+        {
+            return None;
+        }
+
+        let num_batch_vars = log2(inputs.len().next_power_of_two()) as usize;
+
+        // The node values are committed to either separately or packed into a
+        // single MLE (cf. NodeValueCommitment), which the number of
+        // commitments tells apart
+        let value_coms = if node_value_commitments.len() == self.nodes.len() + 1 {
+            NodeValueCommitment::separate(&node_value_commitments)
+        } else if node_value_commitments.len() == 1 {
+            NodeValueCommitment::packed(
+                &node_value_commitments[0],
+                &self.node_value_num_vars(num_batch_vars),
+            )
+        } else {
+            return None;
+        };
+
+        // The sponge must be the one of the configuration, since a weaker
+        // one would undermine all challenges
+        if vk.config().check_sponge::<F, S>(sponge).is_err() {
+            return None;
+        }
+
+        let mut claims = OpeningClaims::new();
+
+        // Binding the proof to the configuration of the verifying key (cf.
+        // ProofConfig)
+        sponge.absorb(vk.config());
+
         // Absorb all commitments into the sponge
         sponge.absorb(&node_value_commitments);
 
@@ -56,35 +236,38 @@ where
         for (((node, node_com), io_com), node_proof) in self
             .nodes
             .iter()
-            .zip(node_commitments.iter())
-            .zip(node_value_commitments.windows(2))
+            .zip(vk.node_coms().iter())
+            .zip(value_coms.windows(2))
             .zip(node_proofs.into_iter())
         {
-            if !node.verify(vk, sponge, node_com, &io_com[0], &io_com[1], node_proof) {
-                return false;
+            if !node.verify(
+                sponge,
+                node_com,
+                &io_com[0],
+                &io_com[1],
+                node_proof,
+                num_batch_vars,
+                &mut claims,
+            ) {
+                return None;
             }
         }
 
+        // Verifying that each actual input was honestly padded with zeros
+        if !honestly_padded_io(self, &inputs, &outputs) {
+            return None;
+        }
+
         // Verifying model IO
         // TODO maybe this can be made more efficient by not committing to the
         // output nodes and instead working witht their plain values all along,
         // but that would require messy node-by-node handling
-        let input_node_com = node_value_commitments.first().unwrap();
-        let input_node_qarray = inputs[0].ref_small();
-        let input_node_f: Vec<F> = input_node_qarray
-            .values()
-            .iter()
-            .map(|x| F::from(*x))
-            .collect();
+        let input_node_com = value_coms.first().unwrap();
+        let input_node_f = stacked_values::<F, ST, LT>(&inputs, num_batch_vars);
 
-        let output_node_com = node_value_commitments.last().unwrap();
+        let output_node_com = value_coms.last().unwrap();
         // TODO maybe it's better to save this as F in the proof?
-        let output_node_f: Vec<F> = outputs[0]
-            .ref_small()
-            .values()
-            .iter()
-            .map(|x| F::from(*x))
-            .collect();
+        let output_node_f = stacked_values::<F, ST, LT>(&outputs, num_batch_vars);
 
         // Absorb the model IO output and squeeze the challenge point
         // Absorb the plain output and squeeze the challenge point
@@ -95,16 +278,6 @@ where
         let output_challenge_point =
             sponge.squeeze_field_elements(log2(output_node_f.len()) as usize);
 
-        // Verifying that the actual input was honestly padded with zeros
-        let padded_input_shape = input_node_qarray.shape().clone();
-        let honestly_padded_input = input_node_qarray
-            .compact_resize(self.input_shape().clone(), ST::ZERO)
-            .compact_resize(padded_input_shape, ST::ZERO);
-
-        if honestly_padded_input.values() != input_node_qarray.values() {
-            return false;
-        }
-
         // The verifier must evaluate the MLE given by the plain input values
         let input_node_eval =
             Poly::from_evaluations_vec(log2(input_node_f.len()) as usize, input_node_f)
@@ -114,31 +287,299 @@ where
                 .evaluate(&output_challenge_point);
 
         // The computed values should match the openings of the corresponding
-        // vectors
-        // TODO rng, None
-        if !PCS::check(
-            vk,
-            [input_node_com],
+        // vectors, which are proved on forks of the transcript
+        let (Some(input_opening_proof), Some(output_opening_proof)) = (
+            input_opening_proofs.into_iter().next(),
+            output_opening_proofs.into_iter().next(),
+        ) else {
+            return None;
+        };
+
+        claims.values.push(OpeningClaim::node_value(
+            input_node_com,
             &input_challenge_point,
-            [input_node_eval],
-            &input_opening_proofs[0],
+            input_node_eval,
+            input_opening_proof,
+            sponge,
+        ));
+
+        claims.values.push(OpeningClaim::node_value(
+            output_node_com,
+            &output_challenge_point,
+            output_node_eval,
+            output_opening_proof,
             sponge,
-            None,
-        )
+        ));
+
+        Some(claims)
+    }
+
+    fn layered_inference_claims(
+        &self,
+        vk: &VerifyingKey<F, S, PCS, WPCS>,
+        sponge: &mut S,
+        inference_proof: LayeredInferenceProof<F, S, PCS, WPCS, ST, LT>,
+    ) -> Option<OpeningClaims<F, S, PCS, WPCS>> {
+        let LayeredInferenceProof {
+            inputs,
+            outputs,
+            boundary_commitments,
+            node_proofs,
+            mut boundary_opening_values,
+            mut boundary_opening_proofs,
+        } = inference_proof;
+
+        let boundaries = self.layer_boundaries();
+
+        // Exactly the nodes with a claim reduction must come with a proof,
+        // the verifying key must hold the commitment of every node and each
+        // boundary value must come with one commitment and one opening
+        if inputs.is_empty()
+            || inputs.len() != outputs.len()
+            || node_proofs.len() != self.nodes.len()
+            || vk.node_coms().len() != self.nodes.len()
+            || node_proofs
+                .iter()
+                .zip(self.nodes.iter())
+                .any(|(proof, node)| proof.is_some() != node.has_claim_reduction())
+            || [
+                boundary_commitments.len(),
+                boundary_opening_values.len(),
+                boundary_opening_proofs.len(),
+            ]
+            .iter()
+            .any(|len| *len != boundaries.len())
+        {
+            return None;
+        }
+
+        // Verifying that each actual input was honestly padded with zeros
+        if !honestly_padded_io(self, &inputs, &outputs) {
+            return None;
+        }
+
+        // As in inference_claims, the sponge must be that of the configuration
+        if vk.config().check_sponge::<F, S>(sponge).is_err() {
+            return None;
+        }
+
+        let num_batch_vars = log2(inputs.len().next_power_of_two()) as usize;
+        let value_num_vars = self.node_value_num_vars(num_batch_vars);
+
+        let input_node_f = stacked_values::<F, ST, LT>(&inputs, num_batch_vars);
+        let output_node_f = stacked_values::<F, ST, LT>(&outputs, num_batch_vars);
+
+        // Absorb the configuration (cf. inference_claims), the plain model IO
+        // and the commitments into the sponge
+        sponge.absorb(vk.config());
+        sponge.absorb(&input_node_f);
+        sponge.absorb(&output_node_f);
+        sponge.absorb(&boundary_commitments);
+
+        let mut claims = OpeningClaims::new();
+
+        // Claim (point and value) about the output of the current node, if
+        // any, as passed on by the claim reduction of the following one
+        let mut claim: Option<(Vec<F>, F)> = None;
+
+        // The boundary openings are consumed from the last one, as the nodes
+        // are verified from the last one
+        for (k, ((node, node_com), node_proof)) in self
+            .nodes
+            .iter()
+            .zip(vk.node_coms().iter())
+            .zip(node_proofs)
+            .enumerate()
+            .rev()
+        {
+            let Some(node_proof) = node_proof else {
+                // A claim reaching the output of a node without a claim
+                // reduction is checked by opening the committed values
+                if let Some((point, value)) = claim.take() {
+                    let com = &boundary_commitments[boundaries.binary_search(&(k + 1)).ok()?];
+
+                    if boundary_opening_values.pop()? != value {
+                        return None;
+                    }
+
+                    claims.values.push(OpeningClaim::new(
+                        vec![com.clone()],
+                        point,
+                        vec![value],
+                        boundary_opening_proofs.pop()?,
+                        sponge,
+                    ));
+                }
+
+                continue;
+            };
+
+            // Otherwise, a new claim is started at a random point if there is
+            // none: the verifier evaluates the model output itself, whereas
+            // committed values are opened
+            let (output_point, output_value) = match claim.take() {
+                Some(claim) => claim,
+                None => {
+                    let point: Vec<F> = sponge.squeeze_field_elements(value_num_vars[k + 1]);
+
+                    let value = if k + 1 == self.nodes.len() {
+                        Poly::from_evaluations_slice(point.len(), &output_node_f).evaluate(&point)
+                    } else {
+                        let com = &boundary_commitments[boundaries.binary_search(&(k + 1)).ok()?];
+                        let value = boundary_opening_values.pop()?;
+
+                        sponge.absorb(&value);
+
+                        claims.values.push(OpeningClaim::new(
+                            vec![com.clone()],
+                            point.clone(),
+                            vec![value],
+                            boundary_opening_proofs.pop()?,
+                            sponge,
+                        ));
+
+                        value
+                    };
+
+                    (point, value)
+                }
+            };
+
+            claim = Some(node.verify_reduction(
+                sponge,
+                node_com,
+                &output_point,
+                output_value,
+                node_proof,
+                &mut claims,
+            )?);
+        }
+
+        // Any remaining claim is about the model input, which the verifier
+        // evaluates itself
+        if let Some((point, value)) = claim {
+            let input_node_mle = Poly::from_evaluations_vec(value_num_vars[0], input_node_f);
+
+            if point.len() != value_num_vars[0] || input_node_mle.evaluate(&point) != value {
+                return None;
+            }
+        }
+
+        Some(claims)
+    }
+}
+
+// Indices of the proofs whose opening claims do not hold, given the claims of
+// several proofs and, for each proof, its index and the (consecutive) ranges
+// of its claims about node values and node parameters. The claims of all
+// proofs are checked together and, if they fail, those of each half are
+// checked separately, so that k failing proofs out of n are found with
+// O(k log(n)) batch checks.
+fn failing_proofs<F, S, PCS, WPCS>(
+    vk: &VerifyingKey<F, S, PCS, WPCS>,
+    claims: &OpeningClaims<F, S, PCS, WPCS>,
+    proof_ranges: &[(usize, Range<usize>, Range<usize>)],
+) -> Vec<usize>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+    WPCS: SecurePCS<F, S>,
+{
+    let (Some((first, first_values, first_weights)), Some((_, last_values, last_weights))) =
+        (proof_ranges.first(), proof_ranges.last())
+    else {
+        return Vec::new();
+    };
+
+    if check_opening_claims(
+        vk.vk(),
+        vk.wvk(),
+        &claims.values[first_values.start..last_values.end],
+        &claims.weights[first_weights.start..last_weights.end],
+    ) {
+        return Vec::new();
+    }
+
+    if proof_ranges.len() == 1 {
+        return vec![*first];
+    }
+
+    let (left, right) = proof_ranges.split_at(proof_ranges.len() / 2);
+
+    [
+        failing_proofs(vk, claims, left),
+        failing_proofs(vk, claims, right),
+    ]
+    .concat()
+}
+
+// Checks that the plain model inputs and outputs have the padded shapes and
+// that each input was honestly padded with zeros
+fn honestly_padded_io<ST, LT>(
+    model: &Model<ST, LT>,
+    inputs: &[QTypeArray<ST, LT>],
+    outputs: &[QTypeArray<ST, LT>],
+) -> bool
+where
+    ST: InnerType + TryFrom<LT>,
+    LT: InnerType + From<ST>,
+{
+    let padded_input_shape: Vec<usize> = model
+        .input_shape()
+        .iter()
+        .map(|x| x.next_power_of_two())
+        .collect();
+
+    let padded_output_shape: Vec<usize> = model
+        .nodes
+        .last()
         .unwrap()
+        .padded_shape_log()
+        .into_iter()
+        .map(|x| 1 << x)
+        .collect();
+
+    for (input, output) in inputs.iter().zip(outputs.iter()) {
+        let input_qarray = input.ref_small();
+
+        if *input_qarray.shape() != padded_input_shape
+            || *output.ref_small().shape() != padded_output_shape
         {
             return false;
         }
 
-        PCS::check(
-            vk,
-            [output_node_com],
-            &output_challenge_point,
-            [output_node_eval],
-            &output_opening_proofs[0],
-            sponge,
-            None,
-        )
-        .unwrap()
+        let honestly_padded_input = input_qarray
+            .compact_resize(model.input_shape().clone(), ST::ZERO)
+            .compact_resize(padded_input_shape.clone(), ST::ZERO);
+
+        if honestly_padded_input.values() != input_qarray.values() {
+            return false;
+        }
     }
+
+    true
+}
+
+// Concatenates the field-element conversions of the given (small-type) arrays
+// into the evaluations of the MLE the prover commits to for the whole batch:
+// the number of slots is rounded up to 2^num_batch_vars by repeating the
+// first array.
+fn stacked_values<F, ST, LT>(arrays: &[QTypeArray<ST, LT>], num_batch_vars: usize) -> Vec<F>
+where
+    F: PrimeField + FieldEncoding<ST>,
+    ST: InnerType,
+    LT: InnerType,
+{
+    (0..1 << num_batch_vars)
+        .flat_map(|k| {
+            arrays
+                .get(k)
+                .unwrap_or(&arrays[0])
+                .ref_small()
+                .values()
+                .iter()
+                .map(|x| F::encode(*x))
+        })
+        .collect()
 }
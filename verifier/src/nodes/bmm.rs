@@ -1,34 +1,37 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
+use ark_poly_commit::PolynomialCommitment;
 use ark_sumcheck::ml_sumcheck::{
     protocol::{verifier::SubClaim, PolynomialInfo},
     MLSumcheck,
 };
 use hcs_common::{
-    BMMNode, BMMNodeCommitment, BMMNodeProof, InnerType, NodeCommitment, NodeProof, Poly,
+    eq_evaluation, BMMNode, BMMNodeCommitment, BMMNodeProof, BMMNodeReductionProof, FieldEncoding,
+    InnerType, NodeCommitment, NodeProof, NodeReductionProof, NodeValueCommitment, Poly,
 };
 
-use crate::NodeOpsVerify;
+use crate::{util::verify_sum, NodeOpsVerify, OpeningClaim, OpeningClaims};
 
-impl<F, S, PCS, ST, LT> NodeOpsVerify<F, S, PCS> for BMMNode<ST, LT>
+impl<F, S, PCS, WPCS, ST, LT> NodeOpsVerify<F, S, PCS, WPCS> for BMMNode<ST, LT>
 where
-    F: PrimeField + Absorb + From<ST>,
+    F: PrimeField + Absorb + FieldEncoding<ST>,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
     ST: InnerType,
 {
     fn verify(
         &self,
-        vk: &PCS::VerifierKey,
         sponge: &mut S,
-        node_com: &NodeCommitment<F, S, PCS>,
-        input_com: &LabeledCommitment<PCS::Commitment>,
-        output_com: &LabeledCommitment<PCS::Commitment>,
-        proof: NodeProof<F, S, PCS>,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        input_com: &NodeValueCommitment<F, S, PCS>,
+        output_com: &NodeValueCommitment<F, S, PCS>,
+        proof: NodeProof<F, S, PCS, WPCS>,
+        num_batch_vars: usize,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
     ) -> bool {
         let NodeCommitment::BMM(BMMNodeCommitment {
-            weight_com,
+            weight_coms,
             bias_com,
         }) = node_com
         else {
@@ -36,97 +39,315 @@ where
         };
 
         let BMMNodeProof {
-            sumcheck_proof,
-            input_opening_proof,
-            input_opening_value,
-            weight_opening_proof,
-            weight_opening_value,
-            output_bias_opening_proof,
+            block_sums,
+            sumcheck_proofs,
+            input_opening_proofs,
+            input_opening_values,
+            weight_opening_proofs,
+            weight_opening_values,
+            output_opening_proof,
             output_opening_value,
+            bias_opening_proof,
             bias_opening_value,
         } = match proof {
             NodeProof::BMM(p) => p,
             _ => panic!("Expected BMMNodeProof"),
         };
 
-        // Squeezing random challenge r to bind the first variables of W^ to
+        let row_blocks = self.row_blocks();
+        let weight_blocks = self.weight_blocks();
+        let num_col_blocks = self.col_blocks().len();
+
+        // The proof must contain one of each value and opening proof for each
+        // block, as the openings are matched with their blocks by zipping
+        if [
+            block_sums.len(),
+            sumcheck_proofs.len(),
+            input_opening_values.len(),
+            input_opening_proofs.len(),
+        ]
+        .iter()
+        .any(|len| *len != row_blocks.len())
+            || [
+                weight_opening_values.len(),
+                weight_opening_proofs.len(),
+                weight_coms.len(),
+            ]
+            .iter()
+            .any(|len| *len != weight_blocks.len())
+        {
+            return false;
+        }
+
+        // Squeezing the random challenge r over the padded output, which
+        // yields the low-order variables r_l of each column block and the
+        // coefficients gamma_l combining their claims
         let r: Vec<F> = sponge.squeeze_field_elements(self.padded_dims_log.1);
+        let (rs, gammas) = self.col_block_challenges(&r);
 
-        // The hypercube sum proved in sumcheck should be the difference between
-        // the output and the bias
-        let sumcheck_evaluation = output_opening_value - bias_opening_value;
+        // Squeezing random challenge rho to combine the inferences in the batch
+        // (empty for a single inference)
+        let rho: Vec<F> = sponge.squeeze_field_elements(num_batch_vars);
 
-        // Public information about the sumchecked polynomial
-        // g(x) = (input - zero_point)^(x) * W^(r, x),
-        let info = PolynomialInfo {
-            max_multiplicands: 2,
-            num_variables: self.padded_dims_log.0,
-            products: vec![(F::one(), vec![0, 1])],
+        // The sums of the sumcheck polynomials of all row blocks should add up
+        // to output^(r, rho) - bias^(r). As the output is opened at the full
+        // point r, this also binds its padding to zero.
+        if block_sums.iter().sum::<F>() != output_opening_value - bias_opening_value {
+            return false;
+        }
+
+        sponge.absorb(&block_sums);
+
+        let i_z_p_f = F::encode(self.input_zero_point);
+
+        let mut sumcheck_points = Vec::new();
+
+        for (j, ((block_sum, sumcheck_proof), (_, log_size))) in block_sums
+            .iter()
+            .zip(sumcheck_proofs.iter())
+            .zip(row_blocks.iter())
+            .enumerate()
+        {
+            // Public information about the sumchecked polynomial
+            // g_j(x) = (input - zero_point)^(x, j, rho) * sum_l gamma_l * W_{j, l}^(r_l, x),
+            // which is a constant (with no sumcheck) for single-row blocks
+            let (oracle_point, oracle_evaluation) = if *log_size == 0 {
+                if !sumcheck_proof.is_empty() {
+                    return false;
+                }
+
+                (Vec::new(), *block_sum)
+            } else {
+                let info = PolynomialInfo {
+                    max_multiplicands: 2,
+                    num_variables: *log_size,
+                    products: vec![(F::one(), vec![0, 1])],
+                };
+
+                // Verify the sumcheck proof for g_j and obtain the oracle-call
+                // point s_j and claimed evaluation g_j(s_j)
+                let Ok(subclaim) = MLSumcheck::verify(&info, *block_sum, sumcheck_proof, sponge)
+                else {
+                    return false;
+                };
+
+                let SubClaim {
+                    point,
+                    expected_evaluation,
+                } = subclaim;
+
+                (point, expected_evaluation)
+            };
+
+            // Verify g_j(s_j) agrees with the claims for
+            // (input - zero_point)^(s_j, j, rho) and the W_{j, l}^(r_l, s_j)
+            let combined_weight_value: F = gammas
+                .iter()
+                .zip(&weight_opening_values[j * num_col_blocks..(j + 1) * num_col_blocks])
+                .map(|(gamma, w)| *gamma * w)
+                .sum();
+
+            if oracle_evaluation != (input_opening_values[j] - i_z_p_f) * combined_weight_value {
+                return false;
+            }
+
+            sumcheck_points.push(oracle_point);
+        }
+
+        // The openings of input^ at s_j || j || rho, each W_{j, l}^ at
+        // r_l || s_j, output^ at r || rho and bias^ at r must
+        // match the claimed values. These are proved on forks of the
+        // transcript and checked later.
+        for (((block, s), value), proof) in row_blocks
+            .iter()
+            .zip(sumcheck_points.iter())
+            .zip(input_opening_values)
+            .zip(input_opening_proofs)
+        {
+            let point: Vec<F> = Self::block_point(s, *block, self.padded_dims_log.0)
+                .into_iter()
+                .chain(rho.iter().cloned())
+                .collect();
+
+            claims.values.push(OpeningClaim::node_value(
+                input_com, &point, value, proof, sponge,
+            ));
+        }
+
+        for ((((j, l), com), value), proof) in weight_blocks
+            .into_iter()
+            .zip(weight_coms.iter())
+            .zip(weight_opening_values)
+            .zip(weight_opening_proofs)
+        {
+            claims.weights.push(OpeningClaim::new(
+                vec![com.clone()],
+                rs[l]
+                    .iter()
+                    .chain(sumcheck_points[j].iter())
+                    .cloned()
+                    .collect(),
+                vec![value],
+                proof,
+                sponge,
+            ));
+        }
+
+        let output_point: Vec<F> = r.iter().chain(rho.iter()).cloned().collect();
+
+        claims.values.push(OpeningClaim::node_value(
+            output_com,
+            &output_point,
+            output_opening_value,
+            output_opening_proof,
+            sponge,
+        ));
+
+        claims.weights.push(OpeningClaim::new(
+            vec![bias_com.clone()],
+            r,
+            vec![bias_opening_value],
+            bias_opening_proof,
+            sponge,
+        ));
+
+        true
+    }
+
+    fn verify_reduction(
+        &self,
+        sponge: &mut S,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        output_point: &[F],
+        output_value: F,
+        proof: NodeReductionProof<F, S, PCS, WPCS>,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)> {
+        let NodeCommitment::BMM(BMMNodeCommitment {
+            weight_coms,
+            bias_com,
+        }) = node_com
+        else {
+            panic!("Expected BMMNodeCommitment")
         };
 
-        // Verify the sumcheck proof for g and obtaining the oracle-call point s
-        // and claimed evaluation g(s)
-        let Ok(subclaim) = MLSumcheck::verify(&info, sumcheck_evaluation, &sumcheck_proof, sponge)
+        let NodeReductionProof::BMM(BMMNodeReductionProof {
+            sumcheck_proof,
+            input_value,
+            weight_opening_proofs,
+            weight_opening_values,
+            bias_opening_proof,
+            bias_opening_value,
+        }) = proof
         else {
-            return false;
+            return None;
         };
 
-        let SubClaim {
-            point: oracle_point,
-            expected_evaluation: oracle_evaluation,
-        } = subclaim;
+        let (num_row_vars, num_col_vars) = self.padded_dims_log;
+        let row_blocks = self.row_blocks();
+        let weight_blocks = self.weight_blocks();
+        let num_col_blocks = self.col_blocks().len();
 
-        // Verify g(s) agrees with the claims for (input - zero_point)^(s) and
-        // W^(r, s)
-        if oracle_evaluation
-            != (input_opening_value - F::from(self.input_zero_point)) * weight_opening_value
+        if output_point.len() < num_col_vars
+            || [
+                weight_opening_values.len(),
+                weight_opening_proofs.len(),
+                weight_coms.len(),
+            ]
+            .iter()
+            .any(|len| *len != weight_blocks.len())
         {
-            return false;
+            return None;
         }
 
-        // Verify that the opening of input^ at s agrees with the claimed value for
-        // (input - zero_point)^(s)
-        // TODO possibly rng, not None
-        if !PCS::check(
-            vk,
-            [input_com],
-            &oracle_point,
-            [input_opening_value],
-            &input_opening_proof,
+        // The output point consists of r, over the padded output, and the
+        // batch challenge point rho (empty for a single inference)
+        let (r, rho) = output_point.split_at(num_col_vars);
+
+        // The low-order variables r_l of r corresponding to each column block
+        // and the coefficients gamma_l of its claim, which come from the
+        // high-order ones
+        let (rs, gammas) = self.col_block_challenges(r);
+
+        sponge.absorb(&bias_opening_value);
+
+        // Sumcheck of (input - zero_point)^(x, rho) * sum_l gamma_l * W^(r_l, x)
+        // over the padded input, whose sum is output^(r, rho) - bias^(r)
+        let info = PolynomialInfo {
+            max_multiplicands: 2,
+            num_variables: num_row_vars,
+            products: vec![(F::one(), vec![0, 1])],
+        };
+
+        let (s, expected_evaluation) = verify_sum(
+            &info,
+            output_value - bias_opening_value,
+            &sumcheck_proof,
             sponge,
-            None,
-        )
-        .unwrap()
-        {
-            return false;
+        )?;
+
+        // The combined weights at s are those of the row block s falls in,
+        // which the verifier selects through eq at the high-order variables
+        // of s
+        let combined_weight_value: F = row_blocks
+            .iter()
+            .enumerate()
+            .map(|(j, block)| {
+                let block_value: F = gammas
+                    .iter()
+                    .zip(&weight_opening_values[j * num_col_blocks..(j + 1) * num_col_blocks])
+                    .map(|(gamma, w)| *gamma * w)
+                    .sum();
+
+                eq_evaluation(
+                    &s[block.1..],
+                    &Self::block_point::<F>(&[], *block, num_row_vars),
+                ) * block_value
+            })
+            .sum();
+
+        let i_z_p_f = F::encode(self.input_zero_point);
+
+        if expected_evaluation != (input_value - i_z_p_f) * combined_weight_value {
+            return None;
         }
 
-        // Verify the openings of W^ at r || s and b and o at r match the claimed
-        // values
-        // TODO possibly rng, not None
-        if !PCS::check(
-            vk,
-            [weight_com],
-            &r.clone().into_iter().chain(oracle_point).collect(),
-            [weight_opening_value],
-            &weight_opening_proof,
-            sponge,
-            None,
-        )
-        .unwrap()
+        sponge.absorb(&weight_opening_values);
+
+        // The openings of each W_{j, l}^ at r_l || s_j and bias^ at r are
+        // proved on forks of the transcript and checked later
+        for ((((j, l), com), value), proof) in weight_blocks
+            .into_iter()
+            .zip(weight_coms.iter())
+            .zip(weight_opening_values)
+            .zip(weight_opening_proofs)
         {
-            return false;
+            claims.weights.push(OpeningClaim::new(
+                vec![com.clone()],
+                rs[l]
+                    .iter()
+                    .chain(s[..row_blocks[j].1].iter())
+                    .cloned()
+                    .collect(),
+                vec![value],
+                proof,
+                sponge,
+            ));
         }
 
-        PCS::check(
-            vk,
-            [output_com, bias_com],
-            &r,
-            [output_opening_value, bias_opening_value],
-            &output_bias_opening_proof,
+        claims.weights.push(OpeningClaim::new(
+            vec![bias_com.clone()],
+            r.to_vec(),
+            vec![bias_opening_value],
+            bias_opening_proof,
             sponge,
-            None,
-        )
-        .unwrap()
+        ));
+
+        // The claimed input value is passed on to the preceding node
+        sponge.absorb(&input_value);
+
+        let input_point = s.into_iter().chain(rho.iter().cloned()).collect();
+
+        Some((input_point, input_value))
     }
 }
@@ -1,25 +1,41 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
-use hcs_common::{NodeCommitment, NodeProof, Poly, RequantiseBMMNode};
+use ark_poly_commit::PolynomialCommitment;
+use hcs_common::{
+    NodeCommitment, NodeProof, NodeReductionProof, NodeValueCommitment, Poly, RequantiseBMMNode,
+};
 
-use crate::NodeOpsVerify;
+use crate::{NodeOpsVerify, OpeningClaims};
 
-impl<F, S, PCS, ST> NodeOpsVerify<F, S, PCS> for RequantiseBMMNode<ST>
+impl<F, S, PCS, WPCS, ST> NodeOpsVerify<F, S, PCS, WPCS> for RequantiseBMMNode<ST>
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
     fn verify(
         &self,
-        _vk: &PCS::VerifierKey,
         _sponge: &mut S,
-        _node_com: &NodeCommitment<F, S, PCS>,
-        _input_com: &LabeledCommitment<PCS::Commitment>,
-        _output_com: &LabeledCommitment<PCS::Commitment>,
-        _proof: NodeProof<F, S, PCS>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _input_com: &NodeValueCommitment<F, S, PCS>,
+        _output_com: &NodeValueCommitment<F, S, PCS>,
+        _proof: NodeProof<F, S, PCS, WPCS>,
+        _num_batch_vars: usize,
+        _claims: &mut OpeningClaims<F, S, PCS, WPCS>,
     ) -> bool {
         true
     }
+
+    fn verify_reduction(
+        &self,
+        _sponge: &mut S,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _output_point: &[F],
+        _output_value: F,
+        _proof: NodeReductionProof<F, S, PCS, WPCS>,
+        _claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)> {
+        panic!("RequantiseBMMNode has no claim reduction")
+    }
 }
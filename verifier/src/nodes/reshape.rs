@@ -1,25 +1,93 @@
 use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
-use ark_poly_commit::{LabeledCommitment, PolynomialCommitment};
-use hcs_common::{NodeCommitment, NodeProof, Poly, ReshapeNode};
+use ark_poly_commit::PolynomialCommitment;
+use ark_sumcheck::ml_sumcheck::protocol::PolynomialInfo;
+use hcs_common::{
+    eq_evaluations, NodeCommitment, NodeProof, NodeReductionProof, NodeValueCommitment, Poly,
+    ReshapeNode, ReshapeNodeReductionProof,
+};
 
-use crate::NodeOpsVerify;
+use crate::{util::verify_sum, NodeOpsVerify, OpeningClaims};
 
-impl<F, S, PCS> NodeOpsVerify<F, S, PCS> for ReshapeNode
+impl<F, S, PCS, WPCS> NodeOpsVerify<F, S, PCS, WPCS> for ReshapeNode
 where
     F: PrimeField + Absorb,
     S: CryptographicSponge,
     PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
 {
     fn verify(
         &self,
-        _vk: &PCS::VerifierKey,
         _sponge: &mut S,
-        _node_com: &NodeCommitment<F, S, PCS>,
-        _input_com: &LabeledCommitment<PCS::Commitment>,
-        _output_com: &LabeledCommitment<PCS::Commitment>,
-        _proof: NodeProof<F, S, PCS>,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        _input_com: &NodeValueCommitment<F, S, PCS>,
+        _output_com: &NodeValueCommitment<F, S, PCS>,
+        _proof: NodeProof<F, S, PCS, WPCS>,
+        _num_batch_vars: usize,
+        _claims: &mut OpeningClaims<F, S, PCS, WPCS>,
     ) -> bool {
         true
     }
+
+    fn verify_reduction(
+        &self,
+        sponge: &mut S,
+        _node_com: &NodeCommitment<F, S, WPCS>,
+        output_point: &[F],
+        output_value: F,
+        proof: NodeReductionProof<F, S, PCS, WPCS>,
+        _claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)> {
+        let NodeReductionProof::Reshape(ReshapeNodeReductionProof {
+            sumcheck_proof,
+            input_value,
+        }) = proof
+        else {
+            return None;
+        };
+
+        let num_input_vars: usize = self.padded_input_shape_log.iter().sum();
+        let num_output_vars: usize = self.padded_output_shape_log.iter().sum();
+
+        if output_point.len() < num_output_vars {
+            return None;
+        }
+
+        // The output point consists of r, over the padded output, and the
+        // batch challenge point rho (empty for a single inference)
+        let (r, rho) = output_point.split_at(num_output_vars);
+
+        // Sumcheck of input^(y, rho) * M^(r, y), whose sum is output^(r, rho)
+        let info = PolynomialInfo {
+            max_multiplicands: 2,
+            num_variables: num_input_vars,
+            products: vec![(F::one(), vec![0, 1])],
+        };
+
+        let (sumcheck_point, expected_evaluation) =
+            verify_sum(&info, output_value, &sumcheck_proof, sponge)?;
+
+        // The verifier evaluates M^(r, s) itself: the reshape is public
+        let eq_r = eq_evaluations(r);
+        let eq_s = eq_evaluations(&sumcheck_point);
+
+        let map_value: F = self
+            .padded_index_map()
+            .into_iter()
+            .map(|(y, x)| eq_s[y] * eq_r[x])
+            .sum();
+
+        if expected_evaluation != input_value * map_value {
+            return None;
+        }
+
+        sponge.absorb(&input_value);
+
+        let input_point = sumcheck_point
+            .into_iter()
+            .chain(rho.iter().cloned())
+            .collect();
+
+        Some((input_point, input_value))
+    }
 }
@@ -0,0 +1,397 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+use ark_sumcheck::ml_sumcheck::protocol::PolynomialInfo;
+use hcs_common::{
+    eq_evaluation, index_evaluation, FieldEncoding, InnerType, NodeCommitment, NodeProof,
+    NodeReductionProof, NodeValueCommitment, Poly, SparseBMMNode, SparseBMMNodeCommitment,
+    SparseBMMNodeProof, SparseBMMNodeReductionProof,
+};
+
+use crate::{util::verify_sum, NodeOpsVerify, OpeningClaim, OpeningClaims};
+
+impl<F, S, PCS, WPCS, ST, LT> NodeOpsVerify<F, S, PCS, WPCS> for SparseBMMNode<ST, LT>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST>,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+    ST: InnerType,
+{
+    fn verify(
+        &self,
+        sponge: &mut S,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        input_com: &NodeValueCommitment<F, S, PCS>,
+        output_com: &NodeValueCommitment<F, S, PCS>,
+        proof: NodeProof<F, S, PCS, WPCS>,
+        num_batch_vars: usize,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> bool {
+        let NodeProof::SparseBMM(SparseBMMNodeProof {
+            reduction_proof,
+            input_opening_proof,
+            output_opening_proof,
+            output_opening_value,
+        }) = proof
+        else {
+            panic!("Expected SparseBMMNodeProof")
+        };
+
+        let num_col_vars = self.padded_dims_log.1;
+
+        // Squeezing the output challenge point r and the random challenge rho
+        // combining the inferences in the batch (empty for a single inference)
+        let r: Vec<F> = sponge.squeeze_field_elements(num_col_vars);
+        let rho: Vec<F> = sponge.squeeze_field_elements(num_batch_vars);
+
+        let output_point: Vec<F> = r.into_iter().chain(rho).collect();
+
+        let Some((input_point, input_value)) = verify_output_claim(
+            self,
+            sponge,
+            node_com,
+            &output_point,
+            output_opening_value,
+            reduction_proof,
+            claims,
+        ) else {
+            return false;
+        };
+
+        // The input and output claims are checked by opening the committed
+        // values, on forks of the transcript as all other openings
+        claims.values.push(OpeningClaim::node_value(
+            input_com,
+            &input_point,
+            input_value,
+            input_opening_proof,
+            sponge,
+        ));
+
+        claims.values.push(OpeningClaim::node_value(
+            output_com,
+            &output_point,
+            output_opening_value,
+            output_opening_proof,
+            sponge,
+        ));
+
+        true
+    }
+
+    fn verify_reduction(
+        &self,
+        sponge: &mut S,
+        node_com: &NodeCommitment<F, S, WPCS>,
+        output_point: &[F],
+        output_value: F,
+        proof: NodeReductionProof<F, S, PCS, WPCS>,
+        claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+    ) -> Option<(Vec<F>, F)> {
+        let NodeReductionProof::SparseBMM(proof) = proof else {
+            return None;
+        };
+
+        let (input_point, input_value) = verify_output_claim(
+            self,
+            sponge,
+            node_com,
+            output_point,
+            output_value,
+            *proof,
+            claims,
+        )?;
+
+        // The claimed input value is passed on to the preceding node
+        sponge.absorb(&input_value);
+
+        Some((input_point, input_value))
+    }
+}
+
+// Verifies the reduction of the claim that the output MLE takes the value
+// output_value at output_point to one about the input MLE, returning the point
+// and value of the latter. This is shared by the verification of the node
+// proof and that of its claim reduction (cf. the prover).
+fn verify_output_claim<F, S, PCS, WPCS, ST, LT>(
+    node: &SparseBMMNode<ST, LT>,
+    sponge: &mut S,
+    node_com: &NodeCommitment<F, S, WPCS>,
+    output_point: &[F],
+    output_value: F,
+    proof: SparseBMMNodeReductionProof<F, S, PCS, WPCS>,
+    claims: &mut OpeningClaims<F, S, PCS, WPCS>,
+) -> Option<(Vec<F>, F)>
+where
+    F: PrimeField + Absorb + FieldEncoding<ST>,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+    ST: InnerType,
+{
+    let NodeCommitment::SparseBMM(SparseBMMNodeCommitment {
+        value_com,
+        row_com,
+        col_com,
+        row_count_com,
+        col_count_com,
+        bias_com,
+    }) = node_com
+    else {
+        panic!("Expected SparseBMMNodeCommitment")
+    };
+
+    let SparseBMMNodeReductionProof {
+        lookup_coms,
+        inverse_coms,
+        inverse_sums,
+        entry_sumcheck_proof,
+        input_table_sumcheck_proof,
+        eq_table_sumcheck_proof,
+        entry_weight_opening_proof,
+        entry_opening_proof,
+        entry_opening_values,
+        input_table_opening_proof,
+        row_count_opening_proof,
+        input_table_opening_values,
+        input_value,
+        eq_table_opening_proof,
+        col_count_opening_proof,
+        eq_table_opening_values,
+        bias_opening_proof,
+        bias_opening_value,
+    } = proof;
+
+    if lookup_coms.len() != 2
+        || inverse_coms.len() != 4
+        || inverse_sums.len() != 2
+        || entry_opening_values.len() != 7
+        || input_table_opening_values.len() != 2
+        || eq_table_opening_values.len() != 2
+    {
+        return None;
+    }
+
+    let num_entry_vars = node.padded_num_entries_log;
+    let (num_row_vars, num_col_vars) = node.padded_dims_log;
+
+    if output_point.len() < num_col_vars {
+        return None;
+    }
+
+    // The output point consists of r, over the padded output, and the
+    // batch challenge point rho (empty for a single inference)
+    let (r, rho) = output_point.split_at(num_col_vars);
+
+    sponge.absorb(&lookup_coms);
+
+    let lookup_challenges: Vec<F> = sponge.squeeze_field_elements(2);
+    let (alpha, beta) = (lookup_challenges[0], lookup_challenges[1]);
+
+    sponge.absorb(&inverse_coms);
+    sponge.absorb(&inverse_sums);
+    sponge.absorb(&vec![output_value, bias_opening_value]);
+
+    let t_entries: Vec<F> = sponge.squeeze_field_elements(num_entry_vars);
+    let t_input_table: Vec<F> = sponge.squeeze_field_elements(num_row_vars);
+    let t_eq_table: Vec<F> = sponge.squeeze_field_elements(num_col_vars);
+    let lambdas: Vec<F> = sponge.squeeze_field_elements(4);
+
+    let i_z_p_f = F::encode(node.input_zero_point);
+
+    // Sumcheck over the entries of
+    //   value * (a - zero_point) * b
+    //   + lambda_0 * eq(t, .) * (h_row * (beta - row - alpha * a) - 1)
+    //   + lambda_1 * eq(t, .) * (h_col * (beta - col - alpha * b) - 1)
+    //   + lambda_2 * h_row + lambda_3 * h_col,
+    // where the multiplicands are, in order: value, a, b, eq(t, .), h_row,
+    // row, h_col and col
+    let entry_info = PolynomialInfo {
+        max_multiplicands: 3,
+        num_variables: num_entry_vars,
+        products: vec![
+            (F::one(), vec![0, 1, 2]),
+            (-i_z_p_f, vec![0, 2]),
+            (lambdas[0] * beta, vec![3, 4]),
+            (-lambdas[0], vec![3, 4, 5]),
+            (-lambdas[0] * alpha, vec![3, 4, 1]),
+            (-lambdas[0], vec![3]),
+            (lambdas[1] * beta, vec![3, 6]),
+            (-lambdas[1], vec![3, 6, 7]),
+            (-lambdas[1] * alpha, vec![3, 6, 2]),
+            (-lambdas[1], vec![3]),
+            (lambdas[2], vec![4]),
+            (lambdas[3], vec![6]),
+        ],
+    };
+
+    let entry_sum = output_value - bias_opening_value
+        + lambdas[2] * inverse_sums[0]
+        + lambdas[3] * inverse_sums[1];
+
+    let (entry_point, entry_evaluation) =
+        verify_sum(&entry_info, entry_sum, &entry_sumcheck_proof, sponge)?;
+
+    let [value, row, col, a, b, h_row, h_col]: [F; 7] =
+        entry_opening_values.clone().try_into().unwrap();
+
+    let eq_t = eq_evaluation(&t_entries, &entry_point);
+
+    if entry_evaluation
+        != value * (a - i_z_p_f) * b
+            + lambdas[0] * eq_t * (h_row * (beta - row - alpha * a) - F::one())
+            + lambdas[1] * eq_t * (h_col * (beta - col - alpha * b) - F::one())
+            + lambdas[2] * h_row
+            + lambdas[3] * h_col
+    {
+        return None;
+    }
+
+    // Sumchecks over the input and eq tables of
+    //   eq(t, .) * (q * (beta - y - alpha * table) - count) + q,
+    // whose sums must match those of h_row and h_col. The verifier can
+    // evaluate eq(r, .) itself, whereas input^ is opened.
+    let (input_table_point, input_table_evaluation) = verify_sum(
+        &table_info(num_row_vars, alpha, beta),
+        inverse_sums[0],
+        &input_table_sumcheck_proof,
+        sponge,
+    )?;
+
+    if input_table_evaluation
+        != table_evaluation(
+            eq_evaluation(&t_input_table, &input_table_point),
+            index_evaluation(&input_table_point),
+            &input_table_opening_values,
+            input_value,
+            alpha,
+            beta,
+        )
+    {
+        return None;
+    }
+
+    let (eq_table_point, eq_table_evaluation) = verify_sum(
+        &table_info(num_col_vars, alpha, beta),
+        inverse_sums[1],
+        &eq_table_sumcheck_proof,
+        sponge,
+    )?;
+
+    if eq_table_evaluation
+        != table_evaluation(
+            eq_evaluation(&t_eq_table, &eq_table_point),
+            index_evaluation(&eq_table_point),
+            &eq_table_opening_values,
+            eq_evaluation(r, &eq_table_point),
+            alpha,
+            beta,
+        )
+    {
+        return None;
+    }
+
+    // The openings are proved on forks of the transcript and checked
+    // later. Those of node parameters and of the polynomials committed to in
+    // the proof are claimed separately, as they may use different PCSs.
+    claims.weights.push(OpeningClaim::new(
+        vec![value_com.clone(), row_com.clone(), col_com.clone()],
+        entry_point.clone(),
+        entry_opening_values[..3].to_vec(),
+        entry_weight_opening_proof,
+        sponge,
+    ));
+
+    claims.values.push(OpeningClaim::new(
+        vec![
+            lookup_coms[0].clone(),
+            lookup_coms[1].clone(),
+            inverse_coms[0].clone(),
+            inverse_coms[1].clone(),
+        ],
+        entry_point,
+        entry_opening_values[3..].to_vec(),
+        entry_opening_proof,
+        sponge,
+    ));
+
+    claims.values.push(OpeningClaim::new(
+        vec![inverse_coms[2].clone()],
+        input_table_point.clone(),
+        vec![input_table_opening_values[0]],
+        input_table_opening_proof,
+        sponge,
+    ));
+
+    claims.weights.push(OpeningClaim::new(
+        vec![row_count_com.clone()],
+        input_table_point.clone(),
+        vec![input_table_opening_values[1]],
+        row_count_opening_proof,
+        sponge,
+    ));
+
+    claims.values.push(OpeningClaim::new(
+        vec![inverse_coms[3].clone()],
+        eq_table_point.clone(),
+        vec![eq_table_opening_values[0]],
+        eq_table_opening_proof,
+        sponge,
+    ));
+
+    claims.weights.push(OpeningClaim::new(
+        vec![col_count_com.clone()],
+        eq_table_point,
+        vec![eq_table_opening_values[1]],
+        col_count_opening_proof,
+        sponge,
+    ));
+
+    claims.weights.push(OpeningClaim::new(
+        vec![bias_com.clone()],
+        r.to_vec(),
+        vec![bias_opening_value],
+        bias_opening_proof,
+        sponge,
+    ));
+
+    let input_point = input_table_point
+        .into_iter()
+        .chain(rho.iter().cloned())
+        .collect();
+
+    Some((input_point, input_value))
+}
+
+// Information about the table polynomial
+// eq(t, .) * (q * (beta - y - alpha * table) - count) + q, whose multiplicands
+// are, in order: eq(t, .), q, y, table and count
+fn table_info<F: PrimeField>(num_variables: usize, alpha: F, beta: F) -> PolynomialInfo<F> {
+    PolynomialInfo {
+        max_multiplicands: 3,
+        num_variables,
+        products: vec![
+            (beta, vec![0, 1]),
+            (-F::one(), vec![0, 1, 2]),
+            (-alpha, vec![0, 1, 3]),
+            (-F::one(), vec![0, 4]),
+            (F::one(), vec![1]),
+        ],
+    }
+}
+
+// Evaluation of the table polynomial at a point, given those of eq(t, .), y,
+// q and count (the latter two in that order) and the table
+fn table_evaluation<F: PrimeField>(
+    eq_value: F,
+    index_value: F,
+    opening_values: &[F],
+    table_value: F,
+    alpha: F,
+    beta: F,
+) -> F {
+    let (q, count) = (opening_values[0], opening_values[1]);
+
+    eq_value * (q * (beta - index_value - alpha * table_value) - count) + q
+}
@@ -0,0 +1,72 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+
+use hcs_common::{OpeningClaim, Poly, SecurePCS};
+
+/// The opening claims of one or several proofs, split by PCS: those about
+/// node values and the other polynomials committed to in the proofs, checked
+/// with the verifier key of `PCS`, and those about node parameters, checked
+/// with that of `WPCS`
+pub struct OpeningClaims<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub values: Vec<OpeningClaim<F, S, PCS>>,
+    pub weights: Vec<OpeningClaim<F, S, WPCS>>,
+}
+
+impl<F, S, PCS, WPCS> OpeningClaims<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// Moves the claims of `other` to the end of those of `self`
+    pub fn append(&mut self, other: Self) {
+        self.values.extend(other.values);
+        self.weights.extend(other.weights);
+    }
+}
+
+impl<F, S, PCS, WPCS> Default for OpeningClaims<F, S, PCS, WPCS>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: PolynomialCommitment<F, Poly<F>, S>,
+    WPCS: PolynomialCommitment<F, Poly<F>, S>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks lists of opening claims about node values and node parameters,
+/// possibly coming from different inference proofs. This is the single place
+/// where claims are discharged, and those of each PCS are checked together
+/// (cf. `SecurePCS::batch_check`).
+pub fn check_opening_claims<F, S, PCS, WPCS>(
+    vk: &PCS::VerifierKey,
+    wvk: &WPCS::VerifierKey,
+    value_claims: &[OpeningClaim<F, S, PCS>],
+    weight_claims: &[OpeningClaim<F, S, WPCS>],
+) -> bool
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+    PCS: SecurePCS<F, S>,
+    WPCS: SecurePCS<F, S>,
+{
+    PCS::batch_check(vk, value_claims) && WPCS::batch_check(wvk, weight_claims)
+}
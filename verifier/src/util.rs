@@ -0,0 +1,31 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_sumcheck::ml_sumcheck::{
+    protocol::{verifier::SubClaim, PolynomialInfo},
+    MLSumcheck, Proof,
+};
+
+// Verifies the sumcheck proof of the claimed sum, returning the challenge
+// point and the expected evaluation of the polynomial at it. A polynomial in
+// no variables is a constant equal to its sum and has an empty proof.
+pub(crate) fn verify_sum<F, S>(
+    info: &PolynomialInfo<F>,
+    claimed_sum: F,
+    proof: &Proof<F>,
+    sponge: &mut S,
+) -> Option<(Vec<F>, F)>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    if info.num_variables == 0 {
+        return proof.is_empty().then_some((Vec::new(), claimed_sum));
+    }
+
+    let SubClaim {
+        point,
+        expected_evaluation,
+    } = MLSumcheck::verify(info, claimed_sum, proof, sponge).ok()?;
+
+    Some((point, expected_evaluation))
+}